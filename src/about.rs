@@ -0,0 +1,413 @@
+//! About window with version and diagnostics.
+//!
+//! Opened from the tray. Shows the version and commit Megatile was built
+//! from, where its config and logs are, the monitors it manages and how many
+//! windows it manages on each. "Copy diagnostics" puts the same report on the
+//! clipboard for bug reports, with the user profile path and user name
+//! replaced so it can be pasted publicly.
+
+use std::path::PathBuf;
+
+use log::error;
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{COLOR_BTNFACE, GetSysColorBrush};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock};
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+use windows::Win32::UI::WindowsAndMessaging::{
+    AdjustWindowRectEx, BS_DEFPUSHBUTTON, BS_PUSHBUTTON, CW_USEDEFAULT, CreateWindowExW,
+    DefWindowProcW, ES_MULTILINE, ES_READONLY, GWLP_USERDATA, GetWindowLongPtrW, IDC_ARROW,
+    LoadCursorW, RegisterClassW, SW_HIDE, SW_SHOW, SetForegroundWindow, SetWindowLongPtrW,
+    SetWindowTextW, ShowWindow, WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_NCDESTROY,
+    WNDCLASSW, WS_CAPTION, WS_MINIMIZEBOX, WS_OVERLAPPED, WS_SYSMENU, WS_VSCROLL,
+};
+use windows::core::{HSTRING, PCWSTR, w};
+
+use crate::notifications::{Severity, notify};
+use crate::settings::create_control;
+
+const ABOUT_CLASS_NAME: PCWSTR = w!("MegatileAbout");
+const CLIENT_WIDTH: i32 = 460;
+const CLIENT_HEIGHT: i32 = 300;
+const MARGIN: i32 = 12;
+const BUTTON_HEIGHT: i32 = 26;
+
+const ID_COPY: u16 = 100;
+const ID_CLOSE: u16 = 101;
+
+/// Commit Megatile was built from, set by `build.rs`.
+const COMMIT: &str = env!("MEGATILE_COMMIT");
+
+/// A monitor as the diagnostics list it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorSummary {
+    /// Device name, e.g. `\\.\DISPLAY2`.
+    pub device: String,
+    /// Index of the zone when the display is split into zones.
+    pub zone: usize,
+    pub width: i32,
+    pub height: i32,
+    pub primary: bool,
+    pub managed: bool,
+    /// Managed windows on all of its workspaces.
+    pub windows: usize,
+}
+
+/// What the About window shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub version: String,
+    pub commit: String,
+    pub config_path: Option<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+    pub monitors: Vec<MonitorSummary>,
+    pub active_workspace: u8,
+}
+
+impl Diagnostics {
+    /// Collects the build information and paths; `monitors` and
+    /// `active_workspace` come from the window manager.
+    pub fn collect(monitors: Vec<MonitorSummary>, active_workspace: u8) -> Self {
+        Diagnostics {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: COMMIT.to_string(),
+            config_path: crate::config::config_path().ok(),
+            log_dir: crate::logging::get_logs_dir().ok(),
+            monitors,
+            active_workspace,
+        }
+    }
+
+    /// Returns the report, one fact per line.
+    pub fn report(&self) -> String {
+        let path = |path: &Option<PathBuf>| match path {
+            Some(path) => path.display().to_string(),
+            None => "unknown".to_string(),
+        };
+        let windows: usize = self.monitors.iter().map(|monitor| monitor.windows).sum();
+        let mut lines = vec![
+            format!("Megatile {} ({})", self.version, self.commit),
+            format!("Config: {}", path(&self.config_path)),
+            format!("Logs: {}", path(&self.log_dir)),
+            format!(
+                "Managed windows: {} (workspace {} active)",
+                windows, self.active_workspace
+            ),
+            format!("Monitors: {}", self.monitors.len()),
+        ];
+        for monitor in &self.monitors {
+            let mut flags = Vec::new();
+            if monitor.primary {
+                flags.push("primary".to_string());
+            }
+            if monitor.zone > 0 {
+                flags.push(format!("zone {}", monitor.zone + 1));
+            }
+            if !monitor.managed {
+                flags.push("unmanaged".to_string());
+            }
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", flags.join(", "))
+            };
+            lines.push(format!(
+                "  {} {}x{}{}: {} {}",
+                monitor.device,
+                monitor.width,
+                monitor.height,
+                flags,
+                monitor.windows,
+                if monitor.windows == 1 {
+                    "window"
+                } else {
+                    "windows"
+                }
+            ));
+        }
+        lines.join("\r\n")
+    }
+
+    /// Returns the report with the user profile path and user name replaced.
+    pub fn redacted_report(&self) -> String {
+        redact(
+            &self.report(),
+            std::env::var("USERPROFILE").ok().as_deref(),
+            std::env::var("USERNAME").ok().as_deref(),
+        )
+    }
+}
+
+/// Replaces `profile` (ignoring ASCII case, as Windows paths do) with
+/// `%USERPROFILE%` and any other mention of `user` with `<user>`, in one
+/// pass so the replacements themselves are left alone.
+fn redact(text: &str, profile: Option<&str>, user: Option<&str>) -> String {
+    let needles: Vec<(String, &str)> = [(profile, "%USERPROFILE%"), (user, "<user>")]
+        .into_iter()
+        .filter_map(|(needle, replacement)| {
+            let needle = needle.filter(|needle| !needle.is_empty())?;
+            Some((needle.to_ascii_lowercase(), replacement))
+        })
+        .collect();
+    // ASCII lowercasing keeps byte offsets, so indices carry over to `text`
+    let lower = text.to_ascii_lowercase();
+    let mut redacted = String::with_capacity(text.len());
+    let mut index = 0;
+    while let Some(c) = text[index..].chars().next() {
+        match needles
+            .iter()
+            .find(|(needle, _)| lower[index..].starts_with(needle.as_str()))
+        {
+            Some((needle, replacement)) => {
+                redacted.push_str(replacement);
+                index += needle.len();
+            }
+            None => {
+                redacted.push(c);
+                index += c.len_utf8();
+            }
+        }
+    }
+    redacted
+}
+
+/// Report read by the window procedure.
+struct AboutState {
+    report: String,
+}
+
+/// Owns the (initially hidden) About window. Lives on the main thread.
+pub struct AboutWindow {
+    hwnd: HWND,
+    text: HWND,
+    state: Box<AboutState>,
+}
+
+impl AboutWindow {
+    /// Creates the About window and its controls, hidden.
+    pub fn new() -> Result<Self, String> {
+        let hinstance = unsafe {
+            GetModuleHandleW(None).map_err(|e| format!("Failed to get module handle: {}", e))
+        }?;
+
+        unsafe {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(about_wnd_proc),
+                hInstance: hinstance.into(),
+                lpszClassName: ABOUT_CLASS_NAME,
+                hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+                hbrBackground: GetSysColorBrush(COLOR_BTNFACE),
+                ..Default::default()
+            };
+            if RegisterClassW(&wc) == 0 {
+                return Err("Failed to register About window class".to_string());
+            }
+        }
+
+        let style = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0 | WS_MINIMIZEBOX.0);
+        let mut frame = RECT {
+            left: 0,
+            top: 0,
+            right: CLIENT_WIDTH,
+            bottom: CLIENT_HEIGHT,
+        };
+        let hwnd = unsafe {
+            let _ = AdjustWindowRectEx(&mut frame, style, false, WINDOW_EX_STYLE(0));
+            CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                ABOUT_CLASS_NAME,
+                w!("About Megatile"),
+                style,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                frame.right - frame.left,
+                frame.bottom - frame.top,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )
+            .map_err(|e| format!("Failed to create About window: {}", e))?
+        };
+
+        let buttons_top = CLIENT_HEIGHT - MARGIN - BUTTON_HEIGHT;
+        let text = create_control(
+            hwnd,
+            "EDIT",
+            "",
+            WS_VSCROLL.0 | (ES_MULTILINE | ES_READONLY) as u32,
+            0,
+            (
+                MARGIN,
+                MARGIN,
+                CLIENT_WIDTH - 2 * MARGIN,
+                buttons_top - 2 * MARGIN,
+            ),
+        );
+        create_control(
+            hwnd,
+            "BUTTON",
+            "Copy diagnostics",
+            BS_PUSHBUTTON as u32,
+            ID_COPY,
+            (MARGIN, buttons_top, 140, BUTTON_HEIGHT),
+        );
+        create_control(
+            hwnd,
+            "BUTTON",
+            "Close",
+            BS_DEFPUSHBUTTON as u32,
+            ID_CLOSE,
+            (CLIENT_WIDTH - MARGIN - 100, buttons_top, 100, BUTTON_HEIGHT),
+        );
+
+        let mut state = Box::new(AboutState {
+            report: String::new(),
+        });
+        unsafe {
+            SetWindowLongPtrW(
+                hwnd,
+                GWLP_USERDATA,
+                state.as_mut() as *mut AboutState as isize,
+            );
+        }
+
+        Ok(AboutWindow { hwnd, text, state })
+    }
+
+    /// Shows the window with `diagnostics`.
+    pub fn open(&mut self, diagnostics: &Diagnostics) {
+        self.state.report = diagnostics.redacted_report();
+        unsafe {
+            let _ = SetWindowTextW(self.text, &HSTRING::from(diagnostics.report()));
+            let _ = ShowWindow(self.hwnd, SW_SHOW);
+            let _ = SetForegroundWindow(self.hwnd);
+        }
+    }
+}
+
+impl AboutState {
+    fn on_command(&self, hwnd: HWND, id: u16) {
+        match id {
+            ID_COPY => {
+                if let Err(e) = copy_to_clipboard(hwnd, &self.report) {
+                    error!("Failed to copy diagnostics: {}", e);
+                    notify(Severity::Warning, "Diagnostics not copied", e);
+                }
+            }
+            ID_CLOSE => unsafe {
+                let _ = ShowWindow(hwnd, SW_HIDE);
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Puts `text` on the clipboard.
+fn copy_to_clipboard(owner: HWND, text: &str) -> Result<(), String> {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        OpenClipboard(Some(owner)).map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+        let result = (|| {
+            EmptyClipboard().map_err(|e| format!("Failed to empty the clipboard: {}", e))?;
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2)
+                .map_err(|e| format!("Failed to allocate clipboard memory: {}", e))?;
+            let target = GlobalLock(memory) as *mut u16;
+            if target.is_null() {
+                return Err("Failed to lock clipboard memory".to_string());
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+            let _ = GlobalUnlock(memory);
+            // The clipboard owns the memory from here on
+            SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(memory.0)))
+                .map_err(|e| format!("Failed to set clipboard data: {}", e))?;
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+unsafe extern "system" fn about_wnd_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut AboutState;
+        match msg {
+            WM_COMMAND if !state_ptr.is_null() => {
+                let id = (wparam.0 & 0xFFFF) as u16;
+                (*state_ptr).on_command(hwnd, id);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                // Keep the window around for the next time it's opened
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                LRESULT(0)
+            }
+            WM_NCDESTROY => {
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_leave_out_the_user() {
+        let diagnostics = Diagnostics {
+            version: "1.2.3".to_string(),
+            commit: "abc1234".to_string(),
+            config_path: Some(PathBuf::from(r"C:\Users\Jo\.megatile\config.toml")),
+            log_dir: None,
+            monitors: vec![
+                MonitorSummary {
+                    device: r"\\.\DISPLAY1".to_string(),
+                    zone: 0,
+                    width: 2560,
+                    height: 1440,
+                    primary: true,
+                    managed: true,
+                    windows: 3,
+                },
+                MonitorSummary {
+                    device: r"\\.\DISPLAY2".to_string(),
+                    zone: 0,
+                    width: 1920,
+                    height: 1080,
+                    primary: false,
+                    managed: false,
+                    windows: 1,
+                },
+            ],
+            active_workspace: 2,
+        };
+        let report = diagnostics.report();
+        assert!(report.starts_with("Megatile 1.2.3 (abc1234)\r\n"));
+        assert!(report.contains("Logs: unknown"));
+        assert!(report.contains("Managed windows: 4 (workspace 2 active)"));
+        assert!(report.contains(r"  \\.\DISPLAY1 2560x1440 (primary): 3 windows"));
+        assert!(report.contains(r"  \\.\DISPLAY2 1920x1080 (unmanaged): 1 window"));
+
+        let redacted = redact(&report, Some(r"c:\users\jo"), Some("Jo"));
+        assert!(redacted.contains(r"Config: %USERPROFILE%\.megatile\config.toml"));
+        assert!(!redacted.contains("Jo"));
+        assert_eq!(redact("Jo and jo", None, Some("jo")), "<user> and <user>");
+        assert_eq!(redact("text", Some(""), None), "text");
+        // A user name inside the replacements is left alone
+        assert_eq!(
+            redact(r"C:\Users\user\x", Some(r"C:\Users\user"), Some("user")),
+            r"%USERPROFILE%\x"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Start-on-login registration.
+//!
+//! Megatile registers itself under the current user's `Run` key, which needs
+//! no elevation and is what the Startup apps page in Settings manages.
+
+use log::info;
+use std::path::Path;
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::{
+    HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ, RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW,
+};
+use windows::core::{PCWSTR, w};
+
+const RUN_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Run");
+const VALUE_NAME: PCWSTR = w!("Megatile");
+
+/// Builds the command line stored in the `Run` key.
+///
+/// The executable path is always quoted; flags are quoted only if they contain spaces.
+pub fn command_line(exe: &Path, flags: &[String]) -> String {
+    let mut command = format!("\"{}\"", exe.display());
+    for flag in flags {
+        command.push(' ');
+        if flag.contains(' ') {
+            command.push_str(&format!("\"{}\"", flag));
+        } else {
+            command.push_str(flag);
+        }
+    }
+    command
+}
+
+/// Returns true if Megatile is registered to start on login.
+pub fn is_enabled() -> bool {
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            None,
+        )
+    };
+    status == ERROR_SUCCESS
+}
+
+/// Registers the running executable to start on login with the given CLI flags.
+pub fn enable(flags: &[String]) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate megatile executable: {}", e))?;
+    let command = command_line(&exe, flags);
+    let data: Vec<u16> = command.encode_utf16().chain(std::iter::once(0)).collect();
+
+    let status = unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            RUN_KEY,
+            VALUE_NAME,
+            REG_SZ.0,
+            Some(data.as_ptr() as *const std::ffi::c_void),
+            (data.len() * std::mem::size_of::<u16>()) as u32,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(format!(
+            "Failed to write autostart entry: error {}",
+            status.0
+        ));
+    }
+
+    info!("Autostart enabled: {}", command);
+    Ok(())
+}
+
+/// Removes the start-on-login registration, if present.
+pub fn disable() -> Result<(), String> {
+    if !is_enabled() {
+        return Ok(());
+    }
+
+    let status = unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, RUN_KEY, VALUE_NAME) };
+    if status != ERROR_SUCCESS {
+        return Err(format!(
+            "Failed to remove autostart entry: error {}",
+            status.0
+        ));
+    }
+
+    info!("Autostart disabled");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_quotes_path_and_spaced_flags() {
+        let exe = Path::new(r"C:\Program Files\Megatile\megatile.exe");
+        let flags = vec!["-i".to_string(), "--send".to_string(), "a b".to_string()];
+        assert_eq!(
+            command_line(exe, &flags),
+            r#""C:\Program Files\Megatile\megatile.exe" -i --send "a b""#
+        );
+    }
+}
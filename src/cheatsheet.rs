@@ -0,0 +1,289 @@
+//! On-screen list of the registered keybindings.
+//!
+//! Toggled with `Alt + /`. The list comes from
+//! [`HotkeyManager::bindings`](crate::hotkeys::HotkeyManager::bindings), so it
+//! includes `[launch]` and `[stash]` keys from the config and leaves out any
+//! binding another application took. Long lists wrap into more columns.
+
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BeginPaint, CLEARTYPE_QUALITY, CLIP_DEFAULT_PRECIS, CreateFontW, CreateSolidBrush,
+    DEFAULT_CHARSET, DT_END_ELLIPSIS, DT_NOPREFIX, DT_SINGLELINE, DeleteObject, DrawTextW,
+    EndPaint, FW_NORMAL, FW_SEMIBOLD, FillRect, HFONT, InvalidateRect, OUT_DEFAULT_PRECIS,
+    PAINTSTRUCT, SelectObject, SetBkMode, SetTextColor, TRANSPARENT,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GWLP_USERDATA, GetClientRect,
+    GetWindowLongPtrW, HWND_TOPMOST, IDC_ARROW, IsWindowVisible, LoadCursorW, MA_NOACTIVATE,
+    RegisterClassW, SPI_GETWORKAREA, SW_HIDE, SWP_NOACTIVATE, SWP_SHOWWINDOW,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SetWindowLongPtrW, SetWindowPos, ShowWindow,
+    SystemParametersInfoW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_LBUTTONUP, WM_MOUSEACTIVATE,
+    WM_NCDESTROY, WM_PAINT, WNDCLASSW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+};
+use windows::core::{PCWSTR, w};
+
+const CHEATSHEET_CLASS_NAME: PCWSTR = w!("MegatileCheatsheet");
+const PADDING: i32 = 20;
+const HEADING_HEIGHT: i32 = 32;
+const ROW_HEIGHT: i32 = 22;
+const KEY_WIDTH: i32 = 150;
+const COLUMN_WIDTH: i32 = 380;
+const BACKGROUND_COLOR: u32 = 0x00202020;
+const KEY_COLOR: u32 = 0x00FFFFFF;
+const DESCRIPTION_COLOR: u32 = 0x00C8C8C8;
+
+/// Bindings and layout read by the window procedure while painting.
+struct CheatsheetState {
+    bindings: Vec<(String, String)>,
+    rows: usize,
+    heading_font: HFONT,
+    key_font: HFONT,
+    description_font: HFONT,
+}
+
+/// Owns the (initially hidden) cheatsheet window. Lives on the main thread.
+pub struct Cheatsheet {
+    hwnd: HWND,
+    state: Box<CheatsheetState>,
+}
+
+impl Cheatsheet {
+    /// Creates the hidden cheatsheet window.
+    pub fn new() -> Result<Self, String> {
+        let hinstance = unsafe {
+            GetModuleHandleW(None).map_err(|e| format!("Failed to get module handle: {}", e))
+        }?;
+
+        unsafe {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(cheatsheet_wnd_proc),
+                hInstance: hinstance.into(),
+                lpszClassName: CHEATSHEET_CLASS_NAME,
+                hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+                ..Default::default()
+            };
+            if RegisterClassW(&wc) == 0 {
+                return Err("Failed to register cheatsheet window class".to_string());
+            }
+        }
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE(WS_EX_TOPMOST.0 | WS_EX_TOOLWINDOW.0 | WS_EX_NOACTIVATE.0),
+                CHEATSHEET_CLASS_NAME,
+                w!(""),
+                WINDOW_STYLE(WS_POPUP.0),
+                0,
+                0,
+                COLUMN_WIDTH,
+                HEADING_HEIGHT,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )
+            .map_err(|e| format!("Failed to create cheatsheet window: {}", e))?
+        };
+
+        let mut state = Box::new(CheatsheetState {
+            bindings: Vec::new(),
+            rows: 0,
+            heading_font: create_font(20, FW_SEMIBOLD.0 as i32),
+            key_font: create_font(14, FW_SEMIBOLD.0 as i32),
+            description_font: create_font(14, FW_NORMAL.0 as i32),
+        });
+        unsafe {
+            SetWindowLongPtrW(
+                hwnd,
+                GWLP_USERDATA,
+                state.as_mut() as *mut CheatsheetState as isize,
+            );
+        }
+
+        Ok(Cheatsheet { hwnd, state })
+    }
+
+    /// Shows `bindings` (key, description) centered on the primary monitor,
+    /// or hides the cheatsheet if it is already up.
+    pub fn toggle(&mut self, bindings: Vec<(String, String)>) {
+        if unsafe { IsWindowVisible(self.hwnd).as_bool() } {
+            unsafe {
+                let _ = ShowWindow(self.hwnd, SW_HIDE);
+            }
+            return;
+        }
+
+        let mut work_area = RECT::default();
+        unsafe {
+            let _ = SystemParametersInfoW(
+                SPI_GETWORKAREA,
+                0,
+                Some(&mut work_area as *mut RECT as *mut std::ffi::c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            );
+        }
+        let available_height = work_area.bottom - work_area.top - 2 * PADDING - HEADING_HEIGHT;
+        let max_rows = (available_height / ROW_HEIGHT).max(1) as usize;
+        let (columns, rows) = grid_size(bindings.len(), max_rows);
+
+        let width = 2 * PADDING + columns as i32 * COLUMN_WIDTH;
+        let height = 2 * PADDING + HEADING_HEIGHT + rows as i32 * ROW_HEIGHT;
+        let x = work_area.left + (work_area.right - work_area.left - width) / 2;
+        let y = work_area.top + (work_area.bottom - work_area.top - height) / 2;
+
+        self.state.bindings = bindings;
+        self.state.rows = rows;
+        unsafe {
+            let _ = SetWindowPos(
+                self.hwnd,
+                Some(HWND_TOPMOST),
+                x,
+                y,
+                width,
+                height,
+                SWP_NOACTIVATE | SWP_SHOWWINDOW,
+            );
+            let _ = InvalidateRect(Some(self.hwnd), None, true);
+        }
+    }
+}
+
+impl Drop for Cheatsheet {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.hwnd);
+            let _ = DeleteObject(self.state.heading_font.into());
+            let _ = DeleteObject(self.state.key_font.into());
+            let _ = DeleteObject(self.state.description_font.into());
+        }
+    }
+}
+
+/// Returns (columns, rows) for `count` entries with at most `max_rows` per
+/// column, filling columns evenly.
+fn grid_size(count: usize, max_rows: usize) -> (usize, usize) {
+    let columns = count.div_ceil(max_rows).max(1);
+    (columns, count.div_ceil(columns))
+}
+
+fn create_font(size: i32, weight: i32) -> HFONT {
+    unsafe {
+        CreateFontW(
+            -size,
+            0,
+            0,
+            0,
+            weight,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY,
+            0,
+            w!("Segoe UI"),
+        )
+    }
+}
+
+extern "system" fn cheatsheet_wnd_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        match msg {
+            WM_PAINT => {
+                let state = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const CheatsheetState;
+                if !state.is_null() {
+                    paint(hwnd, &*state);
+                    return LRESULT(0);
+                }
+            }
+            // Click to dismiss, without taking focus from the user's window
+            WM_MOUSEACTIVATE => return LRESULT(MA_NOACTIVATE as isize),
+            WM_LBUTTONUP => {
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                return LRESULT(0);
+            }
+            WM_NCDESTROY => {
+                let _ = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+            }
+            _ => {}
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+}
+
+unsafe fn paint(hwnd: HWND, state: &CheatsheetState) {
+    unsafe {
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+
+        let background = CreateSolidBrush(COLORREF(BACKGROUND_COLOR));
+        FillRect(hdc, &client, background);
+        let _ = DeleteObject(background.into());
+
+        SetBkMode(hdc, TRANSPARENT);
+        let flags = DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX;
+
+        let old_font = SelectObject(hdc, state.heading_font.into());
+        SetTextColor(hdc, COLORREF(KEY_COLOR));
+        let mut heading_rect = RECT {
+            left: client.left + PADDING,
+            top: client.top + PADDING,
+            right: client.right - PADDING,
+            bottom: client.top + PADDING + HEADING_HEIGHT,
+        };
+        let mut heading: Vec<u16> = "Keybindings".encode_utf16().collect();
+        DrawTextW(hdc, &mut heading, &mut heading_rect, flags);
+
+        let rows = state.rows.max(1);
+        for (i, (keys, description)) in state.bindings.iter().enumerate() {
+            let left = client.left + PADDING + (i / rows) as i32 * COLUMN_WIDTH;
+            let top = heading_rect.bottom + (i % rows) as i32 * ROW_HEIGHT;
+
+            let mut key_rect = RECT {
+                left,
+                top,
+                right: left + KEY_WIDTH,
+                bottom: top + ROW_HEIGHT,
+            };
+            SelectObject(hdc, state.key_font.into());
+            SetTextColor(hdc, COLORREF(KEY_COLOR));
+            let mut key_text: Vec<u16> = keys.encode_utf16().collect();
+            DrawTextW(hdc, &mut key_text, &mut key_rect, flags);
+
+            let mut description_rect = RECT {
+                left: left + KEY_WIDTH,
+                right: left + COLUMN_WIDTH - PADDING,
+                ..key_rect
+            };
+            SelectObject(hdc, state.description_font.into());
+            SetTextColor(hdc, COLORREF(DESCRIPTION_COLOR));
+            let mut description_text: Vec<u16> = description.encode_utf16().collect();
+            DrawTextW(hdc, &mut description_text, &mut description_rect, flags);
+        }
+
+        SelectObject(hdc, old_font);
+        let _ = EndPaint(hwnd, &ps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lists_wrap_into_even_columns() {
+        assert_eq!(grid_size(10, 30), (1, 10));
+        assert_eq!(grid_size(45, 30), (2, 23));
+        assert_eq!(grid_size(0, 30), (1, 0));
+    }
+}
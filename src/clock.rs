@@ -0,0 +1,238 @@
+//! Status bar clock text.
+//!
+//! The clock is formatted with a strftime-style string from
+//! `[statusbar.clock]`, and can show the time in a second time zone after
+//! the local one, e.g. `14:05 03/11  NYC 09:05`.
+
+use crate::config::ClockConfig;
+use windows::Win32::Foundation::SYSTEMTIME;
+use windows::Win32::System::SystemInformation::{GetLocalTime, GetSystemTime};
+use windows::Win32::System::Time::{
+    DYNAMIC_TIME_ZONE_INFORMATION, EnumDynamicTimeZoneInformation,
+    SystemTimeToTzSpecificLocalTimeEx,
+};
+
+/// The specifiers [`format_time`] understands, after the `%`.
+const SPECIFIERS: &str = "HIMSpdemyYaAbBj%";
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Checks `format` for specifiers [`format_time`] doesn't know.
+pub fn validate_format(format: &str) -> Result<(), String> {
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some(specifier) if SPECIFIERS.contains(specifier) => {}
+            Some(specifier) => {
+                return Err(format!(
+                    "unknown clock specifier %{} in {:?}",
+                    specifier, format
+                ));
+            }
+            None => return Err(format!("clock format {:?} ends with %", format)),
+        }
+    }
+    Ok(())
+}
+
+/// Formats `time` with strftime-style specifiers:
+///
+/// | Specifier | Example |
+/// |---|---|
+/// | `%H` / `%I` | hour, 24-hour `14` / 12-hour `02` |
+/// | `%M` / `%S` | minute / second, `05` |
+/// | `%p` | `AM` or `PM` |
+/// | `%d` / `%e` | day of the month, `03` / ` 3` |
+/// | `%m` | month, `11` |
+/// | `%y` / `%Y` | year, `25` / `2025` |
+/// | `%a` / `%A` | weekday, `Mon` / `Monday` |
+/// | `%b` / `%B` | month name, `Nov` / `November` |
+/// | `%j` | day of the year, `307` |
+/// | `%%` | `%` |
+pub fn format_time(format: &str, time: &SYSTEMTIME) -> String {
+    let weekday = WEEKDAYS[time.wDayOfWeek as usize % 7];
+    let month = MONTHS[(time.wMonth as usize).clamp(1, 12) - 1];
+    let mut text = String::with_capacity(format.len() + 8);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('H') => text.push_str(&format!("{:02}", time.wHour)),
+            Some('I') => text.push_str(&format!("{:02}", (time.wHour + 11) % 12 + 1)),
+            Some('M') => text.push_str(&format!("{:02}", time.wMinute)),
+            Some('S') => text.push_str(&format!("{:02}", time.wSecond)),
+            Some('p') => text.push_str(if time.wHour < 12 { "AM" } else { "PM" }),
+            Some('d') => text.push_str(&format!("{:02}", time.wDay)),
+            Some('e') => text.push_str(&format!("{:2}", time.wDay)),
+            Some('m') => text.push_str(&format!("{:02}", time.wMonth)),
+            Some('y') => text.push_str(&format!("{:02}", time.wYear % 100)),
+            Some('Y') => text.push_str(&time.wYear.to_string()),
+            Some('a') => text.push_str(&weekday[..3]),
+            Some('A') => text.push_str(weekday),
+            Some('b') => text.push_str(&month[..3]),
+            Some('B') => text.push_str(month),
+            Some('j') => text.push_str(&format!("{:03}", day_of_year(time))),
+            Some('%') => text.push('%'),
+            Some(other) => {
+                // Rejected by validate_format; shown as written
+                text.push('%');
+                text.push(other);
+            }
+            None => text.push('%'),
+        }
+    }
+    text
+}
+
+fn day_of_year(time: &SYSTEMTIME) -> u16 {
+    const DAYS_BEFORE: [u16; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let year = time.wYear as u32;
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let month = (time.wMonth as usize).clamp(1, 12);
+    DAYS_BEFORE[month - 1] + time.wDay + u16::from(leap && month > 2)
+}
+
+/// A time zone from the Windows time zone list.
+#[derive(Debug, Clone)]
+pub struct TimeZone {
+    info: DYNAMIC_TIME_ZONE_INFORMATION,
+}
+
+impl TimeZone {
+    /// Looks up a zone by its Windows name, as `tzutil /l` lists them, e.g.
+    /// `"Eastern Standard Time"`.
+    pub fn find(name: &str) -> Result<Self, String> {
+        for index in 0.. {
+            let mut info = DYNAMIC_TIME_ZONE_INFORMATION::default();
+            if unsafe { EnumDynamicTimeZoneInformation(index, &mut info) } != 0 {
+                break;
+            }
+            let key = &info.TimeZoneKeyName;
+            let len = key.iter().position(|&c| c == 0).unwrap_or(key.len());
+            if String::from_utf16_lossy(&key[..len]).eq_ignore_ascii_case(name) {
+                return Ok(TimeZone { info });
+            }
+        }
+        Err(format!("Unknown time zone {:?}", name))
+    }
+
+    /// Returns the current time in this zone.
+    pub fn now(&self) -> Option<SYSTEMTIME> {
+        let mut local = SYSTEMTIME::default();
+        unsafe {
+            let utc = GetSystemTime();
+            SystemTimeToTzSpecificLocalTimeEx(Some(&self.info), &utc, &mut local).ok()?;
+        }
+        Some(local)
+    }
+}
+
+/// Produces the clock text from `[statusbar.clock]`.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    format: String,
+    /// The extra zone with its label and format.
+    extra: Option<(TimeZone, String, String)>,
+}
+
+impl Clock {
+    /// Creates the clock. Fails if the extra time zone doesn't exist.
+    pub fn new(config: &ClockConfig) -> Result<Self, String> {
+        let extra = match &config.timezone {
+            Some(name) => Some((
+                TimeZone::find(name)?,
+                config.timezone_label.clone().unwrap_or_default(),
+                config.timezone_format.clone(),
+            )),
+            None => None,
+        };
+        Ok(Clock {
+            format: config.format.clone(),
+            extra,
+        })
+    }
+
+    /// Returns the clock text for now.
+    pub fn text(&self) -> String {
+        let mut text = format_time(&self.format, &unsafe { GetLocalTime() });
+        if let Some((zone, label, format)) = &self.extra
+            && let Some(time) = zone.now()
+        {
+            text.push_str("  ");
+            if !label.is_empty() {
+                text.push_str(label);
+                text.push(' ');
+            }
+            text.push_str(&format_time(format, &time));
+        }
+        text
+    }
+}
+
+impl Default for Clock {
+    /// The clock as it looks without a `[statusbar.clock]` section.
+    fn default() -> Self {
+        Clock {
+            format: ClockConfig::default().format,
+            extra: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_strftime_specifiers() {
+        let time = SYSTEMTIME {
+            wYear: 2024,
+            wMonth: 3,
+            wDayOfWeek: 5,
+            wDay: 1,
+            wHour: 0,
+            wMinute: 7,
+            wSecond: 9,
+            wMilliseconds: 0,
+        };
+        assert_eq!(format_time("%H:%M %d/%m", &time), "00:07 01/03");
+        assert_eq!(format_time("%I:%M:%S %p", &time), "12:07:09 AM");
+        assert_eq!(
+            format_time("%a %e %b %Y, day %j", &time),
+            "Fri  1 Mar 2024, day 061"
+        );
+        assert_eq!(format_time("%A %B %y 100%%", &time), "Friday March 24 100%");
+
+        assert!(validate_format("%H:%M:%S").is_ok());
+        assert!(validate_format("%Q").is_err());
+        assert!(validate_format("%H %").is_err());
+    }
+}
@@ -0,0 +1,1545 @@
+//! User configuration loaded from `~/.megatile/config.toml`.
+//!
+//! The file is optional; a missing file or section means default behavior.
+//! Every section uses `#[serde(default)]` so partial configs stay valid.
+
+use crate::hooks::Hook;
+use crate::hotkeys::parse_hotkey;
+use crate::layouts::LayoutTemplate;
+use crate::rules::WindowRule;
+use crate::stash::MAX_STASH_SLOTS;
+use crate::tiling::{DEFAULT_GAP, DEFAULT_PADDING, TileLayout};
+use crate::workspace::{DEFAULT_WORKSPACES, MAX_WORKSPACES};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Largest gap or padding a workspace may set, in pixels.
+const MAX_SPACING: i32 = 400;
+/// Longest a `[[quirks]]` entry may hold a new window back, in milliseconds.
+const MAX_MANAGE_DELAY_MS: u64 = 10_000;
+
+/// Top-level configuration file contents.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Commands launched once when Megatile starts.
+    pub autostart: AutostartConfig,
+    /// Built-in hotkey settings.
+    pub hotkeys: HotkeysConfig,
+    /// Hotkeys that launch programs.
+    pub launch: LaunchConfig,
+    /// Where new windows are placed, by process, class or title.
+    pub rules: Vec<WindowRule>,
+    /// Layouts workspaces adopt as their first windows arrive.
+    pub templates: Vec<LayoutTemplate>,
+    /// Workarounds for programs that misbehave when tiled.
+    pub quirks: Vec<AppQuirks>,
+    /// Layout tweaks.
+    pub layout: LayoutConfig,
+    /// Per-monitor settings.
+    pub monitors: Vec<MonitorConfig>,
+    /// Window chrome tweaks.
+    pub decorations: DecorationsConfig,
+    /// Stash slot hotkeys.
+    pub stash: StashConfig,
+    /// Workspace settings.
+    pub workspaces: WorkspacesConfig,
+    /// Focus behavior.
+    pub focus: FocusConfig,
+    /// Event handler script (needs the `scripting` build feature).
+    pub scripting: ScriptingConfig,
+    /// Shell commands run on events.
+    pub hooks: Vec<Hook>,
+    /// Localhost HTTP endpoint (needs the `http` build feature).
+    pub http: HttpConfig,
+    /// Release checks (needs the `updates` build feature).
+    pub updates: UpdatesConfig,
+    /// Status bar settings.
+    pub statusbar: StatusbarConfig,
+    /// Tray menu settings.
+    pub tray: TrayConfig,
+    /// Battery saving.
+    pub power: PowerConfig,
+}
+
+/// The `[statusbar]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusbarConfig {
+    /// Show the bar at startup. `Alt+B` still toggles it.
+    pub visible: bool,
+    /// Leave the built-in bar out for a third-party one.
+    pub external: bool,
+    /// Show a button per window on the active workspace, like the taskbar.
+    pub window_list: bool,
+    /// Show what's playing and the output volume.
+    pub media: bool,
+    /// Hide the bar when the pointer isn't on it, giving its space to windows.
+    pub auto_hide: bool,
+    /// How long the pointer must be away before the bar hides.
+    pub auto_hide_delay_ms: u64,
+    /// How the clock is shown.
+    pub clock: ClockConfig,
+    /// Space kept clear on every monitor for the third-party bar.
+    pub reserve: ReservedEdges,
+    /// Which edge of the monitor the built-in bar sits on.
+    pub position: BarPosition,
+}
+
+impl Default for StatusbarConfig {
+    fn default() -> Self {
+        StatusbarConfig {
+            visible: true,
+            external: false,
+            window_list: false,
+            media: false,
+            auto_hide: false,
+            auto_hide_delay_ms: 1500,
+            clock: ClockConfig::default(),
+            reserve: ReservedEdges::default(),
+            position: BarPosition::default(),
+        }
+    }
+}
+
+/// The `[statusbar.clock]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+    /// strftime-style format of the local time.
+    pub format: String,
+    /// Windows name of a second time zone to show, e.g. `"Pacific Standard Time"`.
+    pub timezone: Option<String>,
+    /// Shown before the second zone's time.
+    pub timezone_label: Option<String>,
+    /// Format of the second zone's time.
+    pub timezone_format: String,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        ClockConfig {
+            format: "%H:%M %d/%m".to_string(),
+            timezone: None,
+            timezone_label: None,
+            timezone_format: "%H:%M".to_string(),
+        }
+    }
+}
+
+/// A `[[quirks]]` entry: workarounds for one program.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppQuirks {
+    /// Process name, e.g. `"idea64.exe"`.
+    pub process: String,
+    /// Move the app's windows into their tiles without resizing them, for
+    /// launchers that break when resized.
+    pub no_resize: bool,
+    /// Wait this long before managing a new window, for apps that show a
+    /// splash screen first and then replace it.
+    pub manage_delay_ms: u64,
+    /// Set the size a second time, for apps that undo the first resize.
+    pub resize_twice: bool,
+    /// Leave windows that open maximized that way, floating, instead of
+    /// restoring them before they are tiled.
+    pub keep_maximized: bool,
+}
+
+/// Returns the `[[quirks]]` entry for `process`, ignoring case.
+pub fn quirks_for<'a>(quirks: &'a [AppQuirks], process: Option<&str>) -> Option<&'a AppQuirks> {
+    let process = process?;
+    quirks
+        .iter()
+        .find(|quirks| quirks.process.eq_ignore_ascii_case(process))
+}
+
+/// The `[tray]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrayConfig {
+    /// Ask before exiting from the tray menu.
+    pub confirm_exit: bool,
+    /// `.ico` shown while managing windows, instead of the built-in icon.
+    pub icon: Option<PathBuf>,
+    /// `.ico` shown while every display is paused.
+    pub paused_icon: Option<PathBuf>,
+    /// `.ico` shown after an error was reported.
+    pub error_icon: Option<PathBuf>,
+    /// What clicking the tray icon does.
+    pub click: TrayClickAction,
+    /// What double-clicking the tray icon does.
+    pub double_click: TrayClickAction,
+}
+
+/// Something clicking the tray icon can do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayClickAction {
+    /// Nothing.
+    None,
+    /// Open the tray menu, as right-clicking does.
+    #[default]
+    Menu,
+    /// Show or hide the keybindings overlay.
+    Cheatsheet,
+    /// Pause every display, or resume them all if they are paused.
+    Pause,
+    /// Show or hide the status bar.
+    Statusbar,
+    /// Open the rules editor.
+    Settings,
+    /// Open the About window.
+    About,
+}
+
+/// The `[hotkeys]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeysConfig {
+    /// Key held for the built-in hotkeys, in place of `Alt`.
+    pub modifier: HotkeyModifier,
+}
+
+/// The key the built-in hotkeys are held with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyModifier {
+    #[default]
+    Alt,
+    /// The Windows key. Combinations Windows reserves, such as `Win + L`,
+    /// stay with Windows.
+    Win,
+}
+
+/// The `[power]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerConfig {
+    /// When to cut back on periodic work and window dimming.
+    pub saving: PowerSaving,
+}
+
+/// When power saving is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSaving {
+    /// On battery or with battery saver on.
+    #[default]
+    Auto,
+    /// All the time.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// The `[updates]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpdatesConfig {
+    /// Look for a newer release on GitHub at startup.
+    pub check: bool,
+}
+
+/// The `[http]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Port on 127.0.0.1 to serve commands on. Unset leaves the endpoint off.
+    pub port: Option<u16>,
+    /// Secret clients send as `Authorization: Bearer <token>`. Required with `port`.
+    pub token: String,
+}
+
+/// The `[scripting]` section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptingConfig {
+    /// Rhai script defining `on_<event>` handlers.
+    pub script: Option<PathBuf>,
+}
+
+/// The `[focus]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FocusConfig {
+    /// Whether newly created windows take focus.
+    pub new_windows: NewWindowFocus,
+    /// Let `Alt+Arrows` continue onto the next monitor in that direction.
+    pub cross_monitor: bool,
+    /// At the last window in a direction, wrap around to the far side.
+    pub wrap: bool,
+    /// Keep focus on a window moved to another monitor with `Alt+Ctrl+Arrows`.
+    pub follow_to_monitor: bool,
+    /// Also move the mouse cursor to the middle of the moved window.
+    pub cursor_follows: bool,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        FocusConfig {
+            new_windows: NewWindowFocus::default(),
+            cross_monitor: true,
+            wrap: false,
+            follow_to_monitor: true,
+            cursor_follows: false,
+        }
+    }
+}
+
+/// Whether a newly created window takes focus.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewWindowFocus {
+    /// Always, switching to the window's workspace if it opens elsewhere.
+    Always,
+    /// Never; focus goes back to the window that had it.
+    Never,
+    /// Only when the window opens on the active workspace.
+    #[default]
+    Active,
+}
+
+/// The `[workspaces]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    /// Number of workspaces per monitor, up to 20. Workspace 10 is reached
+    /// with `Alt+0`; 11-20 have no hotkeys and are reached over IPC.
+    pub count: u8,
+    /// Workspaces locked at startup; new windows skip them.
+    pub locked: Vec<u8>,
+    /// Switch to the workspace a window is sent to with `Alt+Shift+E`.
+    pub follow_to_empty: bool,
+    /// Skip workspaces without windows when cycling with `Alt+PageUp/PageDown`.
+    pub cycle_skips_empty: bool,
+    /// Only offer the workspaces in use plus one empty one, removing empty
+    /// workspaces between them. `count` becomes the upper limit. Overrides,
+    /// templates and rules still name workspaces by number, so they follow
+    /// the number, not the workspace, when the ones before it collapse.
+    pub dynamic: bool,
+    /// Per-workspace settings, as `[[workspaces.overrides]]` entries.
+    pub overrides: Vec<WorkspaceOverride>,
+    /// Process names whose windows are moved off-screen instead of hidden
+    /// on inactive workspaces, for apps that stop rendering or leave Alt+Tab.
+    pub offscreen_processes: Vec<String>,
+    /// Where windows that are already open when Megatile starts go.
+    pub startup: StartupPlacement,
+    /// With `startup = "monitor"`, how many windows each workspace takes
+    /// before the next one is used.
+    pub startup_per_workspace: usize,
+}
+
+/// Where windows that are already open at startup are placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupPlacement {
+    /// All on workspace 1.
+    #[default]
+    First,
+    /// On their `[[rules]]` workspace, the rest on workspace 1.
+    Rules,
+    /// On workspace 1, floating where they are until adopted with `Alt+Shift+A`.
+    Float,
+    /// On their `[[rules]]` workspace, the rest spread over each monitor's
+    /// unlocked workspaces, `startup_per_workspace` at a time.
+    Monitor,
+}
+
+/// How windows on inactive workspaces are kept out of sight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HideMethod {
+    /// Hide the window and take it off the taskbar.
+    #[default]
+    Hide,
+    /// Move the window off-screen; it keeps rendering and stays on the taskbar.
+    Offscreen,
+}
+
+impl WorkspacesConfig {
+    /// Hiding methods by lowercase process name, for apps that don't use
+    /// the default.
+    pub fn hide_methods(&self) -> HashMap<String, HideMethod> {
+        self.offscreen_processes
+            .iter()
+            .map(|process| (process.to_lowercase(), HideMethod::Offscreen))
+            .collect()
+    }
+
+    /// Returns the workspace for each window open at startup, given as its
+    /// monitor and the workspace a rule sends it to, if any.
+    pub fn startup_workspaces(&self, windows: &[(usize, Option<u8>)]) -> Vec<u8> {
+        let unlocked: Vec<u8> = (1..=self.count)
+            .filter(|workspace| !self.locked.contains(workspace))
+            .collect();
+        let mut placed_per_monitor: HashMap<usize, usize> = HashMap::new();
+        windows
+            .iter()
+            .map(|&(monitor, rule_workspace)| match self.startup {
+                StartupPlacement::First | StartupPlacement::Float => 1,
+                StartupPlacement::Rules => rule_workspace.unwrap_or(1),
+                StartupPlacement::Monitor => rule_workspace.unwrap_or_else(|| {
+                    let placed = placed_per_monitor.entry(monitor).or_default();
+                    let index = *placed / self.startup_per_workspace.max(1);
+                    *placed += 1;
+                    unlocked
+                        .get(index)
+                        .or(unlocked.last())
+                        .copied()
+                        .unwrap_or(1)
+                }),
+            })
+            .collect()
+    }
+}
+
+/// A `[[workspaces.overrides]]` entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceOverride {
+    /// Workspace the settings apply to.
+    pub workspace: u8,
+    /// Pixels between tiles, instead of `[layout] gap`.
+    pub gap: Option<i32>,
+    /// Pixels between the tiles and the monitor edges, instead of `[layout] padding`.
+    pub padding: Option<i32>,
+    /// Don't tile windows here; they float where they are put.
+    pub floating: bool,
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        WorkspacesConfig {
+            count: DEFAULT_WORKSPACES,
+            locked: Vec::new(),
+            follow_to_empty: true,
+            cycle_skips_empty: true,
+            dynamic: false,
+            overrides: Vec::new(),
+            offscreen_processes: Vec::new(),
+            startup: StartupPlacement::First,
+            startup_per_workspace: 4,
+        }
+    }
+}
+
+/// The `[stash]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StashConfig {
+    /// One hotkey per slot; it summons the slot's window or parks it again.
+    pub slots: Vec<String>,
+}
+
+impl Default for StashConfig {
+    fn default() -> Self {
+        StashConfig {
+            slots: vec!["Alt+S".to_string()],
+        }
+    }
+}
+
+/// The `[decorations]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DecorationsConfig {
+    /// Remove the title bar and resize frame of tiled windows.
+    pub hide_title_bars: bool,
+    /// Process names (e.g. `"Code.exe"`) that keep their title bars.
+    pub title_bar_exclusions: Vec<String>,
+    /// Corners of tiled windows. Square by default so gaps line up.
+    pub corners: CornerPreference,
+    /// Corners of floating and fullscreen windows.
+    pub floating_corners: CornerPreference,
+    /// Opacity of unfocused windows, 0-255. 255 turns transparency off.
+    pub unfocused_alpha: u8,
+    /// Process names that always stay opaque (e.g. video players).
+    pub transparency_exclusions: Vec<String>,
+    /// Border colors for some apps in place of the accent color. The first
+    /// matching entry wins.
+    pub borders: Vec<BorderRule>,
+}
+
+/// A `[[decorations.borders]]` entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BorderRule {
+    /// Process name, e.g. `"WindowsTerminal.exe"`. Unset matches every app.
+    pub process: Option<String>,
+    /// Only match apps running as administrator.
+    pub elevated: bool,
+    /// `"#RRGGBB"`.
+    pub color: String,
+    /// Keep the color on the window while it's unfocused, too.
+    pub unfocused: bool,
+}
+
+impl BorderRule {
+    /// Returns the color as 0x00BBGGRR.
+    pub fn colorref(&self) -> Option<u32> {
+        parse_color(&self.color).ok()
+    }
+}
+
+/// Parses a `"#RRGGBB"` color into 0x00BBGGRR, the way Windows takes it.
+pub fn parse_color(text: &str) -> Result<u32, String> {
+    let hex = text
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| format!("color {:?} is not #RRGGBB", text))?;
+    let rgb =
+        u32::from_str_radix(hex, 16).map_err(|_| format!("color {:?} is not #RRGGBB", text))?;
+    let (r, g, b) = (rgb >> 16, (rgb >> 8) & 0xFF, rgb & 0xFF);
+    Ok(b << 16 | g << 8 | r)
+}
+
+impl Default for DecorationsConfig {
+    fn default() -> Self {
+        DecorationsConfig {
+            hide_title_bars: false,
+            title_bar_exclusions: Vec::new(),
+            corners: CornerPreference::Square,
+            floating_corners: CornerPreference::Default,
+            unfocused_alpha: 245,
+            transparency_exclusions: Vec::new(),
+            borders: Vec::new(),
+        }
+    }
+}
+
+/// How Windows 11 rounds a window's corners.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CornerPreference {
+    /// Whatever the system or the app picks.
+    #[default]
+    Default,
+    Round,
+    Small,
+    Square,
+}
+
+impl DecorationsConfig {
+    /// Returns true if tiled windows of `process` should lose their title bar.
+    pub fn hides_title_bar(&self, process: Option<&str>) -> bool {
+        self.hide_title_bars && !is_listed(&self.title_bar_exclusions, process)
+    }
+
+    /// Opacity for an unfocused window of `process`.
+    pub fn unfocused_alpha_for(&self, process: Option<&str>) -> u8 {
+        if is_listed(&self.transparency_exclusions, process) {
+            255
+        } else {
+            self.unfocused_alpha
+        }
+    }
+
+    /// Returns the first `borders` entry for a window of `process`, which
+    /// runs as administrator if `elevated` is set.
+    pub fn border_for(&self, process: Option<&str>, elevated: bool) -> Option<&BorderRule> {
+        self.borders.iter().find(|rule| {
+            let process_matches = match &rule.process {
+                Some(name) => process.is_some_and(|process| name.eq_ignore_ascii_case(process)),
+                None => true,
+            };
+            process_matches && (elevated || !rule.elevated)
+        })
+    }
+
+    /// Returns true if a `borders` entry depends on whether apps run elevated.
+    pub fn borders_need_elevation(&self) -> bool {
+        self.borders.iter().any(|rule| rule.elevated)
+    }
+}
+
+/// Returns true if `process` is in a list of process names, ignoring case.
+fn is_listed(processes: &[String], process: Option<&str>) -> bool {
+    process.is_some_and(|process| {
+        processes
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(process))
+    })
+}
+
+/// A `[[monitors]]` entry.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Device name (e.g. `\\.\DISPLAY2`, as logged at startup). Entries
+    /// without one apply to every monitor not matched by name.
+    pub device: Option<String>,
+    /// Number of side-by-side columns the monitor is tiled as, each acting
+    /// as its own monitor for tiling and directional focus.
+    pub zones: u8,
+    /// `"dwindle"` or `"stack"`. Defaults to stack on portrait monitors.
+    pub layout: Option<TileLayout>,
+    /// Leave the monitor's windows alone, e.g. for a TV used for media.
+    pub unmanaged: bool,
+    /// Space along the monitor edges kept free of tiles, e.g. for a widget
+    /// or another bar.
+    pub reserve: ReservedEdges,
+    /// Treat the monitor as the primary one, whichever display Windows calls
+    /// primary. Needs `device`.
+    pub primary: bool,
+    /// Take the windows of a monitor that is unplugged, instead of the
+    /// primary monitor. Needs `device`.
+    pub fallback: bool,
+    /// Give the monitor a built-in status bar. Defaults to the primary only.
+    pub bar: Option<bool>,
+    /// What the bar shows besides the workspace dots. Defaults to the window
+    /// list and media as `[statusbar]` turns them on, and the clock.
+    pub bar_modules: Option<Vec<BarModule>>,
+    /// Where along the top edge the bar sits.
+    pub bar_align: BarAlign,
+}
+
+/// A part of the status bar that can be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarModule {
+    /// A button per window on the active workspace.
+    Windows,
+    /// What's playing and the output volume.
+    Media,
+    /// The clock, with the stash next to it.
+    Clock,
+}
+
+/// Where the status bar sits along the top edge of its monitor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Which edge of its monitor the status bar sits on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+/// A built-in status bar as configured for one monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarPlacement {
+    pub modules: Vec<BarModule>,
+    pub align: BarAlign,
+}
+
+/// Pixels kept clear at each edge of a monitor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReservedEdges {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        MonitorConfig {
+            device: None,
+            zones: 1,
+            layout: None,
+            unmanaged: false,
+            reserve: ReservedEdges::default(),
+            primary: false,
+            fallback: false,
+            bar: None,
+            bar_modules: None,
+            bar_align: BarAlign::default(),
+        }
+    }
+}
+
+/// Returns the settings for the monitor with `device_name`, if any.
+pub fn monitor_config<'a>(
+    monitors: &'a [MonitorConfig],
+    device_name: &str,
+) -> Option<&'a MonitorConfig> {
+    monitors
+        .iter()
+        .find(|m| {
+            m.device
+                .as_deref()
+                .is_some_and(|device| device.eq_ignore_ascii_case(device_name))
+        })
+        .or_else(|| monitors.iter().find(|m| m.device.is_none()))
+}
+
+/// Returns whether the display with `device_name` is the primary one: the
+/// one a `[[monitors]]` entry marks `primary`, or else the one Windows calls
+/// primary (`windows_primary`). The `[[monitors]]` entry only counts while
+/// its display is among the `connected` ones.
+pub fn is_primary_display(
+    monitors: &[MonitorConfig],
+    device_name: &str,
+    windows_primary: bool,
+    connected: &[String],
+) -> bool {
+    let configured = monitors
+        .iter()
+        .filter(|m| m.primary)
+        .find_map(|m| m.device.as_deref())
+        .filter(|device| {
+            connected
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(device))
+        });
+    match configured {
+        Some(device) => device.eq_ignore_ascii_case(device_name),
+        None => windows_primary,
+    }
+}
+
+/// The `[layout]` section.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Pixels between tiles. `[[workspaces.overrides]]` can change it per workspace.
+    pub gap: i32,
+    /// Pixels between the tiles and the monitor edges.
+    pub padding: i32,
+    /// Width of the centered window in zen mode, as a share of the work area.
+    pub zen_width: f32,
+    /// Keep fullscreen windows above all others (`HWND_TOPMOST`). When false
+    /// they are only raised, so popups and dialogs can still show over them.
+    pub fullscreen_topmost: bool,
+    /// Pixels a tiled window must move by before it counts as moved by the user.
+    pub move_threshold: i32,
+    /// Minimum milliseconds between checks for windows moved by the user.
+    pub move_debounce_ms: u64,
+    /// Only react to windows the user dragged, once they are dropped,
+    /// instead of to every location change.
+    pub retile_on_drop: bool,
+    /// Manage dialogs and small popups as floating windows centered on their
+    /// monitor, instead of leaving them wherever they open.
+    pub float_transient: bool,
+    /// Move dialogs over the window that opened them, on its monitor.
+    pub center_dialogs: bool,
+    /// Which monitor new windows are tiled on.
+    pub new_window_monitor: NewWindowMonitor,
+}
+
+/// Which monitor a new window is tiled on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewWindowMonitor {
+    /// The one the window opened on.
+    #[default]
+    Window,
+    /// The one under the mouse cursor.
+    Cursor,
+    /// The one with the focused window.
+    Focused,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            gap: DEFAULT_GAP,
+            padding: DEFAULT_PADDING,
+            zen_width: 0.6,
+            fullscreen_topmost: true,
+            move_threshold: 50,
+            move_debounce_ms: 50,
+            retile_on_drop: false,
+            float_transient: false,
+            center_dialogs: false,
+            new_window_monitor: NewWindowMonitor::Window,
+        }
+    }
+}
+
+/// The `[autostart]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutostartConfig {
+    pub commands: Vec<LaunchCommand>,
+}
+
+/// The `[launch]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchConfig {
+    pub bindings: Vec<LaunchBinding>,
+}
+
+/// A hotkey that launches a command, e.g. `keys = "Alt+Enter"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchBinding {
+    /// Modifiers and key joined by `+`, see [`parse_hotkey`].
+    pub keys: String,
+    pub command: String,
+    pub workspace: Option<u8>,
+    pub process: Option<String>,
+}
+
+impl LaunchBinding {
+    /// The command this binding launches.
+    pub fn launch_command(&self) -> LaunchCommand {
+        LaunchCommand {
+            command: self.command.clone(),
+            workspace: self.workspace,
+            process: self.process.clone(),
+        }
+    }
+}
+
+/// A command to launch, optionally routing its window to a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchCommand {
+    /// Program and arguments, e.g. `"wt.exe -d C:\\src"`.
+    pub command: String,
+    /// Workspace the first window of the launched program is placed on.
+    pub workspace: Option<u8>,
+    /// Process that owns the window, when it differs from the program
+    /// launched (e.g. `wt.exe` opens `WindowsTerminal.exe`).
+    pub process: Option<String>,
+}
+
+impl LaunchCommand {
+    fn validate(&self, workspace_count: u8) -> Result<(), String> {
+        if self.command.trim().is_empty() {
+            return Err("launch command must not be empty".to_string());
+        }
+        if let Some(workspace) = self.workspace
+            && !(1..=workspace_count).contains(&workspace)
+        {
+            return Err(format!(
+                "workspace {} for '{}' is not between 1 and {}",
+                workspace, self.command, workspace_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Parses and validates a config file's contents.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(text).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config file, falling back to defaults if it doesn't exist.
+    pub fn load() -> Result<Self, String> {
+        let path = config_path()?;
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Returns the built-in status bar for the monitor with `device_name`,
+    /// or `None` if it doesn't get one. `windows_primary` tells whether
+    /// Windows calls the monitor primary, and `connected` lists the device
+    /// names of the displays plugged in.
+    pub fn bar_placement(
+        &self,
+        device_name: &str,
+        windows_primary: bool,
+        connected: &[String],
+    ) -> Option<BarPlacement> {
+        let monitor = monitor_config(&self.monitors, device_name);
+        let primary = is_primary_display(&self.monitors, device_name, windows_primary, connected);
+        if !monitor.and_then(|m| m.bar).unwrap_or(primary) {
+            return None;
+        }
+        let modules = match monitor.and_then(|m| m.bar_modules.clone()) {
+            Some(modules) => modules,
+            None => [
+                (BarModule::Windows, self.statusbar.window_list),
+                (BarModule::Media, self.statusbar.media),
+                (BarModule::Clock, true),
+            ]
+            .into_iter()
+            .filter_map(|(module, shown)| shown.then_some(module))
+            .collect(),
+        };
+        Some(BarPlacement {
+            modules,
+            align: monitor.map(|m| m.bar_align).unwrap_or_default(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let count = self.workspaces.count;
+        if !(1..=MAX_WORKSPACES).contains(&count) {
+            return Err(format!(
+                "workspaces: count {} is not between 1 and {}",
+                count, MAX_WORKSPACES
+            ));
+        }
+        for entry in &self.autostart.commands {
+            entry
+                .validate(count)
+                .map_err(|e| format!("autostart: {}", e))?;
+        }
+        for binding in &self.launch.bindings {
+            parse_hotkey(&binding.keys).map_err(|e| format!("launch: {}", e))?;
+            binding
+                .launch_command()
+                .validate(count)
+                .map_err(|e| format!("launch: {}", e))?;
+        }
+        for rule in &self.rules {
+            rule.validate(count).map_err(|e| format!("rules: {}", e))?;
+        }
+        for (i, template) in self.templates.iter().enumerate() {
+            template
+                .validate(count)
+                .map_err(|e| format!("templates: {}", e))?;
+            if self.templates[..i]
+                .iter()
+                .any(|t| t.workspace == template.workspace)
+            {
+                return Err(format!(
+                    "templates: workspace {} has more than one template",
+                    template.workspace
+                ));
+            }
+        }
+        for hook in &self.hooks {
+            hook.validate().map_err(|e| format!("hooks: {}", e))?;
+        }
+        for quirks in &self.quirks {
+            if quirks.process.trim().is_empty() {
+                return Err("quirks: process must not be empty".to_string());
+            }
+            if quirks.manage_delay_ms > MAX_MANAGE_DELAY_MS {
+                return Err(format!(
+                    "quirks: manage_delay_ms for {} is more than {}",
+                    quirks.process, MAX_MANAGE_DELAY_MS
+                ));
+            }
+        }
+        if let Some(port) = self.http.port {
+            if port == 0 {
+                return Err("http: port must not be 0".to_string());
+            }
+            if self.http.token.trim().is_empty() {
+                return Err("http: a token is required when port is set".to_string());
+            }
+        }
+        for &workspace in &self.workspaces.locked {
+            if !(1..=count).contains(&workspace) {
+                return Err(format!(
+                    "workspaces: locked workspace {} is not between 1 and {}",
+                    workspace, count
+                ));
+            }
+        }
+        if self.workspaces.startup_per_workspace == 0 {
+            return Err("workspaces: startup_per_workspace must be at least 1".to_string());
+        }
+        for entry in &self.workspaces.overrides {
+            if !(1..=count).contains(&entry.workspace) {
+                return Err(format!(
+                    "workspaces: override for workspace {} is not between 1 and {}",
+                    entry.workspace, count
+                ));
+            }
+            for value in [entry.gap, entry.padding].into_iter().flatten() {
+                if !(0..=MAX_SPACING).contains(&value) {
+                    return Err(format!(
+                        "workspaces: gap and padding for workspace {} must be between 0 and {}",
+                        entry.workspace, MAX_SPACING
+                    ));
+                }
+            }
+        }
+        if self.stash.slots.len() > MAX_STASH_SLOTS {
+            return Err(format!(
+                "stash: {} slots configured, at most {} are supported",
+                self.stash.slots.len(),
+                MAX_STASH_SLOTS
+            ));
+        }
+        for keys in &self.stash.slots {
+            parse_hotkey(keys).map_err(|e| format!("stash: {}", e))?;
+        }
+        for rule in &self.decorations.borders {
+            parse_color(&rule.color).map_err(|e| format!("decorations.borders: {}", e))?;
+        }
+        for monitor in &self.monitors {
+            if !(1..=4).contains(&monitor.zones) {
+                return Err(format!(
+                    "monitors: zones {} is not between 1 and 4",
+                    monitor.zones
+                ));
+            }
+            let reserve = monitor.reserve;
+            if [reserve.top, reserve.right, reserve.bottom, reserve.left]
+                .iter()
+                .any(|edge| *edge < 0)
+            {
+                return Err("monitors: reserve must not be negative".to_string());
+            }
+            if monitor.primary && monitor.device.is_none() {
+                return Err("monitors: primary needs a device".to_string());
+            }
+            if monitor.fallback && monitor.device.is_none() {
+                return Err("monitors: fallback needs a device".to_string());
+            }
+        }
+        if self.monitors.iter().filter(|m| m.primary).count() > 1 {
+            return Err("monitors: only one monitor can be primary".to_string());
+        }
+        if self.monitors.iter().filter(|m| m.fallback).count() > 1 {
+            return Err("monitors: only one monitor can be the fallback".to_string());
+        }
+        let reserve = self.statusbar.reserve;
+        if [reserve.top, reserve.right, reserve.bottom, reserve.left]
+            .iter()
+            .any(|edge| *edge < 0)
+        {
+            return Err("statusbar: reserve must not be negative".to_string());
+        }
+        if self.statusbar.auto_hide_delay_ms > 60000 {
+            return Err(format!(
+                "statusbar: auto_hide_delay_ms {} is more than 60000",
+                self.statusbar.auto_hide_delay_ms
+            ));
+        }
+        let clock = &self.statusbar.clock;
+        for format in [&clock.format, &clock.timezone_format] {
+            crate::clock::validate_format(format).map_err(|e| format!("statusbar.clock: {}", e))?;
+        }
+        for value in [self.layout.gap, self.layout.padding] {
+            if !(0..=MAX_SPACING).contains(&value) {
+                return Err(format!(
+                    "layout: gap and padding must be between 0 and {}",
+                    MAX_SPACING
+                ));
+            }
+        }
+        if !(0..=1000).contains(&self.layout.move_threshold) {
+            return Err(format!(
+                "layout: move_threshold {} is not between 0 and 1000",
+                self.layout.move_threshold
+            ));
+        }
+        if self.layout.move_debounce_ms > 5000 {
+            return Err(format!(
+                "layout: move_debounce_ms {} is more than 5000",
+                self.layout.move_debounce_ms
+            ));
+        }
+        if !(0.2..=1.0).contains(&self.layout.zen_width) {
+            return Err(format!(
+                "layout: zen_width {} is not between 0.2 and 1.0",
+                self.layout.zen_width
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the config file path, `%USERPROFILE%\.megatile\config.toml`.
+pub fn config_path() -> Result<PathBuf, String> {
+    let home_dir = std::env::var("USERPROFILE")
+        .map_err(|_| "Failed to get USERPROFILE environment variable".to_string())?;
+
+    let mut path = PathBuf::from(home_dir);
+    path.push(".megatile");
+    path.push("config.toml");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layouts::TemplateShape;
+
+    #[test]
+    fn http_endpoint_needs_a_token() {
+        assert!(Config::parse("[http]\nport = 7080").is_err());
+        let config = Config::parse("[http]\nport = 7080\ntoken = \"s3cret\"").unwrap();
+        assert_eq!(config.http.port, Some(7080));
+        assert_eq!(Config::default().http.port, None);
+    }
+
+    #[test]
+    fn parses_tray_click_actions() {
+        let config =
+            Config::parse("[tray]\nclick = \"cheatsheet\"\ndouble_click = \"pause\"").unwrap();
+        assert_eq!(config.tray.click, TrayClickAction::Cheatsheet);
+        assert_eq!(config.tray.double_click, TrayClickAction::Pause);
+        assert_eq!(Config::default().tray.click, TrayClickAction::Menu);
+        assert!(Config::parse("[tray]\nclick = \"overview\"").is_err());
+    }
+
+    #[test]
+    fn picks_border_colors_per_app() {
+        let config = Config::parse(
+            r##"
+            [[decorations.borders]]
+            elevated = true
+            color = "#FF0000"
+            unfocused = true
+
+            [[decorations.borders]]
+            process = "WindowsTerminal.exe"
+            color = "#00c000"
+            "##,
+        )
+        .unwrap();
+        let decorations = &config.decorations;
+        assert!(decorations.borders_need_elevation());
+        let color = |process, elevated| {
+            decorations
+                .border_for(process, elevated)
+                .and_then(BorderRule::colorref)
+        };
+        assert_eq!(color(Some("windowsterminal.exe"), false), Some(0x0000C000));
+        assert_eq!(color(Some("WindowsTerminal.exe"), true), Some(0x000000FF));
+        assert_eq!(color(Some("Code.exe"), false), None);
+        assert_eq!(color(None, true), Some(0x000000FF));
+
+        assert_eq!(parse_color("#123456"), Ok(0x00563412));
+        assert!(parse_color("123456").is_err());
+        assert!(parse_color("#12345G").is_err());
+        assert!(Config::parse("[[decorations.borders]]\ncolor = \"red\"").is_err());
+    }
+
+    #[test]
+    fn places_bars_per_monitor() {
+        let config = Config::parse(
+            r#"
+            [statusbar]
+            media = true
+
+            [[monitors]]
+            device = '\\.\DISPLAY2'
+            bar = true
+            bar_modules = []
+            bar_align = "left"
+            "#,
+        )
+        .unwrap();
+        let connected = [r"\\.\DISPLAY1".to_string(), r"\\.\DISPLAY2".to_string()];
+        assert_eq!(
+            config.bar_placement(r"\\.\DISPLAY1", true, &connected),
+            Some(BarPlacement {
+                modules: vec![BarModule::Media, BarModule::Clock],
+                align: BarAlign::Center,
+            })
+        );
+        assert_eq!(
+            config.bar_placement(r"\\.\display2", false, &connected),
+            Some(BarPlacement {
+                modules: Vec::new(),
+                align: BarAlign::Left,
+            })
+        );
+        assert_eq!(
+            config.bar_placement(r"\\.\DISPLAY3", false, &connected),
+            None
+        );
+        assert!(Config::parse("[[monitors]]\nbar_modules = [\"battery\"]").is_err());
+    }
+
+    #[test]
+    fn primary_monitor_can_be_overridden() {
+        let config = Config::parse(
+            r#"
+            [[monitors]]
+            device = '\\.\DISPLAY2'
+            primary = true
+            "#,
+        )
+        .unwrap();
+        let connected = [r"\\.\DISPLAY1".to_string(), r"\\.\DISPLAY2".to_string()];
+        assert!(is_primary_display(
+            &config.monitors,
+            r"\\.\display2",
+            false,
+            &connected
+        ));
+        assert!(!is_primary_display(
+            &config.monitors,
+            r"\\.\DISPLAY1",
+            true,
+            &connected
+        ));
+        assert!(
+            config
+                .bar_placement(r"\\.\DISPLAY2", false, &connected)
+                .is_some()
+        );
+        assert_eq!(
+            config.bar_placement(r"\\.\DISPLAY1", true, &connected),
+            None
+        );
+        assert!(is_primary_display(&[], r"\\.\DISPLAY1", true, &connected));
+
+        // With the configured primary unplugged, Windows' primary takes over
+        let unplugged = [r"\\.\DISPLAY1".to_string()];
+        assert!(is_primary_display(
+            &config.monitors,
+            r"\\.\DISPLAY1",
+            true,
+            &unplugged
+        ));
+        assert!(
+            config
+                .bar_placement(r"\\.\DISPLAY1", true, &unplugged)
+                .is_some()
+        );
+
+        assert!(Config::parse("[[monitors]]\nprimary = true").is_err());
+        assert!(Config::parse("[[monitors]]\nfallback = true").is_err());
+        assert!(
+            Config::parse(
+                "[[monitors]]\ndevice = 'A'\nprimary = true\n\
+                 [[monitors]]\ndevice = 'B'\nprimary = true"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_autostart_commands() {
+        let config = Config::parse(
+            r#"
+            [autostart]
+            commands = [
+                { command = "wt.exe", workspace = 1, process = "WindowsTerminal.exe" },
+                { command = "firefox" },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.autostart.commands.len(), 2);
+        assert_eq!(config.autostart.commands[0].workspace, Some(1));
+        assert_eq!(
+            config.autostart.commands[0].process.as_deref(),
+            Some("WindowsTerminal.exe")
+        );
+        assert_eq!(config.autostart.commands[1].workspace, None);
+    }
+
+    #[test]
+    fn rejects_out_of_range_workspace_and_unknown_keys() {
+        assert!(
+            Config::parse("[autostart]\ncommands = [{ command = \"a\", workspace = 10 }]").is_err()
+        );
+        assert!(Config::parse("[autostart]\ncomands = []").is_err());
+        assert!(Config::parse("").unwrap().autostart.commands.is_empty());
+    }
+
+    #[test]
+    fn parses_launch_bindings_and_rules() {
+        let config = Config::parse(
+            r#"
+            [launch]
+            bindings = [{ keys = "Alt+Enter", command = "wt.exe", workspace = 1 }]
+
+            [[rules]]
+            process = "Slack.exe"
+            workspace = 3
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.launch.bindings[0].launch_command().workspace,
+            Some(1)
+        );
+        assert_eq!(config.rules[0].workspace, Some(3));
+
+        assert!(
+            Config::parse("[launch]\nbindings = [{ keys = \"Alt+Nope\", command = \"a\" }]")
+                .is_err()
+        );
+        assert!(Config::parse("[[rules]]\nworkspace = 2").is_err());
+        assert!(config.layout.fullscreen_topmost);
+        assert!(
+            !Config::parse("[layout]\nfullscreen_topmost = false")
+                .unwrap()
+                .layout
+                .fullscreen_topmost
+        );
+    }
+
+    #[test]
+    fn workspace_numbers_are_checked_against_the_count() {
+        assert!(Config::parse("[[rules]]\nprocess = \"a.exe\"\nworkspace = 12").is_err());
+        let config = Config::parse(
+            "[workspaces]\ncount = 12\n[[rules]]\nprocess = \"a.exe\"\nworkspace = 12",
+        )
+        .unwrap();
+        assert_eq!(config.workspaces.count, 12);
+        assert!(Config::parse("[workspaces]\ncount = 21").is_err());
+        assert!(Config::parse("[workspaces]\ncount = 0").is_err());
+    }
+
+    #[test]
+    fn workspace_overrides_parse_and_are_range_checked() {
+        let config = Config::parse(
+            r#"
+            [[workspaces.overrides]]
+            workspace = 3
+            gap = 0
+            padding = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.workspaces.overrides,
+            vec![WorkspaceOverride {
+                workspace: 3,
+                gap: Some(0),
+                padding: Some(0),
+                floating: false,
+            }]
+        );
+        assert!(
+            Config::parse(
+                "[[workspaces.overrides]]
+workspace = 10
+gap = 2"
+            )
+            .is_err()
+        );
+        assert!(
+            Config::parse(
+                "[[workspaces.overrides]]
+workspace = 1
+padding = -1"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn templates_parse_and_are_checked() {
+        let config = Config::parse(
+            r#"
+            [[templates]]
+            workspace = 2
+            slots = [{ process = "Code.exe" }, { process = "WindowsTerminal.exe" }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.templates[0].shape, TemplateShape::MainStack);
+        assert_eq!(config.templates[0].main_ratio, 0.6);
+        assert_eq!(config.templates[0].slots.len(), 2);
+        assert!(Config::parse("[[templates]]\nworkspace = 2\nslots = []").is_err());
+        assert!(
+            Config::parse(
+                "[[templates]]
+workspace = 2
+main_ratio = 0.95
+slots = [{ process = \"a.exe\" }]"
+            )
+            .is_err()
+        );
+        assert!(
+            Config::parse(
+                "[[templates]]
+workspace = 2
+slots = [{ process = \"a.exe\", workspace = 3 }]"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn new_window_focus_defaults_to_the_active_workspace() {
+        assert_eq!(Config::default().focus.new_windows, NewWindowFocus::Active);
+        let config = Config::parse("[focus]\nnew_windows = \"never\"").unwrap();
+        assert_eq!(config.focus.new_windows, NewWindowFocus::Never);
+        assert!(config.focus.cross_monitor);
+        assert!(!config.focus.wrap);
+        assert!(Config::parse("[focus]\nnew_windows = \"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn move_detection_is_configurable_within_limits() {
+        let config = Config::parse("[layout]\nmove_threshold = 10\nretile_on_drop = true").unwrap();
+        assert_eq!(config.layout.move_threshold, 10);
+        assert_eq!(config.layout.move_debounce_ms, 50);
+        assert!(config.layout.retile_on_drop);
+        assert!(Config::parse("[layout]\nmove_threshold = -1").is_err());
+        assert!(Config::parse("[layout]\nmove_debounce_ms = 60000").is_err());
+    }
+
+    #[test]
+    fn quirks_are_found_by_process() {
+        let config = Config::parse(
+            r#"
+            [[quirks]]
+            process = "idea64.exe"
+            manage_delay_ms = 1500
+
+            [[quirks]]
+            process = "Launcher.exe"
+            no_resize = true
+            keep_maximized = true
+            "#,
+        )
+        .unwrap();
+
+        let idea = quirks_for(&config.quirks, Some("IDEA64.exe")).unwrap();
+        assert_eq!(idea.manage_delay_ms, 1500);
+        assert!(!idea.no_resize);
+        let launcher = quirks_for(&config.quirks, Some("launcher.exe")).unwrap();
+        assert!(launcher.no_resize && launcher.keep_maximized);
+        assert!(!idea.keep_maximized);
+        assert!(quirks_for(&config.quirks, None).is_none());
+        assert!(Config::parse("[[quirks]]\nprocess = \"a.exe\"\nmanage_delay_ms = 60000").is_err());
+    }
+
+    #[test]
+    fn hide_methods_are_chosen_per_process() {
+        let config = Config::parse(
+            r#"
+            [workspaces]
+            offscreen_processes = ["Spotify.exe"]
+            "#,
+        )
+        .unwrap();
+
+        let methods = config.workspaces.hide_methods();
+        assert_eq!(methods.get("spotify.exe"), Some(&HideMethod::Offscreen));
+        assert_eq!(methods.get("firefox.exe"), None);
+    }
+
+    #[test]
+    fn title_bar_exclusions_are_case_insensitive() {
+        let config = Config::parse(
+            r#"
+            [decorations]
+            hide_title_bars = true
+            title_bar_exclusions = ["Code.exe"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.decorations.hides_title_bar(Some("firefox.exe")));
+        assert!(config.decorations.hides_title_bar(None));
+        assert!(!config.decorations.hides_title_bar(Some("code.exe")));
+        assert!(
+            !Config::default()
+                .decorations
+                .hides_title_bar(Some("firefox.exe"))
+        );
+    }
+
+    #[test]
+    fn transparency_exclusions_stay_opaque() {
+        let config = Config::parse(
+            r#"
+            [decorations]
+            unfocused_alpha = 230
+            transparency_exclusions = ["mpv.exe"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.decorations.unfocused_alpha_for(Some("MPV.exe")), 255);
+        assert_eq!(
+            config.decorations.unfocused_alpha_for(Some("Code.exe")),
+            230
+        );
+        assert_eq!(config.decorations.unfocused_alpha_for(None), 230);
+        assert_eq!(Config::default().decorations.unfocused_alpha, 245);
+    }
+
+    #[test]
+    fn tiled_corners_default_to_square() {
+        let config = Config::parse("[decorations]\nfloating_corners = \"small\"").unwrap();
+        assert_eq!(config.decorations.corners, CornerPreference::Square);
+        assert_eq!(config.decorations.floating_corners, CornerPreference::Small);
+        assert!(Config::parse("[decorations]\ncorners = \"pointy\"").is_err());
+    }
+
+    #[test]
+    fn spreads_startup_windows_over_workspaces() {
+        let config = Config::parse(
+            "[workspaces]\ncount = 4\nlocked = [2]\n\
+             startup = \"monitor\"\nstartup_per_workspace = 2",
+        )
+        .unwrap();
+        let windows = [
+            (0, None),
+            (0, None),
+            (1, None),
+            (0, Some(2)),
+            (0, None),
+            (0, None),
+            (0, None),
+            (0, None),
+        ];
+        assert_eq!(
+            config.workspaces.startup_workspaces(&windows),
+            [1, 1, 1, 2, 3, 3, 4, 4]
+        );
+
+        let mut workspaces = config.workspaces.clone();
+        workspaces.startup = StartupPlacement::Rules;
+        assert_eq!(
+            workspaces.startup_workspaces(&[(0, None), (1, Some(3))]),
+            [1, 3]
+        );
+        workspaces.startup = StartupPlacement::First;
+        assert_eq!(workspaces.startup_workspaces(&[(1, Some(3))]), [1]);
+        workspaces.startup = StartupPlacement::Float;
+        assert_eq!(workspaces.startup_workspaces(&[(1, Some(3))]), [1]);
+        assert!(Config::parse("[workspaces]\nstartup_per_workspace = 0").is_err());
+    }
+
+    #[test]
+    fn named_monitor_config_wins_over_catch_all() {
+        let config = Config::parse(
+            r#"
+            [[monitors]]
+            zones = 2
+
+            [[monitors]]
+            device = '\\.\DISPLAY2'
+            zones = 3
+            "#,
+        )
+        .unwrap();
+
+        let zones = |device| monitor_config(&config.monitors, device).map(|m| m.zones);
+        assert_eq!(zones(r"\\.\display2"), Some(3));
+        assert_eq!(zones(r"\\.\DISPLAY1"), Some(2));
+        assert_eq!(monitor_config(&[], r"\\.\DISPLAY1"), None);
+        assert!(Config::parse("[[monitors]]\nzones = 0").is_err());
+        assert!(Config::parse("[[monitors]]\nreserve = { right = -40 }").is_err());
+        assert_eq!(
+            Config::parse("[[monitors]]\nreserve = { right = 40 }")
+                .unwrap()
+                .monitors[0]
+                .reserve,
+            ReservedEdges {
+                right: 40,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            Config::parse("[[monitors]]\nlayout = \"stack\"")
+                .unwrap()
+                .monitors[0]
+                .layout,
+            Some(TileLayout::Stack)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Where each application's floating windows were last placed.
+//!
+//! Rects are keyed by lowercase process name and saved to
+//! `float-geometry.toml` next to the config file shortly after one changes,
+//! and again when Megatile exits. A window floated later opens at its app's
+//! last floating size and position instead of wherever it was tiled.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use windows::Win32::Foundation::RECT;
+
+/// A window rect in screen coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl From<RECT> for SavedRect {
+    fn from(rect: RECT) -> Self {
+        SavedRect {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+impl From<SavedRect> for RECT {
+    fn from(rect: SavedRect) -> Self {
+        RECT {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+/// Last floating rect by lowercase process name.
+pub type FloatRects = BTreeMap<String, SavedRect>;
+
+/// Returns the float geometry file path, next to the config file.
+pub fn float_geometry_path() -> Result<PathBuf, String> {
+    let mut path = crate::config::config_path()?;
+    path.set_file_name("float-geometry.toml");
+    Ok(path)
+}
+
+/// Reads the saved rects. A missing file means none were saved yet.
+pub fn load() -> Result<FloatRects, String> {
+    let path = float_geometry_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(FloatRects::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Saves `rects`, replacing the file.
+pub fn save(rects: &FloatRects) -> Result<(), String> {
+    let path = float_geometry_path()?;
+    let text = toml::to_string(rects).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
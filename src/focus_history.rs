@@ -0,0 +1,117 @@
+//! Focus history: the order windows on a workspace were focused in.
+//!
+//! Works like browser history. Focusing a window adds it at the end; stepping
+//! back and forward moves a cursor through the entries without reordering
+//! them, so repeated steps walk further into the past.
+
+/// Most windows remembered per history.
+const MAX_ENTRIES: usize = 32;
+
+/// Focus history for one workspace.
+#[derive(Debug, Clone, Default)]
+pub struct FocusHistory {
+    entries: Vec<isize>,
+    /// Index of the entry focused last.
+    cursor: usize,
+}
+
+impl FocusHistory {
+    /// Records that `hwnd` was focused.
+    ///
+    /// Focusing the entry under the cursor (e.g. as the result of a step)
+    /// changes nothing. Any other window moves to the end of the history.
+    pub fn record(&mut self, hwnd: isize) {
+        if self.entries.get(self.cursor) == Some(&hwnd) {
+            return;
+        }
+        self.entries.retain(|&entry| entry != hwnd);
+        self.entries.push(hwnd);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    /// Steps to the window focused before the current one.
+    pub fn back(&mut self) -> Option<isize> {
+        self.cursor = self.cursor.checked_sub(1)?;
+        self.entries.get(self.cursor).copied()
+    }
+
+    /// Returns the window focused before the current one, without stepping.
+    pub fn previous(&self) -> Option<isize> {
+        self.entries.get(self.cursor.checked_sub(1)?).copied()
+    }
+
+    /// Steps to the window focused after the current one, undoing [`FocusHistory::back`].
+    pub fn forward(&mut self) -> Option<isize> {
+        if self.cursor + 1 >= self.entries.len() {
+            return None;
+        }
+        self.cursor += 1;
+        self.entries.get(self.cursor).copied()
+    }
+
+    /// Drops `hwnd` from the history, e.g. when it is closed.
+    pub fn forget(&mut self, hwnd: isize) {
+        if let Some(index) = self.entries.iter().position(|&entry| entry == hwnd) {
+            self.entries.remove(index);
+            // Keep the cursor on the same entry, or the one before a removed current entry
+            if index <= self.cursor && self.cursor > 0 {
+                self.cursor -= 1;
+            }
+        }
+    }
+
+    /// Drops every window `keep` rejects.
+    pub fn retain(&mut self, keep: impl Fn(isize) -> bool) {
+        let stale: Vec<isize> = self
+            .entries
+            .iter()
+            .copied()
+            .filter(|&entry| !keep(entry))
+            .collect();
+        for hwnd in stale {
+            self.forget(hwnd);
+        }
+    }
+
+    /// Returns true if no window has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_walk_the_history_without_reordering_it() {
+        let mut history = FocusHistory::default();
+        for hwnd in [1, 2, 3] {
+            history.record(hwnd);
+        }
+
+        assert_eq!(history.back(), Some(2));
+        history.record(2); // The focus event caused by the step
+        assert_eq!(history.back(), Some(1));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(2));
+        assert_eq!(history.forward(), Some(3));
+        assert_eq!(history.forward(), None);
+
+        // Focusing something else moves it to the end
+        history.back();
+        history.record(1);
+        assert_eq!(history.back(), Some(3));
+
+        history.forget(3);
+        assert_eq!(history.forward(), Some(1));
+        history.retain(|hwnd| hwnd == 2);
+        assert_eq!(history.back(), None);
+        history.forget(2);
+        assert!(history.is_empty());
+        assert_eq!(history.back(), None);
+    }
+}
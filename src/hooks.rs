@@ -0,0 +1,108 @@
+//! Shell commands run on lifecycle events.
+//!
+//! A lighter alternative to scripting: `[[hooks]]` entries in the config
+//! attach a program to an event. Programs are started directly, never through a
+//! shell, so event data such as window titles can't turn into commands. They
+//! run detached with the event's data in `MEGATILE_*` environment variables,
+//! and Megatile never waits for them.
+
+use crate::lifecycle::{EVENT_NAMES, LifecycleEvent};
+use log::{debug, warn};
+use serde::Deserialize;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::process::{Command, Stdio};
+#[cfg(windows)]
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+/// A `[[hooks]]` entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hook {
+    /// Event name, e.g. `"workspace_switched"`.
+    pub event: String,
+    /// Program to start, e.g. `"powershell"`.
+    pub command: String,
+    /// Arguments passed to the program as-is.
+    pub args: Vec<String>,
+}
+
+impl Hook {
+    /// Checks a hook from the config file.
+    pub fn validate(&self) -> Result<(), String> {
+        if !EVENT_NAMES.contains(&self.event.as_str()) {
+            return Err(format!(
+                "unknown event '{}', expected one of {}",
+                self.event,
+                EVENT_NAMES.join(", ")
+            ));
+        }
+        if self.command.trim().is_empty() {
+            return Err(format!("command for '{}' must not be empty", self.event));
+        }
+        Ok(())
+    }
+}
+
+/// Runs every hook attached to `event`.
+pub fn run_hooks(hooks: &[Hook], event: &LifecycleEvent) {
+    for hook in hooks.iter().filter(|hook| hook.event == event.name()) {
+        debug!("Running {} hook: {}", hook.event, hook.command);
+        let mut command = Command::new(&hook.command);
+        command
+            .args(&hook.args)
+            .envs(hook_env(event))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        #[cfg(windows)]
+        command.creation_flags(CREATE_NO_WINDOW.0);
+        let result = command.spawn();
+        if let Err(e) = result {
+            warn!(
+                "Failed to run {} hook '{}': {}",
+                hook.event, hook.command, e
+            );
+        }
+    }
+}
+
+/// Environment variables describing `event`, e.g. `MEGATILE_TO=3`.
+fn hook_env(event: &LifecycleEvent) -> Vec<(String, String)> {
+    let mut env = vec![("MEGATILE_EVENT".to_string(), event.name().to_string())];
+    env.extend(
+        event
+            .fields()
+            .into_iter()
+            .map(|(name, value)| (format!("MEGATILE_{}", name.to_uppercase()), value)),
+    );
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_data_becomes_environment_variables() {
+        let env = hook_env(&LifecycleEvent::WorkspaceSwitched { from: 1, to: 3 });
+        assert_eq!(
+            env,
+            [
+                (
+                    "MEGATILE_EVENT".to_string(),
+                    "workspace_switched".to_string()
+                ),
+                ("MEGATILE_FROM".to_string(), "1".to_string()),
+                ("MEGATILE_TO".to_string(), "3".to_string()),
+            ]
+        );
+
+        let hook = Hook {
+            event: "window_closed".to_string(),
+            command: "notify.exe".to_string(),
+            args: Vec::new(),
+        };
+        assert!(hook.validate().is_err());
+    }
+}
@@ -3,24 +3,36 @@
 //! This module handles registering system-wide hotkeys with Windows
 //! and mapping them to [`HotkeyAction`] values for the window manager.
 
-use log::debug;
+use crate::config::{HotkeyModifier, LaunchBinding, LaunchCommand};
+use log::{debug, warn};
 use std::collections::HashMap;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
 
+/// Hotkey IDs from here up are used for `[stash]` slot keys from the config.
+const STASH_HOTKEY_ID_BASE: i32 = 60;
+/// Hotkey IDs from here up are used for `[launch]` bindings from the config.
+const LAUNCH_HOTKEY_ID_BASE: i32 = 100;
+
 /// Manages global hotkey registration and lookup.
 pub struct HotkeyManager {
     registered_hotkeys: HashMap<i32, HotkeyAction>,
+    /// Keys of the registered hotkeys, for the cheatsheet.
+    registered_keys: HashMap<i32, (HOT_KEY_MODIFIERS, VIRTUAL_KEY)>,
 }
 
 /// Actions that can be triggered by hotkeys.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum HotkeyAction {
     // Focus movement
     FocusLeft,
     FocusRight,
     FocusUp,
     FocusDown,
+    FocusPrevious,
+    FocusNext,
+    /// Jump to the window that most recently flashed for attention.
+    FocusUrgent,
 
     // Window movement
     MoveLeft,
@@ -36,22 +48,138 @@ pub enum HotkeyAction {
 
     // Layout operations
     FlipRegion,
+    /// Give the focused window this percentage of its split.
+    SetSplitRatio(u8),
+    SaveLayout(String),
+    LoadLayout(String),
 
     // Workspace switching
     SwitchWorkspace(u8),
     MoveToWorkspace(u8),
+    MoveToEmptyWorkspace,
+    NextWorkspace,
+    PreviousWorkspace,
 
     // Window operations
     CloseWindow,
+    MinimizeWindow,
     ToggleTiling,
+    /// Float every window on the workspace, or tile them all again.
+    ToggleWorkspaceTiling,
+    /// Tile the windows left floating at startup.
+    AdoptWindows,
+    ToggleTopmost,
+    IncreaseOpacity,
+    DecreaseOpacity,
     ToggleFullscreen,
+    ToggleZen,
+    ToggleMaximize,
     ToggleStatusBar,
+    FlashFocus,
+    ToggleWorkspaceLock,
+    ToggleCheatsheet,
+    ToggleShowDesktop,
+    ToggleTileGroup,
+    CycleTileGroup,
+    MergeLeft,
+    MergeRight,
+    MergeUp,
+    MergeDown,
+
+    // Stash slots
+    Stash,
+    ToggleStashSlot(u8),
 
     // Monitor movement
     MoveToMonitorLeft,
     MoveToMonitorRight,
     MoveToMonitorUp,
     MoveToMonitorDown,
+    /// Move the window to the next monitor without following it, whatever
+    /// `[focus] follow_to_monitor` says.
+    SendToMonitorLeft,
+    SendToMonitorRight,
+    SendToMonitorUp,
+    SendToMonitorDown,
+    /// Pause or resume management of the display under the mouse cursor.
+    ToggleMonitorManaged,
+    /// Spread the workspace's tiles evenly over the monitors.
+    BalanceMonitors,
+    /// Pull the workspace's tiles onto the focused window's monitor.
+    GatherOnMonitor,
+
+    // Launching (bindings from the config file)
+    Launch(LaunchCommand),
+}
+
+impl HotkeyAction {
+    /// Short description for the cheatsheet, e.g. `"Switch to workspace 3"`.
+    pub fn description(&self) -> String {
+        match self {
+            HotkeyAction::FocusLeft => "Focus left".to_string(),
+            HotkeyAction::FocusRight => "Focus right".to_string(),
+            HotkeyAction::FocusUp => "Focus up".to_string(),
+            HotkeyAction::FocusDown => "Focus down".to_string(),
+            HotkeyAction::FocusPrevious => "Back in focus history".to_string(),
+            HotkeyAction::FocusNext => "Forward in focus history".to_string(),
+            HotkeyAction::FocusUrgent => "Jump to the window wanting attention".to_string(),
+            HotkeyAction::ToggleWorkspaceTiling => "Toggle tiling for the workspace".to_string(),
+            HotkeyAction::AdoptWindows => "Tile windows open at startup".to_string(),
+            HotkeyAction::MoveLeft => "Move window left".to_string(),
+            HotkeyAction::MoveRight => "Move window right".to_string(),
+            HotkeyAction::MoveUp => "Move window up".to_string(),
+            HotkeyAction::MoveDown => "Move window down".to_string(),
+            HotkeyAction::ResizeHorizontalIncrease => "Grow horizontally".to_string(),
+            HotkeyAction::ResizeHorizontalDecrease => "Shrink horizontally".to_string(),
+            HotkeyAction::ResizeVerticalIncrease => "Grow vertically".to_string(),
+            HotkeyAction::ResizeVerticalDecrease => "Shrink vertically".to_string(),
+            HotkeyAction::FlipRegion => "Flip region".to_string(),
+            HotkeyAction::SaveLayout(name) => format!("Save layout '{}'", name),
+            HotkeyAction::LoadLayout(name) => format!("Load layout '{}'", name),
+            HotkeyAction::SetSplitRatio(percent) => {
+                format!("Split {}/{}", percent, 100u8.saturating_sub(*percent))
+            }
+            HotkeyAction::SwitchWorkspace(ws) => format!("Switch to workspace {}", ws),
+            HotkeyAction::MoveToWorkspace(ws) => format!("Move window to workspace {}", ws),
+            HotkeyAction::MoveToEmptyWorkspace => "Move window to an empty workspace".to_string(),
+            HotkeyAction::NextWorkspace => "Next workspace".to_string(),
+            HotkeyAction::PreviousWorkspace => "Previous workspace".to_string(),
+            HotkeyAction::CloseWindow => "Close window".to_string(),
+            HotkeyAction::MinimizeWindow => "Minimize window".to_string(),
+            HotkeyAction::ToggleTiling => "Toggle tiling".to_string(),
+            HotkeyAction::ToggleTopmost => "Keep floating window on top".to_string(),
+            HotkeyAction::IncreaseOpacity => "More opaque (per app)".to_string(),
+            HotkeyAction::DecreaseOpacity => "More transparent (per app)".to_string(),
+            HotkeyAction::ToggleFullscreen => "Toggle fullscreen".to_string(),
+            HotkeyAction::ToggleZen => "Toggle zen mode".to_string(),
+            HotkeyAction::ToggleMaximize => "Toggle maximize".to_string(),
+            HotkeyAction::ToggleStatusBar => "Toggle status bar".to_string(),
+            HotkeyAction::FlashFocus => "Show where focus is".to_string(),
+            HotkeyAction::ToggleWorkspaceLock => "Lock workspace".to_string(),
+            HotkeyAction::ToggleCheatsheet => "Show keybindings".to_string(),
+            HotkeyAction::ToggleShowDesktop => "Show desktop".to_string(),
+            HotkeyAction::ToggleTileGroup => "Group with previous window".to_string(),
+            HotkeyAction::CycleTileGroup => "Raise next window in group".to_string(),
+            HotkeyAction::MergeLeft => "Group into tile left".to_string(),
+            HotkeyAction::MergeRight => "Group into tile right".to_string(),
+            HotkeyAction::MergeUp => "Group into tile above".to_string(),
+            HotkeyAction::MergeDown => "Group into tile below".to_string(),
+            HotkeyAction::Stash => "Stash window".to_string(),
+            HotkeyAction::ToggleStashSlot(slot) => format!("Stash slot {}", slot),
+            HotkeyAction::MoveToMonitorLeft => "Move window to monitor left".to_string(),
+            HotkeyAction::MoveToMonitorRight => "Move window to monitor right".to_string(),
+            HotkeyAction::MoveToMonitorUp => "Move window to monitor up".to_string(),
+            HotkeyAction::MoveToMonitorDown => "Move window to monitor down".to_string(),
+            HotkeyAction::SendToMonitorLeft => "Send window to monitor left".to_string(),
+            HotkeyAction::SendToMonitorRight => "Send window to monitor right".to_string(),
+            HotkeyAction::SendToMonitorUp => "Send window to monitor up".to_string(),
+            HotkeyAction::SendToMonitorDown => "Send window to monitor down".to_string(),
+            HotkeyAction::ToggleMonitorManaged => "Pause or resume this monitor".to_string(),
+            HotkeyAction::BalanceMonitors => "Spread windows over the monitors".to_string(),
+            HotkeyAction::GatherOnMonitor => "Gather windows on this monitor".to_string(),
+            HotkeyAction::Launch(launch) => format!("Launch {}", launch.command),
+        }
+    }
 }
 
 impl HotkeyManager {
@@ -59,6 +187,7 @@ impl HotkeyManager {
     pub fn new() -> Self {
         Self {
             registered_hotkeys: HashMap::new(),
+            registered_keys: HashMap::new(),
         }
     }
 
@@ -66,20 +195,50 @@ impl HotkeyManager {
     ///
     /// # Hotkey Bindings
     /// - `Alt + Arrows`: Move focus
+    /// - `Alt + [ / ]`: Step back/forward through the workspace's focus history
+    /// - `Alt + U`: Jump to the window that last flashed for attention
     /// - `Alt + Shift + Arrows`: Move window
     /// - `Alt + Ctrl + Arrows`: Move window to adjacent monitor
-    /// - `Alt + 1-9`: Switch workspace
-    /// - `Alt + Shift + 1-9`: Move window to workspace and follow
+    /// - `Alt + Ctrl + B`: Spread the workspace's windows evenly over the monitors
+    /// - `Alt + Ctrl + G`: Gather the workspace's windows on the focused monitor
+    /// - `Alt + Ctrl + Shift + Arrows`: Group window into the adjacent tile
+    /// - `Alt + 1-9, 0`: Switch to workspace 1-10
+    /// - `Alt + Shift + 1-9, 0`: Move window to workspace 1-10 and follow
+    /// - `Alt + Shift + E`: Move window to the first empty workspace
+    /// - `Alt + PageDown/PageUp`: Cycle to the next/previous workspace
+    /// - `Alt + Shift + PageUp/PageDown`: Make the focused app more/less opaque
     /// - `Alt + +/-`: Resize horizontally
     /// - `Alt + Shift + +/-`: Resize vertically
     /// - `Alt + J`: Flip region
     /// - `Alt + W`: Close window
+    /// - `Alt + N`: Minimize window
     /// - `Alt + T`: Toggle tiling
+    /// - `Alt + Shift + T`: Toggle tiling for every window on the workspace
+    /// - `Alt + P`: Keep a floating window on top
     /// - `Alt + F`: Toggle fullscreen
+    /// - `Alt + Z`: Toggle zen mode
+    /// - `Alt + M`: Toggle maximize within the workspace
     /// - `Alt + B`: Toggle status bar
-    pub fn register_hotkeys(&mut self, hwnd: HWND) -> Result<(), String> {
-        // Virtual key codes for number keys 1-9
-        const VK_NUMS: [VIRTUAL_KEY; 9] = [VK_1, VK_2, VK_3, VK_4, VK_5, VK_6, VK_7, VK_8, VK_9];
+    /// - `Alt + Shift + F`: Flash the focused window
+    /// - `Alt + Shift + A`: Tile the windows left floating at startup
+    /// - `Alt + L`: Lock the workspace against new windows
+    /// - `Alt + Shift + S`: Park the focused window in a stash slot
+    /// - `Alt + /`: Show the keybindings
+    /// - `Alt + D`: Hide the workspace's windows, or bring them back
+    /// - `Alt + G`: Group the window into the previous window's tile, or ungroup it
+    /// - ``Alt + ` ``: Raise the next window in the tile group
+    ///
+    /// Workspace bindings are only registered up to `workspace_count`, and
+    /// `modifier` is held in place of `Alt` in every binding.
+    pub fn register_hotkeys(
+        &mut self,
+        hwnd: HWND,
+        workspace_count: u8,
+        modifier: HotkeyModifier,
+    ) -> Result<(), String> {
+        // Virtual key codes for number keys 1-9, then 0
+        const VK_NUMS: [VIRTUAL_KEY; 10] =
+            [VK_1, VK_2, VK_3, VK_4, VK_5, VK_6, VK_7, VK_8, VK_9, VK_0];
 
         let mut hotkeys: Vec<(HOT_KEY_MODIFIERS, VIRTUAL_KEY, i32, HotkeyAction)> = vec![
             // Focus movement (Alt + Arrows)
@@ -87,6 +246,10 @@ impl HotkeyManager {
             (MOD_ALT, VK_RIGHT, 2, HotkeyAction::FocusRight),
             (MOD_ALT, VK_UP, 3, HotkeyAction::FocusUp),
             (MOD_ALT, VK_DOWN, 4, HotkeyAction::FocusDown),
+            // Focus history (Alt + [ / ])
+            (MOD_ALT, VK_OEM_4, 43, HotkeyAction::FocusPrevious),
+            (MOD_ALT, VK_OEM_6, 44, HotkeyAction::FocusNext),
+            (MOD_ALT, VIRTUAL_KEY(0x55), 95, HotkeyAction::FocusUrgent),
             // Window movement (Alt + Shift + Arrows)
             (MOD_ALT | MOD_SHIFT, VK_LEFT, 5, HotkeyAction::MoveLeft),
             (MOD_ALT | MOD_SHIFT, VK_RIGHT, 6, HotkeyAction::MoveRight),
@@ -120,19 +283,87 @@ impl HotkeyManager {
             // Layout and window operations
             (MOD_ALT, VIRTUAL_KEY(0x4A), 32, HotkeyAction::FlipRegion),
             (MOD_ALT, VIRTUAL_KEY(0x57), 33, HotkeyAction::CloseWindow),
+            (MOD_ALT, VIRTUAL_KEY(0x4E), 58, HotkeyAction::MinimizeWindow),
             (MOD_ALT, VIRTUAL_KEY(0x54), 34, HotkeyAction::ToggleTiling),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VIRTUAL_KEY(0x54),
+                97,
+                HotkeyAction::ToggleWorkspaceTiling,
+            ),
+            (MOD_ALT, VIRTUAL_KEY(0x50), 59, HotkeyAction::ToggleTopmost),
             (
                 MOD_ALT,
                 VIRTUAL_KEY(0x46),
                 35,
                 HotkeyAction::ToggleFullscreen,
             ),
+            (MOD_ALT, VIRTUAL_KEY(0x5A), 36, HotkeyAction::ToggleZen),
+            (MOD_ALT, VIRTUAL_KEY(0x4D), 37, HotkeyAction::ToggleMaximize),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VIRTUAL_KEY(0x53),
+                38,
+                HotkeyAction::Stash,
+            ),
+            (
+                MOD_ALT,
+                VIRTUAL_KEY(0x4C),
+                39,
+                HotkeyAction::ToggleWorkspaceLock,
+            ),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VIRTUAL_KEY(0x45),
+                40,
+                HotkeyAction::MoveToEmptyWorkspace,
+            ),
+            (MOD_ALT, VK_NEXT, 41, HotkeyAction::NextWorkspace),
+            (MOD_ALT, VK_PRIOR, 42, HotkeyAction::PreviousWorkspace),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VK_PRIOR,
+                92,
+                HotkeyAction::IncreaseOpacity,
+            ),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VK_NEXT,
+                93,
+                HotkeyAction::DecreaseOpacity,
+            ),
             (
                 MOD_ALT,
                 VIRTUAL_KEY(0x42),
                 45,
                 HotkeyAction::ToggleStatusBar,
             ),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VIRTUAL_KEY(0x46),
+                94,
+                HotkeyAction::FlashFocus,
+            ),
+            (
+                MOD_ALT | MOD_SHIFT,
+                VIRTUAL_KEY(0x41),
+                96,
+                HotkeyAction::AdoptWindows,
+            ),
+            (MOD_ALT, VK_OEM_2, 46, HotkeyAction::ToggleCheatsheet),
+            (
+                MOD_ALT,
+                VIRTUAL_KEY(0x44),
+                49,
+                HotkeyAction::ToggleShowDesktop,
+            ),
+            (
+                MOD_ALT,
+                VIRTUAL_KEY(0x47),
+                47,
+                HotkeyAction::ToggleTileGroup,
+            ),
+            (MOD_ALT, VK_OEM_3, 48, HotkeyAction::CycleTileGroup),
             // Monitor movement (Alt + Ctrl + Arrows)
             (
                 MOD_ALT | MOD_CONTROL,
@@ -158,49 +389,168 @@ impl HotkeyManager {
                 53,
                 HotkeyAction::MoveToMonitorDown,
             ),
+            (
+                MOD_ALT | MOD_CONTROL,
+                VIRTUAL_KEY(0x42),
+                98,
+                HotkeyAction::BalanceMonitors,
+            ),
+            (
+                MOD_ALT | MOD_CONTROL,
+                VIRTUAL_KEY(0x47),
+                99,
+                HotkeyAction::GatherOnMonitor,
+            ),
+            // Tile groups (Alt + Ctrl + Shift + Arrows)
+            (
+                MOD_ALT | MOD_CONTROL | MOD_SHIFT,
+                VK_LEFT,
+                54,
+                HotkeyAction::MergeLeft,
+            ),
+            (
+                MOD_ALT | MOD_CONTROL | MOD_SHIFT,
+                VK_RIGHT,
+                55,
+                HotkeyAction::MergeRight,
+            ),
+            (
+                MOD_ALT | MOD_CONTROL | MOD_SHIFT,
+                VK_UP,
+                56,
+                HotkeyAction::MergeUp,
+            ),
+            (
+                MOD_ALT | MOD_CONTROL | MOD_SHIFT,
+                VK_DOWN,
+                57,
+                HotkeyAction::MergeDown,
+            ),
         ];
 
-        // Add workspace hotkeys (1-9) using iteration
-        for (i, &vk) in VK_NUMS.iter().enumerate() {
-            let ws = (i + 1) as u8;
+        // Add workspace hotkeys for 1-10. Alt + Ctrl is AltGr on many European
+        // layouts, where it types characters like @ and { from the digit row,
+        // so 11-20 are only reached through IPC and workspace cycling
+        for ws in 1..=workspace_count.min(VK_NUMS.len() as u8) {
+            let index = (ws - 1) as usize;
+            // Workspaces 1-9 keep their original ids; 10 uses 70 and 81
+            let (switch_id, move_id) = if ws <= 9 {
+                (10 + index as i32, 19 + index as i32)
+            } else {
+                (70, 81)
+            };
             hotkeys.push((
                 MOD_ALT,
-                vk,
-                10 + i as i32,
+                VK_NUMS[index],
+                switch_id,
                 HotkeyAction::SwitchWorkspace(ws),
             ));
             hotkeys.push((
                 MOD_ALT | MOD_SHIFT,
-                vk,
-                19 + i as i32,
+                VK_NUMS[index],
+                move_id,
                 HotkeyAction::MoveToWorkspace(ws),
             ));
         }
 
+        let hotkeys = hotkeys
+            .into_iter()
+            .map(|(modifiers, vk, id, action)| (with_modifier(modifiers, modifier), vk, id, action))
+            .collect();
+        self.register_all(hwnd, hotkeys)
+    }
+
+    /// Registers the `[launch]` hotkeys from the config file.
+    pub fn register_launch_hotkeys(
+        &mut self,
+        hwnd: HWND,
+        bindings: &[LaunchBinding],
+    ) -> Result<(), String> {
+        let mut hotkeys = Vec::new();
+        for (i, binding) in bindings.iter().enumerate() {
+            let (modifiers, vk) = parse_hotkey(&binding.keys)?;
+            hotkeys.push((
+                modifiers,
+                vk,
+                LAUNCH_HOTKEY_ID_BASE + i as i32,
+                HotkeyAction::Launch(binding.launch_command()),
+            ));
+        }
+        self.register_all(hwnd, hotkeys)
+    }
+
+    /// Registers the `[stash]` slot hotkeys from the config file.
+    pub fn register_stash_hotkeys(&mut self, hwnd: HWND, slots: &[String]) -> Result<(), String> {
+        let mut hotkeys = Vec::new();
+        for (i, keys) in slots.iter().enumerate() {
+            let (modifiers, vk) = parse_hotkey(keys)?;
+            hotkeys.push((
+                modifiers,
+                vk,
+                STASH_HOTKEY_ID_BASE + i as i32,
+                HotkeyAction::ToggleStashSlot(i as u8 + 1),
+            ));
+        }
+        self.register_all(hwnd, hotkeys)
+    }
+
+    fn register_all(
+        &mut self,
+        hwnd: HWND,
+        hotkeys: Vec<(HOT_KEY_MODIFIERS, VIRTUAL_KEY, i32, HotkeyAction)>,
+    ) -> Result<(), String> {
+        // Keep going past failures so one conflict doesn't take out every binding
+        let mut failed = Vec::new();
         for (modifiers, vk, id, action) in hotkeys {
             unsafe {
                 debug!("Registering hotkey: vk={}, id={}", vk.0, id);
                 match RegisterHotKey(Some(hwnd), id, modifiers, vk.0 as u32) {
                     Ok(()) => {
-                        self.registered_hotkeys.insert(id, action);
                         debug!("Registered hotkey: {:?} (ID: {})", action, id);
+                        self.registered_hotkeys.insert(id, action);
+                        self.registered_keys.insert(id, (modifiers, vk));
                     }
                     Err(e) => {
-                        return Err(format!(
+                        warn!(
                             "Failed to register hotkey: {:?} (vk={}, id={}, error={:?})",
                             action, vk.0, id, e
-                        ));
+                        );
+                        failed.push(match action {
+                            HotkeyAction::Launch(launch) => format!("Launch({})", launch.command),
+                            action => format!("{:?}", action),
+                        });
                     }
                 }
             }
         }
 
-        Ok(())
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Hotkeys already in use by another application: {}",
+                failed.join(", ")
+            ))
+        }
     }
 
     /// Returns the action associated with a hotkey ID.
     pub fn get_action(&self, hotkey_id: i32) -> Option<HotkeyAction> {
-        self.registered_hotkeys.get(&hotkey_id).copied()
+        self.registered_hotkeys.get(&hotkey_id).cloned()
+    }
+
+    /// Returns (keys, description) for every registered hotkey, e.g.
+    /// `("Alt + Shift + 3", "Move window to workspace 3")`, in binding order.
+    pub fn bindings(&self) -> Vec<(String, String)> {
+        let mut ids: Vec<i32> = self.registered_hotkeys.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter_map(|id| {
+                let action = self.registered_hotkeys.get(&id)?;
+                let (modifiers, vk) = self.registered_keys.get(&id)?;
+                Some((format_hotkey(*modifiers, *vk), action.description()))
+            })
+            .collect()
     }
 
     /// Unregisters all hotkeys.
@@ -212,3 +562,170 @@ impl HotkeyManager {
         }
     }
 }
+
+impl Default for HotkeyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a built-in binding's `modifiers` with `modifier` held instead of `Alt`.
+fn with_modifier(modifiers: HOT_KEY_MODIFIERS, modifier: HotkeyModifier) -> HOT_KEY_MODIFIERS {
+    let held = match modifier {
+        HotkeyModifier::Alt => MOD_ALT,
+        HotkeyModifier::Win => MOD_WIN,
+    };
+    HOT_KEY_MODIFIERS(modifiers.0 & !MOD_ALT.0) | held
+}
+
+/// Parses a hotkey such as `"Alt+Enter"` or `"Ctrl+Shift+F5"`.
+///
+/// Modifiers are `Alt`, `Ctrl`, `Shift` and `Win`; the key is a letter, digit,
+/// `F1`-`F24`, or one of `Enter`, `Space`, `Tab`, `Escape`, `Backspace`,
+/// `Delete`, `Insert`, `Home`, `End`, `PageUp`, `PageDown` and the arrow keys
+/// `Left`, `Right`, `Up`, `Down`. Names are case-insensitive.
+pub fn parse_hotkey(keys: &str) -> Result<(HOT_KEY_MODIFIERS, VIRTUAL_KEY), String> {
+    let parts: Vec<&str> = keys.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts
+        .split_last()
+        .ok_or_else(|| format!("empty hotkey '{}'", keys))?;
+
+    let mut modifiers = HOT_KEY_MODIFIERS(0);
+    for name in modifier_names {
+        modifiers |= match name.to_ascii_lowercase().as_str() {
+            "alt" => MOD_ALT,
+            "ctrl" | "control" => MOD_CONTROL,
+            "shift" => MOD_SHIFT,
+            "win" => MOD_WIN,
+            _ => return Err(format!("unknown modifier '{}' in '{}'", name, keys)),
+        };
+    }
+    if modifiers.0 == 0 {
+        return Err(format!("hotkey '{}' needs at least one modifier", keys));
+    }
+
+    let key_name = key.to_ascii_uppercase();
+    let vk = match key_name.as_str() {
+        "ENTER" | "RETURN" => VK_RETURN,
+        "SPACE" => VK_SPACE,
+        "TAB" => VK_TAB,
+        "ESCAPE" | "ESC" => VK_ESCAPE,
+        "BACKSPACE" => VK_BACK,
+        "DELETE" => VK_DELETE,
+        "INSERT" => VK_INSERT,
+        "HOME" => VK_HOME,
+        "END" => VK_END,
+        "PAGEUP" => VK_PRIOR,
+        "PAGEDOWN" => VK_NEXT,
+        "LEFT" => VK_LEFT,
+        "RIGHT" => VK_RIGHT,
+        "UP" => VK_UP,
+        "DOWN" => VK_DOWN,
+        // Letters and digits share their ASCII codes with their virtual keys
+        name if name.len() == 1 && name.as_bytes()[0].is_ascii_alphanumeric() => {
+            VIRTUAL_KEY(name.as_bytes()[0] as u16)
+        }
+        name => match name.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+            Some(n @ 1..=24) => VIRTUAL_KEY(VK_F1.0 + n - 1),
+            _ => return Err(format!("unknown key '{}' in '{}'", key, keys)),
+        },
+    };
+    Ok((modifiers, vk))
+}
+
+/// Formats a hotkey for display, e.g. `"Alt + Shift + Left"`.
+pub fn format_hotkey(modifiers: HOT_KEY_MODIFIERS, vk: VIRTUAL_KEY) -> String {
+    let mut parts = Vec::new();
+    for (modifier, name) in [
+        (MOD_ALT, "Alt"),
+        (MOD_CONTROL, "Ctrl"),
+        (MOD_SHIFT, "Shift"),
+        (MOD_WIN, "Win"),
+    ] {
+        if modifiers.0 & modifier.0 != 0 {
+            parts.push(name.to_string());
+        }
+    }
+    let key = match vk {
+        VK_RETURN => "Enter".to_string(),
+        VK_SPACE => "Space".to_string(),
+        VK_TAB => "Tab".to_string(),
+        VK_ESCAPE => "Escape".to_string(),
+        VK_BACK => "Backspace".to_string(),
+        VK_DELETE => "Delete".to_string(),
+        VK_INSERT => "Insert".to_string(),
+        VK_HOME => "Home".to_string(),
+        VK_END => "End".to_string(),
+        VK_PRIOR => "PageUp".to_string(),
+        VK_NEXT => "PageDown".to_string(),
+        VK_LEFT => "Left".to_string(),
+        VK_RIGHT => "Right".to_string(),
+        VK_UP => "Up".to_string(),
+        VK_DOWN => "Down".to_string(),
+        VK_OEM_2 => "/".to_string(),
+        VK_OEM_3 => "`".to_string(),
+        VK_OEM_4 => "[".to_string(),
+        VK_OEM_6 => "]".to_string(),
+        VK_OEM_PLUS => "=".to_string(),
+        VK_OEM_MINUS => "-".to_string(),
+        VIRTUAL_KEY(code) if (VK_F1.0..=VK_F24.0).contains(&code) => {
+            format!("F{}", code - VK_F1.0 + 1)
+        }
+        VIRTUAL_KEY(code) if (code as u8).is_ascii_alphanumeric() && code < 0x80 => {
+            (code as u8 as char).to_string()
+        }
+        VIRTUAL_KEY(code) => format!("0x{:02X}", code),
+    };
+    parts.push(key);
+    parts.join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(parse_hotkey("Alt+Enter"), Ok((MOD_ALT, VK_RETURN)));
+        assert_eq!(
+            parse_hotkey("ctrl + shift + t"),
+            Ok((MOD_CONTROL | MOD_SHIFT, VIRTUAL_KEY(b'T' as u16)))
+        );
+        assert_eq!(parse_hotkey("Win+F12"), Ok((MOD_WIN, VK_F12)));
+        assert_eq!(parse_hotkey("Alt+0"), Ok((MOD_ALT, VK_0)));
+    }
+
+    #[test]
+    fn the_configured_modifier_replaces_alt() {
+        assert_eq!(
+            with_modifier(MOD_ALT | MOD_SHIFT, HotkeyModifier::Win),
+            MOD_WIN | MOD_SHIFT
+        );
+        assert_eq!(
+            with_modifier(MOD_ALT | MOD_CONTROL, HotkeyModifier::Alt),
+            MOD_ALT | MOD_CONTROL
+        );
+    }
+
+    #[test]
+    fn formats_hotkeys_for_display() {
+        assert_eq!(
+            format_hotkey(MOD_ALT | MOD_SHIFT, VK_LEFT),
+            "Alt + Shift + Left"
+        );
+        assert_eq!(format_hotkey(MOD_ALT, VK_OEM_2), "Alt + /");
+        for keys in ["Ctrl+Alt+T", "Win+F12", "Alt+PageDown", "Shift+0"] {
+            let (modifiers, vk) = parse_hotkey(keys).unwrap();
+            let shown = format_hotkey(modifiers, vk);
+            assert_eq!(parse_hotkey(&shown.replace(' ', "")), Ok((modifiers, vk)));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_or_unmodified_keys() {
+        assert!(parse_hotkey("Enter").is_err());
+        assert!(parse_hotkey("Hyper+Enter").is_err());
+        assert!(parse_hotkey("Alt+F25").is_err());
+        assert!(parse_hotkey("Alt+").is_err());
+    }
+}
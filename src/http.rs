@@ -0,0 +1,173 @@
+//! Localhost HTTP endpoint mirroring the IPC commands.
+//!
+//! Only built with the `http` feature, and only started when `[http] port`
+//! is set. Tools that can't open a named pipe (Stream Deck plugins, browser
+//! extensions, home automation) send the same command lines as `POST`
+//! requests, either in the body or as the path (`POST /workspace/3`). `GET`
+//! routes read the state the state stream sends: `/state`, `/workspace`,
+//! `/windows` and `/focus`.
+//!
+//! Every request must carry the configured token as `Authorization: Bearer
+//! <token>`, since any local program or web page can reach localhost.
+
+use crate::state_stream::{BarSnapshot, BarState};
+use log::{debug, error, info};
+use std::io::Read;
+use std::thread;
+use tiny_http::{Method, Response, Server};
+
+/// Longest request body the server reads.
+const MAX_BODY_LEN: u64 = 4096;
+
+/// Starts the HTTP server thread on `127.0.0.1:port`.
+///
+/// `handler` works like the IPC handler: it receives the command line and
+/// returns the reply text, on the server thread. `GET` requests are answered
+/// from `state`.
+pub fn spawn_server<F>(
+    port: u16,
+    token: String,
+    state: BarSnapshot,
+    handler: F,
+) -> Result<(), String>
+where
+    F: Fn(&str) -> String + Send + 'static,
+{
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    info!("HTTP server listening on 127.0.0.1:{}", port);
+
+    thread::Builder::new()
+        .name("megatile-http".to_string())
+        .spawn(move || {
+            for mut request in server.incoming_requests() {
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.as_str().to_string());
+                let (status, reply) = if !is_authorized(authorization.as_deref(), &token) {
+                    (401, "error: missing or wrong token".to_string())
+                } else if *request.method() == Method::Get {
+                    match state_route(request.url(), &state.get()) {
+                        Some(json) => (200, json),
+                        None => (404, "error: no such state route".to_string()),
+                    }
+                } else if *request.method() != Method::Post {
+                    (
+                        405,
+                        "error: commands must be sent with POST, state read with GET".to_string(),
+                    )
+                } else {
+                    let mut body = String::new();
+                    match request
+                        .as_reader()
+                        .take(MAX_BODY_LEN)
+                        .read_to_string(&mut body)
+                    {
+                        Ok(_) => {
+                            let command = command_from_request(request.url(), &body);
+                            debug!("HTTP command: {}", command);
+                            let reply = handler(&command);
+                            let status = if reply.starts_with("error:") {
+                                400
+                            } else {
+                                200
+                            };
+                            (status, reply)
+                        }
+                        Err(e) => (400, format!("error: unreadable body: {}", e)),
+                    }
+                };
+                if let Err(e) =
+                    request.respond(Response::from_string(reply).with_status_code(status))
+                {
+                    error!("Failed to send HTTP reply: {}", e);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn HTTP thread: {}", e))?;
+    Ok(())
+}
+
+/// Returns true if an `Authorization` header carries `token`.
+fn is_authorized(authorization: Option<&str>, token: &str) -> bool {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.trim().as_bytes()))
+}
+
+/// Compares two byte strings in time that depends only on their lengths, so
+/// the reply time doesn't give away how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The JSON a `GET` of `url` answers with, or `None` for an unknown route.
+fn state_route(url: &str, state: &BarState) -> Option<String> {
+    let path = url.split('?').next().unwrap_or_default();
+    match path.trim_end_matches('/') {
+        "/state" => Some(state.to_json()),
+        "/workspace" => Some(format!("{{\"workspace\":{}}}", state.active_workspace)),
+        "/windows" => Some(state.workspaces_json()),
+        "/focus" => Some(state.focused_json()),
+        _ => None,
+    }
+}
+
+/// The command line for a request: the body if there is one, otherwise the
+/// path with its segments joined by spaces.
+fn command_from_request(url: &str, body: &str) -> String {
+    if !body.trim().is_empty() {
+        return body.trim().to_string();
+    }
+    let path = url.split('?').next().unwrap_or_default();
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_come_from_the_body_or_the_path() {
+        assert_eq!(command_from_request("/", "workspace 3\n"), "workspace 3");
+        assert_eq!(
+            command_from_request("/move-to-workspace/12?source=deck", ""),
+            "move-to-workspace 12"
+        );
+    }
+
+    #[test]
+    fn only_the_configured_bearer_token_is_authorized() {
+        assert!(is_authorized(Some("Bearer s3cret"), "s3cret"));
+        assert!(is_authorized(Some("Bearer s3cret "), "s3cret\n"));
+        assert!(!is_authorized(Some("Bearer wrong"), "s3cret"));
+        assert!(!is_authorized(None, "s3cret"));
+        assert!(!is_authorized(Some("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(Some("s3cret"), "s3cret"));
+    }
+
+    #[test]
+    fn state_routes_read_the_snapshot() {
+        let state = BarState {
+            active_workspace: 2,
+            window_counts: vec![1, 0],
+            ..BarState::default()
+        };
+        assert_eq!(
+            state_route("/workspace", &state).as_deref(),
+            Some(r#"{"workspace":2}"#)
+        );
+        assert_eq!(
+            state_route("/windows/?pretty", &state).as_deref(),
+            Some(r#"[{"number":1,"windows":1},{"number":2,"windows":0}]"#)
+        );
+        assert_eq!(state_route("/focus", &state).as_deref(), Some("null"));
+        assert_eq!(state_route("/state", &state), Some(state.to_json()));
+        assert_eq!(state_route("/workspace/3", &state), None);
+    }
+}
@@ -0,0 +1,240 @@
+//! Application icons for windows, converted once and cached.
+//!
+//! A window's icon comes from the window itself (`WM_GETICON`), its window
+//! class, or failing both, its program's executable. Icons are kept as 32-bit
+//! pixels rather than icon handles, so they stay valid after the window that
+//! owned the handle is gone, and any GDI+ surface can draw them.
+
+use crate::windows_lib::get_process_path_for_window;
+use std::collections::HashMap;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BI_RGB, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, DeleteObject, GetDC, GetDIBits,
+    GetObjectW, HBITMAP, ReleaseDC,
+};
+use windows::Win32::Graphics::GdiPlus::{
+    GdipCreateBitmapFromScan0, GdipDisposeImage, GdipDrawImageRectI, GpBitmap, GpGraphics, GpImage,
+};
+use windows::Win32::UI::Shell::ExtractIconExW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    DestroyIcon, GCLP_HICON, GCLP_HICONSM, GetClassLongPtrW, GetIconInfo, HICON, ICON_BIG,
+    ICON_SMALL, ICON_SMALL2, ICONINFO, SMTO_ABORTIFHUNG, SendMessageTimeoutW, WM_GETICON,
+};
+use windows::core::HSTRING;
+
+/// GDI+'s 32-bit straight alpha pixel format, which the windows crate doesn't export.
+const PIXEL_FORMAT_32BPP_ARGB: i32 = 0x0026200A;
+/// How long a window gets to answer `WM_GETICON` before it's treated as hung.
+const GET_ICON_TIMEOUT_MS: u32 = 50;
+
+/// An icon's pixels: BGRA rows, top to bottom, with straight alpha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconImage {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<u8>,
+}
+
+/// Window icons, looked up once per window.
+#[derive(Debug, Default)]
+pub struct IconCache {
+    /// Windows without an icon are kept too, so they aren't looked up again.
+    icons: HashMap<isize, Option<IconImage>>,
+}
+
+impl IconCache {
+    /// Returns the icon of `hwnd`, looking it up the first time.
+    pub fn get(&mut self, hwnd: isize) -> Option<&IconImage> {
+        self.icons
+            .entry(hwnd)
+            .or_insert_with(|| window_icon(HWND(hwnd as _)))
+            .as_ref()
+    }
+
+    /// Returns the icon of `hwnd` if it was looked up before.
+    pub fn cached(&self, hwnd: isize) -> Option<&IconImage> {
+        self.icons.get(&hwnd).and_then(Option::as_ref)
+    }
+
+    /// Forgets the windows `keep` returns false for.
+    pub fn retain(&mut self, keep: impl Fn(isize) -> bool) {
+        self.icons.retain(|&hwnd, _| keep(hwnd));
+    }
+}
+
+/// Returns the small icon of a window, or of its executable if the window
+/// has none.
+pub fn window_icon(hwnd: HWND) -> Option<IconImage> {
+    unsafe {
+        for kind in [ICON_SMALL2, ICON_SMALL, ICON_BIG] {
+            let mut result = 0usize;
+            let answered = SendMessageTimeoutW(
+                hwnd,
+                WM_GETICON,
+                WPARAM(kind as usize),
+                LPARAM(0),
+                SMTO_ABORTIFHUNG,
+                GET_ICON_TIMEOUT_MS,
+                Some(&mut result),
+            );
+            // The window keeps ownership of the handle
+            if answered.0 != 0
+                && result != 0
+                && let Some(icon) = icon_image(HICON(result as _))
+            {
+                return Some(icon);
+            }
+        }
+        for index in [GCLP_HICONSM, GCLP_HICON] {
+            let handle = GetClassLongPtrW(hwnd, index);
+            if handle != 0
+                && let Some(icon) = icon_image(HICON(handle as _))
+            {
+                return Some(icon);
+            }
+        }
+        executable_icon(hwnd)
+    }
+}
+
+/// Returns the first small icon in the executable that owns `hwnd`.
+fn executable_icon(hwnd: HWND) -> Option<IconImage> {
+    let path = get_process_path_for_window(hwnd)?;
+    unsafe {
+        let mut small = HICON::default();
+        if ExtractIconExW(&HSTRING::from(path), 0, None, Some(&mut small), 1) == 0
+            || small.is_invalid()
+        {
+            return None;
+        }
+        let icon = icon_image(small);
+        let _ = DestroyIcon(small);
+        icon
+    }
+}
+
+/// Copies an icon's pixels out of its bitmaps. Monochrome icons are skipped.
+unsafe fn icon_image(icon: HICON) -> Option<IconImage> {
+    unsafe {
+        let mut info = ICONINFO::default();
+        GetIconInfo(icon, &mut info).ok()?;
+        let image = if info.hbmColor.is_invalid() {
+            None
+        } else {
+            bitmap_pixels(info.hbmColor).map(|(width, height, mut pixels)| {
+                if let Some((_, _, mask)) = bitmap_pixels(info.hbmMask) {
+                    apply_mask(&mut pixels, &mask);
+                }
+                IconImage {
+                    width,
+                    height,
+                    pixels,
+                }
+            })
+        };
+        // GetIconInfo hands back copies of the bitmaps
+        let _ = DeleteObject(info.hbmColor.into());
+        let _ = DeleteObject(info.hbmMask.into());
+        image
+    }
+}
+
+/// Reads a bitmap as 32-bit top-down BGRA rows.
+unsafe fn bitmap_pixels(bitmap: HBITMAP) -> Option<(i32, i32, Vec<u8>)> {
+    unsafe {
+        let mut header = BITMAP::default();
+        if GetObjectW(
+            bitmap.into(),
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut header as *mut BITMAP as *mut std::ffi::c_void),
+        ) == 0
+        {
+            return None;
+        }
+        let (width, height) = (header.bmWidth, header.bmHeight);
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        let mut bmi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, // Top-down
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        let screen_dc = GetDC(None);
+        let lines = GetDIBits(
+            screen_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut bmi,
+            DIB_RGB_COLORS,
+        );
+        let _ = ReleaseDC(None, screen_dc);
+        (lines == height).then_some((width, height, pixels))
+    }
+}
+
+/// Gives icons drawn without alpha their transparency from the AND mask,
+/// where black marks the pixels that are drawn. Icons with alpha are left
+/// alone.
+fn apply_mask(pixels: &mut [u8], mask: &[u8]) {
+    if pixels.chunks_exact(4).any(|pixel| pixel[3] != 0) {
+        return;
+    }
+    for (pixel, mask) in pixels.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+        pixel[3] = if mask[..3] == [0, 0, 0] { 255 } else { 0 };
+    }
+}
+
+/// Draws `icon` scaled into a `size` pixel square at (`x`, `y`).
+///
+/// # Safety
+/// `graphics` must be a live GDI+ graphics object.
+pub unsafe fn draw_icon(graphics: *mut GpGraphics, icon: &IconImage, x: i32, y: i32, size: i32) {
+    unsafe {
+        let mut bitmap: *mut GpBitmap = std::ptr::null_mut();
+        if GdipCreateBitmapFromScan0(
+            icon.width,
+            icon.height,
+            icon.width * 4,
+            PIXEL_FORMAT_32BPP_ARGB,
+            Some(icon.pixels.as_ptr()),
+            &mut bitmap,
+        )
+        .0 != 0
+            || bitmap.is_null()
+        {
+            return;
+        }
+        let _ = GdipDrawImageRectI(graphics, bitmap as *mut GpImage, x, y, size, size);
+        GdipDisposeImage(bitmap as *mut GpImage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_without_alpha_take_it_from_the_mask() {
+        let mut pixels = vec![10, 20, 30, 0, 40, 50, 60, 0];
+        let mask = vec![0, 0, 0, 0, 255, 255, 255, 0];
+        apply_mask(&mut pixels, &mask);
+        assert_eq!(pixels, vec![10, 20, 30, 255, 40, 50, 60, 0]);
+
+        // An icon with an alpha channel already says what's transparent
+        let mut pixels = vec![10, 20, 30, 128, 40, 50, 60, 0];
+        apply_mask(&mut pixels, &mask);
+        assert_eq!(pixels, vec![10, 20, 30, 128, 40, 50, 60, 0]);
+    }
+}
@@ -0,0 +1,484 @@
+//! Named-pipe IPC for talking to a running instance.
+//!
+//! Clients write a single command line to [`PIPE_NAME`] and read the reply
+//! until the server closes the connection. Commands are served one at a time
+//! on a dedicated thread.
+//!
+//! Scripts that can't easily open a pipe can instead send the same command
+//! lines as `WM_COPYDATA` to the hidden message window; see
+//! [`decode_copydata`].
+//!
+//! Besides Megatile's own commands, [`parse_command`] understands a subset of
+//! komorebic's vocabulary so scripts written for komorebi keep working.
+
+use crate::hotkeys::HotkeyAction;
+use log::{debug, error, info};
+use std::thread;
+use std::time::Duration;
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_BROKEN_PIPE, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED,
+    ERROR_PIPE_NOT_CONNECTED, GENERIC_READ, GENERIC_WRITE, HANDLE,
+};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_NONE, FlushFileBuffers, OPEN_EXISTING,
+    PIPE_ACCESS_DUPLEX, ReadFile, WriteFile,
+};
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT, WaitNamedPipeW,
+};
+use windows::core::{PCWSTR, w};
+
+/// Pipe path the server listens on.
+pub const PIPE_NAME: &str = r"\\.\pipe\megatile";
+const PIPE_NAME_W: PCWSTR = w!(r"\\.\pipe\megatile");
+
+/// Longest command line the server accepts.
+const MAX_COMMAND_LEN: usize = 4096;
+
+/// `dwData` of a `WM_COPYDATA` command encoded as UTF-8.
+pub const COPYDATA_COMMAND_UTF8: usize = 0x4D54;
+/// `dwData` of a `WM_COPYDATA` command encoded as UTF-16 (AutoHotkey's native strings).
+pub const COPYDATA_COMMAND_UTF16: usize = 0x4D55;
+
+/// A parsed IPC command.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Report the performance counters.
+    Telemetry,
+    /// Restart Megatile, keeping every window on its workspace.
+    Restart,
+    /// Run an action as if its hotkey was pressed.
+    Action(HotkeyAction),
+}
+
+/// Parses a command line.
+///
+/// Megatile's workspace numbers start at 1. The komorebic aliases
+/// (`focus-workspace`, `send-to-workspace`) count from 0 like komorebic does.
+/// `move-to-workspace` keeps Megatile's meaning unless the line starts with
+/// `komorebic`, which makes it count from 0 as well. Workspaces past
+/// `workspace_count` are rejected.
+pub fn parse_command(line: &str, workspace_count: u8) -> Result<Command, String> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let komorebic = words.first() == Some(&"komorebic");
+    if komorebic {
+        words.remove(0);
+    }
+    let action = match words.as_slice() {
+        ["telemetry"] => return Ok(Command::Telemetry),
+        ["restart"] => return Ok(Command::Restart),
+        ["workspace", number] => {
+            HotkeyAction::SwitchWorkspace(workspace_arg(number, 1, workspace_count)?)
+        }
+        ["move-to-workspace", number] => {
+            let first = if komorebic { 0 } else { 1 };
+            HotkeyAction::MoveToWorkspace(workspace_arg(number, first, workspace_count)?)
+        }
+        ["split-ratio", percent] => HotkeyAction::SetSplitRatio(percent_arg(percent)?),
+        ["save-layout", name] => HotkeyAction::SaveLayout(name.to_string()),
+        ["load-layout", name] => HotkeyAction::LoadLayout(name.to_string()),
+        ["show-desktop"] => HotkeyAction::ToggleShowDesktop,
+        ["toggle-topmost"] => HotkeyAction::ToggleTopmost,
+        ["toggle-monitor"] => HotkeyAction::ToggleMonitorManaged,
+        ["balance-monitors"] => HotkeyAction::BalanceMonitors,
+        ["gather-windows"] => HotkeyAction::GatherOnMonitor,
+        ["flash-focus"] => HotkeyAction::FlashFocus,
+        ["focus-urgent"] => HotkeyAction::FocusUrgent,
+        ["adopt-windows"] => HotkeyAction::AdoptWindows,
+        ["opacity", "increase"] => HotkeyAction::IncreaseOpacity,
+        ["opacity", "decrease"] => HotkeyAction::DecreaseOpacity,
+
+        // komorebic aliases
+        ["focus-workspace", index] => {
+            HotkeyAction::SwitchWorkspace(workspace_arg(index, 0, workspace_count)?)
+        }
+        ["send-to-workspace", index] => {
+            HotkeyAction::MoveToWorkspace(workspace_arg(index, 0, workspace_count)?)
+        }
+        ["focus", direction] => match *direction {
+            "left" => HotkeyAction::FocusLeft,
+            "right" => HotkeyAction::FocusRight,
+            "up" => HotkeyAction::FocusUp,
+            "down" => HotkeyAction::FocusDown,
+            _ => return Err(format!("invalid direction '{}'", direction)),
+        },
+        ["move", direction] => match *direction {
+            "left" => HotkeyAction::MoveLeft,
+            "right" => HotkeyAction::MoveRight,
+            "up" => HotkeyAction::MoveUp,
+            "down" => HotkeyAction::MoveDown,
+            _ => return Err(format!("invalid direction '{}'", direction)),
+        },
+        ["stack", direction] => match *direction {
+            "left" => HotkeyAction::MergeLeft,
+            "right" => HotkeyAction::MergeRight,
+            "up" => HotkeyAction::MergeUp,
+            "down" => HotkeyAction::MergeDown,
+            _ => return Err(format!("invalid direction '{}'", direction)),
+        },
+        ["send-to-monitor", direction] => match *direction {
+            "left" => HotkeyAction::SendToMonitorLeft,
+            "right" => HotkeyAction::SendToMonitorRight,
+            "up" => HotkeyAction::SendToMonitorUp,
+            "down" => HotkeyAction::SendToMonitorDown,
+            _ => return Err(format!("invalid direction '{}'", direction)),
+        },
+        ["cycle-stack", "next"] => HotkeyAction::CycleTileGroup,
+        ["cycle-focus", "next"] => HotkeyAction::FocusNext,
+        ["cycle-focus", "previous"] => HotkeyAction::FocusPrevious,
+        ["cycle-workspace", "next"] => HotkeyAction::NextWorkspace,
+        ["cycle-workspace", "previous"] => HotkeyAction::PreviousWorkspace,
+        ["resize-axis", axis, sizing] => match (*axis, *sizing) {
+            ("horizontal", "increase") => HotkeyAction::ResizeHorizontalIncrease,
+            ("horizontal", "decrease") => HotkeyAction::ResizeHorizontalDecrease,
+            ("vertical", "increase") => HotkeyAction::ResizeVerticalIncrease,
+            ("vertical", "decrease") => HotkeyAction::ResizeVerticalDecrease,
+            _ => return Err(format!("invalid resize '{} {}'", axis, sizing)),
+        },
+        // komorebic takes an axis; Megatile flips the focused region's split
+        ["flip-layout", ..] => HotkeyAction::FlipRegion,
+        ["toggle-float"] => HotkeyAction::ToggleTiling,
+        ["toggle-float-workspace"] => HotkeyAction::ToggleWorkspaceTiling,
+        ["toggle-monocle"] => HotkeyAction::ToggleMaximize,
+        ["toggle-maximize"] => HotkeyAction::ToggleMaximize,
+        ["close"] => HotkeyAction::CloseWindow,
+        ["minimize"] => HotkeyAction::MinimizeWindow,
+        _ => return Err(format!("unknown command '{}'", line)),
+    };
+    Ok(Command::Action(action))
+}
+
+/// Parses a workspace argument counted from `first` (0 or 1), up to
+/// `count` workspaces.
+fn workspace_arg(arg: &str, first: u8, count: u8) -> Result<u8, String> {
+    arg.parse::<u8>()
+        .ok()
+        .and_then(|number| number.checked_add(1 - first))
+        .filter(|workspace| (1..=count).contains(workspace))
+        .ok_or_else(|| {
+            format!(
+                "invalid workspace '{}', expected {}-{}",
+                arg,
+                first,
+                count + first - 1
+            )
+        })
+}
+
+/// Parses a split share in percent, within the limits the tiler allows.
+fn percent_arg(arg: &str) -> Result<u8, String> {
+    arg.trim_end_matches('%')
+        .parse::<u8>()
+        .ok()
+        .filter(|percent| (10..=90).contains(percent))
+        .ok_or_else(|| format!("invalid split ratio '{}', expected 10-90", arg))
+}
+
+/// Starts the IPC server thread.
+///
+/// `handler` receives each trimmed command line and returns the reply text.
+/// It runs on the IPC thread, so anything touching window state must be
+/// forwarded to the main event loop.
+pub fn spawn_server<F>(handler: F) -> Result<(), String>
+where
+    F: Fn(&str) -> String + Send + 'static,
+{
+    thread::Builder::new()
+        .name("megatile-ipc".to_string())
+        .spawn(move || {
+            let pipe = unsafe {
+                CreateNamedPipeW(
+                    PIPE_NAME_W,
+                    PIPE_ACCESS_DUPLEX,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    MAX_COMMAND_LEN as u32,
+                    MAX_COMMAND_LEN as u32,
+                    0,
+                    None,
+                )
+            };
+            if pipe.is_invalid() {
+                error!("Failed to create IPC pipe {}", PIPE_NAME);
+                return;
+            }
+            info!("IPC server listening on {}", PIPE_NAME);
+
+            loop {
+                if let Err(e) = unsafe { ConnectNamedPipe(pipe, None) }
+                    && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+                {
+                    error!("Failed to accept IPC client: {}", e);
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+
+                match read_command(pipe) {
+                    Ok(command) => {
+                        debug!("IPC command: {}", command);
+                        let reply = handler(&command);
+                        if let Err(e) = write_all(pipe, reply.as_bytes()) {
+                            error!("Failed to send IPC reply: {}", e);
+                        }
+                        unsafe {
+                            let _ = FlushFileBuffers(pipe);
+                        }
+                    }
+                    Err(e) => error!("Failed to read IPC command: {}", e),
+                }
+
+                unsafe {
+                    let _ = DisconnectNamedPipe(pipe);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn IPC thread: {}", e))?;
+    Ok(())
+}
+
+/// Sends a command to the running instance and returns its reply.
+pub fn send_command(command: &str) -> Result<String, String> {
+    let pipe = open_client_pipe()?;
+
+    let mut message = command.trim().to_string();
+    message.push('\n');
+    let result = write_all(pipe, message.as_bytes()).and_then(|_| read_to_end(pipe));
+
+    unsafe {
+        let _ = CloseHandle(pipe);
+    }
+    result
+}
+
+fn open_client_pipe() -> Result<HANDLE, String> {
+    for _ in 0..2 {
+        let result = unsafe {
+            CreateFileW(
+                PIPE_NAME_W,
+                GENERIC_READ.0 | GENERIC_WRITE.0,
+                FILE_SHARE_NONE,
+                None,
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL,
+                None,
+            )
+        };
+        match result {
+            Ok(pipe) => return Ok(pipe),
+            Err(e) if e.code() == ERROR_PIPE_BUSY.to_hresult() => unsafe {
+                // Another client is being served; wait for the pipe to free up
+                let _ = WaitNamedPipeW(PIPE_NAME_W, 2000);
+            },
+            Err(e) => return Err(format!("Failed to connect to {}: {}", PIPE_NAME, e)),
+        }
+    }
+    Err(format!("Timed out waiting for {}", PIPE_NAME))
+}
+
+/// Reads bytes up to the first newline (or end of input) as a command line.
+fn read_command(pipe: HANDLE) -> Result<String, String> {
+    let mut command = Vec::new();
+    let mut buffer = [0u8; 512];
+    while command.len() < MAX_COMMAND_LEN && !command.contains(&b'\n') {
+        let mut read = 0u32;
+        unsafe { ReadFile(pipe, Some(&mut buffer), Some(&mut read), None) }
+            .map_err(|e| format!("Failed to read from pipe: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        command.extend_from_slice(&buffer[..read as usize]);
+    }
+
+    let line = command.split(|b| *b == b'\n').next().unwrap_or_default();
+    Ok(String::from_utf8_lossy(line).trim().to_string())
+}
+
+fn read_to_end(pipe: HANDLE) -> Result<String, String> {
+    let mut reply = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        let mut read = 0u32;
+        match unsafe { ReadFile(pipe, Some(&mut buffer), Some(&mut read), None) } {
+            Ok(()) if read == 0 => break,
+            Ok(()) => reply.extend_from_slice(&buffer[..read as usize]),
+            // The server closing its end is the normal end of a reply
+            Err(e)
+                if e.code() == ERROR_BROKEN_PIPE.to_hresult()
+                    || e.code() == ERROR_PIPE_NOT_CONNECTED.to_hresult() =>
+            {
+                break;
+            }
+            Err(e) => return Err(format!("Failed to read reply: {}", e)),
+        }
+    }
+    Ok(String::from_utf8_lossy(&reply).into_owned())
+}
+
+pub(crate) fn write_all(pipe: HANDLE, mut bytes: &[u8]) -> Result<(), String> {
+    while !bytes.is_empty() {
+        let mut written = 0u32;
+        unsafe { WriteFile(pipe, Some(bytes), Some(&mut written), None) }
+            .map_err(|e| format!("Failed to write to pipe: {}", e))?;
+        bytes = &bytes[written as usize..];
+    }
+    Ok(())
+}
+
+/// Decodes the payload of a `WM_COPYDATA` command.
+///
+/// Returns `None` for other `dwData` values and oversized payloads, so
+/// unrelated messages are left alone. A trailing NUL is allowed.
+pub fn decode_copydata(kind: usize, bytes: &[u8]) -> Option<String> {
+    if bytes.len() > MAX_COMMAND_LEN * 2 {
+        return None;
+    }
+    let command = match kind {
+        COPYDATA_COMMAND_UTF8 => String::from_utf8_lossy(bytes).into_owned(),
+        COPYDATA_COMMAND_UTF16 => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    Some(command.trim_end_matches('\0').trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::MAX_WORKSPACES;
+
+    #[test]
+    fn komorebic_aliases_map_to_actions() {
+        let action = |line| match parse_command(line, MAX_WORKSPACES) {
+            Ok(Command::Action(action)) => Some(action),
+            _ => None,
+        };
+        assert!(matches!(
+            action("workspace 3"),
+            Some(HotkeyAction::SwitchWorkspace(3))
+        ));
+        assert!(matches!(
+            action("focus-workspace 0"),
+            Some(HotkeyAction::SwitchWorkspace(1))
+        ));
+        assert!(matches!(
+            action("send-to-workspace 19"),
+            Some(HotkeyAction::MoveToWorkspace(20))
+        ));
+        assert!(matches!(
+            action("focus left"),
+            Some(HotkeyAction::FocusLeft)
+        ));
+        assert!(matches!(
+            action("resize-axis vertical decrease"),
+            Some(HotkeyAction::ResizeVerticalDecrease)
+        ));
+        assert!(matches!(
+            action("toggle-float"),
+            Some(HotkeyAction::ToggleTiling)
+        ));
+        assert!(matches!(
+            action("minimize"),
+            Some(HotkeyAction::MinimizeWindow)
+        ));
+        assert!(matches!(
+            action("stack right"),
+            Some(HotkeyAction::MergeRight)
+        ));
+        assert!(matches!(
+            action("split-ratio 66"),
+            Some(HotkeyAction::SetSplitRatio(66))
+        ));
+        assert!(action("split-ratio 95").is_none());
+        assert!(matches!(
+            action("toggle-monitor"),
+            Some(HotkeyAction::ToggleMonitorManaged)
+        ));
+        assert!(matches!(
+            action("send-to-monitor up"),
+            Some(HotkeyAction::SendToMonitorUp)
+        ));
+        assert!(action("send-to-monitor sideways").is_none());
+        assert!(matches!(
+            action("balance-monitors"),
+            Some(HotkeyAction::BalanceMonitors)
+        ));
+        assert!(matches!(
+            action("flash-focus"),
+            Some(HotkeyAction::FlashFocus)
+        ));
+        assert!(matches!(
+            action("toggle-float-workspace"),
+            Some(HotkeyAction::ToggleWorkspaceTiling)
+        ));
+        assert!(matches!(
+            action("adopt-windows"),
+            Some(HotkeyAction::AdoptWindows)
+        ));
+        assert!(matches!(
+            action("load-layout dev"),
+            Some(HotkeyAction::LoadLayout(name)) if name == "dev"
+        ));
+
+        assert!(matches!(
+            action("move-to-workspace 3"),
+            Some(HotkeyAction::MoveToWorkspace(3))
+        ));
+        assert!(matches!(
+            action("komorebic move-to-workspace 3"),
+            Some(HotkeyAction::MoveToWorkspace(4))
+        ));
+        assert!(matches!(
+            action("komorebic focus-workspace 0"),
+            Some(HotkeyAction::SwitchWorkspace(1))
+        ));
+        assert!(matches!(
+            action("toggle-monocle"),
+            Some(HotkeyAction::ToggleMaximize)
+        ));
+
+        assert!(action("workspace 0").is_none());
+        assert!(action("send-to-workspace 20").is_none());
+        assert!(action("focus sideways").is_none());
+        assert!(matches!(
+            parse_command("telemetry", MAX_WORKSPACES),
+            Ok(Command::Telemetry)
+        ));
+    }
+
+    #[test]
+    fn workspaces_past_the_configured_count_are_rejected() {
+        assert!(matches!(
+            parse_command("workspace 9", 9),
+            Ok(Command::Action(HotkeyAction::SwitchWorkspace(9)))
+        ));
+        assert_eq!(
+            parse_command("workspace 10", 9).err().as_deref(),
+            Some("invalid workspace '10', expected 1-9")
+        );
+        assert!(parse_command("focus-workspace 9", 9).is_err());
+        assert!(parse_command("komorebic move-to-workspace 8", 9).is_ok());
+    }
+
+    #[test]
+    fn copydata_commands_decode_from_either_encoding() {
+        assert_eq!(
+            decode_copydata(COPYDATA_COMMAND_UTF8, b"workspace 3\0").as_deref(),
+            Some("workspace 3")
+        );
+        let utf16: Vec<u8> = "move-to-workspace 12\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            decode_copydata(COPYDATA_COMMAND_UTF16, &utf16).as_deref(),
+            Some("move-to-workspace 12")
+        );
+        assert_eq!(decode_copydata(1, b"workspace 3"), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Launching programs and routing their windows to a workspace.
+//!
+//! Routing is done by the [`RuleEngine`]: a launch with a target workspace
+//! registers a one-shot rule for the process expected to own its window.
+
+use crate::config::LaunchCommand;
+use crate::rules::RuleEngine;
+use log::info;
+use std::path::Path;
+use windows::Win32::UI::Shell::ShellExecuteW;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+use windows::core::{HSTRING, PCWSTR, w};
+
+/// Launches a command, routing its first window if it targets a workspace.
+pub fn launch(launch: &LaunchCommand, rules: &mut RuleEngine) -> Result<(), String> {
+    spawn(&launch.command)?;
+
+    if let Some(workspace) = launch.workspace {
+        rules.expect_launch(&expected_process(launch), workspace);
+    }
+    Ok(())
+}
+
+/// Starts a command line through the shell, so `PATH` and App Paths entries
+/// (e.g. plain `firefox`) resolve the same way as in the Run dialog.
+pub fn spawn(command: &str) -> Result<(), String> {
+    let (program, arguments) = split_command(command);
+    let program_w = HSTRING::from(program);
+    let arguments_w = HSTRING::from(arguments);
+    let parameters = if arguments.is_empty() {
+        PCWSTR::null()
+    } else {
+        PCWSTR(arguments_w.as_ptr())
+    };
+
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("open"),
+            &program_w,
+            parameters,
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values above 32 indicate success
+    if result.0 as usize <= 32 {
+        return Err(format!(
+            "Failed to launch '{}': error {}",
+            command, result.0 as usize
+        ));
+    }
+
+    info!("Launched: {}", command);
+    Ok(())
+}
+
+/// Splits a command line into the program and its (unparsed) arguments.
+/// The program may be quoted to allow spaces in its path.
+fn split_command(command: &str) -> (&str, &str) {
+    let command = command.trim();
+    if let Some(rest) = command.strip_prefix('"') {
+        match rest.find('"') {
+            Some(end) => (&rest[..end], rest[end + 1..].trim_start()),
+            None => (rest, ""),
+        }
+    } else {
+        match command.find(char::is_whitespace) {
+            Some(end) => (&command[..end], command[end..].trim_start()),
+            None => (command, ""),
+        }
+    }
+}
+
+/// Lowercase process name expected to own the launched program's window.
+fn expected_process(launch: &LaunchCommand) -> String {
+    if let Some(process) = &launch.process {
+        return process.to_lowercase();
+    }
+
+    let (program, _) = split_command(&launch.command);
+    let file_name = program
+        .rsplit(['\\', '/'])
+        .next()
+        .unwrap_or(program)
+        .to_lowercase();
+    if Path::new(&file_name).extension().is_some() {
+        file_name
+    } else {
+        format!("{}.exe", file_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::WindowIdentity;
+
+    fn launch(command: &str, process: Option<&str>) -> LaunchCommand {
+        LaunchCommand {
+            command: command.to_string(),
+            workspace: Some(2),
+            process: process.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn splits_quoted_and_plain_programs() {
+        assert_eq!(
+            split_command(r#""C:\Program Files\App\app.exe" --new "x y""#),
+            (r"C:\Program Files\App\app.exe", r#"--new "x y""#)
+        );
+        assert_eq!(split_command("wt.exe -d C:\\src"), ("wt.exe", "-d C:\\src"));
+        assert_eq!(split_command("  firefox  "), ("firefox", ""));
+    }
+
+    #[test]
+    fn expected_process_defaults_to_program_file_name() {
+        assert_eq!(
+            expected_process(&launch(r#""C:\Apps\Slack.exe" --x"#, None)),
+            "slack.exe"
+        );
+        assert_eq!(expected_process(&launch("firefox", None)), "firefox.exe");
+        assert_eq!(
+            expected_process(&launch("wt", Some("WindowsTerminal.exe"))),
+            "windowsterminal.exe"
+        );
+    }
+
+    #[test]
+    fn claims_are_consumed_once() {
+        let mut rules = RuleEngine::new(Vec::new());
+        let process = expected_process(&launch(r#""C:\Apps\Slack.exe" --x"#, None));
+        rules.expect_launch(&process, 3);
+        let window = |process| WindowIdentity {
+            process: Some(process),
+            class: "",
+            title: "",
+        };
+
+        assert_eq!(rules.workspace_for(&window("Code.exe")), None);
+        assert_eq!(rules.workspace_for(&window("Slack.exe")), Some(3));
+        assert_eq!(rules.workspace_for(&window("Slack.exe")), None);
+    }
+}
@@ -0,0 +1,426 @@
+//! Named layout snapshots: a workspace's splits and which program sat in
+//! each tile, saved to `layouts.toml` next to the config file.
+//!
+//! A snapshot doesn't remember window handles. When it is applied, each
+//! tile is filled with a window from the same process, preferring one with
+//! the same title, so a layout saved yesterday fits today's windows. Tiles
+//! nobody matches are dropped and windows nobody claimed are split in as
+//! usual.
+//!
+//! [`LayoutTemplate`]s are the config-file counterpart: a shape such as
+//! "main window left, the rest stacked right" with a rule per slot, which a
+//! workspace grows into as its windows arrive.
+
+use crate::rules::{WindowIdentity, WindowRule};
+use crate::tiling::{MAX_SPLIT_RATIO, MIN_SPLIT_RATIO, SplitDirection, Tile};
+use crate::workspace::Window;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use windows::Win32::Foundation::RECT;
+
+/// One node of a saved layout tree: a split with two children, or a tile.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LayoutNode {
+    /// Process of the window in this tile, e.g. `"Code.exe"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    /// Title of the window in this tile when the layout was saved.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    /// Handle of the window in this tile, only kept for a restart, where
+    /// handles survive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hwnd: Option<isize>,
+    /// How this node is split, if it isn't a tile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<SplitDirection>,
+    /// Share of the first child.
+    #[serde(default = "default_ratio")]
+    pub ratio: f32,
+    /// The two halves of a split; empty for a tile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<LayoutNode>,
+}
+
+fn default_ratio() -> f32 {
+    0.5
+}
+
+impl LayoutNode {
+    /// Describes `tile`, looking up the window in each leaf in `windows`.
+    pub fn from_tile(tile: &Tile, windows: &[Window]) -> Self {
+        Self::describe(tile, windows, false)
+    }
+
+    /// Like [`LayoutNode::from_tile`], but also remembers each tile's window
+    /// handle, so a restart puts the same windows back in the same tiles.
+    pub fn from_tile_with_handles(tile: &Tile, windows: &[Window]) -> Self {
+        Self::describe(tile, windows, true)
+    }
+
+    fn describe(tile: &Tile, windows: &[Window], handles: bool) -> Self {
+        match (tile.children.as_ref(), tile.split_direction) {
+            (Some(children), Some(direction)) => LayoutNode {
+                split: Some(direction),
+                ratio: tile.split_ratio,
+                children: vec![
+                    Self::describe(&children.0, windows, handles),
+                    Self::describe(&children.1, windows, handles),
+                ],
+                ..Default::default()
+            },
+            _ => {
+                let window = tile
+                    .windows
+                    .first()
+                    .and_then(|hwnd| windows.iter().find(|w| w.hwnd == *hwnd));
+                LayoutNode {
+                    process: window.and_then(|w| w.process_name.clone()),
+                    title: window.map(|w| w.title.clone()).unwrap_or_default(),
+                    hwnd: window.filter(|_| handles).map(|w| w.hwnd),
+                    ratio: default_ratio(),
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    /// Builds a tile tree from this layout, filling tiles with `windows`.
+    ///
+    /// Returns the tree and the windows in tree order. Tiles without a
+    /// matching window hold a placeholder id below zero, which the tiler
+    /// drops as if its window had closed.
+    pub fn to_tile(&self, windows: &[Window]) -> (Tile, Vec<isize>) {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+
+        let mut assigned: Vec<Option<isize>> = vec![None; leaves.len()];
+        let mut taken: Vec<isize> = Vec::new();
+        // Remembered handles, then process-and-title matches, then process-only matches
+        for (leaf, slot) in leaves.iter().zip(assigned.iter_mut()) {
+            if let Some(hwnd) = leaf
+                .hwnd
+                .filter(|hwnd| windows.iter().any(|w| w.hwnd == *hwnd))
+            {
+                *slot = Some(hwnd);
+                taken.push(hwnd);
+            }
+        }
+        for exact in [true, false] {
+            for (leaf, slot) in leaves.iter().zip(assigned.iter_mut()) {
+                if slot.is_some() || leaf.process.is_none() {
+                    continue;
+                }
+                let found = windows.iter().find(|w| {
+                    !taken.contains(&w.hwnd)
+                        && w.process_name
+                            .as_deref()
+                            .zip(leaf.process.as_deref())
+                            .is_some_and(|(a, b)| a.eq_ignore_ascii_case(b))
+                        && (!exact || w.title == leaf.title)
+                });
+                if let Some(window) = found {
+                    *slot = Some(window.hwnd);
+                    taken.push(window.hwnd);
+                }
+            }
+        }
+
+        let order = assigned.iter().flatten().copied().collect();
+        let mut ids = assigned.into_iter();
+        let mut placeholder = 0;
+        let tile = self.build(&mut || {
+            ids.next().flatten().unwrap_or_else(|| {
+                placeholder -= 1;
+                placeholder
+            })
+        });
+        (tile, order)
+    }
+
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a LayoutNode>) {
+        if self.children.len() == 2 {
+            self.children[0].collect_leaves(leaves);
+            self.children[1].collect_leaves(leaves);
+        } else {
+            leaves.push(self);
+        }
+    }
+
+    fn build(&self, next_id: &mut impl FnMut() -> isize) -> Tile {
+        let mut tile = Tile::new(RECT::default());
+        match (self.split, self.children.as_slice()) {
+            (Some(direction), [first, second]) => {
+                tile.split_direction = Some(direction);
+                tile.split_ratio = self.ratio;
+                let first = first.build(next_id);
+                let second = second.build(next_id);
+                tile.children = Some(Box::new((first, second)));
+            }
+            _ => tile.windows = vec![next_id()],
+        }
+        tile
+    }
+}
+
+/// How a [`LayoutTemplate`] arranges its slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemplateShape {
+    /// The first slot on the left, the others stacked on the right.
+    #[default]
+    MainStack,
+    /// Equal columns, left to right.
+    Columns,
+    /// Equal rows, top to bottom.
+    Rows,
+}
+
+/// A `[[templates]]` entry: the layout a workspace adopts as its first
+/// windows arrive.
+///
+/// Each window takes the first free slot whose rule it matches. Slots still
+/// waiting for a window are left out, so the layout fills in as windows
+/// open. Once every slot is taken, a window fits no free slot or one closes,
+/// the workspace tiles as usual until it is empty again.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutTemplate {
+    /// Workspace the template applies to.
+    pub workspace: u8,
+    /// How the slots are arranged.
+    #[serde(default)]
+    pub shape: TemplateShape,
+    /// Share of the main slot in a `main-stack` template.
+    #[serde(default = "default_main_ratio")]
+    pub main_ratio: f32,
+    /// Which window goes in each slot, in layout order.
+    pub slots: Vec<WindowRule>,
+}
+
+fn default_main_ratio() -> f32 {
+    0.6
+}
+
+impl LayoutTemplate {
+    /// Checks a template from the config file, with `workspace_count` workspaces.
+    pub fn validate(&self, workspace_count: u8) -> Result<(), String> {
+        if !(1..=workspace_count).contains(&self.workspace) {
+            return Err(format!(
+                "template workspace {} is not between 1 and {}",
+                self.workspace, workspace_count
+            ));
+        }
+        if self.slots.is_empty() {
+            return Err(format!(
+                "template for workspace {} has no slots",
+                self.workspace
+            ));
+        }
+        if !(MIN_SPLIT_RATIO..=MAX_SPLIT_RATIO).contains(&self.main_ratio) {
+            return Err(format!(
+                "template main_ratio must be between {} and {}",
+                MIN_SPLIT_RATIO, MAX_SPLIT_RATIO
+            ));
+        }
+        for slot in &self.slots {
+            if slot.workspace.is_some() {
+                return Err("template slots can't set a workspace".to_string());
+            }
+            slot.validate(workspace_count)?;
+        }
+        Ok(())
+    }
+
+    /// Builds a tile tree with `windows` in their slots, or `None` if one of
+    /// them fits no free slot.
+    ///
+    /// Returns the tree and the windows in tree order, like
+    /// [`LayoutNode::to_tile`].
+    pub fn fill(&self, windows: &[Window]) -> Option<(Tile, Vec<isize>)> {
+        let mut assigned: Vec<Option<isize>> = vec![None; self.slots.len()];
+        for window in windows {
+            let identity = WindowIdentity {
+                process: window.process_name.as_deref(),
+                class: &window.class,
+                title: &window.title,
+            };
+            let slot = self
+                .slots
+                .iter()
+                .zip(assigned.iter_mut())
+                .find(|(rule, slot)| slot.is_none() && rule.matches(&identity))?;
+            *slot.1 = Some(window.hwnd);
+        }
+
+        let order = assigned.iter().flatten().copied().collect();
+        let mut ids = assigned.into_iter();
+        let mut placeholder = 0;
+        let tile = self.node().build(&mut || {
+            ids.next().flatten().unwrap_or_else(|| {
+                placeholder -= 1;
+                placeholder
+            })
+        });
+        Some((tile, order))
+    }
+
+    /// Returns the template's shape as a layout tree with one tile per slot.
+    fn node(&self) -> LayoutNode {
+        let count = self.slots.len();
+        match self.shape {
+            TemplateShape::Columns => even_split(SplitDirection::Vertical, count),
+            TemplateShape::Rows => even_split(SplitDirection::Horizontal, count),
+            TemplateShape::MainStack if count > 1 => LayoutNode {
+                split: Some(SplitDirection::Vertical),
+                ratio: self.main_ratio,
+                children: vec![
+                    LayoutNode::default(),
+                    even_split(SplitDirection::Horizontal, count - 1),
+                ],
+                ..Default::default()
+            },
+            TemplateShape::MainStack => LayoutNode::default(),
+        }
+    }
+}
+
+/// Returns `count` equal tiles side by side along `direction`.
+fn even_split(direction: SplitDirection, count: usize) -> LayoutNode {
+    if count <= 1 {
+        return LayoutNode::default();
+    }
+    LayoutNode {
+        split: Some(direction),
+        ratio: 1.0 / count as f32,
+        children: vec![LayoutNode::default(), even_split(direction, count - 1)],
+        ..Default::default()
+    }
+}
+
+/// Returns the layouts file path, next to the config file.
+pub fn layouts_path() -> Result<PathBuf, String> {
+    let mut path = crate::config::config_path()?;
+    path.set_file_name("layouts.toml");
+    Ok(path)
+}
+
+/// Reads every saved layout. A missing file means none were saved yet.
+fn read_layouts() -> Result<BTreeMap<String, LayoutNode>, String> {
+    let path = layouts_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Saves `layout` under `name`, replacing any layout saved with that name.
+pub fn save(name: &str, layout: LayoutNode) -> Result<(), String> {
+    let mut layouts = read_layouts()?;
+    layouts.insert(name.to_string(), layout);
+    let path = layouts_path()?;
+    let text = toml::to_string(&layouts).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Loads the layout saved under `name`.
+pub fn load(name: &str) -> Result<LayoutNode, String> {
+    read_layouts()?
+        .remove(name)
+        .ok_or_else(|| format!("No layout named '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiling::DwindleTiler;
+
+    fn window(hwnd: isize, process: &str, title: &str) -> Window {
+        let mut window = Window::new(hwnd, 1, 0, RECT::default(), Some(process.to_string()));
+        window.title = title.to_string();
+        window
+    }
+
+    #[test]
+    fn layouts_refill_tiles_by_process_and_title() {
+        let area = RECT {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let saved_windows = [
+            window(1, "Code.exe", "main.rs"),
+            window(2, "WindowsTerminal.exe", "cargo"),
+            window(3, "Code.exe", "README.md"),
+        ];
+        let mut tree = None;
+        DwindleTiler::new(0).layout(area, &[1, 2, 3], &mut tree, None);
+        tree.as_mut().unwrap().split_ratio = 0.7;
+        let layout = LayoutNode::from_tile(tree.as_ref().unwrap(), &saved_windows);
+
+        let text = toml::to_string(&BTreeMap::from([("dev", layout.clone())])).unwrap();
+        let read: BTreeMap<String, LayoutNode> = toml::from_str(&text).unwrap();
+        assert_eq!(read["dev"], layout);
+
+        // New handles, the README window is gone and an extra Code window is open
+        let windows = [
+            window(13, "code.exe", "notes.md"),
+            window(11, "Code.exe", "main.rs"),
+            window(12, "WindowsTerminal.exe", "pwsh"),
+        ];
+        let (tile, order) = layout.to_tile(&windows);
+        assert_eq!(order, vec![11, 12, 13]);
+        assert_eq!(tile.split_ratio, 0.7);
+    }
+
+    #[test]
+    fn templates_fill_slots_as_windows_arrive() {
+        let template = LayoutTemplate {
+            workspace: 2,
+            shape: TemplateShape::MainStack,
+            main_ratio: 0.6,
+            slots: vec![
+                WindowRule {
+                    process: Some("Code.exe".to_string()),
+                    ..Default::default()
+                },
+                WindowRule {
+                    process: Some("WindowsTerminal.exe".to_string()),
+                    ..Default::default()
+                },
+                WindowRule {
+                    title: Some("docs".to_string()),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert!(template.validate(9).is_ok());
+
+        // Only the terminal so far: it takes the second slot, the others wait
+        let terminal = window(2, "WindowsTerminal.exe", "pwsh");
+        let (tile, order) = template.fill(std::slice::from_ref(&terminal)).unwrap();
+        assert_eq!(order, vec![2]);
+        assert_eq!(tile.split_ratio, 0.6);
+        let (main, stack) = tile.children.as_deref().unwrap();
+        assert!(main.windows[0] < 0);
+        assert_eq!(stack.children.as_deref().unwrap().0.windows, vec![2]);
+
+        let windows = [terminal, window(1, "code.exe", "main.rs")];
+        let (_, order) = template.fill(&windows).unwrap();
+        assert_eq!(order, vec![1, 2]);
+
+        // A second editor has no slot left, so the template no longer applies
+        let windows = [
+            window(1, "Code.exe", "main.rs"),
+            window(3, "Code.exe", "lib.rs"),
+        ];
+        assert!(template.fill(&windows).is_none());
+    }
+}
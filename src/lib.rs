@@ -0,0 +1,103 @@
+//! # Megatile - A Tiling Window Manager for Windows
+//!
+//! Megatile is a lightweight tiling window manager designed for Windows 10/11.
+//! It provides automatic window tiling with a dwindle layout algorithm,
+//! multi-monitor support, and workspace management.
+//!
+//! ## Features
+//!
+//! - **Automatic Tiling**: Windows are automatically arranged using a dwindle algorithm
+//! - **Workspaces**: 9 virtual workspaces per monitor by default, up to 20
+//! - **Hotkey Support**: Comprehensive keyboard shortcuts for window management
+//! - **Multi-Monitor**: Full support for multiple displays
+//! - **System Tray**: Minimal tray icon for easy access
+//! - **Status Bar**: Visual workspace indicator
+//!
+//! ## Architecture
+//!
+//! - [`windows_lib`] - Windows API abstractions and window management utilities
+//! - [`window_system`] - The window operations the workspace manager drives, mockable for tests
+//! - [`workspace`] - Core data structures (Window, Workspace, Monitor)
+//! - [`workspace_manager`] - High-level workspace operations and state management
+//! - [`tiling`] - Tiling algorithms and layout calculations
+//! - [`hotkeys`] - Hotkey registration and action mapping
+//! - `http` - Localhost HTTP commands (with the `http` feature)
+//! - [`hooks`] - Shell commands run on lifecycle events
+//! - [`tray`] - System tray integration
+//! - [`statusbar`] - Visual workspace indicator
+//! - [`icons`] - Cached window icons
+//! - [`media`] - Volume and media playback for the status bar
+//! - [`autostart`] - Start-on-login registration
+//! - [`cheatsheet`] - Keybinding overlay
+//! - [`clock`] - Status bar clock formatting and time zones
+//! - [`config`] - Optional `config.toml` settings
+//! - [`float_geometry`] - Last floating position of each app
+//! - [`focus_history`] - Per-workspace focus history
+//! - [`ipc`] - Named-pipe commands for a running instance
+//! - [`launcher`] - Program launching with workspace routing
+//! - [`layouts`] - Named layout snapshots
+//! - [`lifecycle`] - Lifecycle events for user scripts and hooks
+//! - [`logging`] - Log file setup
+//! - [`notifications`] - On-screen popups for problems worth the user's attention
+//! - [`opacity`] - Per-process opacity chosen with hotkeys
+//! - [`power`] - Power saving on battery
+//! - [`process_names`] - Background process-name lookups
+//! - [`recovery`] - Restoring windows hidden by a crashed instance
+//! - [`rules`] - Window rules for placing new windows
+//! - [`session`] - Window layout handover on restart
+//! - [`settings`] - Settings window for editing window rules
+//! - [`about`] - About window with version and copyable diagnostics
+//! - [`setup`] - First-run questions that write the initial config
+//! - `scripting` - Rhai event handlers (with the `scripting` feature)
+//! - [`stash`] - Stash slots for parking windows outside workspaces
+//! - [`state_stream`] - Workspace and focus state for external status bars
+//! - [`telemetry`] - Opt-in performance counters
+//! - [`theme`] - Windows light/dark app theme
+//! - `updates` - Release checks and self-update (with the `updates` feature)
+//!
+//! The `megatile` binary is a thin event loop on top of this library. The
+//! [`tiling`] and [`workspace`] modules, and the geometry helpers in
+//! [`workspace_manager`], do not call into Win32 and can be unit-tested directly.
+
+pub mod about;
+pub mod autostart;
+pub mod cheatsheet;
+pub mod clock;
+pub mod config;
+pub mod float_geometry;
+pub mod focus_history;
+pub mod hooks;
+pub mod hotkeys;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod icons;
+pub mod ipc;
+pub mod launcher;
+pub mod layouts;
+pub mod lifecycle;
+pub mod logging;
+pub mod media;
+pub mod notifications;
+pub mod opacity;
+pub mod power;
+pub mod process_names;
+pub mod recovery;
+pub mod rules;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod session;
+pub mod settings;
+pub mod setup;
+pub mod stash;
+pub mod state_stream;
+pub mod statusbar;
+pub mod telemetry;
+pub mod theme;
+pub mod tiling;
+pub mod tray;
+#[cfg(feature = "updates")]
+pub mod updates;
+pub mod window_system;
+pub mod windows_lib;
+pub mod workspace;
+pub mod workspace_manager;
@@ -0,0 +1,60 @@
+//! Lifecycle events: things that happened that user scripts and hooks can
+//! react to.
+//!
+//! The event loop collects these as it handles Windows events and hands them
+//! out once per iteration, after the window manager state has settled.
+
+/// Names of all events, as returned by [`LifecycleEvent::name`].
+pub const EVENT_NAMES: [&str; 3] = ["window_created", "workspace_switched", "monitors_changed"];
+
+/// Something that happened in the window manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// A new window was registered and placed on a workspace.
+    WindowCreated {
+        hwnd: isize,
+        process: Option<String>,
+        class: String,
+        title: String,
+        workspace: u8,
+    },
+    /// The active workspace changed.
+    WorkspaceSwitched { from: u8, to: u8 },
+    /// Monitors were connected or disconnected.
+    MonitorsChanged { count: usize },
+}
+
+impl LifecycleEvent {
+    /// The event's name in `snake_case`, e.g. `"window_created"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LifecycleEvent::WindowCreated { .. } => "window_created",
+            LifecycleEvent::WorkspaceSwitched { .. } => "workspace_switched",
+            LifecycleEvent::MonitorsChanged { .. } => "monitors_changed",
+        }
+    }
+
+    /// The event's data as name/value pairs. A window without a known
+    /// process has an empty `process`.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            LifecycleEvent::WindowCreated {
+                hwnd,
+                process,
+                class,
+                title,
+                workspace,
+            } => vec![
+                ("hwnd", hwnd.to_string()),
+                ("process", process.clone().unwrap_or_default()),
+                ("class", class.clone()),
+                ("title", title.clone()),
+                ("workspace", workspace.to_string()),
+            ],
+            LifecycleEvent::WorkspaceSwitched { from, to } => {
+                vec![("from", from.to_string()), ("to", to.to_string())]
+            }
+            LifecycleEvent::MonitorsChanged { count } => vec![("count", count.to_string())],
+        }
+    }
+}
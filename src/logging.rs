@@ -31,7 +31,7 @@ impl LogLevel {
 }
 
 /// Gets the logs directory path, expanding ~/.megatile/logs to Windows user profile.
-fn get_logs_dir() -> Result<PathBuf, String> {
+pub fn get_logs_dir() -> Result<PathBuf, String> {
     // On Windows, use USERPROFILE environment variable
     let home_dir = std::env::var("USERPROFILE")
         .map_err(|_| "Failed to get USERPROFILE environment variable".to_string())?;
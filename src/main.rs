@@ -1,66 +1,61 @@
 //! # Megatile - A Tiling Window Manager for Windows
 //!
-//! Megatile is a lightweight tiling window manager designed for Windows 10/11.
-//! It provides automatic window tiling with a dwindle layout algorithm,
-//! multi-monitor support, and workspace management.
-//!
-//! ## Features
-//!
-//! - **Automatic Tiling**: Windows are automatically arranged using a dwindle algorithm
-//! - **Workspaces**: 9 virtual workspaces per monitor
-//! - **Hotkey Support**: Comprehensive keyboard shortcuts for window management
-//! - **Multi-Monitor**: Full support for multiple displays
-//! - **System Tray**: Minimal tray icon for easy access
-//! - **Status Bar**: Visual workspace indicator
-//!
-//! ## Architecture
-//!
-//! - [`windows_lib`] - Windows API abstractions and window management utilities
-//! - [`workspace`] - Core data structures (Window, Workspace, Monitor)
-//! - [`workspace_manager`] - High-level workspace operations and state management
-//! - [`tiling`] - Tiling algorithms and layout calculations
-//! - [`hotkeys`] - Hotkey registration and action mapping
-//! - [`tray`] - System tray integration
-//! - [`statusbar`] - Visual workspace indicator
+//! Application entry point: parses CLI flags, wires the Win32 event hooks,
+//! hotkeys, tray and status bar to the [`megatile`] library, and runs the
+//! main event loop.
 
 #![cfg_attr(
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
 
-mod hotkeys;
-mod logging;
-mod statusbar;
-mod tiling;
-mod tray;
-mod windows_lib;
-mod workspace;
-mod workspace_manager;
-
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU8, AtomicU32, Ordering};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::DataExchange::COPYDATASTRUCT;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Accessibility::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::core::PCWSTR;
-
-use log::{debug, error, info};
-
-use hotkeys::HotkeyManager;
-use statusbar::{
-    STATUSBAR_HEIGHT, STATUSBAR_TOP_GAP, STATUSBAR_WIDTH, StatusBar, init_gdiplus, shutdown_gdiplus,
-};
-use tray::TrayManager;
-use windows_lib::get_process_name_for_window;
-use windows_lib::{
-    enumerate_monitors, get_normal_windows, reset_window_decorations, show_window_in_taskbar,
-};
-use workspace_manager::WorkspaceManager;
+use windows::core::{PCWSTR, w};
+
+use log::{debug, error, info, warn};
+
+use megatile::about::{AboutWindow, Diagnostics};
+use megatile::autostart;
+use megatile::cheatsheet::Cheatsheet;
+use megatile::clock::Clock;
+use megatile::config::{BarModule, Config, StartupPlacement, TrayClickAction, quirks_for};
+use megatile::float_geometry;
+use megatile::hooks;
+use megatile::hotkeys::{self, HotkeyManager};
+use megatile::ipc;
+use megatile::launcher;
+use megatile::layouts;
+use megatile::lifecycle::LifecycleEvent;
+use megatile::notifications::{self, Notifier, Severity, notify};
+use megatile::opacity;
+use megatile::power::{self, Intervals, PowerState};
+use megatile::process_names::ProcessNameResolver;
+use megatile::recovery;
+use megatile::rules::{RuleEngine, TITLE_RULE_GRACE, WindowIdentity};
+#[cfg(feature = "scripting")]
+use megatile::scripting::ScriptHost;
+use megatile::session::{self, SavedSession};
+use megatile::settings::SettingsWindow;
+use megatile::setup;
+use megatile::state_stream::{BarSnapshot, BarState, StateStream};
+use megatile::statusbar::{self, StatusBar, WindowListClick, init_gdiplus, shutdown_gdiplus};
+use megatile::telemetry;
+use megatile::tray::{self, TrayIcons, TrayManager, TrayState};
+use megatile::windows_lib::{self, WindowKind, get_process_name_for_window};
+use megatile::windows_lib::{enumerate_monitors, get_normal_windows, reset_window_decorations};
+use megatile::workspace;
+use megatile::workspace_manager::{self, WorkspaceManager};
 
 use argh::FromArgs;
-use logging::LogLevel;
+use megatile::logging::{self, LogLevel};
 
 /// Megatile - A Tiling Window Manager for Windows
 #[derive(FromArgs, Debug)]
@@ -81,6 +76,53 @@ struct Args {
     /// set log level to error (default, least verbose)
     #[argh(switch, short = 'e')]
     error: bool,
+
+    /// record performance counters, logged at debug level and queryable with `--send telemetry`
+    #[argh(switch)]
+    telemetry: bool,
+
+    /// send a command to the running instance over IPC, print the reply and exit
+    #[argh(option)]
+    send: Option<String>,
+
+    /// start megatile on login with the other flags given here, then exit
+    #[argh(switch)]
+    install_autostart: bool,
+
+    /// stop megatile from starting on login, then exit
+    #[argh(switch)]
+    uninstall_autostart: bool,
+
+    /// take over the windows of a restarting instance with this process ID (used by restart)
+    #[argh(option)]
+    restart_from: Option<u32>,
+
+    /// show the windows a crashed instance left hidden, then exit
+    #[argh(switch)]
+    force_cleanup: bool,
+}
+
+impl Args {
+    /// Returns the flags that should be passed again when starting on login.
+    fn autostart_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.debug {
+            flags.push("--debug".to_string());
+        }
+        if self.info {
+            flags.push("--info".to_string());
+        }
+        if self.warning {
+            flags.push("--warning".to_string());
+        }
+        if self.error {
+            flags.push("--error".to_string());
+        }
+        if self.telemetry {
+            flags.push("--telemetry".to_string());
+        }
+        flags
+    }
 }
 
 /// Window class name for the hidden message window ("MegatileMessageWindow" as UTF-16).
@@ -92,21 +134,46 @@ static CLASS_NAME: [u16; 22] = [
 /// Window title ("Megatile" as UTF-16).
 static TITLE: [u16; 9] = [77, 101, 103, 97, 84, 105, 108, 101, 0];
 
+/// How many times an Explorer window that wasn't ready is looked at again.
+const EXPLORER_RECHECKS: u8 = 10;
+/// Time between those looks.
+const EXPLORER_RECHECK_INTERVAL: Duration = Duration::from_millis(200);
+
 /// Internal events processed by the main event loop.
 #[derive(Debug)]
 enum WindowEvent {
     Hotkey(hotkeys::HotkeyAction),
     WindowCreated(isize),
+    ProcessNameResolved(isize, Option<String>),
     WindowDestroyed(isize),
     WindowMinimized(isize),
     WindowRestored(isize),
     WindowMoved(isize),
+    WindowDragStarted(isize),
+    WindowDropped(isize),
+    TitleChanged(isize),
     WindowHidden(isize), // New: fires when WS_VISIBLE is cleared
     FocusChanged(isize),
+    WindowUrgent(isize), // Flashed its taskbar button for attention
     DisplayChange,
+    DeviceChange,   // Device arrived or removed; may or may not be a monitor
+    MetricsChanged, // DPI, theme or system settings changed
+    ColorizationChanged,
+    PowerChanged, // Plugged in, unplugged or battery saver toggled
     TrayExit,
+    Restart,
+    #[cfg(feature = "updates")]
+    UpdateAvailable(megatile::updates::Update),
+    #[cfg(feature = "updates")]
+    UpdateFailed(String),
 }
 
+/// Message id of shell hook notifications, registered at startup.
+static SHELLHOOK_MESSAGE: AtomicU32 = AtomicU32::new(0);
+
+/// The configured workspace count, for validating IPC commands off the main thread.
+static WORKSPACE_COUNT: AtomicU8 = AtomicU8::new(workspace::DEFAULT_WORKSPACES);
+
 /// Global event queue for inter-thread communication.
 static EVENT_QUEUE: OnceLock<Mutex<VecDeque<WindowEvent>>> = OnceLock::new();
 
@@ -116,6 +183,7 @@ fn push_event(event: WindowEvent) {
         && let Ok(mut q) = queue.lock()
     {
         q.push_back(event);
+        telemetry::record_event();
     }
 }
 
@@ -140,7 +208,8 @@ unsafe extern "system" fn win_event_proc(
         EVENT_SYSTEM_FOREGROUND => {
             push_event(WindowEvent::FocusChanged(hwnd.0 as isize));
         }
-        EVENT_OBJECT_CREATE | EVENT_OBJECT_SHOW => {
+        // Explorer and UWP apps stay cloaked until their window is ready
+        EVENT_OBJECT_CREATE | EVENT_OBJECT_SHOW | EVENT_OBJECT_UNCLOAKED => {
             push_event(WindowEvent::WindowCreated(hwnd.0 as isize));
         }
         EVENT_OBJECT_DESTROY => {
@@ -160,6 +229,15 @@ unsafe extern "system" fn win_event_proc(
         EVENT_OBJECT_LOCATIONCHANGE => {
             push_event(WindowEvent::WindowMoved(hwnd.0 as isize));
         }
+        EVENT_SYSTEM_MOVESIZESTART => {
+            push_event(WindowEvent::WindowDragStarted(hwnd.0 as isize));
+        }
+        EVENT_SYSTEM_MOVESIZEEND => {
+            push_event(WindowEvent::WindowDropped(hwnd.0 as isize));
+        }
+        EVENT_OBJECT_NAMECHANGE => {
+            push_event(WindowEvent::TitleChanged(hwnd.0 as isize));
+        }
         _ => {}
     }
 }
@@ -167,11 +245,16 @@ unsafe extern "system" fn win_event_proc(
 /// Restores all managed windows to their visible state before exit.
 ///
 /// This ensures windows are not left hidden in the taskbar when Megatile exits.
-fn cleanup_on_exit(wm: &mut WorkspaceManager) {
+/// `progress` is told how many of the windows have been restored so far.
+fn cleanup_on_exit(wm: &mut WorkspaceManager, progress: impl Fn(usize, usize)) {
     info!("Restoring all hidden windows...");
 
     // Get all managed windows from all workspaces
     let all_hwnds = wm.get_all_managed_hwnds();
+    wm.restore_window_frames();
+    if let Err(e) = float_geometry::save(wm.float_rects()) {
+        error!("Failed to save floating window positions: {}", e);
+    }
     debug!("Found {} managed windows to restore", all_hwnds.len());
 
     let normal_windows = get_normal_windows();
@@ -186,11 +269,13 @@ fn cleanup_on_exit(wm: &mut WorkspaceManager) {
     let mut restored_count = 0;
     let mut failed_count = 0;
 
-    for hwnd in all_hwnds {
+    let total = all_hwnds.len();
+    for (done, hwnd) in all_hwnds.into_iter().enumerate() {
+        progress(done, total);
         let hwnd_handle = HWND(hwnd as *mut std::ffi::c_void);
 
         // Try to restore each window
-        match show_window_in_taskbar(hwnd_handle) {
+        match wm.reveal_window(hwnd_handle) {
             Ok(()) => {
                 restored_count += 1;
                 debug!("Restored window {:?}", hwnd);
@@ -205,14 +290,159 @@ fn cleanup_on_exit(wm: &mut WorkspaceManager) {
         }
     }
 
+    wm.restore_initial_rects();
+    progress(total, total);
+    if let Err(e) = recovery::clear() {
+        error!("Failed to remove the crash recovery file: {}", e);
+    }
+
     info!(
         "Window restoration complete: {} restored, {} failed",
         restored_count, failed_count
     );
 }
 
+/// Returns true if windows of `kind` are managed with this config.
+fn is_managed_kind(kind: WindowKind, config: &Config) -> bool {
+    match kind {
+        WindowKind::Normal => true,
+        WindowKind::Transient => config.layout.float_transient,
+        WindowKind::Ignored => false,
+    }
+}
+
+/// Looks at an Explorer window that wasn't ready again shortly. Explorer
+/// reuses its process and shows new windows before they have a title, so a
+/// first look often turns them away.
+fn recheck_explorer_window(
+    hwnd_val: isize,
+    rechecks: &mut HashMap<isize, u8>,
+    delayed_events: &mut Vec<(Instant, WindowEvent)>,
+) {
+    let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
+    if !windows_lib::is_explorer_frame(hwnd) {
+        return;
+    }
+    let attempts = rechecks.entry(hwnd_val).or_insert(0);
+    if *attempts < EXPLORER_RECHECKS {
+        *attempts += 1;
+        debug!("Explorer window {:?} not ready, rechecking", hwnd);
+        delayed_events.push((
+            Instant::now() + EXPLORER_RECHECK_INTERVAL,
+            WindowEvent::WindowCreated(hwnd_val),
+        ));
+    }
+}
+
+/// Pauses every managed display, or resumes them all if none is managed.
+fn toggle_all_displays(wm: &mut WorkspaceManager) {
+    let displays = wm.displays();
+    let pause = displays.iter().any(|(_, managed)| *managed);
+    for (device, managed) in displays {
+        if managed == pause {
+            toggle_display(wm, &device);
+        }
+    }
+}
+
+/// Gives each display that is configured for a built-in status bar and
+/// doesn't have one yet its bar, unless a third-party bar takes their place.
+fn sync_statusbars(wm: &mut WorkspaceManager, owner: HWND, config: &Config, clock: &Clock) {
+    if config.statusbar.external {
+        return;
+    }
+    let monitors = enumerate_monitors();
+    let connected: Vec<String> = monitors.iter().map(|m| m.device_name.clone()).collect();
+    for monitor in monitors {
+        if wm.has_statusbar(&monitor.device_name) {
+            continue;
+        }
+        let Some(placement) =
+            config.bar_placement(&monitor.device_name, monitor.is_primary, &connected)
+        else {
+            continue;
+        };
+        let mut statusbar = match StatusBar::new(owner) {
+            Ok(statusbar) => statusbar,
+            Err(e) => {
+                error!(
+                    "Failed to create the status bar of {}: {}",
+                    monitor.device_name, e
+                );
+                continue;
+            }
+        };
+        statusbar.set_window_list(placement.modules.contains(&BarModule::Windows));
+        statusbar.set_media(placement.modules.contains(&BarModule::Media));
+        statusbar.set_clock_shown(placement.modules.contains(&BarModule::Clock));
+        statusbar.set_clock(clock.clone());
+        statusbar.set_align(placement.align);
+        statusbar.set_edge(config.statusbar.position);
+        info!("Adding a status bar on {}", monitor.device_name);
+        wm.add_statusbar(monitor.device_name, statusbar);
+    }
+}
+
+/// Pauses or resumes management of a display. Resuming takes over the
+/// windows already on it, on the active workspace.
+fn toggle_display(wm: &mut WorkspaceManager, device: &str) {
+    match wm.toggle_display_managed(device) {
+        Ok(false) => info!("Stopped managing {}", device),
+        Ok(true) => {
+            info!("Managing {} again", device);
+            let managed: HashSet<isize> = wm.get_all_managed_hwnds().into_iter().collect();
+            let workspace = wm.get_active_workspace();
+            for window_info in get_normal_windows() {
+                let hwnd_val = window_info.hwnd.0 as isize;
+                let Some(monitor_index) = wm.get_monitor_for_window(window_info.hwnd) else {
+                    continue;
+                };
+                if managed.contains(&hwnd_val) || wm.monitor_device(monitor_index) != Some(device) {
+                    continue;
+                }
+                let mut window = workspace::Window::new(
+                    hwnd_val,
+                    workspace,
+                    monitor_index,
+                    window_info.rect,
+                    get_process_name_for_window(window_info.hwnd),
+                );
+                window.title = window_info.title;
+                window.class = window_info.class_name;
+                place_window(wm, window);
+            }
+        }
+        Err(e) => error!("Failed to toggle {}: {}", device, e),
+    }
+}
+
+/// Adds a window to the workspace manager, showing and tiling it if its
+/// workspace is active and hiding it otherwise.
+fn place_window(wm: &mut WorkspaceManager, mut window: workspace::Window) {
+    let hwnd = HWND(window.hwnd as *mut std::ffi::c_void);
+    if window.workspace == wm.get_active_workspace() {
+        let _ = wm.set_window_hidden(&window, false);
+        window.is_hidden_by_workspace = false;
+        wm.add_window(window);
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+    } else {
+        info!(
+            "Placing window {:?} on workspace {}",
+            hwnd, window.workspace
+        );
+        window.is_hidden_by_workspace = wm.set_window_hidden(&window, true).is_ok();
+        wm.add_window(window);
+    }
+}
+
 /// Dispatches a hotkey action to the workspace manager.
-fn handle_action(action: hotkeys::HotkeyAction, wm: &mut WorkspaceManager) {
+fn handle_action(
+    action: hotkeys::HotkeyAction,
+    wm: &mut WorkspaceManager,
+    rules: &mut RuleEngine,
+    config: &Config,
+) {
     match action {
         hotkeys::HotkeyAction::SwitchWorkspace(num) => {
             match wm.switch_workspace_with_windows(num) {
@@ -266,13 +496,32 @@ fn handle_action(action: hotkeys::HotkeyAction, wm: &mut WorkspaceManager) {
                 error!("Failed to move window: {}", e);
             }
         }
-        hotkeys::HotkeyAction::MoveToWorkspace(num) => match wm.move_window_to_workspace(num) {
-            Ok(()) => {
-                info!("Moved window to workspace {}", num);
-                wm.print_workspace_status();
+        hotkeys::HotkeyAction::MoveToWorkspace(num) => {
+            match wm.move_window_to_workspace(num, true) {
+                Ok(()) => {
+                    info!("Moved window to workspace {}", num);
+                    wm.print_workspace_status();
+                }
+                Err(e) => error!("Failed to move window: {}", e),
             }
-            Err(e) => error!("Failed to move window: {}", e),
-        },
+        }
+        hotkeys::HotkeyAction::NextWorkspace | hotkeys::HotkeyAction::PreviousWorkspace => {
+            let forward = matches!(action, hotkeys::HotkeyAction::NextWorkspace);
+            if let Some(num) = wm.adjacent_workspace(forward, config.workspaces.cycle_skips_empty) {
+                handle_action(
+                    hotkeys::HotkeyAction::SwitchWorkspace(num),
+                    wm,
+                    rules,
+                    config,
+                );
+            }
+        }
+        hotkeys::HotkeyAction::MoveToEmptyWorkspace => {
+            match wm.move_window_to_empty_workspace(config.workspaces.follow_to_empty) {
+                Ok(num) => info!("Moved window to empty workspace {}", num),
+                Err(e) => error!("Failed to move window: {}", e),
+            }
+        }
         hotkeys::HotkeyAction::ToggleTiling => {
             if let Some(focused) = wm.get_focused_window()
                 && let Err(e) = wm.toggle_window_tiling(HWND(focused.hwnd as _))
@@ -312,6 +561,21 @@ fn handle_action(action: hotkeys::HotkeyAction, wm: &mut WorkspaceManager) {
                 error!("Failed to resize window: {}", e);
             }
         }
+        hotkeys::HotkeyAction::FocusPrevious => {
+            if let Err(e) = wm.step_focus_history(true) {
+                error!("Failed to focus previous window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::FocusNext => {
+            if let Err(e) = wm.step_focus_history(false) {
+                error!("Failed to focus next window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::FocusUrgent => {
+            if let Err(e) = wm.focus_urgent_window() {
+                info!("{}", e);
+            }
+        }
         hotkeys::HotkeyAction::FlipRegion => {
             if let Err(e) = wm.flip_focused_region() {
                 error!("Failed to flip region: {}", e);
@@ -321,29 +585,209 @@ fn handle_action(action: hotkeys::HotkeyAction, wm: &mut WorkspaceManager) {
             Ok(()) => info!("Window closed successfully"),
             Err(e) => error!("Failed to close window: {}", e),
         },
+        hotkeys::HotkeyAction::ToggleTopmost => {
+            if let Err(e) = wm.toggle_topmost() {
+                error!("Failed to toggle always-on-top: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::IncreaseOpacity | hotkeys::HotkeyAction::DecreaseOpacity => {
+            let increase = matches!(action, hotkeys::HotkeyAction::IncreaseOpacity);
+            match wm.adjust_opacity(increase) {
+                Ok(_) => {
+                    if let Err(e) = opacity::save(wm.opacity_levels()) {
+                        error!("Failed to save opacity: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to change opacity: {}", e),
+            }
+        }
+        hotkeys::HotkeyAction::MinimizeWindow => {
+            if let Err(e) = wm.minimize_focused_window() {
+                error!("Failed to minimize window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::ToggleMaximize => match wm.toggle_maximize() {
+            Ok(()) => info!("Maximize toggled"),
+            Err(e) => error!("Failed to toggle maximize: {}", e),
+        },
+        hotkeys::HotkeyAction::ToggleWorkspaceLock => {
+            let workspace = wm.get_active_workspace();
+            if wm.toggle_workspace_lock() {
+                notify(
+                    Severity::Info,
+                    format!("Workspace {} locked", workspace),
+                    "New windows will open on the next unlocked workspace.",
+                );
+            } else {
+                notify(
+                    Severity::Info,
+                    format!("Workspace {} unlocked", workspace),
+                    "New windows will open here again.",
+                );
+            }
+        }
+        hotkeys::HotkeyAction::Stash => {
+            if let Err(e) = wm.stash_focused_window() {
+                error!("Failed to stash window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::ToggleStashSlot(slot) => {
+            if let Err(e) = wm.toggle_stash_slot(slot) {
+                error!("Failed to toggle stash slot {}: {}", slot, e);
+            }
+        }
+        hotkeys::HotkeyAction::ToggleZen => match wm.toggle_zen() {
+            Ok(()) => info!("Zen mode toggled"),
+            Err(e) => error!("Failed to toggle zen mode: {}", e),
+        },
         hotkeys::HotkeyAction::ToggleStatusBar => {
             wm.invert_statusbar_visibility();
         }
+        hotkeys::HotkeyAction::FlashFocus => wm.flash_focus(),
+        hotkeys::HotkeyAction::ToggleWorkspaceTiling => {
+            if let Err(e) = wm.toggle_workspace_tiling() {
+                info!("{}", e);
+            }
+        }
+        hotkeys::HotkeyAction::AdoptWindows => {
+            if let Err(e) = wm.adopt_windows() {
+                info!("{}", e);
+            }
+        }
+        hotkeys::HotkeyAction::SaveLayout(name) => {
+            match wm
+                .current_layout()
+                .and_then(|layout| layouts::save(&name, layout))
+            {
+                Ok(()) => info!("Saved layout '{}'", name),
+                Err(e) => error!("Failed to save layout '{}': {}", name, e),
+            }
+        }
+        hotkeys::HotkeyAction::LoadLayout(name) => {
+            match layouts::load(&name).and_then(|layout| wm.apply_layout(&layout)) {
+                Ok(()) => info!("Loaded layout '{}'", name),
+                Err(e) => error!("Failed to load layout '{}': {}", name, e),
+            }
+        }
+        hotkeys::HotkeyAction::SetSplitRatio(percent) => {
+            if let Err(e) = wm.set_split_ratio(percent as f32 / 100.0) {
+                error!("Failed to set split ratio: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::ToggleShowDesktop => {
+            if let Err(e) = wm.toggle_show_desktop() {
+                error!("Failed to toggle show desktop: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::ToggleTileGroup => {
+            if let Err(e) = wm.toggle_tile_group() {
+                error!("Failed to toggle tile group: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::CycleTileGroup => {
+            if let Err(e) = wm.cycle_tile_group() {
+                error!("Failed to cycle tile group: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::MergeLeft => {
+            if let Err(e) = wm.merge_into_neighbor(workspace_manager::FocusDirection::Left) {
+                error!("Failed to group window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::MergeRight => {
+            if let Err(e) = wm.merge_into_neighbor(workspace_manager::FocusDirection::Right) {
+                error!("Failed to group window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::MergeUp => {
+            if let Err(e) = wm.merge_into_neighbor(workspace_manager::FocusDirection::Up) {
+                error!("Failed to group window: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::MergeDown => {
+            if let Err(e) = wm.merge_into_neighbor(workspace_manager::FocusDirection::Down) {
+                error!("Failed to group window: {}", e);
+            }
+        }
+        // The event loop owns the cheatsheet and shows it before getting here
+        hotkeys::HotkeyAction::ToggleCheatsheet => {}
         hotkeys::HotkeyAction::MoveToMonitorLeft => {
-            if let Err(e) = wm.move_window_to_monitor(workspace_manager::FocusDirection::Left) {
+            if let Err(e) = wm.move_window_to_monitor(
+                workspace_manager::FocusDirection::Left,
+                config.focus.follow_to_monitor,
+            ) {
                 error!("Failed to move window to monitor: {}", e);
             }
         }
         hotkeys::HotkeyAction::MoveToMonitorRight => {
-            if let Err(e) = wm.move_window_to_monitor(workspace_manager::FocusDirection::Right) {
+            if let Err(e) = wm.move_window_to_monitor(
+                workspace_manager::FocusDirection::Right,
+                config.focus.follow_to_monitor,
+            ) {
                 error!("Failed to move window to monitor: {}", e);
             }
         }
         hotkeys::HotkeyAction::MoveToMonitorUp => {
-            if let Err(e) = wm.move_window_to_monitor(workspace_manager::FocusDirection::Up) {
+            if let Err(e) = wm.move_window_to_monitor(
+                workspace_manager::FocusDirection::Up,
+                config.focus.follow_to_monitor,
+            ) {
                 error!("Failed to move window to monitor: {}", e);
             }
         }
         hotkeys::HotkeyAction::MoveToMonitorDown => {
-            if let Err(e) = wm.move_window_to_monitor(workspace_manager::FocusDirection::Down) {
+            if let Err(e) = wm.move_window_to_monitor(
+                workspace_manager::FocusDirection::Down,
+                config.focus.follow_to_monitor,
+            ) {
                 error!("Failed to move window to monitor: {}", e);
             }
         }
+        hotkeys::HotkeyAction::SendToMonitorLeft => {
+            if let Err(e) =
+                wm.move_window_to_monitor(workspace_manager::FocusDirection::Left, false)
+            {
+                error!("Failed to send window to monitor: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::SendToMonitorRight => {
+            if let Err(e) =
+                wm.move_window_to_monitor(workspace_manager::FocusDirection::Right, false)
+            {
+                error!("Failed to send window to monitor: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::SendToMonitorUp => {
+            if let Err(e) = wm.move_window_to_monitor(workspace_manager::FocusDirection::Up, false)
+            {
+                error!("Failed to send window to monitor: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::SendToMonitorDown => {
+            if let Err(e) =
+                wm.move_window_to_monitor(workspace_manager::FocusDirection::Down, false)
+            {
+                error!("Failed to send window to monitor: {}", e);
+            }
+        }
+        hotkeys::HotkeyAction::BalanceMonitors => match wm.balance_monitors(false) {
+            Ok(moved) => info!("Moved {} windows to balance the monitors", moved),
+            Err(e) => info!("{}", e),
+        },
+        hotkeys::HotkeyAction::GatherOnMonitor => match wm.balance_monitors(true) {
+            Ok(moved) => info!("Gathered {} windows on the focused monitor", moved),
+            Err(e) => info!("{}", e),
+        },
+        hotkeys::HotkeyAction::ToggleMonitorManaged => match wm.display_under_cursor() {
+            Some(device) => toggle_display(wm, &device),
+            None => error!("No display under the cursor"),
+        },
+        hotkeys::HotkeyAction::Launch(launch) => {
+            if let Err(e) = launcher::launch(&launch, rules) {
+                error!("{}", e);
+                notify(Severity::Warning, "Launch failed", e);
+            }
+        }
     }
 }
 
@@ -351,6 +795,18 @@ fn main() {
     // Parse CLI arguments
     let args: Args = argh::from_env();
 
+    if let Some(command) = args.send.as_deref() {
+        std::process::exit(send_ipc_command(command));
+    }
+
+    if args.install_autostart || args.uninstall_autostart {
+        std::process::exit(run_autostart_command(&args));
+    }
+
+    if args.force_cleanup {
+        std::process::exit(run_force_cleanup());
+    }
+
     // Determine log level from CLI flags (default to Error if none specified)
     let log_level = if args.debug {
         LogLevel::Debug
@@ -367,11 +823,57 @@ fn main() {
 
     log::info!("Megatile - Window Manager");
 
+    // After a restart, pick up where the previous instance left off
+    let saved_session = args.restart_from.and_then(|pid| {
+        session::wait_for_exit(pid);
+        SavedSession::take()
+            .inspect_err(|e| error!("Failed to read the restart session: {}", e))
+            .ok()
+    });
+
+    // Ask a few questions and write a config file the first time Megatile runs
+    if args.restart_from.is_none() {
+        setup::run_if_first_launch(&args.autostart_flags());
+    }
+
+    let config = Config::load().unwrap_or_else(|e| {
+        error!("Failed to load config: {}", e);
+        notify(Severity::Error, "Config error", e);
+        Config::default()
+    });
+
     // Initialize event queue
     EVENT_QUEUE.set(Mutex::new(VecDeque::new())).unwrap();
 
     // Initialize workspace manager
     let mut wm = WorkspaceManager::new();
+    wm.set_layout_config(config.layout.clone());
+    wm.set_focus_config(config.focus.clone());
+    wm.set_monitor_configs(config.monitors.clone());
+    wm.set_decorations_config(config.decorations.clone());
+    wm.set_stash_slots(config.stash.slots.len());
+    wm.set_locked_workspaces(&config.workspaces.locked);
+    wm.set_workspace_count(config.workspaces.count);
+    WORKSPACE_COUNT.store(config.workspaces.count, Ordering::SeqCst);
+    wm.set_dynamic_workspaces(config.workspaces.dynamic);
+    wm.set_hide_methods(config.workspaces.hide_methods());
+    wm.set_workspace_overrides(config.workspaces.overrides.clone());
+    wm.set_layout_templates(config.templates.clone());
+    wm.set_app_quirks(config.quirks.clone());
+    wm.set_statusbar_position(config.statusbar.position);
+    if config.statusbar.external {
+        wm.set_external_bar(config.statusbar.reserve);
+    } else if config.statusbar.auto_hide {
+        wm.set_statusbar_auto_hide(Duration::from_millis(config.statusbar.auto_hide_delay_ms));
+    }
+    match opacity::load() {
+        Ok(levels) => wm.set_opacity_levels(levels),
+        Err(e) => error!("Failed to load opacity levels: {}", e),
+    }
+    match float_geometry::load() {
+        Ok(rects) => wm.set_float_rects(rects),
+        Err(e) => error!("Failed to load floating window positions: {}", e),
+    }
 
     // Setup Ctrl+C handler for cleanup
     ctrlc::set_handler(move || {
@@ -380,59 +882,136 @@ fn main() {
     })
     .expect("Error setting Ctrl+C handler");
 
+    // Windows a crashed instance hid would otherwise stay hidden for good
+    match recovery::restore() {
+        Ok(0) => {}
+        Ok(count) => info!(
+            "Restored {} windows left hidden by a crashed instance",
+            count
+        ),
+        Err(e) => error!("Failed to restore windows from a crashed instance: {}", e),
+    }
+
     // Enumerate monitors and create monitor structs
     let monitor_infos = enumerate_monitors();
     info!("Found {} monitor(s)", monitor_infos.len());
 
-    let monitors: Vec<workspace::Monitor> = monitor_infos
-        .iter()
-        .enumerate()
-        .map(|(i, info)| {
-            debug!("Monitor {}: {:?}", i + 1, info.rect);
-            workspace::Monitor::new(info.hmonitor, info.rect)
-        })
-        .collect();
+    for (i, info) in monitor_infos.iter().enumerate() {
+        info!("Monitor {}: {} {:?}", i + 1, info.device_name, info.rect);
+    }
 
-    wm.set_monitors(monitors);
+    wm.set_monitors(wm.monitors_from_infos(&monitor_infos));
 
-    // Enumerate windows and assign to workspace 1
+    // Enumerate windows and assign them per `[workspaces] startup`, or where they
+    // were before a restart
     let normal_windows = get_normal_windows();
     info!("Found {} normal windows", normal_windows.len());
 
     let focused_hwnd = unsafe { GetForegroundWindow() };
-    for window_info in normal_windows {
-        debug!(
-            "Window: {} (Class: {})",
-            window_info.title, window_info.class_name
-        );
-        let is_focused = window_info.hwnd == focused_hwnd;
-        let monitor_index = wm.get_monitor_for_window(window_info.hwnd).unwrap_or(0);
-        let process_name = get_process_name_for_window(window_info.hwnd);
-        let mut window = workspace::Window::new(
-            window_info.hwnd.0 as isize,
-            1, // Assign to workspace 1
-            monitor_index,
-            window_info.rect,
-            process_name,
-        );
-        window.is_focused = is_focused;
-        // Since workspace 1 is active, show in taskbar
-        let _ = show_window_in_taskbar(window_info.hwnd);
+    let startup_windows: Vec<workspace::Window> = normal_windows
+        .iter()
+        .filter_map(|window_info| {
+            debug!(
+                "Window: {} (Class: {})",
+                window_info.title, window_info.class_name
+            );
+            let monitor_index = wm
+                .get_monitor_for_window(window_info.hwnd)
+                .unwrap_or_else(|| wm.primary_monitor_index());
+            if !wm.is_monitor_managed(monitor_index) {
+                return None;
+            }
+            let mut window = workspace::Window::new(
+                window_info.hwnd.0 as isize,
+                1,
+                monitor_index,
+                window_info.rect,
+                get_process_name_for_window(window_info.hwnd),
+            );
+            window.title = window_info.title.clone();
+            window.class = window_info.class_name.clone();
+            window.is_focused = window_info.hwnd == focused_hwnd;
+            // Fullscreen games and videos, and maximized apps that ask for it,
+            // stay as they are instead of being shrunk into a tile
+            let keep_maximized = windows_lib::is_window_maximized(window_info.hwnd)
+                && quirks_for(&config.quirks, window.process_name.as_deref())
+                    .is_some_and(|quirks| quirks.keep_maximized);
+            if keep_maximized || wm.covers_display(window_info.hwnd) {
+                info!("Leaving {} as it is, floating", window_info.title);
+                window.is_tiled = false;
+            }
+            Some(window)
+        })
+        .collect();
+    let placements: Vec<(usize, Option<u8>)> = startup_windows
+        .iter()
+        .map(|window| {
+            let identity = WindowIdentity {
+                process: window.process_name.as_deref(),
+                class: &window.class,
+                title: &window.title,
+            };
+            let rule_workspace = config
+                .rules
+                .iter()
+                .filter(|rule| rule.matches(&identity))
+                .find_map(|rule| rule.workspace);
+            (window.monitor, rule_workspace)
+        })
+        .collect();
+    let startup_workspaces = config.workspaces.startup_workspaces(&placements);
+    let saved_windows = saved_session
+        .as_ref()
+        .map(|session| session.match_windows(&startup_windows))
+        .unwrap_or_default();
+    for (mut window, workspace) in startup_windows.into_iter().zip(startup_workspaces) {
+        window.workspace = workspace;
+        if let Some(saved) = saved_windows.get(&window.hwnd) {
+            window.workspace = saved.workspace.min(config.workspaces.count);
+            window.is_tiled = saved.is_tiled;
+            if let Some(rect) = saved.float_rect.filter(|_| !saved.is_tiled) {
+                window.rect = rect.into();
+                window.original_rect = window.rect;
+                wm.place_floating(&window);
+            }
+        } else if config.workspaces.startup == StartupPlacement::Float && window.is_tiled {
+            window.is_tiled = false;
+            wm.leave_unadopted(HWND(window.hwnd as *mut std::ffi::c_void));
+        }
+        if window.workspace == 1 {
+            // Since workspace 1 is active, show in taskbar
+            let _ = wm.set_window_hidden(&window, false);
+        } else {
+            window.is_hidden_by_workspace = wm.set_window_hidden(&window, true).is_ok();
+        }
         wm.add_window(window);
     }
 
-    info!("Assigned all windows to workspace 1");
+    info!("Assigned windows to their workspaces");
+    if let Some(session) = &saved_session {
+        wm.restore_session(session, &saved_windows);
+    }
 
     // Apply initial tiling
     wm.tile_active_workspaces();
     wm.apply_window_positions();
     info!("Applied initial tiling to workspace 1");
+    if let Some(session) = &saved_session
+        && session.active_workspace != 1
+        && let Err(e) = wm.switch_workspace_with_windows(session.active_workspace)
+    {
+        error!(
+            "Failed to return to workspace {}: {}",
+            session.active_workspace, e
+        );
+    }
 
     // Setup window event hooks
     let _event_hook = unsafe {
         SetWinEventHook(
             EVENT_SYSTEM_FOREGROUND,
-            EVENT_OBJECT_LOCATIONCHANGE,
+            // Past LOCATIONCHANGE so title changes come through too
+            EVENT_OBJECT_NAMECHANGE,
             None,
             Some(win_event_proc),
             0,
@@ -454,77 +1033,360 @@ fn main() {
         )
     };
 
+    // Setup uncloak event hook
+    let _uncloak_hook = unsafe {
+        SetWinEventHook(
+            EVENT_OBJECT_UNCLOAKED,
+            EVENT_OBJECT_UNCLOAKED,
+            None,
+            Some(win_event_proc),
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT,
+        )
+    };
+
+    if args.telemetry {
+        telemetry::enable();
+        info!("Performance telemetry enabled");
+    }
+
+    // Serve IPC queries from other processes (e.g. `megatile --send telemetry`)
+    if let Err(e) = ipc::spawn_server(handle_ipc_command) {
+        error!("Failed to start IPC server: {}", e);
+    }
+    // The same commands over localhost HTTP, if configured, along with the
+    // state the state stream sends
+    let bar_snapshot = BarSnapshot::default();
+    if let Some(port) = config.http.port {
+        #[cfg(feature = "http")]
+        if let Err(e) = megatile::http::spawn_server(
+            port,
+            config.http.token.clone(),
+            bar_snapshot.clone(),
+            handle_ipc_command,
+        ) {
+            error!("{}", e);
+            notify(Severity::Warning, "HTTP endpoint failed", e);
+        }
+        #[cfg(not(feature = "http"))]
+        notify(
+            Severity::Warning,
+            "HTTP endpoint unavailable",
+            format!(
+                "Port {} is configured, but this build of Megatile was made without the \
+                 http feature",
+                port
+            ),
+        );
+    }
+
+    // Resolve process names for new windows off the event loop
+    let process_names = ProcessNameResolver::spawn(|hwnd, process_name| {
+        push_event(WindowEvent::ProcessNameResolved(hwnd, process_name));
+    })
+    .expect("Failed to start process name worker");
+    let mut pending_lookups: HashSet<isize> = HashSet::new();
+    // Events put off for later: windows held back by a `manage_delay_ms` quirk
+    // and Explorer windows that weren't ready to be looked at yet
+    let mut delayed_events: Vec<(Instant, WindowEvent)> = Vec::new();
+    let mut released_windows: HashSet<isize> = HashSet::new();
+    let mut explorer_rechecks: HashMap<isize, u8> = HashMap::new();
+
+    // Event handler script, if configured
+    #[cfg(feature = "scripting")]
+    let mut scripts = config.scripting.script.as_deref().and_then(|path| {
+        ScriptHost::load(path)
+            .inspect(|_| info!("Loaded script {}", path.display()))
+            .inspect_err(|e| {
+                error!("{}", e);
+                notify(Severity::Error, "Script error", e.clone());
+            })
+            .ok()
+    });
+    #[cfg(not(feature = "scripting"))]
+    if config.scripting.script.is_some() {
+        notify(
+            Severity::Warning,
+            "Scripting unavailable",
+            "This build of Megatile was made without the scripting feature".to_string(),
+        );
+    }
+    let mut lifecycle_events: Vec<LifecycleEvent> = Vec::new();
+
     // Initialize tray icon
-    let tray = TrayManager::new().expect("Failed to create tray icon");
+    let check_updates = cfg!(feature = "updates") && config.updates.check;
+    let (tray_icons, icon_problems) =
+        TrayIcons::load(&config.tray).expect("Failed to create tray icon");
+    for problem in icon_problems {
+        warn!("Failed to load tray icon {}", problem);
+        notify(Severity::Warning, "Tray icon not loaded", problem);
+    }
+    let mut tray = TrayManager::new(
+        args.autostart_flags(),
+        check_updates,
+        tray_icons,
+        config.tray.click,
+        config.tray.double_click,
+    )
+    .expect("Failed to create tray icon");
+
+    // Look for a newer release in the background
+    #[cfg(feature = "updates")]
+    let mut available_update: Option<megatile::updates::Update> = None;
+    #[cfg(feature = "updates")]
+    {
+        megatile::updates::remove_old_binary();
+        if check_updates {
+            megatile::updates::spawn_check(|update| {
+                push_event(WindowEvent::UpdateAvailable(update));
+            });
+        }
+    }
+    #[cfg(not(feature = "updates"))]
+    if config.updates.check {
+        notify(
+            Severity::Warning,
+            "Update checks unavailable",
+            "This build of Megatile was made without the updates feature".to_string(),
+        );
+    }
 
     // Create hidden window for hotkey messages
     let hwnd = create_message_window().expect("Failed to create message window");
 
     // Register hotkeys
     let mut hotkey_manager = HotkeyManager::new();
-    hotkey_manager
-        .register_hotkeys(hwnd)
-        .expect("Failed to register hotkeys");
+    if let Err(e) =
+        hotkey_manager.register_hotkeys(hwnd, config.workspaces.count, config.hotkeys.modifier)
+    {
+        error!("{}", e);
+        notify(Severity::Warning, "Hotkey conflict", e);
+    }
+    if let Err(e) = hotkey_manager.register_launch_hotkeys(hwnd, &config.launch.bindings) {
+        error!("{}", e);
+        notify(Severity::Warning, "Hotkey conflict", e);
+    }
+    if let Err(e) = hotkey_manager.register_stash_hotkeys(hwnd, &config.stash.slots) {
+        error!("{}", e);
+        notify(Severity::Warning, "Hotkey conflict", e);
+    }
+
+    // Popup for problems worth the user's attention
+    let mut notifier = Notifier::new()
+        .inspect_err(|e| error!("Failed to create notification window: {}", e))
+        .ok();
+
+    // Keybinding overlay, toggled with Alt+/
+    let mut cheatsheet = Cheatsheet::new()
+        .inspect_err(|e| error!("Failed to create cheatsheet window: {}", e))
+        .ok();
+
+    // Rules editor, opened from the tray
+    let mut settings = SettingsWindow::new(config.workspaces.count)
+        .inspect_err(|e| {
+            error!("Failed to create settings window: {}", e);
+            tray.disable_settings();
+        })
+        .ok();
+    let mut about = AboutWindow::new()
+        .inspect_err(|e| {
+            error!("Failed to create About window: {}", e);
+            tray.disable_about();
+        })
+        .ok();
+
+    // Launch the configured startup programs; their windows are routed as they appear
+    let mut rules = RuleEngine::new(config.rules.clone());
+    for entry in &config.autostart.commands {
+        if let Err(e) = launcher::launch(entry, &mut rules) {
+            error!("{}", e);
+            notify(Severity::Warning, "Autostart failed", e);
+        }
+    }
 
     // Initialize GDI+ for anti-aliased rendering
     init_gdiplus().expect("Failed to initialize GDI+");
 
-    // Initialize status bar
-    let statusbar = StatusBar::new(hwnd).expect("Failed to create status bar");
-
-    // Set status bar position and size (top center of primary monitor)
-    let monitor_infos = windows_lib::enumerate_monitors();
-    if let Some(primary_monitor) = monitor_infos.iter().find(|m| m.is_primary) {
-        let rect = primary_monitor.rect;
-        let statusbar_width = STATUSBAR_WIDTH;
-        let statusbar_height = STATUSBAR_HEIGHT;
-        let x = rect.left + (rect.right - rect.left - statusbar_width) / 2;
-        let y = rect.top + STATUSBAR_TOP_GAP;
-
-        statusbar.set_position(x, y, statusbar_width, statusbar_height);
-        statusbar.show(); // Show the status bar on startup
+    // Initialize the status bars, unless a third-party bar takes their place
+    let clock = Clock::new(&config.statusbar.clock).unwrap_or_else(|e| {
+        error!("Failed to set up the clock: {}", e);
+        notify(Severity::Warning, "Clock", e);
+        Clock::default()
+    });
+    if !config.statusbar.visible {
+        wm.toggle_statusbar(false);
     }
-
-    wm.set_statusbar(statusbar);
-    wm.update_statusbar();
+    sync_statusbars(&mut wm, hwnd, &config, &clock);
     wm.update_decorations();
 
+    // Workspace and focus state for third-party bars
+    let state_stream = StateStream::spawn()
+        .inspect_err(|e| error!("Failed to start the state stream: {}", e))
+        .ok();
+    let mut last_bar_state = BarState::default();
+
     info!("Megatile is running. Use the tray icon to exit.");
 
+    let power_setting = config.power.saving;
+    let mut power_saving = power::should_save(power_setting, PowerState::current());
+    wm.set_power_saving(power_saving);
+    let mut intervals = Intervals::new(power_saving);
+    if power_saving {
+        info!("Power saving is on");
+    }
+
+    let mut last_maintenance = Instant::now();
+    // Monitor changes arrive as WM_DISPLAYCHANGE / WM_DEVICECHANGE; polling is only a fallback
     let mut last_monitor_check = Instant::now();
-    let monitor_check_interval = Duration::from_millis(100);
     let mut last_clock_update = Instant::now();
-    let clock_update_interval = Duration::from_secs(1);
+    let mut last_telemetry_log = Instant::now();
+    let telemetry_log_interval = Duration::from_secs(30);
+    let mut last_telemetry = telemetry::snapshot();
+    let mut last_workspace = wm.get_active_workspace();
+    let mut last_display_count = wm.display_count();
+    let mut last_displays = wm.displays();
+    tray.set_displays(&last_displays);
+    tray.set_state(TrayState::from_displays(
+        &last_displays,
+        notifications::error_reported(),
+    ));
+    let mut last_recoverable = Vec::new();
 
     // Main event loop
     loop {
-        // 1. Check monitor configuration first (every 100ms)
-        if last_monitor_check.elapsed() >= monitor_check_interval {
+        // 1. Fallback check for missed monitor configuration changes (every 5s, 30s saving power)
+        if last_monitor_check.elapsed() >= intervals.monitor_check {
             if wm.check_monitor_changes() {
-                info!("Monitor change detected in main loop");
+                info!("Monitor change detected by fallback poll");
                 if let Err(e) = wm.reenumerate_monitors() {
                     error!("Failed to reenumerate monitors: {}", e);
                 } else {
-                    // Recenter status bar on primary monitor after monitor changes
-                    wm.recenter_statusbar();
+                    // Move the status bars along with the monitor changes
+                    wm.place_statusbars();
+                    sync_statusbars(&mut wm, hwnd, &config, &clock);
                 }
             }
-            // Periodic maintenance tasks
+            last_monitor_check = Instant::now();
+        }
+
+        // Periodic maintenance tasks (every 100ms, 500ms saving power)
+        if last_maintenance.elapsed() >= intervals.maintenance {
             wm.update_decorations();
+            wm.update_fullscreen_statusbar();
             wm.cleanup_invalid_windows();
-            last_monitor_check = Instant::now();
+            wm.apply_pending_resizes();
+            if let Some(rects) = wm.float_rects_to_save()
+                && let Err(e) = float_geometry::save(rects)
+            {
+                error!("Failed to save floating window positions: {}", e);
+            }
+            last_maintenance = Instant::now();
         }
 
-        // 2. Update status bar clock (every second)
-        if last_clock_update.elapsed() >= clock_update_interval {
+        // 2. Update status bar clock (every second, 5s saving power)
+        if last_clock_update.elapsed() >= intervals.clock {
             wm.update_statusbar_clock();
             last_clock_update = Instant::now();
         }
+        wm.update_statusbar_auto_hide();
+        wm.update_focus_flash();
+
+        // Periodic telemetry summary (every 30s, only with --telemetry)
+        if telemetry::is_enabled() && last_telemetry_log.elapsed() >= telemetry_log_interval {
+            let current = telemetry::snapshot();
+            debug!(
+                "Telemetry (last {}s): {}",
+                telemetry_log_interval.as_secs(),
+                current
+                    .since(&last_telemetry)
+                    .report(last_telemetry_log.elapsed())
+            );
+            last_telemetry = current;
+            last_telemetry_log = Instant::now();
+        }
+
+        if let Some(notifier) = &mut notifier {
+            notifier.tick();
+        }
 
         // 3. Check for tray exit
-        if tray.should_exit() {
+        if tray.take_exit_request()
+            && (!config.tray.confirm_exit || tray::confirm_exit(wm.get_all_managed_hwnds().len()))
+        {
             push_event(WindowEvent::TrayExit);
         }
+        if tray.take_settings_request()
+            && let Some(settings) = &mut settings
+        {
+            settings.open();
+        }
+        if tray.take_statusbar_toggle() {
+            wm.invert_statusbar_visibility();
+        }
+        if tray.take_effects_toggle() {
+            let enabled = !wm.window_effects();
+            wm.set_window_effects(enabled);
+        }
+        tray.set_toggles(wm.statusbar_visible(), wm.window_effects());
+        if tray.take_about_request()
+            && let Some(about) = &mut about
+        {
+            about.open(&Diagnostics::collect(
+                wm.monitor_summaries(),
+                wm.get_active_workspace(),
+            ));
+        }
+        match tray.take_click_action() {
+            Some(TrayClickAction::Cheatsheet) => {
+                if let Some(cheatsheet) = &mut cheatsheet {
+                    cheatsheet.toggle(hotkey_manager.bindings());
+                }
+            }
+            Some(TrayClickAction::Pause) => toggle_all_displays(&mut wm),
+            Some(TrayClickAction::Statusbar) => wm.invert_statusbar_visibility(),
+            Some(TrayClickAction::Settings) => {
+                if let Some(settings) = &mut settings {
+                    settings.open();
+                }
+            }
+            Some(TrayClickAction::About) => {
+                if let Some(about) = &mut about {
+                    about.open(&Diagnostics::collect(
+                        wm.monitor_summaries(),
+                        wm.get_active_workspace(),
+                    ));
+                }
+            }
+            Some(TrayClickAction::None | TrayClickAction::Menu) | None => {}
+        }
+        if tray.take_restart_request() {
+            push_event(WindowEvent::Restart);
+        }
+        if let Some(device) = tray.take_display_toggle() {
+            toggle_display(&mut wm, &device);
+        }
+        while let Some(click) = statusbar::take_window_list_click() {
+            match click {
+                WindowListClick::Focus(hwnd) => wm.set_window_focus(HWND(hwnd as _)),
+                WindowListClick::Close(hwnd) => {
+                    if let Err(e) = wm.close_window(HWND(hwnd as _)) {
+                        error!("Failed to close window: {}", e);
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "updates")]
+        if tray.take_update_request()
+            && let Some(update) = available_update.take()
+        {
+            // Download off the event loop; the restart is queued once it's in place
+            tray.show_update_installing();
+            std::thread::spawn(move || match megatile::updates::install(&update) {
+                Ok(()) => push_event(WindowEvent::Restart),
+                Err(e) => push_event(WindowEvent::UpdateFailed(e)),
+            });
+        }
 
         // 4. Process window messages
         let mut msg = MSG::default();
@@ -536,8 +1398,6 @@ fn main() {
                 if let Some(action) = action {
                     push_event(WindowEvent::Hotkey(action));
                 }
-            } else if msg.message == WM_DISPLAYCHANGE {
-                push_event(WindowEvent::DisplayChange);
             } else {
                 unsafe {
                     let _ = TranslateMessage(&msg);
@@ -546,6 +1406,27 @@ fn main() {
             }
         }
 
+        // Rules edited in the settings window apply to the next new window
+        if let Some(new_rules) = settings
+            .as_mut()
+            .and_then(|settings| settings.take_changed_rules())
+        {
+            rules.set_rules(new_rules);
+        }
+
+        // Put-off events whose time has come join the queue
+        let now = Instant::now();
+        let (due, waiting) = delayed_events
+            .drain(..)
+            .partition::<Vec<_>, _>(|(due, _)| *due <= now);
+        delayed_events = waiting;
+        for (_, event) in due {
+            if let WindowEvent::ProcessNameResolved(hwnd_val, _) = &event {
+                released_windows.insert(*hwnd_val);
+            }
+            push_event(event);
+        }
+
         // 5. Process all events from the queue per iteration
         loop {
             let event = if let Some(queue) = EVENT_QUEUE.get() {
@@ -560,48 +1441,176 @@ fn main() {
 
             if let Some(event) = event {
                 match event {
+                    WindowEvent::Hotkey(hotkeys::HotkeyAction::ToggleCheatsheet) => {
+                        if let Some(cheatsheet) = &mut cheatsheet {
+                            cheatsheet.toggle(hotkey_manager.bindings());
+                        }
+                    }
                     WindowEvent::Hotkey(action) => {
-                        handle_action(action, &mut wm);
+                        handle_action(action, &mut wm, &mut rules, &config);
                     }
                     WindowEvent::WindowCreated(hwnd_val) => {
                         let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
 
-                        // Check if we already manage this window
-                        if wm.get_window(hwnd).is_some() {
+                        // Check if we already manage this window or are already looking it up
+                        if wm.get_window(hwnd).is_some()
+                            || wm.is_stashed(hwnd)
+                            || pending_lookups.contains(&hwnd_val)
+                        {
                             continue;
                         }
 
-                        // Use is_normal_window_hwnd which is more efficient
-                        if windows_lib::is_normal_window_hwnd(hwnd) {
-                            info!("Event: Window Registered {:?}", hwnd);
-                            let info = windows_lib::WindowInfo {
-                                hwnd,
-                                title: windows_lib::get_window_title(hwnd),
-                                class_name: windows_lib::get_window_class(hwnd),
-                                rect: windows_lib::get_window_rect(hwnd).unwrap_or_default(),
-                                is_visible: true,
-                                is_minimized: false,
-                            };
-
-                            let active_workspace = wm.get_active_workspace();
-                            let monitor_index = wm.get_monitor_for_window(hwnd).unwrap_or(0);
-                            let process_name = get_process_name_for_window(hwnd);
-                            let window = workspace::Window::new(
+                        // Cheap pre-check; app-specific filters run once the process name is known
+                        let kind = windows_lib::window_kind_hwnd_for_process(hwnd, None);
+                        if is_managed_kind(kind, &config) {
+                            explorer_rechecks.remove(&hwnd_val);
+                            pending_lookups.insert(hwnd_val);
+                            process_names.request(hwnd_val);
+                        } else if kind == WindowKind::Transient {
+                            // Unmanaged dialogs still open over their window if asked to
+                            wm.center_dialog(hwnd);
+                        } else {
+                            recheck_explorer_window(
+                                hwnd_val,
+                                &mut explorer_rechecks,
+                                &mut delayed_events,
+                            );
+                        }
+                    }
+                    WindowEvent::ProcessNameResolved(hwnd_val, process_name) => {
+                        pending_lookups.remove(&hwnd_val);
+                        let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
+
+                        // The window may have been registered, hidden or destroyed in the meantime
+                        if wm.get_window(hwnd).is_some() || wm.is_stashed(hwnd) {
+                            continue;
+                        }
+                        // Apps with a splash screen get time to replace it with the real window
+                        let delay = quirks_for(&config.quirks, process_name.as_deref())
+                            .map_or(0, |quirks| quirks.manage_delay_ms);
+                        if delay > 0 && !released_windows.remove(&hwnd_val) {
+                            debug!("Holding window {:?} back for {} ms", hwnd, delay);
+                            pending_lookups.insert(hwnd_val);
+                            delayed_events.push((
+                                Instant::now() + Duration::from_millis(delay),
+                                WindowEvent::ProcessNameResolved(hwnd_val, process_name),
+                            ));
+                            continue;
+                        }
+                        let kind = windows_lib::window_kind_hwnd_for_process(
+                            hwnd,
+                            process_name.as_deref(),
+                        );
+                        if !is_managed_kind(kind, &config) {
+                            recheck_explorer_window(
                                 hwnd_val,
-                                active_workspace,
-                                monitor_index,
-                                info.rect,
-                                process_name,
+                                &mut explorer_rechecks,
+                                &mut delayed_events,
                             );
-                            let _ = show_window_in_taskbar(hwnd);
-                            wm.add_window(window);
-                            wm.tile_active_workspaces();
-                            wm.apply_window_positions();
+                            continue;
+                        }
+                        let transient = kind == WindowKind::Transient;
+
+                        info!("Event: Window Registered {:?}", hwnd);
+                        // Apps that open maximized fight their first tile, so restore them
+                        // first, unless a quirk keeps them maximized (and floating)
+                        let mut keep_maximized = false;
+                        if windows_lib::is_window_maximized(hwnd) {
+                            keep_maximized = quirks_for(&config.quirks, process_name.as_deref())
+                                .is_some_and(|quirks| quirks.keep_maximized);
+                            if !keep_maximized {
+                                debug!("Restoring maximized window {:?} before tiling it", hwnd);
+                                windows_lib::unmaximize_window(hwnd);
+                            }
+                        }
+                        let rect = windows_lib::get_window_rect(hwnd).unwrap_or_default();
+                        let active_workspace = wm.get_active_workspace();
+                        // Rules (including programs we launched) may send it to another workspace;
+                        // otherwise it opens here unless this workspace is locked
+                        let class = windows_lib::get_window_class(hwnd);
+                        let title = windows_lib::get_window_title(hwnd);
+                        let target_workspace = rules
+                            .workspace_for(&WindowIdentity {
+                                process: process_name.as_deref(),
+                                class: &class,
+                                title: &title,
+                            })
+                            .unwrap_or_else(|| wm.unlocked_workspace_for(active_workspace));
+                        // Dialogs belong with the window that opened them, which is in front
+                        let target_workspace = if transient {
+                            active_workspace
+                        } else {
+                            target_workspace
+                        };
+                        // Windows left floating stay on the monitor they opened on
+                        let monitor_index = if transient || keep_maximized {
+                            wm.get_monitor_for_window(hwnd)
+                                .unwrap_or_else(|| wm.primary_monitor_index())
+                        } else {
+                            wm.monitor_for_new_window(hwnd)
+                        };
+                        if !wm.is_monitor_managed(monitor_index) {
+                            debug!("Window {:?} opened on an unmanaged monitor", hwnd);
+                            continue;
+                        }
+                        let mut window = workspace::Window::new(
+                            hwnd_val,
+                            target_workspace,
+                            monitor_index,
+                            rect,
+                            process_name,
+                        );
+                        window.title = title.clone();
+                        window.class = class.clone();
+                        window.is_tiled = !transient && !keep_maximized;
+                        lifecycle_events.push(LifecycleEvent::WindowCreated {
+                            hwnd: hwnd_val,
+                            process: window.process_name.clone(),
+                            class,
+                            title,
+                            workspace: target_workspace,
+                        });
+                        place_window(&mut wm, window);
+                        if transient && let Err(e) = wm.center_window(hwnd) {
+                            error!("Failed to center {:?}: {}", hwnd, e);
+                        }
+                        wm.focus_new_window(hwnd);
+                    }
+                    WindowEvent::TitleChanged(hwnd_val) => {
+                        let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
+                        let title = windows_lib::get_window_title(hwnd);
+                        if !wm.set_window_title(hwnd, &title) {
+                            continue;
+                        }
+                        let Some(window) = wm.get_window(hwnd) else {
+                            continue;
+                        };
+                        if window.registered_at.elapsed() >= TITLE_RULE_GRACE {
+                            continue;
+                        }
+                        // The real title may match a rule the placeholder didn't
+                        let class = windows_lib::get_window_class(hwnd);
+                        let target_workspace = rules.workspace_for_title(&WindowIdentity {
+                            process: window.process_name.as_deref(),
+                            class: &class,
+                            title: &title,
+                        });
+                        if let Some(target_workspace) = target_workspace
+                            && target_workspace != window.workspace
+                            && let Some(mut window) = wm.remove_window_with_tiling(hwnd)
+                        {
+                            info!(
+                                "Window {:?} retitled to {:?}, moving it to workspace {}",
+                                hwnd, title, target_workspace
+                            );
+                            window.workspace = target_workspace;
+                            place_window(&mut wm, window);
                         }
                     }
                     WindowEvent::WindowDestroyed(hwnd_val) => {
                         let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
                         info!("Event: Window Destroyed {:?}", hwnd);
+                        explorer_rechecks.remove(&hwnd_val);
                         wm.remove_window_with_tiling(hwnd);
                     }
                     WindowEvent::WindowMinimized(hwnd_val) => {
@@ -639,25 +1648,107 @@ fn main() {
                     WindowEvent::WindowMoved(hwnd_val) => {
                         let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
                         // Only process move events if not from our own positioning
-                        if !wm.is_positioning_window(hwnd) {
+                        if !wm.is_positioning_window(hwnd) && !config.layout.retile_on_drop {
                             wm.update_window_positions();
                         }
                     }
-                    WindowEvent::FocusChanged(_hwnd_val) => {
+                    WindowEvent::WindowDragStarted(hwnd_val) => {
+                        wm.handle_window_drag_started(HWND(hwnd_val as *mut std::ffi::c_void));
+                    }
+                    WindowEvent::WindowDropped(hwnd_val) => {
+                        wm.handle_window_dropped(HWND(hwnd_val as *mut std::ffi::c_void));
+                    }
+                    WindowEvent::FocusChanged(hwnd_val) => {
+                        wm.enforce_focus_guard(HWND(hwnd_val as *mut std::ffi::c_void));
                         wm.update_decorations();
                     }
+                    WindowEvent::WindowUrgent(hwnd_val) => {
+                        wm.mark_urgent(HWND(hwnd_val as *mut std::ffi::c_void));
+                    }
                     WindowEvent::DisplayChange => {
                         info!("Event: Display Change");
                         if let Err(e) = wm.reenumerate_monitors() {
                             error!("Failed to reenumerate monitors: {}", e);
                         } else {
-                            // Recenter status bar on primary monitor after display change
-                            wm.recenter_statusbar();
+                            // Move the status bars along with the display change
+                            wm.place_statusbars();
+                            sync_statusbars(&mut wm, hwnd, &config, &clock);
+                        }
+                    }
+                    WindowEvent::DeviceChange => {
+                        // Only pay for a full re-enumeration if the monitor set actually changed
+                        if wm.check_monitor_changes() {
+                            info!("Event: Device Change affected monitors");
+                            if let Err(e) = wm.reenumerate_monitors() {
+                                error!("Failed to reenumerate monitors: {}", e);
+                            } else {
+                                wm.place_statusbars();
+                                sync_statusbars(&mut wm, hwnd, &config, &clock);
+                            }
+                        }
+                    }
+                    WindowEvent::MetricsChanged => {
+                        debug!("Event: Metrics Changed");
+                        wm.invalidate_dwm_borders();
+                        // Accent and light/dark changes arrive as
+                        // WM_SETTINGCHANGE("ImmersiveColorSet")
+                        wm.refresh_theme();
+                    }
+                    WindowEvent::ColorizationChanged => {
+                        debug!("Event: Colorization Changed");
+                        wm.refresh_theme();
+                    }
+                    WindowEvent::PowerChanged => {
+                        let saving = power::should_save(power_setting, PowerState::current());
+                        if saving != power_saving {
+                            info!("Power saving turned {}", if saving { "on" } else { "off" });
+                            power_saving = saving;
+                            intervals = Intervals::new(saving);
+                            wm.set_power_saving(saving);
                         }
                     }
                     WindowEvent::TrayExit => {
                         info!("Exiting Megatile...");
-                        cleanup_on_exit(&mut wm);
+                        cleanup_on_exit(&mut wm, |restored, total| {
+                            tray.show_exit_progress(restored, total)
+                        });
+                        hotkey_manager.unregister_all(hwnd);
+                        shutdown_gdiplus();
+                        return;
+                    }
+                    #[cfg(feature = "updates")]
+                    WindowEvent::UpdateAvailable(update) => {
+                        tray.offer_update(&update.version);
+                        notify(
+                            Severity::Info,
+                            "Update available",
+                            format!(
+                                "Megatile {} is out. Install it from the tray menu.",
+                                update.version
+                            ),
+                        );
+                        available_update = Some(update);
+                    }
+                    #[cfg(feature = "updates")]
+                    WindowEvent::UpdateFailed(e) => {
+                        error!("{}", e);
+                        notify(Severity::Error, "Update failed", e);
+                    }
+                    WindowEvent::Restart => {
+                        info!("Restarting Megatile...");
+                        // The new instance waits for this one to exit before taking over
+                        if let Err(e) = wm
+                            .saved_session()
+                            .save()
+                            .and_then(|_| spawn_replacement(&args))
+                        {
+                            error!("Failed to restart: {}", e);
+                            notify(Severity::Error, "Restart failed", e);
+                            continue;
+                        }
+                        cleanup_on_exit(&mut wm, |restored, total| {
+                            tray.show_exit_progress(restored, total)
+                        });
                         hotkey_manager.unregister_all(hwnd);
                         shutdown_gdiplus();
                         return;
@@ -668,20 +1759,215 @@ fn main() {
             }
         }
 
+        // 6. Hand lifecycle events to hooks and the script once the state has settled
+        let active_workspace = wm.get_active_workspace();
+        if active_workspace != last_workspace {
+            lifecycle_events.push(LifecycleEvent::WorkspaceSwitched {
+                from: last_workspace,
+                to: active_workspace,
+            });
+            last_workspace = active_workspace;
+            wm.reveal_statusbar();
+        }
+        let display_count = wm.display_count();
+        if display_count != last_display_count {
+            lifecycle_events.push(LifecycleEvent::MonitorsChanged {
+                count: display_count,
+            });
+            last_display_count = display_count;
+        }
+        let bar_state = wm.bar_state();
+        if bar_state != last_bar_state {
+            bar_snapshot.set(&bar_state);
+            if let Some(stream) = &state_stream {
+                stream.publish(&bar_state);
+            }
+            last_bar_state = bar_state;
+        }
+        wm.update_window_list();
+        let displays = wm.displays();
+        if displays != last_displays {
+            tray.set_displays(&displays);
+            last_displays = displays;
+        }
+        tray.set_state(TrayState::from_displays(
+            &last_displays,
+            notifications::error_reported(),
+        ));
+        let recoverable = wm.recoverable_windows();
+        if recoverable != last_recoverable {
+            if let Err(e) = recovery::save(&recoverable) {
+                error!("Failed to save the crash recovery file: {}", e);
+            }
+            last_recoverable = recoverable;
+        }
+        for event in lifecycle_events.drain(..) {
+            debug!("Lifecycle event: {:?}", event);
+            hooks::run_hooks(&config.hooks, &event);
+            #[cfg(feature = "scripting")]
+            if let Some(scripts) = scripts.as_mut() {
+                for action in scripts.handle(&event) {
+                    push_event(WindowEvent::Hotkey(action));
+                }
+            }
+        }
+
         std::thread::sleep(Duration::from_millis(5));
     }
 }
 
+/// Starts a new instance with the same flags that takes over from this one.
+fn spawn_replacement(args: &Args) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the Megatile executable: {}", e))?;
+    std::process::Command::new(&exe)
+        .args(args.autostart_flags())
+        .arg("--restart-from")
+        .arg(std::process::id().to_string())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", exe.display(), e))?;
+    Ok(())
+}
+
+/// Answers a command received over IPC.
+///
+/// Runs on the IPC thread, so only thread-safe state may be read here.
+fn handle_ipc_command(command: &str) -> String {
+    match ipc::parse_command(command, WORKSPACE_COUNT.load(Ordering::SeqCst)) {
+        Ok(ipc::Command::Telemetry) => telemetry::report(),
+        Ok(ipc::Command::Restart) => {
+            push_event(WindowEvent::Restart);
+            "ok".to_string()
+        }
+        Ok(ipc::Command::Action(action)) => {
+            // Actions must run on the main thread, so queue it like a hotkey
+            push_event(WindowEvent::Hotkey(action));
+            "ok".to_string()
+        }
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+/// Handles `--install-autostart` / `--uninstall-autostart`.
+///
+/// Returns the process exit code.
+fn run_autostart_command(args: &Args) -> i32 {
+    attach_parent_console();
+    let result = if args.install_autostart {
+        autostart::enable(&args.autostart_flags())
+    } else {
+        autostart::disable()
+    };
+    match result {
+        Ok(()) if args.install_autostart => {
+            println!("Megatile will start on login");
+            0
+        }
+        Ok(()) => {
+            println!("Megatile will no longer start on login");
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Shows the windows a crashed instance left hidden.
+///
+/// Returns the process exit code.
+fn run_force_cleanup() -> i32 {
+    attach_parent_console();
+    match recovery::restore() {
+        Ok(count) => {
+            println!("Restored {} windows", count);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Attaches to the calling terminal so CLI-only modes can print.
+///
+/// Release builds use the GUI subsystem and have no console of their own.
+fn attach_parent_console() {
+    unsafe {
+        let _ = windows::Win32::System::Console::AttachConsole(
+            windows::Win32::System::Console::ATTACH_PARENT_PROCESS,
+        );
+    }
+}
+
+/// Sends `command` to the running instance and prints the reply.
+///
+/// Returns the process exit code.
+fn send_ipc_command(command: &str) -> i32 {
+    attach_parent_console();
+    match ipc::send_command(command) {
+        Ok(reply) => {
+            println!("{}", reply);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
 /// Window procedure for the hidden message window.
 extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     unsafe {
         if msg == WM_DESTROY {
             PostQuitMessage(0);
+        } else if msg == WM_DISPLAYCHANGE {
+            push_event(WindowEvent::DisplayChange);
+        } else if msg == WM_DEVICECHANGE && wparam.0 as u32 == DBT_DEVNODES_CHANGED {
+            push_event(WindowEvent::DeviceChange);
+        } else if msg == WM_DPICHANGED || msg == WM_THEMECHANGED || msg == WM_SETTINGCHANGE {
+            // Sent rather than posted, so these never reach the PeekMessage loop
+            push_event(WindowEvent::MetricsChanged);
+        } else if msg == WM_POWERBROADCAST && wparam.0 as u32 == PBT_APMPOWERSTATUSCHANGE {
+            push_event(WindowEvent::PowerChanged);
+        } else if msg == WM_DWMCOLORIZATIONCOLORCHANGED {
+            push_event(WindowEvent::ColorizationChanged);
+        } else if msg == WM_COPYDATA {
+            return handle_copydata(lparam);
+        } else if msg != 0
+            && msg == SHELLHOOK_MESSAGE.load(Ordering::Relaxed)
+            && wparam.0 as u32 == HSHELL_REDRAW | HSHELL_HIGHBIT
+        {
+            // HSHELL_FLASH: a window flashed its taskbar button
+            push_event(WindowEvent::WindowUrgent(lparam.0));
         }
         DefWindowProcW(hwnd, msg, wparam, lparam)
     }
 }
 
+/// Runs an IPC command sent as `WM_COPYDATA`.
+///
+/// The sender only learns whether the command was accepted (1) or not (0);
+/// the reply text is logged instead.
+unsafe fn handle_copydata(lparam: LPARAM) -> LRESULT {
+    let data = unsafe { &*(lparam.0 as *const COPYDATASTRUCT) };
+    let bytes = if data.lpData.is_null() || data.cbData == 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize) }
+    };
+    let Some(command) = ipc::decode_copydata(data.dwData, bytes) else {
+        return LRESULT(0);
+    };
+    debug!("WM_COPYDATA command: {}", command);
+    let reply = handle_ipc_command(&command);
+    info!("{}", reply);
+    LRESULT(if reply.starts_with("error:") { 0 } else { 1 })
+}
+
 /// Creates a hidden window for receiving hotkey and system messages.
 fn create_message_window() -> Result<HWND, String> {
     unsafe {
@@ -718,6 +2004,12 @@ fn create_message_window() -> Result<HWND, String> {
             Err(_) => return Err("Failed to create window".to_string()),
         };
 
+        // Shell hook messages tell when a window flashes for attention
+        SHELLHOOK_MESSAGE.store(RegisterWindowMessageW(w!("SHELLHOOK")), Ordering::Relaxed);
+        if !RegisterShellHookWindow(hwnd).as_bool() {
+            warn!("Failed to register for shell hook messages");
+        }
+
         Ok(hwnd)
     }
 }
@@ -0,0 +1,247 @@
+//! Output volume and the media playing, for the status bar's media module.
+//!
+//! A worker thread polls the default output device's volume and the current
+//! media session (GlobalSystemMediaTransportControls), and carries out the
+//! bar's commands, so a slow media app never holds up the event loop.
+
+use log::{debug, error};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use windows::Media::Control::{
+    GlobalSystemMediaTransportControlsSession, GlobalSystemMediaTransportControlsSessionManager,
+    GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+};
+use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
+use windows::Win32::Media::Audio::{IMMDeviceEnumerator, MMDeviceEnumerator, eConsole, eRender};
+use windows::Win32::System::Com::{
+    CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx,
+};
+
+/// How often the volume and media session are read.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The media a player reports as current.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub title: String,
+    pub artist: String,
+    pub playing: bool,
+}
+
+/// What the media module shows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaState {
+    /// Output volume in percent, if there is an output device.
+    pub volume: Option<u8>,
+    pub muted: bool,
+    pub now_playing: Option<NowPlaying>,
+}
+
+impl MediaState {
+    /// Returns the media label, e.g. `Title - Artist`.
+    pub fn media_label(&self) -> Option<String> {
+        let now_playing = self.now_playing.as_ref()?;
+        match (now_playing.title.is_empty(), now_playing.artist.is_empty()) {
+            (true, true) => None,
+            (false, true) => Some(now_playing.title.clone()),
+            (true, false) => Some(now_playing.artist.clone()),
+            (false, false) => Some(format!("{} - {}", now_playing.title, now_playing.artist)),
+        }
+    }
+
+    /// Returns the volume label, e.g. `45%` or `Muted`.
+    pub fn volume_label(&self) -> Option<String> {
+        let volume = self.volume?;
+        Some(if self.muted {
+            "Muted".to_string()
+        } else {
+            format!("{}%", volume)
+        })
+    }
+}
+
+/// Something the media module asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaCommand {
+    /// Raise or lower the volume by this many percent.
+    AdjustVolume(i32),
+    TogglePlayPause,
+}
+
+/// Handle to the media worker thread.
+///
+/// The worker exits once this handle is dropped.
+#[derive(Debug)]
+pub struct MediaMonitor {
+    sender: Sender<MediaCommand>,
+    state: Arc<Mutex<MediaState>>,
+}
+
+impl MediaMonitor {
+    /// Spawns the worker thread.
+    ///
+    /// `on_change` runs on the worker thread whenever the state changes, and
+    /// is expected to tell the bar to read it with [`MediaMonitor::state`].
+    pub fn spawn<F>(on_change: F) -> Result<Self, String>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<MediaCommand>();
+        let state = Arc::new(Mutex::new(MediaState::default()));
+        let shared = Arc::clone(&state);
+        thread::Builder::new()
+            .name("megatile-media".to_string())
+            .spawn(move || {
+                if let Err(e) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok() {
+                    error!("Failed to initialize COM for the media module: {}", e);
+                    return;
+                }
+                let mut sessions = None;
+                loop {
+                    match receiver.recv_timeout(POLL_INTERVAL) {
+                        Ok(command) => {
+                            if let Err(e) = run_command(command, &mut sessions) {
+                                debug!("Media command {:?} failed: {}", command, e);
+                            }
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                    let current = read_state(&mut sessions);
+                    let Ok(mut state) = shared.lock() else {
+                        return;
+                    };
+                    if *state != current {
+                        *state = current;
+                        drop(state);
+                        on_change();
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn media thread: {}", e))?;
+        Ok(MediaMonitor { sender, state })
+    }
+
+    /// Returns the latest volume and media.
+    pub fn state(&self) -> MediaState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// Queues a command for the worker.
+    pub fn send(&self, command: MediaCommand) {
+        let _ = self.sender.send(command);
+    }
+}
+
+type Sessions = Option<GlobalSystemMediaTransportControlsSessionManager>;
+
+fn run_command(command: MediaCommand, sessions: &mut Sessions) -> windows::core::Result<()> {
+    match command {
+        MediaCommand::AdjustVolume(delta) => {
+            let volume = endpoint_volume()?;
+            unsafe {
+                let level = volume.GetMasterVolumeLevelScalar()?;
+                let level = (level + delta as f32 / 100.0).clamp(0.0, 1.0);
+                volume.SetMasterVolumeLevelScalar(level, std::ptr::null())
+            }
+        }
+        MediaCommand::TogglePlayPause => {
+            if let Some(session) = current_session(sessions)? {
+                session.TryTogglePlayPauseAsync()?.join()?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn read_state(sessions: &mut Sessions) -> MediaState {
+    let volume = endpoint_volume().and_then(|volume| unsafe {
+        Ok((
+            volume.GetMasterVolumeLevelScalar()?,
+            volume.GetMute()?.as_bool(),
+        ))
+    });
+    let (volume, muted) = match volume {
+        Ok((level, muted)) => (Some((level * 100.0).round() as u8), muted),
+        Err(_) => (None, false),
+    };
+    let now_playing = match now_playing(sessions) {
+        Ok(now_playing) => now_playing,
+        Err(e) => {
+            debug!("Failed to read the media session: {}", e);
+            None
+        }
+    };
+    MediaState {
+        volume,
+        muted,
+        now_playing,
+    }
+}
+
+/// Returns the volume control of the default output device, which changes
+/// as headphones come and go.
+fn endpoint_volume() -> windows::core::Result<IAudioEndpointVolume> {
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?;
+        device.Activate(CLSCTX_ALL, None)
+    }
+}
+
+fn current_session(
+    sessions: &mut Sessions,
+) -> windows::core::Result<Option<GlobalSystemMediaTransportControlsSession>> {
+    let manager = match sessions {
+        Some(manager) => manager,
+        None => sessions
+            .insert(GlobalSystemMediaTransportControlsSessionManager::RequestAsync()?.join()?),
+    };
+    // Fails when nothing is playing
+    Ok(manager.GetCurrentSession().ok())
+}
+
+fn now_playing(sessions: &mut Sessions) -> windows::core::Result<Option<NowPlaying>> {
+    let Some(session) = current_session(sessions)? else {
+        return Ok(None);
+    };
+    let properties = session.TryGetMediaPropertiesAsync()?.join()?;
+    let status = session.GetPlaybackInfo()?.PlaybackStatus()?;
+    Ok(Some(NowPlaying {
+        title: properties.Title()?.to_string_lossy(),
+        artist: properties.Artist()?.to_string_lossy(),
+        playing: status == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_leave_out_what_is_missing() {
+        let mut state = MediaState::default();
+        assert_eq!(state.media_label(), None);
+        assert_eq!(state.volume_label(), None);
+
+        state.volume = Some(45);
+        state.now_playing = Some(NowPlaying {
+            title: "Song".to_string(),
+            artist: String::new(),
+            playing: true,
+        });
+        assert_eq!(state.media_label().as_deref(), Some("Song"));
+        assert_eq!(state.volume_label().as_deref(), Some("45%"));
+
+        state.muted = true;
+        state.now_playing.as_mut().unwrap().artist = "Band".to_string();
+        assert_eq!(state.media_label().as_deref(), Some("Song - Band"));
+        assert_eq!(state.volume_label().as_deref(), Some("Muted"));
+    }
+}
@@ -0,0 +1,374 @@
+//! Lightweight on-screen notifications.
+//!
+//! Problems the user can act on (hotkey conflicts, windows that can't be
+//! managed, ...) otherwise only reach the log file. [`notify`] queues a
+//! message from any thread; the [`Notifier`] owned by the main loop shows them
+//! one at a time in a small popup at the bottom-right of the primary monitor.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BeginPaint, CLEARTYPE_QUALITY, CLIP_DEFAULT_PRECIS, CreateFontW, CreateSolidBrush,
+    DEFAULT_CHARSET, DT_END_ELLIPSIS, DT_NOPREFIX, DT_SINGLELINE, DT_WORDBREAK, DeleteObject,
+    DrawTextW, EndPaint, FW_NORMAL, FW_SEMIBOLD, FillRect, HFONT, InvalidateRect,
+    OUT_DEFAULT_PRECIS, PAINTSTRUCT, SelectObject, SetBkMode, SetTextColor, TRANSPARENT,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GWLP_USERDATA, GetClientRect,
+    GetWindowLongPtrW, HWND_TOPMOST, IDC_ARROW, IsWindowVisible, LoadCursorW, MA_NOACTIVATE,
+    RegisterClassW, SPI_GETWORKAREA, SW_HIDE, SWP_NOACTIVATE, SWP_SHOWWINDOW,
+    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SetWindowLongPtrW, SetWindowPos, ShowWindow,
+    SystemParametersInfoW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_LBUTTONUP, WM_MOUSEACTIVATE,
+    WM_NCDESTROY, WM_PAINT, WNDCLASSW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+};
+use windows::core::{PCWSTR, w};
+
+const POPUP_CLASS_NAME: PCWSTR = w!("MegatileNotification");
+const POPUP_WIDTH: i32 = 380;
+const POPUP_HEIGHT: i32 = 84;
+const POPUP_MARGIN: i32 = 12;
+const PADDING: i32 = 14;
+const STRIPE_WIDTH: i32 = 4;
+const BACKGROUND_COLOR: u32 = 0x00202020;
+const TITLE_COLOR: u32 = 0x00FFFFFF;
+const MESSAGE_COLOR: u32 = 0x00C8C8C8;
+/// How long a notification stays up unless clicked away.
+const DISPLAY_DURATION: Duration = Duration::from_secs(6);
+/// Notifications beyond this many waiting are dropped.
+const MAX_PENDING: usize = 5;
+
+static PENDING: Mutex<VecDeque<Notification>> = Mutex::new(VecDeque::new());
+
+/// Set once an error notification is queued, for the tray's error icon.
+static ERROR_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// How serious a notification is; picks the popup's accent stripe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Stripe color in COLORREF format (0x00BBGGRR).
+    fn color(self) -> u32 {
+        match self {
+            Severity::Info => 0x00D77800,
+            Severity::Warning => 0x0000A5FF,
+            Severity::Error => 0x003C3CE8,
+        }
+    }
+}
+
+/// A message waiting to be shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub title: String,
+    pub message: String,
+}
+
+/// Queues a notification for display. Safe to call from any thread.
+pub fn notify(severity: Severity, title: impl Into<String>, message: impl Into<String>) {
+    if severity == Severity::Error {
+        ERROR_REPORTED.store(true, Ordering::SeqCst);
+    }
+    let notification = Notification {
+        severity,
+        title: title.into(),
+        message: message.into(),
+    };
+    if let Ok(mut pending) = PENDING.lock() {
+        push_pending(&mut pending, notification);
+    }
+}
+
+/// Returns true if an error notification was queued since startup.
+pub fn error_reported() -> bool {
+    ERROR_REPORTED.load(Ordering::SeqCst)
+}
+
+/// Adds a notification to the queue, skipping exact duplicates and dropping
+/// it if the queue is already full.
+fn push_pending(pending: &mut VecDeque<Notification>, notification: Notification) {
+    if pending.len() >= MAX_PENDING || pending.contains(&notification) {
+        return;
+    }
+    pending.push_back(notification);
+}
+
+/// Popup contents read by the window procedure while painting.
+struct PopupState {
+    notification: Notification,
+    title_font: HFONT,
+    message_font: HFONT,
+}
+
+/// Owns the notification popup window. Lives on the main thread.
+pub struct Notifier {
+    hwnd: HWND,
+    state: Box<PopupState>,
+    shown_at: Option<Instant>,
+}
+
+impl Notifier {
+    /// Creates the (initially hidden) popup window.
+    pub fn new() -> Result<Self, String> {
+        let hinstance = unsafe {
+            GetModuleHandleW(None).map_err(|e| format!("Failed to get module handle: {}", e))
+        }?;
+
+        unsafe {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(popup_wnd_proc),
+                hInstance: hinstance.into(),
+                lpszClassName: POPUP_CLASS_NAME,
+                hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+                ..Default::default()
+            };
+            if RegisterClassW(&wc) == 0 {
+                return Err("Failed to register notification window class".to_string());
+            }
+        }
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE(WS_EX_TOPMOST.0 | WS_EX_TOOLWINDOW.0 | WS_EX_NOACTIVATE.0),
+                POPUP_CLASS_NAME,
+                w!(""),
+                WINDOW_STYLE(WS_POPUP.0),
+                0,
+                0,
+                POPUP_WIDTH,
+                POPUP_HEIGHT,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )
+            .map_err(|e| format!("Failed to create notification window: {}", e))?
+        };
+
+        let mut state = Box::new(PopupState {
+            notification: Notification {
+                severity: Severity::Info,
+                title: String::new(),
+                message: String::new(),
+            },
+            title_font: create_font(15, FW_SEMIBOLD.0 as i32),
+            message_font: create_font(13, FW_NORMAL.0 as i32),
+        });
+        unsafe {
+            SetWindowLongPtrW(
+                hwnd,
+                GWLP_USERDATA,
+                state.as_mut() as *mut PopupState as isize,
+            );
+        }
+
+        Ok(Notifier {
+            hwnd,
+            state,
+            shown_at: None,
+        })
+    }
+
+    /// Hides the current notification once it has expired and shows the next
+    /// queued one. Call this regularly from the main loop.
+    pub fn tick(&mut self) {
+        if let Some(shown_at) = self.shown_at {
+            let visible = unsafe { IsWindowVisible(self.hwnd).as_bool() };
+            if visible && shown_at.elapsed() < DISPLAY_DURATION {
+                return;
+            }
+            unsafe {
+                let _ = ShowWindow(self.hwnd, SW_HIDE);
+            }
+            self.shown_at = None;
+        }
+
+        let next = PENDING
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.pop_front());
+        if let Some(notification) = next {
+            self.show(notification);
+        }
+    }
+
+    fn show(&mut self, notification: Notification) {
+        self.state.notification = notification;
+
+        let mut work_area = RECT::default();
+        unsafe {
+            let _ = SystemParametersInfoW(
+                SPI_GETWORKAREA,
+                0,
+                Some(&mut work_area as *mut RECT as *mut std::ffi::c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            );
+        }
+        let x = work_area.right - POPUP_WIDTH - POPUP_MARGIN;
+        let y = work_area.bottom - POPUP_HEIGHT - POPUP_MARGIN;
+
+        unsafe {
+            let _ = SetWindowPos(
+                self.hwnd,
+                Some(HWND_TOPMOST),
+                x,
+                y,
+                POPUP_WIDTH,
+                POPUP_HEIGHT,
+                SWP_NOACTIVATE | SWP_SHOWWINDOW,
+            );
+            let _ = InvalidateRect(Some(self.hwnd), None, true);
+        }
+        self.shown_at = Some(Instant::now());
+    }
+}
+
+impl Drop for Notifier {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.hwnd);
+            let _ = DeleteObject(self.state.title_font.into());
+            let _ = DeleteObject(self.state.message_font.into());
+        }
+    }
+}
+
+fn create_font(size: i32, weight: i32) -> HFONT {
+    unsafe {
+        CreateFontW(
+            -size,
+            0,
+            0,
+            0,
+            weight,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY,
+            0,
+            w!("Segoe UI"),
+        )
+    }
+}
+
+extern "system" fn popup_wnd_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    unsafe {
+        match msg {
+            WM_PAINT => {
+                let state = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const PopupState;
+                if !state.is_null() {
+                    paint(hwnd, &*state);
+                    return LRESULT(0);
+                }
+            }
+            // Click to dismiss, without taking focus from the user's window
+            WM_MOUSEACTIVATE => return LRESULT(MA_NOACTIVATE as isize),
+            WM_LBUTTONUP => {
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                return LRESULT(0);
+            }
+            WM_NCDESTROY => {
+                let _ = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+            }
+            _ => {}
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+}
+
+unsafe fn paint(hwnd: HWND, state: &PopupState) {
+    unsafe {
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+
+        let background = CreateSolidBrush(COLORREF(BACKGROUND_COLOR));
+        FillRect(hdc, &client, background);
+        let _ = DeleteObject(background.into());
+
+        let mut stripe = client;
+        stripe.right = stripe.left + STRIPE_WIDTH;
+        let stripe_brush = CreateSolidBrush(COLORREF(state.notification.severity.color()));
+        FillRect(hdc, &stripe, stripe_brush);
+        let _ = DeleteObject(stripe_brush.into());
+
+        SetBkMode(hdc, TRANSPARENT);
+
+        let mut title_rect = RECT {
+            left: client.left + STRIPE_WIDTH + PADDING,
+            top: client.top + PADDING,
+            right: client.right - PADDING,
+            bottom: client.top + PADDING + 20,
+        };
+        let mut message_rect = RECT {
+            top: title_rect.bottom + 4,
+            bottom: client.bottom - PADDING,
+            ..title_rect
+        };
+
+        let old_font = SelectObject(hdc, state.title_font.into());
+        SetTextColor(hdc, COLORREF(TITLE_COLOR));
+        let mut title: Vec<u16> = state.notification.title.encode_utf16().collect();
+        DrawTextW(
+            hdc,
+            &mut title,
+            &mut title_rect,
+            DT_SINGLELINE | DT_END_ELLIPSIS | DT_NOPREFIX,
+        );
+
+        SelectObject(hdc, state.message_font.into());
+        SetTextColor(hdc, COLORREF(MESSAGE_COLOR));
+        let mut message: Vec<u16> = state.notification.message.encode_utf16().collect();
+        DrawTextW(
+            hdc,
+            &mut message,
+            &mut message_rect,
+            DT_WORDBREAK | DT_END_ELLIPSIS | DT_NOPREFIX,
+        );
+
+        SelectObject(hdc, old_font);
+        let _ = EndPaint(hwnd, &ps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(message: &str) -> Notification {
+        Notification {
+            severity: Severity::Warning,
+            title: "Megatile".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn pending_queue_skips_duplicates_and_caps_length() {
+        let mut pending = VecDeque::new();
+        push_pending(&mut pending, notification("a"));
+        push_pending(&mut pending, notification("a"));
+        assert_eq!(pending.len(), 1);
+
+        for i in 0..10 {
+            push_pending(&mut pending, notification(&i.to_string()));
+        }
+        assert_eq!(pending.len(), MAX_PENDING);
+        assert_eq!(pending.front(), Some(&notification("a")));
+    }
+}
@@ -0,0 +1,79 @@
+//! Per-process window opacity chosen with `Alt + Shift + PageUp/PageDown`.
+//!
+//! Levels are keyed by lowercase process name and saved to `opacity.toml`
+//! next to the config file, so a terminal made see-through stays that way
+//! after a restart. A level applies to every window of the process, focused
+//! or not; unfocused windows still dim to `unfocused_alpha` if that is lower.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// How much one key press changes a window's opacity.
+pub const OPACITY_STEP: u8 = 25;
+/// Lowest opacity a window can be set to, so it can't vanish completely.
+pub const MIN_OPACITY: u8 = 55;
+
+/// Opacity levels by lowercase process name, 255 being opaque.
+pub type OpacityLevels = BTreeMap<String, u8>;
+
+/// Returns `alpha` moved one step up or down, within the allowed range.
+pub fn step(alpha: u8, increase: bool) -> u8 {
+    if increase {
+        alpha.saturating_add(OPACITY_STEP)
+    } else {
+        alpha.saturating_sub(OPACITY_STEP).max(MIN_OPACITY)
+    }
+}
+
+/// Returns the opacity file path, next to the config file.
+pub fn opacity_path() -> Result<PathBuf, String> {
+    let mut path = crate::config::config_path()?;
+    path.set_file_name("opacity.toml");
+    Ok(path)
+}
+
+/// Reads the saved levels. A missing file means none were chosen yet.
+pub fn load() -> Result<OpacityLevels, String> {
+    let path = opacity_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(OpacityLevels::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Saves `levels`, replacing the file.
+pub fn save(levels: &OpacityLevels) -> Result<(), String> {
+    let path = opacity_path()?;
+    let text = toml::to_string(levels).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn parse(text: &str) -> Result<OpacityLevels, String> {
+    let levels: OpacityLevels = toml::from_str(text).map_err(|e| e.to_string())?;
+    // Hand-edited files get the same limits as the hotkeys
+    Ok(levels
+        .into_iter()
+        .map(|(process, alpha)| (process.to_lowercase(), alpha.max(MIN_OPACITY)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_step_within_limits_and_round_trip() {
+        assert_eq!(step(255, true), 255);
+        assert_eq!(step(255, false), 230);
+        assert_eq!(step(70, false), MIN_OPACITY);
+
+        let levels = OpacityLevels::from([("windowsterminal.exe".to_string(), 205)]);
+        let text = toml::to_string(&levels).unwrap();
+        assert_eq!(parse(&text).unwrap(), levels);
+        assert_eq!(
+            parse("\"Code.exe\" = 10").unwrap(),
+            OpacityLevels::from([("code.exe".to_string(), MIN_OPACITY)])
+        );
+    }
+}
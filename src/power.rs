@@ -0,0 +1,101 @@
+//! Cutting Megatile's idle cost on battery.
+//!
+//! While the laptop runs on battery or battery saver is on, the main loop
+//! refreshes decorations, the clock and the monitor poll less often, and
+//! unfocused windows are no longer dimmed. `[power] saving` in the config
+//! forces this on or off regardless of the power state.
+
+use crate::config::PowerSaving;
+use std::time::Duration;
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+/// How often the main loop runs its periodic tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Intervals {
+    /// Decorations refresh and dead window cleanup.
+    pub maintenance: Duration,
+    /// Fallback poll for missed monitor changes.
+    pub monitor_check: Duration,
+    /// Status bar clock.
+    pub clock: Duration,
+}
+
+impl Intervals {
+    /// Returns the intervals to use with power saving on or off.
+    pub fn new(saving: bool) -> Self {
+        if saving {
+            Intervals {
+                maintenance: Duration::from_millis(500),
+                monitor_check: Duration::from_secs(30),
+                clock: Duration::from_secs(5),
+            }
+        } else {
+            Intervals {
+                maintenance: Duration::from_millis(100),
+                monitor_check: Duration::from_secs(5),
+                clock: Duration::from_secs(1),
+            }
+        }
+    }
+}
+
+/// The parts of the power state power saving depends on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub battery_saver: bool,
+}
+
+impl PowerState {
+    /// Reads the current power state. Desktops, and machines that won't
+    /// say, count as plugged in.
+    pub fn current() -> Self {
+        let mut status = SYSTEM_POWER_STATUS::default();
+        if unsafe { GetSystemPowerStatus(&mut status) }.is_err() {
+            return PowerState::default();
+        }
+        PowerState {
+            // 0 is offline, 1 online, 255 unknown
+            on_battery: status.ACLineStatus == 0,
+            battery_saver: status.SystemStatusFlag == 1,
+        }
+    }
+}
+
+/// Returns true if power saving should be on.
+pub fn should_save(setting: PowerSaving, state: PowerState) -> bool {
+    match setting {
+        PowerSaving::Auto => state.on_battery || state.battery_saver,
+        PowerSaving::Always => true,
+        PowerSaving::Never => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_follows_the_power_state_unless_overridden() {
+        let plugged_in = PowerState::default();
+        let on_battery = PowerState {
+            on_battery: true,
+            battery_saver: false,
+        };
+        let saver = PowerState {
+            on_battery: false,
+            battery_saver: true,
+        };
+        assert!(!should_save(PowerSaving::Auto, plugged_in));
+        assert!(should_save(PowerSaving::Auto, on_battery));
+        assert!(should_save(PowerSaving::Auto, saver));
+        assert!(should_save(PowerSaving::Always, plugged_in));
+        assert!(!should_save(PowerSaving::Never, on_battery));
+
+        let normal = Intervals::new(false);
+        let saving = Intervals::new(true);
+        assert!(saving.maintenance > normal.maintenance);
+        assert!(saving.clock > normal.clock);
+        assert!(saving.monitor_check > normal.monitor_check);
+    }
+}
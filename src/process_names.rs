@@ -0,0 +1,54 @@
+//! Background process-name lookups.
+//!
+//! Resolving a window's executable name means opening its process, which can
+//! stall under load. [`ProcessNameResolver`] runs those lookups on a worker
+//! thread so bursts of window creation don't hold up hotkey handling.
+
+use crate::windows_lib::get_process_name_for_window;
+use log::{debug, error};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use windows::Win32::Foundation::HWND;
+
+/// Handle to the process-name worker thread.
+///
+/// The worker exits once this handle is dropped and its queue is drained.
+pub struct ProcessNameResolver {
+    sender: Sender<isize>,
+}
+
+impl ProcessNameResolver {
+    /// Spawns the worker thread.
+    ///
+    /// `on_resolved` runs on the worker thread with each window handle and its
+    /// process name, and is expected to hand the result back to the event loop.
+    pub fn spawn<F>(on_resolved: F) -> Result<Self, String>
+    where
+        F: Fn(isize, Option<String>) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<isize>();
+        thread::Builder::new()
+            .name("megatile-process-names".to_string())
+            .spawn(move || {
+                for hwnd_val in receiver {
+                    let process_name = get_process_name_for_window(HWND(hwnd_val as _));
+                    debug!(
+                        "Resolved process name for {:?}: {:?}",
+                        hwnd_val, process_name
+                    );
+                    on_resolved(hwnd_val, process_name);
+                }
+                debug!("Process name worker exiting");
+            })
+            .map_err(|e| format!("Failed to spawn process name worker: {}", e))?;
+
+        Ok(ProcessNameResolver { sender })
+    }
+
+    /// Queues a window for process-name lookup.
+    pub fn request(&self, hwnd: isize) {
+        if let Err(e) = self.sender.send(hwnd) {
+            error!("Failed to queue process name lookup for {:?}: {}", hwnd, e);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Bringing back windows left hidden by an instance that crashed.
+//!
+//! While it runs, Megatile keeps `recovery.toml` next to the config file up
+//! to date with the windows it has hidden or stripped of their title bar. A
+//! clean exit deletes the file. After a crash those windows would stay
+//! hidden for good; the next start, or `megatile --force-cleanup`, reads the
+//! file and shows them again.
+
+use crate::config::HideMethod;
+use crate::window_system::{Win32WindowSystem, WindowSystem};
+use crate::windows_lib::{
+    get_process_name_for_window, reset_window_decorations, restore_window_style,
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+/// A managed window Megatile has changed in a way that outlives it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoverableWindow {
+    pub hwnd: isize,
+    /// Checked before touching the window, in case the handle was reused.
+    pub process: Option<String>,
+    /// How the window is hidden, if it is.
+    pub hidden_by: Option<HideMethod>,
+    /// The window's style before its title bar was removed.
+    pub style: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecoveryFile {
+    windows: Vec<RecoverableWindow>,
+}
+
+/// Returns the recovery file path, next to the config file.
+pub fn recovery_path() -> Result<PathBuf, String> {
+    let mut path = crate::config::config_path()?;
+    path.set_file_name("recovery.toml");
+    Ok(path)
+}
+
+/// Records the windows to bring back if this instance crashes.
+pub fn save(windows: &[RecoverableWindow]) -> Result<(), String> {
+    let path = recovery_path()?;
+    let file = RecoveryFile {
+        windows: windows.to_vec(),
+    };
+    let text = toml::to_string(&file).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Deletes the recovery file after a clean exit.
+pub fn clear() -> Result<(), String> {
+    let path = recovery_path()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+/// Shows and restores the windows recorded by an instance that didn't exit
+/// cleanly, then deletes the record. Returns how many windows were restored.
+pub fn restore() -> Result<usize, String> {
+    let path = recovery_path()?;
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let file: RecoveryFile =
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let system = Win32WindowSystem;
+    let mut restored = 0;
+    for window in &file.windows {
+        let hwnd = HWND(window.hwnd as *mut std::ffi::c_void);
+        if !unsafe { IsWindow(Some(hwnd)) }.as_bool() {
+            debug!("Window {:?} is gone", window.hwnd);
+            continue;
+        }
+        if get_process_name_for_window(hwnd) != window.process {
+            debug!("Window {:?} now belongs to another process", window.hwnd);
+            continue;
+        }
+        if let Some(method) = window.hidden_by
+            && let Err(e) = system.show_window(hwnd, method)
+        {
+            warn!("Failed to show window {:?}: {}", window.hwnd, e);
+        }
+        if let Some(style) = window.style
+            && let Err(e) = restore_window_style(hwnd, style)
+        {
+            warn!("Failed to restore title bar of {:?}: {}", window.hwnd, e);
+        }
+        let _ = reset_window_decorations(hwnd);
+        restored += 1;
+    }
+    clear()?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_file_round_trips() {
+        let file = RecoveryFile {
+            windows: vec![
+                RecoverableWindow {
+                    hwnd: 42,
+                    process: Some("spotify.exe".to_string()),
+                    hidden_by: Some(HideMethod::Offscreen),
+                    style: None,
+                },
+                RecoverableWindow {
+                    hwnd: 7,
+                    process: None,
+                    hidden_by: None,
+                    style: Some(0x14CF0000),
+                },
+            ],
+        };
+        let text = toml::to_string(&file).unwrap();
+        let parsed: RecoveryFile = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.windows, file.windows);
+    }
+}
@@ -0,0 +1,224 @@
+//! Window rules: where new windows go, based on what they are.
+//!
+//! Rules come from `[[rules]]` in the config file. Launching a program with a
+//! target workspace adds a one-shot rule for its process that is consumed by
+//! the first matching window (or dropped after [`LAUNCH_TIMEOUT`]), since
+//! Windows doesn't tell us which window a launched program will open.
+
+use log::debug;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// How long a launch waits for its window before its rule is dropped.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long after a window appears a title change can still move it.
+///
+/// Browsers and editors open with a placeholder title and set the real one a
+/// moment later. After that, title changes (tabs, files) shouldn't move the
+/// window around.
+pub const TITLE_RULE_GRACE: Duration = Duration::from_secs(10);
+
+/// A `[[rules]]` entry. Every matcher that is set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowRule {
+    /// Process name, case-insensitive (e.g. `"Slack.exe"`).
+    pub process: Option<String>,
+    /// Exact window class name.
+    pub class: Option<String>,
+    /// Case-insensitive substring of the window title.
+    pub title: Option<String>,
+    /// Workspace matching windows are placed on when they appear.
+    pub workspace: Option<u8>,
+}
+
+/// What a rule can match a window on.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowIdentity<'a> {
+    pub process: Option<&'a str>,
+    pub class: &'a str,
+    pub title: &'a str,
+}
+
+impl WindowRule {
+    /// Returns true if the rule has at least one matcher and all of them match.
+    pub fn matches(&self, window: &WindowIdentity) -> bool {
+        if self.process.is_none() && self.class.is_none() && self.title.is_none() {
+            return false;
+        }
+        let process_matches = self.process.as_ref().is_none_or(|process| {
+            window
+                .process
+                .is_some_and(|name| name.eq_ignore_ascii_case(process))
+        });
+        let class_matches = self
+            .class
+            .as_ref()
+            .is_none_or(|class| window.class == class);
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|title| window.title.to_lowercase().contains(&title.to_lowercase()));
+        process_matches && class_matches && title_matches
+    }
+
+    /// Checks a rule from the config file, with `workspace_count` workspaces.
+    pub fn validate(&self, workspace_count: u8) -> Result<(), String> {
+        if self.process.is_none() && self.class.is_none() && self.title.is_none() {
+            return Err("rule needs at least one of process, class or title".to_string());
+        }
+        if let Some(workspace) = self.workspace
+            && !(1..=workspace_count).contains(&workspace)
+        {
+            return Err(format!(
+                "rule workspace {} is not between 1 and {}",
+                workspace, workspace_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A one-shot rule left behind by a launch.
+struct PendingLaunch {
+    rule: WindowRule,
+    launched_at: Instant,
+}
+
+/// Applies configured and launch rules to new windows.
+pub struct RuleEngine {
+    rules: Vec<WindowRule>,
+    launches: Vec<PendingLaunch>,
+}
+
+impl RuleEngine {
+    /// Creates an engine with the rules from the config file.
+    pub fn new(rules: Vec<WindowRule>) -> Self {
+        Self {
+            rules,
+            launches: Vec::new(),
+        }
+    }
+
+    /// Replaces the configured rules, e.g. after they were edited in the
+    /// settings window. Pending launches are kept.
+    pub fn set_rules(&mut self, rules: Vec<WindowRule>) {
+        self.rules = rules;
+    }
+
+    /// Routes the next new window of `process` to `workspace`.
+    pub fn expect_launch(&mut self, process: &str, workspace: u8) {
+        debug!(
+            "Waiting for a {} window to place on workspace {}",
+            process, workspace
+        );
+        self.launches.push(PendingLaunch {
+            rule: WindowRule {
+                process: Some(process.to_string()),
+                workspace: Some(workspace),
+                ..Default::default()
+            },
+            launched_at: Instant::now(),
+        });
+    }
+
+    /// Returns the workspace a new window should be placed on, if any rule
+    /// says so. Pending launches win over configured rules and are consumed.
+    pub fn workspace_for(&mut self, window: &WindowIdentity) -> Option<u8> {
+        self.launches
+            .retain(|launch| launch.launched_at.elapsed() < LAUNCH_TIMEOUT);
+
+        if let Some(index) = self
+            .launches
+            .iter()
+            .position(|launch| launch.rule.matches(window))
+        {
+            return self.launches.remove(index).rule.workspace;
+        }
+
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(window))
+            .find_map(|rule| rule.workspace)
+    }
+
+    /// Returns the workspace a configured rule with a `title` matcher sends
+    /// `window` to. Used when a window's title changes; launches aren't
+    /// consulted, since they were settled when the window appeared.
+    pub fn workspace_for_title(&self, window: &WindowIdentity) -> Option<u8> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.title.is_some() && rule.matches(window))
+            .find_map(|rule| rule.workspace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window<'a>(process: &'a str, title: &'a str) -> WindowIdentity<'a> {
+        WindowIdentity {
+            process: Some(process),
+            class: "Chrome_WidgetWin_1",
+            title,
+        }
+    }
+
+    #[test]
+    fn all_set_matchers_must_match() {
+        let rule = WindowRule {
+            process: Some("slack.exe".to_string()),
+            title: Some("huddle".to_string()),
+            workspace: Some(3),
+            ..Default::default()
+        };
+        assert!(rule.matches(&window("Slack.exe", "Huddle with Sam")));
+        assert!(!rule.matches(&window("Slack.exe", "general")));
+        assert!(!rule.matches(&window("Code.exe", "huddle notes")));
+        assert!(!WindowRule::default().matches(&window("Slack.exe", "x")));
+    }
+
+    #[test]
+    fn launch_rules_are_consumed_once_before_config_rules() {
+        let mut engine = RuleEngine::new(vec![WindowRule {
+            process: Some("slack.exe".to_string()),
+            workspace: Some(4),
+            ..Default::default()
+        }]);
+        engine.expect_launch("slack.exe", 3);
+
+        assert_eq!(engine.workspace_for(&window("Code.exe", "")), None);
+        assert_eq!(engine.workspace_for(&window("Slack.exe", "")), Some(3));
+        assert_eq!(engine.workspace_for(&window("Slack.exe", "")), Some(4));
+    }
+
+    #[test]
+    fn title_changes_only_consult_title_rules() {
+        let mut engine = RuleEngine::new(vec![
+            WindowRule {
+                process: Some("firefox.exe".to_string()),
+                workspace: Some(2),
+                ..Default::default()
+            },
+            WindowRule {
+                title: Some("youtube".to_string()),
+                workspace: Some(5),
+                ..Default::default()
+            },
+        ]);
+        engine.expect_launch("firefox.exe", 3);
+
+        assert_eq!(
+            engine.workspace_for_title(&window("firefox.exe", "New Tab")),
+            None
+        );
+        assert_eq!(
+            engine.workspace_for_title(&window("firefox.exe", "YouTube - Mozilla Firefox")),
+            Some(5)
+        );
+        // The launch is still waiting for its window
+        assert_eq!(engine.workspace_for(&window("firefox.exe", "")), Some(3));
+    }
+}
@@ -0,0 +1,285 @@
+//! Rhai scripts that react to lifecycle events.
+//!
+//! Only built with the `scripting` feature. The script named by
+//! `[scripting] script` defines handlers called `on_<event>`, e.g.
+//! `on_window_created(event)`, which receive the event's data as a map.
+//!
+//! Scripts can't call into Windows. The functions they get queue ordinary
+//! actions, which run after the handler returns just like hotkeys do, and
+//! each handler call is capped at [`MAX_OPERATIONS`] so a runaway loop can't
+//! hang the window manager.
+
+use crate::hotkeys::HotkeyAction;
+use crate::lifecycle::LifecycleEvent;
+use crate::workspace::MAX_WORKSPACES;
+use log::{info, warn};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, Map, Scope};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Most Rhai operations one handler call may run.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A compiled script and the engine that runs it.
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    actions: Rc<RefCell<Vec<HotkeyAction>>>,
+}
+
+impl ScriptHost {
+    /// Compiles the script at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::compile(&source).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Compiles a script from source.
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let actions = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.on_print(|text| info!("script: {}", text));
+        engine.on_debug(|text, _, _| info!("script: {}", text));
+        register_api(&mut engine, &actions);
+
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        Ok(ScriptHost {
+            engine,
+            ast,
+            actions,
+        })
+    }
+
+    /// Runs the handler for `event`, if the script defines one, and returns
+    /// the actions it queued. A handler that fails queues nothing.
+    pub fn handle(&mut self, event: &LifecycleEvent) -> Vec<HotkeyAction> {
+        let handler = format!("on_{}", event.name());
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == handler && function.params.len() == 1);
+        if !defined {
+            return Vec::new();
+        }
+
+        let result = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &self.ast,
+            &handler,
+            (event_map(event),),
+        );
+        let actions = std::mem::take(&mut *self.actions.borrow_mut());
+        match result {
+            Ok(_) => actions,
+            Err(e) => {
+                warn!("Script handler {} failed: {}", handler, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Registers the functions scripts may call.
+fn register_api(engine: &mut Engine, actions: &Rc<RefCell<Vec<HotkeyAction>>>) {
+    let queue = actions.clone();
+    engine.register_fn(
+        "switch_workspace",
+        move |workspace: i64| -> Result<(), Box<EvalAltResult>> {
+            queue
+                .borrow_mut()
+                .push(HotkeyAction::SwitchWorkspace(workspace_number(workspace)?));
+            Ok(())
+        },
+    );
+    let queue = actions.clone();
+    engine.register_fn(
+        "move_to_workspace",
+        move |workspace: i64| -> Result<(), Box<EvalAltResult>> {
+            queue
+                .borrow_mut()
+                .push(HotkeyAction::MoveToWorkspace(workspace_number(workspace)?));
+            Ok(())
+        },
+    );
+    let queue = actions.clone();
+    engine.register_fn(
+        "split_ratio",
+        move |percent: i64| -> Result<(), Box<EvalAltResult>> {
+            let percent = u8::try_from(percent)
+                .ok()
+                .filter(|percent| (10..=90).contains(percent))
+                .ok_or_else(|| format!("split ratio {} is not between 10 and 90", percent))?;
+            queue
+                .borrow_mut()
+                .push(HotkeyAction::SetSplitRatio(percent));
+            Ok(())
+        },
+    );
+    let queue = actions.clone();
+    engine.register_fn("load_layout", move |name: &str| {
+        queue
+            .borrow_mut()
+            .push(HotkeyAction::LoadLayout(name.to_string()));
+    });
+    let queue = actions.clone();
+    engine.register_fn(
+        "action",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let action = named_action(name).ok_or_else(|| format!("unknown action '{}'", name))?;
+            queue.borrow_mut().push(action);
+            Ok(())
+        },
+    );
+}
+
+fn workspace_number(workspace: i64) -> Result<u8, Box<EvalAltResult>> {
+    u8::try_from(workspace)
+        .ok()
+        .filter(|workspace| (1..=MAX_WORKSPACES).contains(workspace))
+        .ok_or_else(|| {
+            format!(
+                "workspace {} is not between 1 and {}",
+                workspace, MAX_WORKSPACES
+            )
+            .into()
+        })
+}
+
+/// Actions scripts can run with `action("name")`. They apply to the focused window.
+fn named_action(name: &str) -> Option<HotkeyAction> {
+    Some(match name {
+        "focus_left" => HotkeyAction::FocusLeft,
+        "focus_right" => HotkeyAction::FocusRight,
+        "focus_up" => HotkeyAction::FocusUp,
+        "focus_down" => HotkeyAction::FocusDown,
+        "focus_urgent" => HotkeyAction::FocusUrgent,
+        "move_left" => HotkeyAction::MoveLeft,
+        "move_right" => HotkeyAction::MoveRight,
+        "move_up" => HotkeyAction::MoveUp,
+        "move_down" => HotkeyAction::MoveDown,
+        "flip_region" => HotkeyAction::FlipRegion,
+        "next_workspace" => HotkeyAction::NextWorkspace,
+        "previous_workspace" => HotkeyAction::PreviousWorkspace,
+        "move_to_empty_workspace" => HotkeyAction::MoveToEmptyWorkspace,
+        "close_window" => HotkeyAction::CloseWindow,
+        "minimize_window" => HotkeyAction::MinimizeWindow,
+        "toggle_tiling" => HotkeyAction::ToggleTiling,
+        "toggle_workspace_tiling" => HotkeyAction::ToggleWorkspaceTiling,
+        "toggle_topmost" => HotkeyAction::ToggleTopmost,
+        "increase_opacity" => HotkeyAction::IncreaseOpacity,
+        "decrease_opacity" => HotkeyAction::DecreaseOpacity,
+        "toggle_fullscreen" => HotkeyAction::ToggleFullscreen,
+        "toggle_zen" => HotkeyAction::ToggleZen,
+        "toggle_maximize" => HotkeyAction::ToggleMaximize,
+        "toggle_workspace_lock" => HotkeyAction::ToggleWorkspaceLock,
+        "flash_focus" => HotkeyAction::FlashFocus,
+        "adopt_windows" => HotkeyAction::AdoptWindows,
+        "send_to_monitor_left" => HotkeyAction::SendToMonitorLeft,
+        "send_to_monitor_right" => HotkeyAction::SendToMonitorRight,
+        "send_to_monitor_up" => HotkeyAction::SendToMonitorUp,
+        "send_to_monitor_down" => HotkeyAction::SendToMonitorDown,
+        "balance_monitors" => HotkeyAction::BalanceMonitors,
+        "gather_windows" => HotkeyAction::GatherOnMonitor,
+        "toggle_show_desktop" => HotkeyAction::ToggleShowDesktop,
+        "toggle_tile_group" => HotkeyAction::ToggleTileGroup,
+        "cycle_tile_group" => HotkeyAction::CycleTileGroup,
+        "merge_left" => HotkeyAction::MergeLeft,
+        "merge_right" => HotkeyAction::MergeRight,
+        "merge_up" => HotkeyAction::MergeUp,
+        "merge_down" => HotkeyAction::MergeDown,
+        _ => return None,
+    })
+}
+
+/// The event's data as a Rhai object map.
+fn event_map(event: &LifecycleEvent) -> Map {
+    let mut map = Map::new();
+    match event {
+        LifecycleEvent::WindowCreated {
+            hwnd,
+            process,
+            class,
+            title,
+            workspace,
+        } => {
+            map.insert("hwnd".into(), Dynamic::from(*hwnd as i64));
+            map.insert(
+                "process".into(),
+                process.clone().map_or(Dynamic::UNIT, Dynamic::from),
+            );
+            map.insert("class".into(), Dynamic::from(class.clone()));
+            map.insert("title".into(), Dynamic::from(title.clone()));
+            map.insert("workspace".into(), Dynamic::from(*workspace as i64));
+        }
+        LifecycleEvent::WorkspaceSwitched { from, to } => {
+            map.insert("from".into(), Dynamic::from(*from as i64));
+            map.insert("to".into(), Dynamic::from(*to as i64));
+        }
+        LifecycleEvent::MonitorsChanged { count } => {
+            map.insert("count".into(), Dynamic::from(*count as i64));
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handlers_queue_actions_for_matching_events() {
+        let mut host = ScriptHost::compile(
+            r#"
+            fn on_window_created(event) {
+                if event.class == "mpv" {
+                    action("toggle_tiling");
+                    move_to_workspace(event.workspace + 1);
+                }
+            }
+            fn on_workspace_switched(event) {
+                switch_workspace(99);
+            }
+            "#,
+        )
+        .unwrap();
+
+        let created = |class: &str| LifecycleEvent::WindowCreated {
+            hwnd: 1,
+            process: None,
+            class: class.to_string(),
+            title: String::new(),
+            workspace: 2,
+        };
+        let actions = host.handle(&created("mpv"));
+        assert!(matches!(
+            actions.as_slice(),
+            [HotkeyAction::ToggleTiling, HotkeyAction::MoveToWorkspace(3)]
+        ));
+        assert!(host.handle(&created("Notepad")).is_empty());
+
+        // Out-of-range workspaces fail the handler instead of queueing anything
+        assert!(
+            host.handle(&LifecycleEvent::WorkspaceSwitched { from: 1, to: 2 })
+                .is_empty()
+        );
+        assert!(
+            host.handle(&LifecycleEvent::MonitorsChanged { count: 2 })
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn runaway_handlers_are_stopped() {
+        let mut host = ScriptHost::compile(
+            "fn on_monitors_changed(event) { action(\"flip_region\"); loop {} }",
+        )
+        .unwrap();
+        assert!(
+            host.handle(&LifecycleEvent::MonitorsChanged { count: 1 })
+                .is_empty()
+        );
+    }
+}
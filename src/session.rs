@@ -0,0 +1,267 @@
+//! Handing the window layout over to a new instance on restart.
+//!
+//! A restarting instance saves which workspace every window is on, each
+//! workspace's splits and focused window, and where floating windows sit to
+//! [`session_path`], restores the windows like a normal exit, and starts its
+//! replacement with `--restart-from <pid>`. The new instance waits for the
+//! old one to exit, then places each window it finds back where it was
+//! instead of piling everything onto workspace 1.
+//!
+//! Windows are matched by handle. Windows an app re-created in the meantime
+//! are matched by process instead, telling several windows of one app apart
+//! by title and monitor.
+
+use crate::float_geometry::SavedRect;
+use crate::layouts::LayoutNode;
+use crate::workspace::Window;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_SYNCHRONIZE, WaitForSingleObject};
+
+/// How long a new instance waits for the old one to exit, in milliseconds.
+const HANDOVER_TIMEOUT_MS: u32 = 10_000;
+
+/// Where a window was when its instance restarted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedWindow {
+    pub hwnd: isize,
+    pub workspace: u8,
+    pub is_tiled: bool,
+    #[serde(default)]
+    pub process: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    /// Index of the monitor the window was on.
+    #[serde(default)]
+    pub monitor: usize,
+    /// Where the window was, if it was floating.
+    #[serde(default)]
+    pub float_rect: Option<SavedRect>,
+}
+
+/// A workspace's tiling and focus when its instance restarted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedWorkspace {
+    /// Index of the monitor the workspace is on.
+    pub monitor: usize,
+    pub workspace: u8,
+    /// The window focused last on the workspace.
+    #[serde(default)]
+    pub focused_window_hwnd: Option<isize>,
+    /// The workspace's splits, if it had tiled windows.
+    #[serde(default)]
+    pub layout_tree: Option<LayoutNode>,
+}
+
+/// Window layout handed from a restarting instance to its replacement.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub active_workspace: u8,
+    pub windows: Vec<SavedWindow>,
+    #[serde(default)]
+    pub workspaces: Vec<SavedWorkspace>,
+}
+
+impl SavedSession {
+    /// Returns where `hwnd` was, if it was managed.
+    pub fn window(&self, hwnd: isize) -> Option<&SavedWindow> {
+        self.windows.iter().find(|window| window.hwnd == hwnd)
+    }
+
+    /// Pairs `windows` with where they were saved, by window handle.
+    ///
+    /// The rest are paired with saved windows of the same process, best
+    /// match first: the more title words in common the better, and being on
+    /// the same monitor breaks ties.
+    pub fn match_windows(&self, windows: &[Window]) -> HashMap<isize, &SavedWindow> {
+        let mut matched: HashMap<isize, &SavedWindow> = windows
+            .iter()
+            .filter_map(|window| Some((window.hwnd, self.window(window.hwnd)?)))
+            .collect();
+        let mut claimed: HashSet<isize> = matched.values().map(|saved| saved.hwnd).collect();
+
+        let mut candidates = Vec::new();
+        for window in windows.iter().filter(|w| !matched.contains_key(&w.hwnd)) {
+            let Some(process) = window.process_name.as_deref() else {
+                continue;
+            };
+            for saved in self.windows.iter().filter(|s| !claimed.contains(&s.hwnd)) {
+                if saved
+                    .process
+                    .as_deref()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(process))
+                {
+                    let mut score = title_similarity(&window.title, &saved.title) * 2.0;
+                    if window.monitor == saved.monitor {
+                        score += 1.0;
+                    }
+                    candidates.push((score, window.hwnd, saved));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, hwnd, saved) in candidates {
+            if !matched.contains_key(&hwnd) && claimed.insert(saved.hwnd) {
+                matched.insert(hwnd, saved);
+            }
+        }
+        matched
+    }
+
+    /// Writes the session for the next instance to pick up.
+    pub fn save(&self) -> Result<(), String> {
+        let path = session_path()?;
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, text)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Reads and deletes the session left by the previous instance.
+    pub fn take() -> Result<Self, String> {
+        let path = session_path()?;
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Share of words two titles have in common, from 0 to 1, ignoring case.
+fn title_similarity(a: &str, b: &str) -> f32 {
+    let words = |title: &str| -> HashSet<String> {
+        title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let total = a.len().max(b.len());
+    if total == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / total as f32
+}
+
+/// Returns the handover file path, next to the config file.
+pub fn session_path() -> Result<PathBuf, String> {
+    let mut path = crate::config::config_path()?;
+    path.set_file_name("restart-session.toml");
+    Ok(path)
+}
+
+/// Waits for the restarting instance with process ID `pid` to exit.
+pub fn wait_for_exit(pid: u32) {
+    unsafe {
+        // It may already be gone, which is what we're waiting for anyway
+        let Ok(process) = OpenProcess(PROCESS_SYNCHRONIZE, false, pid) else {
+            return;
+        };
+        info!("Waiting for the previous instance ({}) to exit", pid);
+        WaitForSingleObject(process, HANDOVER_TIMEOUT_MS);
+        let _ = CloseHandle(process);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_survive_the_file_round_trip() {
+        let session = SavedSession {
+            active_workspace: 3,
+            windows: vec![
+                SavedWindow {
+                    hwnd: 0x1234,
+                    workspace: 3,
+                    is_tiled: true,
+                    process: Some("Code.exe".to_string()),
+                    title: "main.rs - megatile".to_string(),
+                    monitor: 0,
+                    float_rect: None,
+                },
+                SavedWindow {
+                    hwnd: 0x5678,
+                    workspace: 12,
+                    is_tiled: false,
+                    process: None,
+                    title: String::new(),
+                    monitor: 1,
+                    float_rect: Some(SavedRect {
+                        left: 10,
+                        top: 20,
+                        right: 810,
+                        bottom: 620,
+                    }),
+                },
+            ],
+            workspaces: vec![SavedWorkspace {
+                monitor: 0,
+                workspace: 3,
+                focused_window_hwnd: Some(0x1234),
+                layout_tree: Some(LayoutNode {
+                    hwnd: Some(0x1234),
+                    ..LayoutNode::default()
+                }),
+            }],
+        };
+        let text = toml::to_string(&session).unwrap();
+        let read: SavedSession = toml::from_str(&text).unwrap();
+        assert_eq!(read, session);
+        assert_eq!(read.window(0x5678).map(|w| w.workspace), Some(12));
+        assert!(read.window(0x9999).is_none());
+    }
+
+    #[test]
+    fn recreated_windows_are_told_apart_by_title_and_monitor() {
+        let saved = |hwnd, workspace, title: &str, monitor| SavedWindow {
+            hwnd,
+            workspace,
+            is_tiled: true,
+            process: Some("Code.exe".to_string()),
+            title: title.to_string(),
+            monitor,
+            float_rect: None,
+        };
+        let session = SavedSession {
+            active_workspace: 1,
+            windows: vec![
+                saved(1, 2, "main.rs - megatile - Visual Studio Code", 0),
+                saved(2, 5, "notes.md - blog - Visual Studio Code", 0),
+                saved(3, 7, "Welcome - Visual Studio Code", 1),
+                saved(4, 9, "Welcome - Visual Studio Code", 0),
+            ],
+            workspaces: Vec::new(),
+        };
+        let live = |hwnd, title: &str, monitor| {
+            let mut window = Window::new(
+                hwnd,
+                1,
+                monitor,
+                Default::default(),
+                Some("code.exe".to_string()),
+            );
+            window.title = title.to_string();
+            window
+        };
+        let windows = [
+            live(4, "Welcome - Visual Studio Code", 0),
+            live(11, "post.md - blog - Visual Studio Code", 0),
+            live(12, "lib.rs - megatile - Visual Studio Code", 0),
+            live(13, "Welcome - Visual Studio Code", 1),
+        ];
+
+        let matched = session.match_windows(&windows);
+        let workspace = |hwnd| matched.get(&hwnd).map(|saved| saved.workspace);
+        assert_eq!(workspace(4), Some(9));
+        assert_eq!(workspace(11), Some(5));
+        assert_eq!(workspace(12), Some(2));
+        assert_eq!(workspace(13), Some(7));
+    }
+}
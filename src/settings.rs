@@ -0,0 +1,610 @@
+//! Settings window for editing window rules.
+//!
+//! Opened from the tray. Lists the `[[rules]]` from the config file and adds
+//! new ones from a form that "Pick window" fills in: the next window clicked
+//! anywhere on screen provides the process and class. Every change is written
+//! straight back to `config.toml`, keeping its comments and other settings,
+//! and handed to the running [`RuleEngine`](crate::rules::RuleEngine).
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+
+use log::{error, info};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    COLOR_BTNFACE, DEFAULT_GUI_FONT, GetStockObject, GetSysColorBrush,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    AdjustWindowRectEx, BS_PUSHBUTTON, CW_USEDEFAULT, CallNextHookEx, CreateWindowExW,
+    DefWindowProcW, ES_AUTOHSCROLL, ES_NUMBER, GA_ROOT, GWLP_USERDATA, GetAncestor,
+    GetWindowLongPtrW, HHOOK, HMENU, IDC_ARROW, LB_ADDSTRING, LB_ERR, LB_GETCURSEL,
+    LB_RESETCONTENT, LBS_NOINTEGRALHEIGHT, LBS_NOTIFY, LoadCursorW, MSLLHOOKSTRUCT, PostMessageW,
+    RegisterClassW, SW_HIDE, SW_SHOW, SendMessageW, SetForegroundWindow, SetWindowLongPtrW,
+    SetWindowTextW, SetWindowsHookExW, ShowWindow, UnhookWindowsHookEx, WH_MOUSE_LL,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_CLOSE, WM_COMMAND, WM_LBUTTONDOWN, WM_LBUTTONUP,
+    WM_NCDESTROY, WM_SETFONT, WNDCLASSW, WS_CAPTION, WS_CHILD, WS_EX_CLIENTEDGE, WS_MINIMIZEBOX,
+    WS_OVERLAPPED, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL, WindowFromPoint,
+};
+use windows::core::{HSTRING, PCWSTR, w};
+
+use crate::config::{Config, config_path};
+use crate::notifications::{Severity, notify};
+use crate::rules::WindowRule;
+use crate::windows_lib::{get_process_name_for_window, get_window_class, get_window_title};
+
+const SETTINGS_CLASS_NAME: PCWSTR = w!("MegatileSettings");
+const CLIENT_WIDTH: i32 = 460;
+const CLIENT_HEIGHT: i32 = 356;
+const MARGIN: i32 = 12;
+const LABEL_WIDTH: i32 = 80;
+const ROW_HEIGHT: i32 = 22;
+const BUTTON_HEIGHT: i32 = 26;
+
+/// Posted to the settings window by the picker hook. `wparam` is the picked
+/// window, or 0 if picking was cancelled.
+const WM_WINDOW_PICKED: u32 = WM_APP + 1;
+
+const ID_RULE_LIST: u16 = 100;
+const ID_REMOVE: u16 = 101;
+const ID_PICK: u16 = 102;
+const ID_ADD: u16 = 103;
+
+/// Mouse hook installed while picking a window, as a raw handle.
+static PICKER_HOOK: AtomicIsize = AtomicIsize::new(0);
+/// Settings window that receives the pick.
+static PICKER_OWNER: AtomicIsize = AtomicIsize::new(0);
+/// Window pressed on, waiting for the button to be released.
+static PICKER_TARGET: AtomicIsize = AtomicIsize::new(0);
+
+/// Controls and rules read by the window procedure.
+struct SettingsState {
+    hwnd: HWND,
+    rule_list: HWND,
+    pick_button: HWND,
+    process_edit: HWND,
+    class_edit: HWND,
+    title_edit: HWND,
+    workspace_edit: HWND,
+    workspace_count: u8,
+    /// Rules as they are in the config file.
+    rules: Vec<WindowRule>,
+    /// Rules written since the main loop last asked.
+    changed: Option<Vec<WindowRule>>,
+}
+
+/// Owns the (initially hidden) settings window. Lives on the main thread.
+pub struct SettingsWindow {
+    hwnd: HWND,
+    state: Box<SettingsState>,
+}
+
+impl SettingsWindow {
+    /// Creates the settings window and its controls, hidden.
+    ///
+    /// Rules added in the window may use workspaces 1 to `workspace_count`.
+    pub fn new(workspace_count: u8) -> Result<Self, String> {
+        let hinstance = unsafe {
+            GetModuleHandleW(None).map_err(|e| format!("Failed to get module handle: {}", e))
+        }?;
+
+        unsafe {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(settings_wnd_proc),
+                hInstance: hinstance.into(),
+                lpszClassName: SETTINGS_CLASS_NAME,
+                hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+                hbrBackground: GetSysColorBrush(COLOR_BTNFACE),
+                ..Default::default()
+            };
+            if RegisterClassW(&wc) == 0 {
+                return Err("Failed to register settings window class".to_string());
+            }
+        }
+
+        let style = WINDOW_STYLE(WS_OVERLAPPED.0 | WS_CAPTION.0 | WS_SYSMENU.0 | WS_MINIMIZEBOX.0);
+        let mut frame = RECT {
+            left: 0,
+            top: 0,
+            right: CLIENT_WIDTH,
+            bottom: CLIENT_HEIGHT,
+        };
+        let hwnd = unsafe {
+            let _ = AdjustWindowRectEx(&mut frame, style, false, WINDOW_EX_STYLE(0));
+            CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                SETTINGS_CLASS_NAME,
+                w!("Megatile rules"),
+                style,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                frame.right - frame.left,
+                frame.bottom - frame.top,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )
+            .map_err(|e| format!("Failed to create settings window: {}", e))?
+        };
+
+        let list_height = 140;
+        let rule_list = create_control(
+            hwnd,
+            "LISTBOX",
+            "",
+            WS_VSCROLL.0 | (LBS_NOTIFY | LBS_NOINTEGRALHEIGHT) as u32,
+            ID_RULE_LIST,
+            (MARGIN, MARGIN, CLIENT_WIDTH - 2 * MARGIN, list_height),
+        );
+        let remove_top = MARGIN + list_height + 6;
+        create_control(
+            hwnd,
+            "BUTTON",
+            "Remove",
+            BS_PUSHBUTTON as u32,
+            ID_REMOVE,
+            (MARGIN, remove_top, 100, BUTTON_HEIGHT),
+        );
+
+        let form_top = remove_top + BUTTON_HEIGHT + 16;
+        let mut edits = Vec::new();
+        for (row, label) in ["Process", "Class", "Title contains", "Workspace"]
+            .into_iter()
+            .enumerate()
+        {
+            let y = form_top + row as i32 * (ROW_HEIGHT + 6);
+            create_control(
+                hwnd,
+                "STATIC",
+                label,
+                0,
+                0,
+                (MARGIN, y + 3, LABEL_WIDTH, ROW_HEIGHT),
+            );
+            let is_workspace = label == "Workspace";
+            let style = ES_AUTOHSCROLL as u32 | if is_workspace { ES_NUMBER as u32 } else { 0 };
+            let width = if is_workspace {
+                60
+            } else {
+                CLIENT_WIDTH - 2 * MARGIN - LABEL_WIDTH
+            };
+            edits.push(create_control(
+                hwnd,
+                "EDIT",
+                "",
+                style,
+                0,
+                (MARGIN + LABEL_WIDTH, y, width, ROW_HEIGHT),
+            ));
+        }
+
+        let buttons_top = CLIENT_HEIGHT - MARGIN - BUTTON_HEIGHT;
+        let pick_button = create_control(
+            hwnd,
+            "BUTTON",
+            "Pick window",
+            BS_PUSHBUTTON as u32,
+            ID_PICK,
+            (MARGIN, buttons_top, 140, BUTTON_HEIGHT),
+        );
+        create_control(
+            hwnd,
+            "BUTTON",
+            "Add rule",
+            BS_PUSHBUTTON as u32,
+            ID_ADD,
+            (CLIENT_WIDTH - MARGIN - 100, buttons_top, 100, BUTTON_HEIGHT),
+        );
+
+        let mut state = Box::new(SettingsState {
+            hwnd,
+            rule_list,
+            pick_button,
+            process_edit: edits[0],
+            class_edit: edits[1],
+            title_edit: edits[2],
+            workspace_edit: edits[3],
+            workspace_count,
+            rules: Vec::new(),
+            changed: None,
+        });
+        unsafe {
+            SetWindowLongPtrW(
+                hwnd,
+                GWLP_USERDATA,
+                state.as_mut() as *mut SettingsState as isize,
+            );
+        }
+
+        Ok(SettingsWindow { hwnd, state })
+    }
+
+    /// Reads the rules from the config file and shows the window.
+    pub fn open(&mut self) {
+        match read_config_text().and_then(|text| Config::parse(&text)) {
+            Ok(config) => {
+                self.state.rules = config.rules;
+                self.state.refresh_list();
+            }
+            Err(e) => {
+                error!("Can't edit rules: {}", e);
+                notify(Severity::Warning, "Can't edit rules", e);
+                return;
+            }
+        }
+        unsafe {
+            let _ = ShowWindow(self.hwnd, SW_SHOW);
+            let _ = SetForegroundWindow(self.hwnd);
+        }
+    }
+
+    /// Returns the rules once after they were changed in the window.
+    pub fn take_changed_rules(&mut self) -> Option<Vec<WindowRule>> {
+        self.state.changed.take()
+    }
+}
+
+impl Drop for SettingsWindow {
+    fn drop(&mut self) {
+        stop_picking(self.state.pick_button);
+    }
+}
+
+impl SettingsState {
+    fn refresh_list(&self) {
+        unsafe {
+            SendMessageW(self.rule_list, LB_RESETCONTENT, None, None);
+            for rule in &self.rules {
+                let text = HSTRING::from(describe_rule(rule));
+                SendMessageW(
+                    self.rule_list,
+                    LB_ADDSTRING,
+                    None,
+                    Some(LPARAM(text.as_ptr() as isize)),
+                );
+            }
+        }
+    }
+
+    fn on_command(&mut self, id: u16) {
+        match id {
+            ID_PICK => start_picking(self.hwnd, self.pick_button),
+            ID_ADD => {
+                let rule = match self.form_rule() {
+                    Ok(rule) => rule,
+                    Err(e) => {
+                        notify(Severity::Warning, "Rule not added", e);
+                        return;
+                    }
+                };
+                self.update_config(|text| with_rule_added(text, &rule));
+            }
+            ID_REMOVE => {
+                let selected = unsafe { SendMessageW(self.rule_list, LB_GETCURSEL, None, None) };
+                if selected.0 != LB_ERR as isize {
+                    let index = selected.0 as usize;
+                    self.update_config(|text| with_rule_removed(text, index));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Fills the form from a window picked on screen.
+    fn on_window_picked(&self, window: HWND) {
+        let process = get_process_name_for_window(window).unwrap_or_default();
+        set_text(self.process_edit, &process);
+        set_text(self.class_edit, &get_window_class(window));
+        // Titles change too often to match on by default
+        set_text(self.title_edit, "");
+        unsafe {
+            let _ = SetForegroundWindow(self.hwnd);
+        }
+    }
+
+    /// Builds a rule from the form.
+    fn form_rule(&self) -> Result<WindowRule, String> {
+        let field = |hwnd: HWND| {
+            let text = get_window_title(hwnd).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        };
+        let workspace = match field(self.workspace_edit) {
+            Some(text) => Some(
+                text.parse()
+                    .map_err(|_| format!("\"{}\" is not a workspace number", text))?,
+            ),
+            None => None,
+        };
+        let rule = WindowRule {
+            process: field(self.process_edit),
+            class: field(self.class_edit),
+            title: field(self.title_edit),
+            workspace,
+        };
+        rule.validate(self.workspace_count)?;
+        Ok(rule)
+    }
+
+    /// Applies `edit` to the config file and, if the result is a valid
+    /// config, writes it and hands its rules to the main loop.
+    fn update_config(&mut self, edit: impl FnOnce(&str) -> Result<String, String>) {
+        let result = read_config_text().and_then(|text| {
+            let text = edit(&text)?;
+            let config = Config::parse(&text)?;
+            let path = config_path()?;
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)
+                    .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            }
+            std::fs::write(&path, text)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Ok(config.rules)
+        });
+        match result {
+            Ok(rules) => {
+                info!("Saved {} window rules", rules.len());
+                self.rules = rules.clone();
+                self.changed = Some(rules);
+                self.refresh_list();
+            }
+            Err(e) => {
+                error!("Failed to save rules: {}", e);
+                notify(Severity::Error, "Rules not saved", e);
+            }
+        }
+    }
+}
+
+/// Creates a child control. `id` is what `WM_COMMAND` reports for it.
+pub(crate) fn create_control(
+    parent: HWND,
+    class: &str,
+    text: &str,
+    style: u32,
+    id: u16,
+    (x, y, width, height): (i32, i32, i32, i32),
+) -> HWND {
+    let ex_style = if class == "EDIT" || class == "LISTBOX" {
+        WS_EX_CLIENTEDGE
+    } else {
+        WINDOW_EX_STYLE(0)
+    };
+    unsafe {
+        let hwnd = CreateWindowExW(
+            ex_style,
+            &HSTRING::from(class),
+            &HSTRING::from(text),
+            WINDOW_STYLE(WS_CHILD.0 | WS_VISIBLE.0 | WS_TABSTOP.0 | style),
+            x,
+            y,
+            width,
+            height,
+            Some(parent),
+            Some(HMENU(id as usize as *mut _)),
+            None,
+            None,
+        )
+        .unwrap_or_default();
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+        SendMessageW(
+            hwnd,
+            WM_SETFONT,
+            Some(WPARAM(font.0 as usize)),
+            Some(LPARAM(1)),
+        );
+        hwnd
+    }
+}
+
+fn set_text(hwnd: HWND, text: &str) {
+    unsafe {
+        let _ = SetWindowTextW(hwnd, &HSTRING::from(text));
+    }
+}
+
+/// Starts waiting for a click on another window.
+fn start_picking(owner: HWND, pick_button: HWND) {
+    if PICKER_HOOK.load(Ordering::SeqCst) != 0 {
+        return;
+    }
+    PICKER_OWNER.store(owner.0 as isize, Ordering::SeqCst);
+    PICKER_TARGET.store(0, Ordering::SeqCst);
+    match unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(picker_hook_proc), None, 0) } {
+        Ok(hook) => {
+            PICKER_HOOK.store(hook.0 as isize, Ordering::SeqCst);
+            set_text(pick_button, "Click a window...");
+        }
+        Err(e) => error!("Failed to install window picker: {}", e),
+    }
+}
+
+fn stop_picking(pick_button: HWND) {
+    let hook = PICKER_HOOK.swap(0, Ordering::SeqCst);
+    if hook != 0 {
+        unsafe {
+            let _ = UnhookWindowsHookEx(HHOOK(hook as *mut _));
+        }
+        set_text(pick_button, "Pick window");
+    }
+}
+
+/// Low-level mouse hook active while picking. Swallows the click on the
+/// picked window so it doesn't press anything there.
+unsafe extern "system" fn picker_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let owner = HWND(PICKER_OWNER.load(Ordering::SeqCst) as *mut _);
+        match wparam.0 as u32 {
+            WM_LBUTTONDOWN => {
+                let info = unsafe { &*(lparam.0 as *const MSLLHOOKSTRUCT) };
+                let target = unsafe { GetAncestor(WindowFromPoint(info.pt), GA_ROOT) };
+                if target != owner && !target.is_invalid() {
+                    PICKER_TARGET.store(target.0 as isize, Ordering::SeqCst);
+                    return LRESULT(1);
+                }
+                // Clicking the settings window itself cancels
+                unsafe {
+                    let _ = PostMessageW(Some(owner), WM_WINDOW_PICKED, WPARAM(0), LPARAM(0));
+                }
+            }
+            WM_LBUTTONUP => {
+                let target = PICKER_TARGET.swap(0, Ordering::SeqCst);
+                if target != 0 {
+                    unsafe {
+                        let _ = PostMessageW(
+                            Some(owner),
+                            WM_WINDOW_PICKED,
+                            WPARAM(target as usize),
+                            LPARAM(0),
+                        );
+                    }
+                    return LRESULT(1);
+                }
+            }
+            _ => {}
+        }
+    }
+    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+}
+
+unsafe extern "system" fn settings_wnd_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut SettingsState;
+        match msg {
+            WM_COMMAND if !state_ptr.is_null() => {
+                let id = (wparam.0 & 0xFFFF) as u16;
+                (*state_ptr).on_command(id);
+                LRESULT(0)
+            }
+            WM_WINDOW_PICKED if !state_ptr.is_null() => {
+                stop_picking((*state_ptr).pick_button);
+                if wparam.0 != 0 {
+                    (*state_ptr).on_window_picked(HWND(wparam.0 as *mut _));
+                }
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                // Keep the window around for the next time it's opened
+                if !state_ptr.is_null() {
+                    stop_picking((*state_ptr).pick_button);
+                }
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                LRESULT(0)
+            }
+            WM_NCDESTROY => {
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
+
+/// Reads the config file, or returns an empty one if there is none yet.
+fn read_config_text() -> Result<String, String> {
+    let path = config_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// One line in the rule list, e.g. `Slack.exe, class "Chrome_WidgetWin_1" -> 3`.
+fn describe_rule(rule: &WindowRule) -> String {
+    let mut matchers = Vec::new();
+    if let Some(process) = &rule.process {
+        matchers.push(process.clone());
+    }
+    if let Some(class) = &rule.class {
+        matchers.push(format!("class \"{}\"", class));
+    }
+    if let Some(title) = &rule.title {
+        matchers.push(format!("title \"{}\"", title));
+    }
+    let target = rule
+        .workspace
+        .map_or("no workspace".to_string(), |workspace| {
+            workspace.to_string()
+        });
+    format!("{} -> {}", matchers.join(", "), target)
+}
+
+/// Returns config file text with `rule` appended to `[[rules]]`.
+fn with_rule_added(text: &str, rule: &WindowRule) -> Result<String, String> {
+    let mut document = parse_document(text)?;
+    let rules = document
+        .entry("rules")
+        .or_insert(toml_edit::Item::ArrayOfTables(
+            toml_edit::ArrayOfTables::new(),
+        ))
+        .as_array_of_tables_mut()
+        .ok_or("rules in the config file are not [[rules]] tables")?;
+
+    let mut table = toml_edit::Table::new();
+    for (key, value) in [
+        ("process", &rule.process),
+        ("class", &rule.class),
+        ("title", &rule.title),
+    ] {
+        if let Some(value) = value {
+            table[key] = toml_edit::value(value.as_str());
+        }
+    }
+    if let Some(workspace) = rule.workspace {
+        table["workspace"] = toml_edit::value(i64::from(workspace));
+    }
+    rules.push(table);
+    Ok(document.to_string())
+}
+
+/// Returns config file text without the `index`th `[[rules]]` entry.
+fn with_rule_removed(text: &str, index: usize) -> Result<String, String> {
+    let mut document = parse_document(text)?;
+    let rules = document
+        .get_mut("rules")
+        .and_then(|item| item.as_array_of_tables_mut())
+        .ok_or("the config file has no [[rules]] tables")?;
+    if index >= rules.len() {
+        return Err(format!("there is no rule {}", index + 1));
+    }
+    rules.remove(index);
+    if rules.is_empty() {
+        document.remove("rules");
+    }
+    Ok(document.to_string())
+}
+
+fn parse_document(text: &str) -> Result<toml_edit::DocumentMut, String> {
+    text.parse()
+        .map_err(|e: toml_edit::TomlError| format!("Invalid config file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_added_and_removed_without_touching_the_rest() {
+        let text = "# My settings\n[layout]\ngap = 0 # no gaps\n";
+        let rule = WindowRule {
+            process: Some("Slack.exe".to_string()),
+            workspace: Some(3),
+            ..Default::default()
+        };
+
+        let added = with_rule_added(text, &rule).unwrap();
+        assert!(added.starts_with(text));
+        let config = Config::parse(&added).unwrap();
+        assert_eq!(config.rules, vec![rule.clone()]);
+        assert_eq!(describe_rule(&rule), "Slack.exe -> 3");
+
+        let removed = with_rule_removed(&added, 0).unwrap();
+        assert_eq!(removed, text);
+        assert!(with_rule_removed(&removed, 0).is_err());
+    }
+}
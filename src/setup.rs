@@ -0,0 +1,149 @@
+//! First-run setup: a few questions, answered in message boxes, that produce
+//! the initial `config.toml`.
+//!
+//! Runs only when there is no config file yet. The file is written whatever
+//! the answers are, so the questions are asked once.
+
+use crate::autostart;
+use crate::config::{BarPosition, HotkeyModifier, config_path};
+use log::{error, info};
+use windows::Win32::UI::WindowsAndMessaging::{
+    IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_YESNO, MessageBoxW,
+};
+use windows::core::HSTRING;
+
+/// What the user picked during setup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetupAnswers {
+    /// Leave gaps between tiles and at the monitor edges.
+    pub gaps: bool,
+    /// Key held for the built-in hotkeys.
+    pub modifier: HotkeyModifier,
+    /// Edge of the screen the status bar sits on.
+    pub bar_position: BarPosition,
+}
+
+/// Asks the setup questions and writes the config file, if there is none yet.
+///
+/// `autostart_flags` are passed to the login entry if the user wants one.
+pub fn run_if_first_launch(autostart_flags: &[String]) {
+    let Ok(path) = config_path() else {
+        return;
+    };
+    if path.exists() {
+        return;
+    }
+    info!(
+        "No config file at {}, running first-time setup",
+        path.display()
+    );
+
+    let answers = SetupAnswers {
+        gaps: ask("Leave small gaps between tiled windows?"),
+        modifier: if ask("Hold the Windows key instead of Alt for Megatile's shortcuts?") {
+            HotkeyModifier::Win
+        } else {
+            HotkeyModifier::Alt
+        },
+        bar_position: if ask(
+            "Put the status bar with workspaces and the clock at the bottom of the screen \
+             instead of the top?",
+        ) {
+            BarPosition::Bottom
+        } else {
+            BarPosition::Top
+        },
+    };
+    if ask("Start Megatile when you log in?")
+        && let Err(e) = autostart::enable(autostart_flags)
+    {
+        error!("Failed to enable autostart: {}", e);
+    }
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, initial_config(answers)));
+    match result {
+        Ok(()) => info!("Wrote {}", path.display()),
+        Err(e) => error!("Failed to write {}: {}", path.display(), e),
+    }
+}
+
+/// Asks a yes/no question. Returns true for yes.
+fn ask(question: &str) -> bool {
+    let answer = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(question),
+            &HSTRING::from("Megatile setup"),
+            MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND,
+        )
+    };
+    answer == IDYES
+}
+
+/// The config file for `answers`. Settings left at their defaults are
+/// written as comments so they are easy to find later.
+pub fn initial_config(answers: SetupAnswers) -> String {
+    let (gap, padding) = if answers.gaps { (4, 2) } else { (0, 0) };
+    let (modifier, key) = match answers.modifier {
+        HotkeyModifier::Alt => ("alt", "Alt"),
+        HotkeyModifier::Win => ("win", "Win"),
+    };
+    let position = match answers.bar_position {
+        BarPosition::Top => "top",
+        BarPosition::Bottom => "bottom",
+    };
+    format!(
+        "# Megatile settings. See the README for everything that can go here.\n\
+         \n\
+         [layout]\n\
+         # Pixels between tiles and at the monitor edges\n\
+         gap = {gap}\n\
+         padding = {padding}\n\
+         # Width of the window in zen mode ({key}+Z), as a share of the monitor\n\
+         # zen_width = 0.6\n\
+         \n\
+         [hotkeys]\n\
+         # Key held for the shortcuts: \"alt\" or \"win\"\n\
+         modifier = \"{modifier}\"\n\
+         \n\
+         [statusbar]\n\
+         # Edge of the screen the bar sits on: \"top\" or \"bottom\"\n\
+         position = \"{position}\"\n\
+         \n\
+         [workspaces]\n\
+         # count = 9\n\
+         \n\
+         # Place windows on a workspace when they appear:\n\
+         # [[rules]]\n\
+         # process = \"Spotify.exe\"\n\
+         # workspace = 9\n",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn every_answer_makes_a_valid_config() {
+        for gaps in [true, false] {
+            for modifier in [HotkeyModifier::Alt, HotkeyModifier::Win] {
+                for bar_position in [BarPosition::Top, BarPosition::Bottom] {
+                    let text = initial_config(SetupAnswers {
+                        gaps,
+                        modifier,
+                        bar_position,
+                    });
+                    let config = Config::parse(&text).unwrap();
+                    assert_eq!(config.layout.gap == 0, !gaps);
+                    assert_eq!(config.hotkeys.modifier, modifier);
+                    assert_eq!(config.statusbar.position, bar_position);
+                }
+            }
+        }
+    }
+}
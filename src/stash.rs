@@ -0,0 +1,138 @@
+//! Stash slots: windows parked outside the numbered workspaces.
+//!
+//! Each slot holds at most one window. A parked window is hidden and kept
+//! here instead of in a workspace; summoning it moves it onto the current
+//! workspace while the slot stays bound to it, so the same slot key parks
+//! it again.
+
+use crate::workspace::Window;
+
+/// Maximum number of stash slots.
+pub const MAX_STASH_SLOTS: usize = 9;
+
+#[derive(Debug, Clone, Default)]
+struct StashSlot {
+    /// Window bound to the slot, parked or out on a workspace.
+    hwnd: Option<isize>,
+    /// The window while it is parked.
+    parked: Option<Window>,
+}
+
+/// The stash slots and the windows bound to them.
+#[derive(Debug, Clone, Default)]
+pub struct Stash {
+    slots: Vec<StashSlot>,
+}
+
+impl Stash {
+    /// Creates a stash with `count` empty slots.
+    pub fn new(count: usize) -> Self {
+        Stash {
+            slots: vec![StashSlot::default(); count.min(MAX_STASH_SLOTS)],
+        }
+    }
+
+    /// Number of slots.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns true if there are no slots.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns the slot `hwnd` is bound to.
+    pub fn slot_of(&self, hwnd: isize) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.hwnd == Some(hwnd))
+    }
+
+    /// Returns the first slot without a window.
+    pub fn free_slot(&self) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.hwnd.is_none())
+    }
+
+    /// Window bound to `slot`, parked or not.
+    pub fn bound(&self, slot: usize) -> Option<isize> {
+        self.slots.get(slot).and_then(|slot| slot.hwnd)
+    }
+
+    /// Parks a window in `slot`, binding the slot to it.
+    pub fn park(&mut self, slot: usize, window: Window) {
+        if let Some(slot) = self.slots.get_mut(slot) {
+            slot.hwnd = Some(window.hwnd);
+            slot.parked = Some(window);
+        }
+    }
+
+    /// Takes the parked window out of `slot` to summon it. The slot stays bound.
+    pub fn take_parked(&mut self, slot: usize) -> Option<Window> {
+        self.slots.get_mut(slot).and_then(|slot| slot.parked.take())
+    }
+
+    /// Returns true if `hwnd` is parked in a slot.
+    pub fn is_parked(&self, hwnd: isize) -> bool {
+        self.parked_hwnds().any(|parked| parked == hwnd)
+    }
+
+    /// Returns the parked window `hwnd`.
+    pub fn parked(&self, hwnd: isize) -> Option<&Window> {
+        self.parked_windows().find(|window| window.hwnd == hwnd)
+    }
+
+    /// All parked windows.
+    pub fn parked_windows(&self) -> impl Iterator<Item = &Window> + '_ {
+        self.slots.iter().filter_map(|slot| slot.parked.as_ref())
+    }
+
+    /// Handles of all parked windows.
+    pub fn parked_hwnds(&self) -> impl Iterator<Item = isize> + '_ {
+        self.parked_windows().map(|window| window.hwnd)
+    }
+
+    /// Unbinds `hwnd` (e.g. it was closed), returning it if it was parked.
+    pub fn forget(&mut self, hwnd: isize) -> Option<Window> {
+        let slot = self.slot_of(hwnd)?;
+        self.slots[slot].hwnd = None;
+        self.slots[slot].parked.take()
+    }
+
+    /// Slot numbers (1-based) holding a parked window, for the status bar.
+    pub fn parked_slots(&self) -> Vec<u8> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.parked.is_some())
+            .map(|(index, _)| index as u8 + 1)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Foundation::RECT;
+
+    fn window(hwnd: isize) -> Window {
+        Window::new(hwnd, 1, 0, RECT::default(), None)
+    }
+
+    #[test]
+    fn summoned_windows_stay_bound_to_their_slot() {
+        let mut stash = Stash::new(2);
+        stash.park(0, window(7));
+        assert_eq!(stash.free_slot(), Some(1));
+        assert_eq!(stash.parked_slots(), vec![1]);
+
+        let summoned = stash.take_parked(0).unwrap();
+        assert_eq!(summoned.hwnd, 7);
+        assert!(!stash.is_parked(7));
+        assert_eq!(stash.slot_of(7), Some(0));
+        assert!(stash.parked_slots().is_empty());
+
+        stash.park(0, summoned);
+        assert!(stash.forget(7).is_some());
+        assert_eq!(stash.free_slot(), Some(0));
+        assert_eq!(stash.bound(0), None);
+    }
+}
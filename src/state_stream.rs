@@ -0,0 +1,325 @@
+//! Workspace and focus state streamed to external status bars.
+//!
+//! Clients connect to [`STATE_PIPE_NAME`] and read one JSON object per line.
+//! The current state is sent as soon as a client connects, then again every
+//! time it changes, so bars like Zebar or yasb can draw Megatile's
+//! workspaces in place of the built-in status bar.
+
+use crate::ipc::write_all;
+use log::{debug, error, info};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use windows::Win32::Foundation::{CloseHandle, ERROR_PIPE_CONNECTED, HANDLE};
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_OUTBOUND;
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_REJECT_REMOTE_CLIENTS,
+    PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use windows::core::{PCWSTR, w};
+
+/// Pipe path state is streamed on.
+pub const STATE_PIPE_NAME: &str = r"\\.\pipe\megatile-state";
+const STATE_PIPE_NAME_W: PCWSTR = w!(r"\\.\pipe\megatile-state");
+
+/// The window with keyboard focus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedWindow {
+    pub hwnd: isize,
+    pub title: String,
+    pub process: Option<String>,
+}
+
+/// What an external bar shows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BarState {
+    /// Active workspace number (1-based).
+    pub active_workspace: u8,
+    /// Window count of each workspace in use; index 0 is workspace 1.
+    pub window_counts: Vec<usize>,
+    /// Stash slots holding a window.
+    pub stash: Vec<u8>,
+    /// The focused managed window, if any.
+    pub focused: Option<FocusedWindow>,
+}
+
+impl BarState {
+    /// Returns the state as a single line of JSON, e.g.
+    /// `{"workspace":2,"workspaces":[{"number":1,"windows":3},...],"stash":[],"focused":null}`.
+    pub fn to_json(&self) -> String {
+        let stash: Vec<String> = self.stash.iter().map(|slot| slot.to_string()).collect();
+        format!(
+            "{{\"workspace\":{},\"workspaces\":{},\"stash\":[{}],\"focused\":{}}}",
+            self.active_workspace,
+            self.workspaces_json(),
+            stash.join(","),
+            self.focused_json()
+        )
+    }
+
+    /// Returns the window count of each workspace as a JSON array, e.g.
+    /// `[{"number":1,"windows":3},{"number":2,"windows":0}]`.
+    pub fn workspaces_json(&self) -> String {
+        let workspaces: Vec<String> = self
+            .window_counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| format!("{{\"number\":{},\"windows\":{}}}", i + 1, count))
+            .collect();
+        format!("[{}]", workspaces.join(","))
+    }
+
+    /// Returns the focused window as a JSON object, or `null`.
+    pub fn focused_json(&self) -> String {
+        match &self.focused {
+            Some(window) => format!(
+                "{{\"hwnd\":{},\"title\":{},\"process\":{}}}",
+                window.hwnd,
+                json_string(&window.title),
+                window
+                    .process
+                    .as_deref()
+                    .map_or("null".to_string(), json_string)
+            ),
+            None => "null".to_string(),
+        }
+    }
+}
+
+/// The latest [`BarState`], shared with the threads that serve it on request
+/// (the HTTP endpoint's `GET` routes).
+#[derive(Debug, Clone, Default)]
+pub struct BarSnapshot(Arc<Mutex<BarState>>);
+
+impl BarSnapshot {
+    /// Replaces the snapshot with `state`.
+    pub fn set(&self, state: &BarState) {
+        if let Ok(mut latest) = self.0.lock() {
+            *latest = state.clone();
+        }
+    }
+
+    /// Returns a copy of the latest state.
+    pub fn get(&self) -> BarState {
+        self.0
+            .lock()
+            .map(|latest| latest.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Quotes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Snapshots waiting for a client's writer thread. A client that still has
+/// one waiting when the next arrives has stopped reading, and is dropped
+/// rather than left to block.
+const CLIENT_BACKLOG: usize = 1;
+
+/// A connected client, written to by its own thread.
+#[derive(Clone)]
+struct Client {
+    id: u64,
+    sender: SyncSender<String>,
+}
+
+/// Connected clients and the last line sent, shared by the stream's threads.
+/// The lock is only held to read or change the list, never across a write.
+#[derive(Default)]
+struct Clients {
+    list: Vec<Client>,
+    latest: String,
+    next_id: u64,
+}
+
+/// Sends state lines to every connected client.
+pub struct StateStream {
+    sender: SyncSender<String>,
+}
+
+impl StateStream {
+    /// Starts accepting clients on [`STATE_PIPE_NAME`].
+    pub fn spawn() -> Result<Self, String> {
+        let clients = Arc::new(Mutex::new(Clients::default()));
+
+        let accepting = Arc::clone(&clients);
+        thread::Builder::new()
+            .name("megatile-state-accept".to_string())
+            .spawn(move || accept_clients(&accepting))
+            .map_err(|e| format!("Failed to spawn state stream thread: {}", e))?;
+
+        // Fans lines out off the event loop; it never writes to a pipe
+        // itself, so no client can hold it up
+        let (sender, receiver) = mpsc::sync_channel::<String>(1);
+        thread::Builder::new()
+            .name("megatile-state-write".to_string())
+            .spawn(move || {
+                while let Ok(mut line) = receiver.recv() {
+                    // Only the newest state matters to a bar
+                    while let Ok(newer) = receiver.try_recv() {
+                        line = newer;
+                    }
+                    let list = {
+                        let Ok(mut clients) = clients.lock() else {
+                            return;
+                        };
+                        clients.latest = line.clone();
+                        clients.list.clone()
+                    };
+                    let dropped: Vec<u64> = list
+                        .iter()
+                        .filter(|client| client.sender.try_send(line.clone()).is_err())
+                        .map(|client| client.id)
+                        .collect();
+                    if !dropped.is_empty() {
+                        debug!("Dropping {} state client(s)", dropped.len());
+                        let Ok(mut clients) = clients.lock() else {
+                            return;
+                        };
+                        clients.list.retain(|client| !dropped.contains(&client.id));
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn state stream thread: {}", e))?;
+
+        info!("Streaming state on {}", STATE_PIPE_NAME);
+        Ok(StateStream { sender })
+    }
+
+    /// Sends `state` to every client and to clients connecting later.
+    pub fn publish(&self, state: &BarState) {
+        let _ = self.sender.send(state.to_json());
+    }
+}
+
+/// Accepts clients for as long as Megatile runs, sending each the latest state.
+fn accept_clients(clients: &Mutex<Clients>) {
+    loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                STATE_PIPE_NAME_W,
+                PIPE_ACCESS_OUTBOUND,
+                PIPE_TYPE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                0,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            error!("Failed to create state pipe {}", STATE_PIPE_NAME);
+            thread::sleep(Duration::from_secs(5));
+            continue;
+        }
+        if let Err(e) = unsafe { ConnectNamedPipe(pipe, None) }
+            && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+        {
+            error!("Failed to accept state client: {}", e);
+            close_pipe(pipe);
+            continue;
+        }
+        debug!("State client connected");
+        let (sender, receiver) = mpsc::sync_channel::<String>(CLIENT_BACKLOG);
+        let Ok(mut clients) = clients.lock() else {
+            close_pipe(pipe);
+            return;
+        };
+        if !clients.latest.is_empty() {
+            let _ = sender.try_send(clients.latest.clone());
+        }
+        let id = clients.next_id;
+        let handle = pipe.0 as isize;
+        let spawned = thread::Builder::new()
+            .name("megatile-state-client".to_string())
+            .spawn(move || write_to_client(HANDLE(handle as _), receiver));
+        match spawned {
+            Ok(_) => {
+                clients.next_id += 1;
+                clients.list.push(Client { id, sender });
+            }
+            Err(e) => {
+                error!("Failed to spawn state client thread: {}", e);
+                close_pipe(pipe);
+            }
+        }
+    }
+}
+
+/// Writes each line a client is sent until it leaves or is dropped, then
+/// closes its pipe.
+fn write_to_client(pipe: HANDLE, lines: Receiver<String>) {
+    for line in lines {
+        if !send_line(pipe, &line) {
+            return;
+        }
+    }
+    close_pipe(pipe);
+}
+
+/// Writes one line to a client. Returns false, closing the pipe, if the
+/// client is gone.
+fn send_line(pipe: HANDLE, line: &str) -> bool {
+    let mut bytes = line.as_bytes().to_vec();
+    bytes.push(b'\n');
+    match write_all(pipe, &bytes) {
+        Ok(()) => true,
+        Err(e) => {
+            debug!("State client left: {}", e);
+            close_pipe(pipe);
+            false
+        }
+    }
+}
+
+fn close_pipe(pipe: HANDLE) {
+    unsafe {
+        let _ = DisconnectNamedPipe(pipe);
+        let _ = CloseHandle(pipe);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_serializes_as_one_line_of_json() {
+        let state = BarState {
+            active_workspace: 2,
+            window_counts: vec![3, 0],
+            stash: vec![1],
+            focused: Some(FocusedWindow {
+                hwnd: 42,
+                title: "\"notes\"\tdraft".to_string(),
+                process: None,
+            }),
+        };
+        assert_eq!(
+            state.to_json(),
+            concat!(
+                r#"{"workspace":2,"workspaces":[{"number":1,"windows":3},"#,
+                r#"{"number":2,"windows":0}],"stash":[1],"#,
+                r#""focused":{"hwnd":42,"title":"\"notes\"\tdraft","process":null}}"#,
+            )
+        );
+        assert!(BarState::default().to_json().ends_with("\"focused\":null}"));
+    }
+}
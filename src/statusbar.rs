@@ -1,44 +1,74 @@
 //! Visual workspace status bar indicator.
 //!
 //! Displays a floating bar showing workspace indicators with numbers,
-//! and the current date/time. Uses the system accent color with a dimmed backdrop.
+//! and the current date/time. Uses the system accent color with a dimmed
+//! backdrop, or a pale one when apps are set to the light theme.
 //! Renders using GDI+ with layered windows for smooth anti-aliased edges.
+//! Hovering a workspace dot shows a tooltip listing that workspace's windows.
+//! An optional window list shows a button per window on the active
+//! workspace, so the Windows taskbar can be hidden, and an optional media
+//! module shows what's playing and the volume.
+//!
+//! The bar's windows are per-monitor DPI aware while the rest of Megatile
+//! isn't: everything is laid out at 96 DPI and scaled to the monitor's DPI
+//! when drawn, so the bar stays sharp and the same size on any display.
+//!
+//! Showing and hiding the bar fades and slides it, and the active workspace's
+//! highlight glides between dots, unless animation effects are turned off in
+//! Windows.
 
-use std::sync::OnceLock;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use windows::Win32::Foundation::{
-    COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, SIZE, SYSTEMTIME, WPARAM,
+    COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, SIZE, WPARAM,
 };
 use windows::Win32::Graphics::Gdi::{
     AC_SRC_ALPHA, AC_SRC_OVER, BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BLENDFUNCTION,
-    CreateCompatibleDC, CreateDIBSection, DIB_RGB_COLORS, DeleteDC, DeleteObject, GetDC, ReleaseDC,
-    SelectObject,
+    CreateCompatibleDC, CreateDIBSection, DIB_RGB_COLORS, DeleteDC, DeleteObject, GetDC,
+    GetMonitorInfoW, HMONITOR, MONITORINFO, ReleaseDC, SelectObject,
 };
 use windows::Win32::Graphics::GdiPlus::{
     FillMode, GdipAddPathArc, GdipAddPathLine, GdipClosePathFigure, GdipCreateFont,
     GdipCreateFontFamilyFromName, GdipCreateFromHDC, GdipCreatePath, GdipCreateSolidFill,
     GdipCreateStringFormat, GdipDeleteBrush, GdipDeleteFont, GdipDeleteFontFamily,
     GdipDeleteGraphics, GdipDeletePath, GdipDeleteStringFormat, GdipDrawString, GdipFillEllipse,
-    GdipFillPath, GdipGraphicsClear, GdipSetSmoothingMode, GdipSetStringFormatAlign,
-    GdipSetStringFormatLineAlign, GdipSetTextRenderingHint, GdiplusShutdown, GdiplusStartup,
-    GdiplusStartupInput, GpBrush, GpFontFamily, GpGraphics, GpPath, GpSolidFill, GpStringFormat,
-    SmoothingModeHighQuality, StringAlignmentCenter, TextRenderingHintClearTypeGridFit, Unit,
+    GdipFillPath, GdipGraphicsClear, GdipMeasureString, GdipScaleWorldTransform,
+    GdipSetSmoothingMode, GdipSetStringFormatAlign, GdipSetStringFormatLineAlign,
+    GdipSetStringFormatTrimming, GdipSetTextRenderingHint, GdipTranslateWorldTransform,
+    GdiplusShutdown, GdiplusStartup, GdiplusStartupInput, GpBrush, GpFontFamily, GpGraphics,
+    GpPath, GpSolidFill, GpStringFormat, MatrixOrderPrepend, SmoothingModeHighQuality,
+    StringAlignmentCenter, StringAlignmentNear, StringFormatFlagsNoWrap,
+    StringTrimmingEllipsisCharacter, TextRenderingHintClearTypeGridFit, Unit,
 };
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
-use windows::Win32::System::SystemInformation::GetLocalTime;
+use windows::Win32::UI::HiDpi::{
+    DPI_AWARENESS_CONTEXT, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, GetDpiForMonitor,
+    GetDpiForWindow, MDT_EFFECTIVE_DPI, SetThreadDpiAwarenessContext,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{TME_LEAVE, TRACKMOUSEEVENT, TrackMouseEvent};
 use windows::Win32::UI::WindowsAndMessaging::{
     CS_HREDRAW, CS_VREDRAW, CreateWindowExW, DefWindowProcW, DestroyWindow, GWLP_USERDATA,
-    GetWindowLongPtrW, GetWindowRect, HMENU, HWND_TOPMOST, IDC_ARROW, LoadCursorW, RegisterClassW,
-    SW_HIDE, SW_SHOW, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SetWindowLongPtrW, SetWindowPos,
-    ShowWindow, ULW_ALPHA, UpdateLayeredWindow, WINDOW_EX_STYLE, WINDOW_STYLE, WM_NCDESTROY,
-    WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+    GetWindowLongPtrW, GetWindowRect, HMENU, HWND_TOPMOST, IDC_ARROW, IsWindowVisible, KillTimer,
+    LoadCursorW, PostMessageW, RegisterClassW, SPI_GETCLIENTAREAANIMATION, SW_HIDE, SW_SHOW,
+    SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_SHOWWINDOW, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    SetTimer, SetWindowLongPtrW, SetWindowPos, ShowWindow, SystemParametersInfoW, ULW_ALPHA,
+    USER_DEFAULT_SCREEN_DPI, UpdateLayeredWindow, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP,
+    WM_DPICHANGED, WM_LBUTTONUP, WM_MBUTTONUP, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_NCDESTROY, WM_TIMER,
+    WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT,
+    WS_POPUP,
 };
 use windows::core::{BOOL, PCWSTR, w};
 
+use crate::clock::Clock;
+use crate::config::{BarAlign, BarPosition};
+use crate::icons::{IconCache, draw_icon};
+use crate::media::{MediaCommand, MediaMonitor, MediaState};
+use crate::theme::Theme;
 use crate::windows_lib::get_accent_color;
+use crate::workspace::{DEFAULT_WORKSPACES, MAX_WORKSPACES};
 
-/// Maximum number of workspaces supported.
-pub const STATUSBAR_MAX_WORKSPACES: u8 = 9;
 /// Height of the status bar in pixels.
 pub const STATUSBAR_HEIGHT: i32 = 34;
 /// Width of the status bar in pixels.
@@ -53,16 +83,47 @@ pub const STATUSBAR_VERTICAL_RESERVE: i32 =
 
 const DOT_DIAMETER: i32 = 20;
 const DOT_SPACING: i32 = 26;
+const MIN_DOT_SPACING: i32 = 14; // Dots shrink down to this when many are shown
+const DOTS_AREA_WIDTH: i32 = STATUSBAR_WIDTH - PADDING_LEFT - PADDING_RIGHT - STASH_AREA_WIDTH;
 const CORNER_RADIUS: i32 = 32;
 const PADDING_LEFT: i32 = 16;
 const PADDING_RIGHT: i32 = 16;
 const PADDING_VERTICAL: i32 = 7;
 const DEFAULT_ACCENT_COLOR: u32 = 0x007A7A7A;
 const ALWAYS_SHOW_WORKSPACES: u8 = 5; // Workspaces 1-5 always shown
+const TOOLTIP_WIDTH: i32 = 260;
+const TOOLTIP_LINE_HEIGHT: i32 = 18;
+const TOOLTIP_PADDING: i32 = 8;
+const TOOLTIP_GAP: i32 = 4; // Between the bar and the tooltip
+const TOOLTIP_MAX_LINES: usize = 12; // Including the heading
+const WINDOW_LIST_LEFT: i32 = PADDING_LEFT + DOTS_AREA_WIDTH; // Between the dots and the stash
+const WINDOW_LIST_MAX_WIDTH: i32 = 800;
+const WINDOW_BUTTON_WIDTH: i32 = 160;
+const MIN_WINDOW_BUTTON_WIDTH: i32 = 48; // Buttons shrink down to this when many are shown
+const WINDOW_BUTTON_GAP: i32 = 4;
+const WINDOW_ICON_SIZE: i32 = 16;
+const MEDIA_WIDTH: i32 = 200; // Volume included
+const MEDIA_VOLUME_WIDTH: i32 = 44;
+const VOLUME_STEP: i32 = 2; // Percent per wheel notch
+const STASH_AREA_WIDTH: i32 = 110; // Stash and clock, at the right end
+const CLOCK_WIDTH: i32 = 100; // Fits the default format; longer ones widen the bar
+const CLOCK_FONT_SIZE: f32 = 12.0;
+const ANIMATION_DURATION: Duration = Duration::from_millis(160);
+const ANIMATION_TIMER_ID: usize = 1;
+const ANIMATION_FRAME_MS: u32 = 15;
+const SLIDE_DISTANCE: f32 = 10.0; // How far the bar slides toward its edge as it hides
+
+/// Lives in `Win32::UI::Controls`, which nothing else here needs.
+const WM_MOUSELEAVE: u32 = 0x02A3;
+/// Posted by the media worker when the volume or media changed.
+const WM_MEDIA_CHANGED: u32 = WM_APP + 1;
 
 static STATUSBAR_CLASS: OnceLock<Result<(), String>> = OnceLock::new();
 const STATUSBAR_CLASS_NAME: PCWSTR = w!("MegatileStatusBar");
 
+/// Clicks on window list buttons, waiting for the main loop.
+static WINDOW_LIST_CLICKS: Mutex<VecDeque<WindowListClick>> = Mutex::new(VecDeque::new());
+
 /// GDI+ token for initialization/shutdown.
 static mut GDIPLUS_TOKEN: usize = 0;
 
@@ -72,14 +133,126 @@ struct StatusBarState {
     active_workspace: u8,
     total_workspaces: u8,
     accent_color: u32,
+    /// Light or dark, following the apps theme
+    theme: Theme,
     /// Cached time string for display.
     time_string: String,
-    /// Bitmask for workspaces 6-9 that have windows (bit 0 = ws6, bit 1 = ws7, etc)
-    occupied_workspaces_6_9: u8,
+    /// Formats the time string
+    clock: Clock,
+    /// Width the clock is drawn in, wider than the default for long formats
+    clock_width: i32,
+    /// Whether the clock and stash are shown
+    show_clock: bool,
+    /// Where along the top of its monitor the bar sits
+    align: BarAlign,
+    /// Which edge of its monitor the bar sits on
+    position: BarPosition,
+    /// Whether the bar may animate; off while power saving
+    animations: bool,
+    /// Bitmask of workspaces that have windows (bit 0 = ws1, bit 1 = ws2, etc)
+    occupied_workspaces: u32,
+    /// Stash slots (1-based) holding a parked window.
+    parked_stash_slots: Vec<u8>,
     /// Current width of the status bar
     width: i32,
     /// Current height of the status bar
     height: i32,
+    /// Monitor DPI over 96; sizes above are at 96 DPI and multiplied by this
+    scale: f32,
+    /// Window titles of each workspace in use (index 0 = ws1)
+    window_titles: Vec<Vec<String>>,
+    /// Popup listing the hovered workspace's windows
+    tooltip_hwnd: HWND,
+    /// Workspace whose dot the cursor is over
+    hovered_workspace: Option<u8>,
+    /// Whether WM_MOUSELEAVE has been requested for the current hover
+    tracking_mouse: bool,
+    /// Buttons of the window list, or None when it's turned off
+    window_list: Option<Vec<WindowButton>>,
+    /// Icons of the windows in the window list
+    icons: IconCache,
+    /// Worker behind the media module, or None when it's turned off
+    media: Option<MediaMonitor>,
+    /// Volume and media as last drawn
+    media_state: MediaState,
+    /// 1 when shown, 0 when hidden; in between while fading
+    visibility: Transition,
+    /// Index of the dot highlighted as active, fractional while gliding
+    active_dot: Transition,
+}
+
+impl StatusBarState {
+    fn clock_area(&self) -> i32 {
+        clock_area(self.clock_width, self.show_clock)
+    }
+}
+
+/// A value easing from one number to another over [`ANIMATION_DURATION`].
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    from: f32,
+    to: f32,
+    started: Instant,
+}
+
+impl Transition {
+    fn at(value: f32) -> Self {
+        Transition {
+            from: value,
+            to: value,
+            started: Instant::now(),
+        }
+    }
+
+    /// Heads for `to` from wherever the value is now, or jumps straight
+    /// there without `animate`.
+    fn go_to(&mut self, to: f32, animate: bool) {
+        let from = if animate { self.value() } else { to };
+        *self = Transition {
+            from,
+            to,
+            started: Instant::now(),
+        };
+    }
+
+    fn value(&self) -> f32 {
+        self.value_after(self.started.elapsed())
+    }
+
+    fn value_after(&self, elapsed: Duration) -> f32 {
+        let t = (elapsed.as_secs_f32() / ANIMATION_DURATION.as_secs_f32()).min(1.0);
+        let eased = 1.0 - (1.0 - t).powi(3); // Ease out
+        self.from + (self.to - self.from) * eased
+    }
+
+    fn is_done(&self) -> bool {
+        self.from == self.to || self.started.elapsed() >= ANIMATION_DURATION
+    }
+}
+
+/// A window shown as a button in the window list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowButton {
+    pub hwnd: isize,
+    pub title: String,
+    pub focused: bool,
+}
+
+/// A click on a window list button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowListClick {
+    /// Left click: focus the window.
+    Focus(isize),
+    /// Middle click: close the window.
+    Close(isize),
+}
+
+/// Returns the oldest window list click not yet handled.
+pub fn take_window_list_click() -> Option<WindowListClick> {
+    WINDOW_LIST_CLICKS
+        .lock()
+        .ok()
+        .and_then(|mut clicks| clicks.pop_front())
 }
 
 /// A floating status bar showing workspace indicators.
@@ -128,17 +301,39 @@ impl StatusBar {
         }?;
         ensure_class(hinstance.into())?;
 
+        // Windows take the DPI awareness of the thread creating them
+        let _dpi = PerMonitorDpi::enter();
         let accent_color = get_accent_color().unwrap_or(DEFAULT_ACCENT_COLOR);
         let mut state = Box::new(StatusBarState {
             active_workspace: 1,
-            total_workspaces: STATUSBAR_MAX_WORKSPACES,
+            total_workspaces: DEFAULT_WORKSPACES,
             accent_color,
+            theme: Theme::current(),
             time_string: String::new(),
-            occupied_workspaces_6_9: 0,
+            occupied_workspaces: 0,
+            parked_stash_slots: Vec::new(),
             width: STATUSBAR_WIDTH,
             height: STATUSBAR_HEIGHT,
+            scale: 1.0,
+            window_titles: Vec::new(),
+            tooltip_hwnd: HWND::default(),
+            hovered_workspace: None,
+            tracking_mouse: false,
+            window_list: None,
+            icons: IconCache::default(),
+            media: None,
+            media_state: MediaState::default(),
+            clock: Clock::default(),
+            clock_width: CLOCK_WIDTH,
+            show_clock: true,
+            align: BarAlign::default(),
+            position: BarPosition::default(),
+            animations: true,
+            visibility: Transition::at(0.0),
+            active_dot: Transition::at(0.0),
         });
         update_time_string(&mut state);
+        state.width = bar_width(0, false, clock_area(state.clock_width, true));
 
         // Create layered window (WS_EX_LAYERED) for per-pixel alpha
         let hwnd = unsafe {
@@ -161,6 +356,35 @@ impl StatusBar {
             .map_err(|e| format!("Failed to create status bar window: {}", e))?
         };
 
+        // The tooltip lets the mouse through so hovering never flickers it
+        state.tooltip_hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE(
+                    WS_EX_TOPMOST.0
+                        | WS_EX_TOOLWINDOW.0
+                        | WS_EX_NOACTIVATE.0
+                        | WS_EX_LAYERED.0
+                        | WS_EX_TRANSPARENT.0,
+                ),
+                STATUSBAR_CLASS_NAME,
+                w!(""),
+                WINDOW_STYLE(WS_POPUP.0),
+                0,
+                0,
+                TOOLTIP_WIDTH,
+                TOOLTIP_LINE_HEIGHT,
+                Some(hwnd),
+                Some(HMENU::default()),
+                Some(hinstance.into()),
+                None,
+            )
+            .map_err(|e| {
+                let _ = DestroyWindow(hwnd);
+                format!("Failed to create status bar tooltip: {}", e)
+            })?
+        };
+
+        state.scale = unsafe { GetDpiForWindow(hwnd) } as f32 / USER_DEFAULT_SCREEN_DPI as f32;
         let mut statusbar = StatusBar { hwnd, state };
         statusbar.sync_state_pointer();
         // Initial render
@@ -168,8 +392,41 @@ impl StatusBar {
         Ok(statusbar)
     }
 
-    /// Sets the position and size of the status bar.
-    pub fn set_position(&self, x: i32, y: i32, width: i32, height: i32) {
+    /// Puts the bar at its edge of `hmonitor` as aligned, scaled to that
+    /// monitor's DPI.
+    pub fn place_on(&mut self, hmonitor: isize) {
+        let _dpi = PerMonitorDpi::enter();
+        let hmonitor = HMONITOR(hmonitor as _);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(hmonitor, &mut info) }.as_bool() {
+            return;
+        }
+        let (mut dpi_x, mut dpi_y) = (USER_DEFAULT_SCREEN_DPI, USER_DEFAULT_SCREEN_DPI);
+        let _ = unsafe { GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) };
+        self.state.scale = dpi_y as f32 / USER_DEFAULT_SCREEN_DPI as f32;
+
+        let rect = info.rcMonitor;
+        let width = scaled(self.state.width, self.state.scale);
+        let height = scaled(self.state.height, self.state.scale);
+        let margin = scaled(STATUSBAR_TOP_GAP, self.state.scale);
+        let x = match self.state.align {
+            BarAlign::Left => rect.left + margin,
+            BarAlign::Center => rect.left + (rect.right - rect.left - width) / 2,
+            BarAlign::Right => rect.right - margin - width,
+        };
+        let y = match self.state.position {
+            BarPosition::Top => rect.top + margin,
+            BarPosition::Bottom => rect.bottom - margin - height,
+        };
+        self.set_position(x, y, width, height);
+        self.render();
+    }
+
+    /// Sets the position and size of the status bar in physical pixels.
+    fn set_position(&self, x: i32, y: i32, width: i32, height: i32) {
         unsafe {
             let _ = SetWindowPos(
                 self.hwnd,
@@ -183,38 +440,197 @@ impl StatusBar {
         }
     }
 
+    /// Returns the bar's bounds on screen, hidden or not.
+    pub fn rect(&self) -> RECT {
+        let mut rect = RECT::default();
+        unsafe {
+            let _ = GetWindowRect(self.hwnd, &mut rect);
+        }
+        rect
+    }
+
     /// Updates the workspace indicator display.
     ///
     /// # Arguments
-    /// * `active_workspace` - Currently active workspace (1-9)
-    /// * `total_workspaces` - Total number of workspaces (1-9)
-    /// * `occupied_6_9` - Bitmask for workspaces 6-9 occupancy (bit 0=ws6, bit 1=ws7, bit 2=ws8, bit 3=ws9)
-    pub fn update_indicator(
-        &mut self,
-        active_workspace: u8,
-        total_workspaces: u8,
-        occupied_6_9: u8,
-    ) {
-        self.state.active_workspace = active_workspace.clamp(1, STATUSBAR_MAX_WORKSPACES);
-        self.state.total_workspaces = total_workspaces.clamp(1, STATUSBAR_MAX_WORKSPACES);
-        self.state.occupied_workspaces_6_9 = occupied_6_9;
-        if let Ok(color) = get_accent_color() {
-            self.state.accent_color = color;
-        }
-        update_time_string(&mut self.state);
+    /// * `active_workspace` - Currently active workspace (1-based)
+    /// * `total_workspaces` - Total number of workspaces (1-20)
+    /// * `occupied` - Bitmask of workspace occupancy (bit 0=ws1, bit 1=ws2, ...)
+    pub fn update_indicator(&mut self, active_workspace: u8, total_workspaces: u8, occupied: u32) {
+        let previous = self.state.active_workspace;
+        self.state.total_workspaces = total_workspaces.clamp(1, MAX_WORKSPACES);
+        self.state.active_workspace = active_workspace.clamp(1, self.state.total_workspaces);
+        self.state.occupied_workspaces = occupied;
+        let active = self.state.active_workspace;
+        if let Some(index) = shown_workspaces(&self.state)
+            .iter()
+            .position(|&ws| ws == active)
+            && self.state.active_dot.to != index as f32
+        {
+            // Glide only on a switch; dots appearing before the active one just move it
+            let animate = active != previous && self.is_visible() && self.animations_enabled();
+            self.state.active_dot.go_to(index as f32, animate);
+            if animate {
+                self.start_animation();
+            }
+        }
+        if update_time_string(&mut self.state) {
+            self.resize();
+        }
         self.render();
     }
 
+    /// Updates the stash slots shown as holding a parked window.
+    pub fn update_stash(&mut self, parked_slots: Vec<u8>) {
+        if self.state.parked_stash_slots != parked_slots {
+            self.state.parked_stash_slots = parked_slots;
+            self.render();
+        }
+    }
+
+    /// Updates the window titles listed when hovering a workspace dot.
+    ///
+    /// # Arguments
+    /// * `titles` - Titles of each workspace's windows (index 0 = ws1)
+    pub fn update_window_titles(&mut self, titles: Vec<Vec<String>>) {
+        if self.state.window_titles != titles {
+            self.state.window_titles = titles;
+            if let Some(workspace) = self.state.hovered_workspace {
+                unsafe { show_tooltip(self.hwnd, &self.state, workspace) };
+            }
+        }
+    }
+
+    /// Turns the window list on or off, resizing the bar around its anchor.
+    pub fn set_window_list(&mut self, enabled: bool) {
+        if self.state.window_list.is_some() != enabled {
+            self.state.window_list = enabled.then(Vec::new);
+            self.resize();
+            self.render();
+        }
+    }
+
+    /// Turns the media module on or off, resizing the bar around its anchor.
+    pub fn set_media(&mut self, enabled: bool) {
+        if self.state.media.is_some() == enabled {
+            return;
+        }
+        self.state.media = None;
+        self.state.media_state = MediaState::default();
+        if enabled {
+            let hwnd = self.hwnd.0 as isize;
+            match MediaMonitor::spawn(move || unsafe {
+                let _ = PostMessageW(
+                    Some(HWND(hwnd as _)),
+                    WM_MEDIA_CHANGED,
+                    WPARAM(0),
+                    LPARAM(0),
+                );
+            }) {
+                Ok(monitor) => self.state.media = Some(monitor),
+                Err(e) => log::error!("Failed to start the media module: {}", e),
+            }
+        }
+        self.resize();
+        self.render();
+    }
+
+    /// Updates the window list's buttons, if it's turned on.
+    pub fn update_window_list(&mut self, buttons: Vec<WindowButton>) {
+        if self
+            .state
+            .window_list
+            .as_ref()
+            .is_none_or(|list| *list == buttons)
+        {
+            return;
+        }
+        let icons = &mut self.state.icons;
+        icons.retain(|hwnd| buttons.iter().any(|button| button.hwnd == hwnd));
+        for button in &buttons {
+            icons.get(button.hwnd);
+        }
+        self.state.window_list = Some(buttons);
+        self.resize();
+        self.render();
+    }
+
+    /// Sets the accent color used for the active workspace and re-renders.
+    ///
+    /// # Arguments
+    /// * `color` - Color in COLORREF format (0x00BBGGRR)
+    pub fn set_accent_color(&mut self, color: u32) {
+        self.state.accent_color = color;
+        self.render();
+    }
+
+    /// Switches between the light and dark palettes and re-renders.
+    pub fn set_theme(&mut self, theme: Theme) {
+        if self.state.theme != theme {
+            self.state.theme = theme;
+            self.render();
+        }
+    }
+
     /// Updates only the clock display without changing workspace indicators.
     ///
     /// This is more efficient than `update_indicator()` when only the time needs refreshing.
     pub fn update_clock(&mut self) {
-        update_time_string(&mut self.state);
+        if update_time_string(&mut self.state) {
+            self.resize();
+        }
+        self.render();
+    }
+
+    /// Sets how the clock is formatted, resizing the bar to fit it.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.state.clock = clock;
+        self.state.time_string.clear();
+        if update_time_string(&mut self.state) {
+            self.resize();
+        }
         self.render();
     }
 
-    /// Shows the status bar.
-    pub fn show(&self) {
+    /// Turns the clock and stash on or off, resizing the bar around its anchor.
+    pub fn set_clock_shown(&mut self, shown: bool) {
+        if self.state.show_clock != shown {
+            self.state.show_clock = shown;
+            self.resize();
+            self.render();
+        }
+    }
+
+    /// Sets which edge of its monitor the bar keeps to. Takes effect the
+    /// next time it's placed.
+    pub fn set_align(&mut self, align: BarAlign) {
+        self.state.align = align;
+    }
+
+    /// Sets whether the bar sits on the top or bottom edge of its monitor.
+    /// Takes effect the next time it's placed.
+    pub fn set_edge(&mut self, position: BarPosition) {
+        self.state.position = position;
+    }
+
+    /// Turns the bar's fades and glides on or off. While off, it jumps
+    /// straight to its new state even if Windows' animation effects are on.
+    pub fn set_animations(&mut self, enabled: bool) {
+        self.state.animations = enabled;
+    }
+
+    /// Returns true if the bar should animate a change right now.
+    fn animations_enabled(&self) -> bool {
+        self.state.animations && animations_enabled()
+    }
+
+    /// Shows the status bar, fading it in.
+    pub fn show(&mut self) {
+        let animate = self.animations_enabled();
+        self.state.visibility.go_to(1.0, animate);
+        self.render();
+        if animate {
+            self.start_animation();
+        }
         unsafe {
             let _ = ShowWindow(self.hwnd, SW_SHOW);
             // Re-render after showing to ensure proper display
@@ -230,10 +646,37 @@ impl StatusBar {
         }
     }
 
-    /// Hides the status bar.
-    pub fn hide(&self) {
+    /// Hides the status bar, fading it out.
+    pub fn hide(&mut self) {
+        self.state.hovered_workspace = None;
+        unsafe {
+            let _ = ShowWindow(self.state.tooltip_hwnd, SW_HIDE);
+        }
+        if self.is_visible() && self.animations_enabled() {
+            // Hidden for good once the fade ends, in the timer
+            self.state.visibility.go_to(0.0, true);
+            self.start_animation();
+        } else {
+            self.state.visibility.go_to(0.0, false);
+            unsafe {
+                let _ = ShowWindow(self.hwnd, SW_HIDE);
+            }
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(self.hwnd) }.as_bool()
+    }
+
+    /// Redraws the bar every frame until its animations are done.
+    fn start_animation(&self) {
         unsafe {
-            let _ = ShowWindow(self.hwnd, SW_HIDE);
+            SetTimer(
+                Some(self.hwnd),
+                ANIMATION_TIMER_ID,
+                ANIMATION_FRAME_MS,
+                None,
+            );
         }
     }
 
@@ -244,17 +687,100 @@ impl StatusBar {
         }
     }
 
+    /// Fits the bar to its modules, keeping its aligned edge (or its center)
+    /// where it was.
+    fn resize(&mut self) {
+        let buttons = self.state.window_list.as_ref().map_or(0, Vec::len);
+        let width = bar_width(buttons, self.state.media.is_some(), self.state.clock_area());
+        if width == self.state.width {
+            return;
+        }
+        let _dpi = PerMonitorDpi::enter();
+        let mut rect = RECT::default();
+        unsafe {
+            let _ = GetWindowRect(self.hwnd, &mut rect);
+        }
+        self.state.width = width;
+        let (width, height) = (
+            scaled(width, self.state.scale),
+            scaled(self.state.height, self.state.scale),
+        );
+        let left = match self.state.align {
+            BarAlign::Left => rect.left,
+            BarAlign::Center => (rect.left + rect.right) / 2 - width / 2,
+            BarAlign::Right => rect.right - width,
+        };
+        self.set_position(left, rect.top, width, height);
+    }
+
     /// Renders the status bar using layered window with per-pixel alpha.
     fn render(&self) {
         unsafe {
-            render_layered_window(self.hwnd, &self.state);
+            render_bar(self.hwnd, &self.state);
+        }
+    }
+}
+
+/// Switches the calling thread to per-monitor DPI awareness until dropped,
+/// so the bar's windows are sized and placed in physical pixels.
+struct PerMonitorDpi(DPI_AWARENESS_CONTEXT);
+
+impl PerMonitorDpi {
+    fn enter() -> Self {
+        PerMonitorDpi(unsafe {
+            SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
+        })
+    }
+}
+
+impl Drop for PerMonitorDpi {
+    fn drop(&mut self) {
+        // Null when the switch failed, e.g. before Windows 10 1703
+        if !self.0.0.is_null() {
+            unsafe {
+                SetThreadDpiAwarenessContext(self.0);
+            }
         }
     }
 }
 
+/// Returns whether Windows' animation effects are turned on.
+fn animations_enabled() -> bool {
+    let mut enabled = BOOL(1);
+    unsafe {
+        let _ = SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut enabled as *mut BOOL as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+    }
+    enabled.as_bool()
+}
+
+/// Returns `value`, in pixels at 96 DPI, at `scale`.
+fn scaled(value: i32, scale: f32) -> i32 {
+    (value as f32 * scale).round() as i32
+}
+
+/// Returns the point in a mouse message in bar coordinates at 96 DPI.
+fn bar_point(lparam: LPARAM, scale: f32) -> (i32, i32) {
+    let (x, y) = client_point(lparam);
+    unscaled(x, y, scale)
+}
+
+/// Returns a point in physical pixels at 96 DPI.
+fn unscaled(x: i32, y: i32, scale: f32) -> (i32, i32) {
+    (
+        (x as f32 / scale).floor() as i32,
+        (y as f32 / scale).floor() as i32,
+    )
+}
+
 impl Drop for StatusBar {
     fn drop(&mut self) {
         unsafe {
+            let _ = DestroyWindow(self.state.tooltip_hwnd);
             let _ = DestroyWindow(self.hwnd);
         }
     }
@@ -288,31 +814,443 @@ extern "system" fn statusbar_wnd_proc(
     lparam: LPARAM,
 ) -> LRESULT {
     unsafe {
-        if msg == WM_NCDESTROY {
-            let _ = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+        match msg {
+            WM_MOUSEMOVE => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null() {
+                    let (x, y) = bar_point(lparam, (*state).scale);
+                    on_mouse_move(hwnd, &mut *state, x, y);
+                }
+            }
+            WM_LBUTTONUP | WM_MBUTTONUP => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null() {
+                    let (x, y) = bar_point(lparam, (*state).scale);
+                    on_button_up(&*state, msg, x, y);
+                }
+            }
+            WM_MOUSEWHEEL => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null()
+                    && let Some(media) = &(*state).media
+                {
+                    // Wheel messages carry screen coordinates
+                    let (screen_x, screen_y) = client_point(lparam);
+                    let mut bar_rect = RECT::default();
+                    let _ = GetWindowRect(hwnd, &mut bar_rect);
+                    let (x, y) = unscaled(
+                        screen_x - bar_rect.left,
+                        screen_y - bar_rect.top,
+                        (*state).scale,
+                    );
+                    if media_part_at((*state).width, (*state).clock_area(), x, y).is_some() {
+                        let notches = ((wparam.0 >> 16) & 0xFFFF) as i16 as i32 / 120;
+                        media.send(MediaCommand::AdjustVolume(notches * VOLUME_STEP));
+                        return LRESULT(0);
+                    }
+                }
+            }
+            WM_MEDIA_CHANGED => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null()
+                    && let Some(media) = &(*state).media
+                {
+                    (*state).media_state = media.state();
+                    render_bar(hwnd, &*state);
+                }
+            }
+            WM_TIMER if wparam.0 == ANIMATION_TIMER_ID => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null() {
+                    render_bar(hwnd, &*state);
+                    if (*state).visibility.is_done() && (*state).active_dot.is_done() {
+                        let _ = KillTimer(Some(hwnd), ANIMATION_TIMER_ID);
+                        if (*state).visibility.to == 0.0 {
+                            let _ = ShowWindow(hwnd, SW_HIDE);
+                        }
+                    }
+                }
+                return LRESULT(0);
+            }
+            WM_DPICHANGED => {
+                // Sent when the bar's monitor changes scale; the tooltip has no state
+                let state = get_state_ptr(hwnd);
+                if !state.is_null() {
+                    (*state).scale =
+                        (wparam.0 >> 16 & 0xFFFF) as f32 / USER_DEFAULT_SCREEN_DPI as f32;
+                    let suggested = &*(lparam.0 as *const RECT);
+                    let _ = SetWindowPos(
+                        hwnd,
+                        Some(HWND_TOPMOST),
+                        suggested.left,
+                        suggested.top,
+                        suggested.right - suggested.left,
+                        suggested.bottom - suggested.top,
+                        SWP_NOACTIVATE,
+                    );
+                    render_bar(hwnd, &*state);
+                    return LRESULT(0);
+                }
+            }
+            WM_MOUSELEAVE => {
+                let state = get_state_ptr(hwnd);
+                if !state.is_null() {
+                    (*state).tracking_mouse = false;
+                    (*state).hovered_workspace = None;
+                    let _ = ShowWindow((*state).tooltip_hwnd, SW_HIDE);
+                }
+            }
+            WM_NCDESTROY => {
+                let _ = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+            }
+            _ => {}
         }
 
         DefWindowProcW(hwnd, msg, wparam, lparam)
     }
 }
 
-/// Updates the time string in the state with current local time.
-fn update_time_string(state: &mut StatusBarState) {
-    let st: SYSTEMTIME = unsafe { GetLocalTime() };
+/// Handles a click on a window button or the media module.
+fn on_button_up(state: &StatusBarState, msg: u32, x: i32, y: i32) {
+    if let Some(buttons) = &state.window_list
+        && let Some(button) = window_button_at(buttons.len(), x, y)
+    {
+        let hwnd = buttons[button].hwnd;
+        let click = if msg == WM_LBUTTONUP {
+            WindowListClick::Focus(hwnd)
+        } else {
+            WindowListClick::Close(hwnd)
+        };
+        if let Ok(mut clicks) = WINDOW_LIST_CLICKS.lock() {
+            clicks.push_back(click);
+        }
+    }
+    if msg == WM_LBUTTONUP
+        && let Some(media) = &state.media
+        && media_part_at(state.width, state.clock_area(), x, y) == Some(MediaPart::Media)
+    {
+        media.send(MediaCommand::TogglePlayPause);
+    }
+}
 
-    // Format: "HH:MM DD/MM"
-    state.time_string = format!(
-        "{:02}:{:02} {:02}/{:02}",
-        st.wHour, st.wMinute, st.wDay, st.wMonth
-    );
+/// Returns the signed client coordinates packed into a mouse message.
+fn client_point(lparam: LPARAM) -> (i32, i32) {
+    let x = (lparam.0 & 0xFFFF) as i16 as i32;
+    let y = ((lparam.0 >> 16) & 0xFFFF) as i16 as i32;
+    (x, y)
 }
 
-/// Renders the status bar to a 32-bit ARGB bitmap and updates the layered window.
-unsafe fn render_layered_window(hwnd: HWND, state: &StatusBarState) {
+/// Shows or hides the tooltip as the cursor moves over the dots.
+unsafe fn on_mouse_move(hwnd: HWND, state: &mut StatusBarState, x: i32, y: i32) {
     unsafe {
-        let width = state.width;
-        let height = state.height;
+        if !state.tracking_mouse {
+            let mut track = TRACKMOUSEEVENT {
+                cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                dwFlags: TME_LEAVE,
+                hwndTrack: hwnd,
+                dwHoverTime: 0,
+            };
+            state.tracking_mouse = TrackMouseEvent(&mut track).is_ok();
+        }
 
+        let hovered = workspace_at(&shown_workspaces(state), x, y);
+        if hovered == state.hovered_workspace {
+            return;
+        }
+        state.hovered_workspace = hovered;
+        match hovered {
+            Some(workspace) => show_tooltip(hwnd, state, workspace),
+            None => {
+                let _ = ShowWindow(state.tooltip_hwnd, SW_HIDE);
+            }
+        }
+    }
+}
+
+/// Workspaces shown as dots: 1-5, plus higher ones that have windows or are active.
+fn shown_workspaces(state: &StatusBarState) -> Vec<u8> {
+    (1..=state.total_workspaces)
+        .filter(|&ws| {
+            ws <= ALWAYS_SHOW_WORKSPACES
+                || ws == state.active_workspace
+                || state.occupied_workspaces & (1 << (ws - 1)) != 0
+        })
+        .collect()
+}
+
+/// Returns the spacing between dots and their diameter when `count` are shown.
+fn dot_layout(count: usize) -> (i32, i32) {
+    // Squeeze the dots together when there are too many to fit before the clock
+    let count = count.max(1) as i32;
+    let spacing = (DOTS_AREA_WIDTH / count).clamp(MIN_DOT_SPACING, DOT_SPACING);
+    (spacing, DOT_DIAMETER.min(spacing - 2))
+}
+
+/// Returns the workspace whose dot is at (`x`, `y`) in bar coordinates.
+/// The gap after each dot counts as part of it, so the tooltip doesn't
+/// blink out between neighbours.
+fn workspace_at(shown: &[u8], x: i32, y: i32) -> Option<u8> {
+    let (spacing, _) = dot_layout(shown.len());
+    if x < PADDING_LEFT || !(PADDING_VERTICAL..PADDING_VERTICAL + DOT_DIAMETER).contains(&y) {
+        return None;
+    }
+    shown.get(((x - PADDING_LEFT) / spacing) as usize).copied()
+}
+
+/// Returns the width of each window button when `count` are shown, gap included.
+fn window_button_width(count: usize) -> i32 {
+    (WINDOW_LIST_MAX_WIDTH / count.max(1) as i32)
+        .clamp(MIN_WINDOW_BUTTON_WIDTH, WINDOW_BUTTON_WIDTH)
+}
+
+/// Returns the width the stash and a clock `clock_width` wide take, or 0
+/// when they aren't `shown`.
+fn clock_area(clock_width: i32, shown: bool) -> i32 {
+    if shown {
+        STASH_AREA_WIDTH + clock_width - CLOCK_WIDTH
+    } else {
+        0
+    }
+}
+
+/// Returns the bar's width with `buttons` window buttons, the media module
+/// if `media` is set, and `clock_area` for the stash and clock.
+fn bar_width(buttons: usize, media: bool, clock_area: i32) -> i32 {
+    let media_width = if media { MEDIA_WIDTH } else { 0 };
+    PADDING_LEFT
+        + DOTS_AREA_WIDTH
+        + buttons as i32 * window_button_width(buttons)
+        + media_width
+        + clock_area
+        + PADDING_RIGHT
+}
+
+/// The parts of the media module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MediaPart {
+    /// What's playing; a click plays or pauses it.
+    Media,
+    Volume,
+}
+
+/// Returns the left edge of the media module, just left of the stash and clock.
+fn media_left(bar_width: i32, clock_area: i32) -> i32 {
+    bar_width - PADDING_RIGHT - clock_area - MEDIA_WIDTH
+}
+
+/// Returns the part of the media module at (`x`, `y`) in bar coordinates.
+fn media_part_at(bar_width: i32, clock_area: i32, x: i32, y: i32) -> Option<MediaPart> {
+    let left = media_left(bar_width, clock_area);
+    if !(left..left + MEDIA_WIDTH).contains(&x)
+        || !(PADDING_VERTICAL..PADDING_VERTICAL + DOT_DIAMETER).contains(&y)
+    {
+        return None;
+    }
+    if x < left + MEDIA_WIDTH - MEDIA_VOLUME_WIDTH {
+        Some(MediaPart::Media)
+    } else {
+        Some(MediaPart::Volume)
+    }
+}
+
+/// Returns the index of the window button at (`x`, `y`) in bar coordinates.
+fn window_button_at(count: usize, x: i32, y: i32) -> Option<usize> {
+    let width = window_button_width(count);
+    if x < WINDOW_LIST_LEFT || !(PADDING_VERTICAL..PADDING_VERTICAL + DOT_DIAMETER).contains(&y) {
+        return None;
+    }
+    let index = ((x - WINDOW_LIST_LEFT) / width) as usize;
+    (index < count && (x - WINDOW_LIST_LEFT) % width < width - WINDOW_BUTTON_GAP).then_some(index)
+}
+
+/// Returns the tooltip's lines for a workspace: a heading, then one line
+/// per window, cut off with a count once the tooltip is full.
+fn tooltip_lines(workspace: u8, titles: &[String]) -> Vec<String> {
+    let mut lines = vec![format!("Workspace {}", workspace)];
+    if titles.is_empty() {
+        lines.push("No windows".to_string());
+    } else if titles.len() < TOOLTIP_MAX_LINES {
+        lines.extend(titles.iter().cloned());
+    } else {
+        let listed = TOOLTIP_MAX_LINES - 2;
+        lines.extend(titles[..listed].iter().cloned());
+        lines.push(format!("+{} more", titles.len() - listed));
+    }
+    lines
+}
+
+/// Renders the tooltip for `workspace` and shows it beside its dot, away
+/// from the bar's edge.
+unsafe fn show_tooltip(hwnd: HWND, state: &StatusBarState, workspace: u8) {
+    unsafe {
+        let shown = shown_workspaces(state);
+        let Some(index) = shown.iter().position(|&ws| ws == workspace) else {
+            let _ = ShowWindow(state.tooltip_hwnd, SW_HIDE);
+            return;
+        };
+        let titles = state
+            .window_titles
+            .get(workspace as usize - 1)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let lines = tooltip_lines(workspace, titles);
+
+        let mut bar_rect = RECT::default();
+        let _ = GetWindowRect(hwnd, &mut bar_rect);
+        let (spacing, _) = dot_layout(shown.len());
+        let scale = state.scale;
+        let x = bar_rect.left + scaled(PADDING_LEFT + index as i32 * spacing, scale);
+        let height = TOOLTIP_PADDING * 2 + lines.len() as i32 * TOOLTIP_LINE_HEIGHT;
+        let y = match state.position {
+            BarPosition::Top => bar_rect.bottom + scaled(TOOLTIP_GAP, scale),
+            BarPosition::Bottom => bar_rect.top - scaled(TOOLTIP_GAP + height, scale),
+        };
+
+        let _ = SetWindowPos(
+            state.tooltip_hwnd,
+            Some(HWND_TOPMOST),
+            x,
+            y,
+            scaled(TOOLTIP_WIDTH, scale),
+            scaled(height, scale),
+            SWP_NOACTIVATE | SWP_SHOWWINDOW,
+        );
+        render_layered_window(
+            state.tooltip_hwnd,
+            TOOLTIP_WIDTH,
+            height,
+            scale,
+            255,
+            |graphics, rect| {
+                draw_tooltip_gdiplus(graphics, rect, &lines, state.accent_color, state.theme)
+            },
+        );
+    }
+}
+
+/// Updates the time string in the state with the current time. Returns true
+/// if the clock's width changed, which means the bar must be resized.
+fn update_time_string(state: &mut StatusBarState) -> bool {
+    let text = state.clock.text();
+    // Digits are all the same width, so only a change in length is measured
+    let remeasure = text.chars().count() != state.time_string.chars().count();
+    state.time_string = text;
+    if !remeasure {
+        return false;
+    }
+    let width = unsafe { measure_clock_text(&state.time_string) }
+        .map_or(CLOCK_WIDTH, |width| (width + 4).max(CLOCK_WIDTH));
+    let changed = width != state.clock_width;
+    state.clock_width = width;
+    changed
+}
+
+/// Returns the width of `text` in the clock's font, if GDI+ can measure it.
+unsafe fn measure_clock_text(text: &str) -> Option<i32> {
+    unsafe {
+        let screen_dc = GetDC(None);
+        let mut graphics: *mut GpGraphics = std::ptr::null_mut();
+        if GdipCreateFromHDC(screen_dc, &mut graphics).0 != 0 || graphics.is_null() {
+            let _ = ReleaseDC(None, screen_dc);
+            return None;
+        }
+        let font_family = create_font_family();
+        let font = create_font(font_family, CLOCK_FONT_SIZE);
+        let mut width = None;
+        if !font.is_null() {
+            let text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+            let layout = windows::Win32::Graphics::GdiPlus::RectF {
+                X: 0.0,
+                Y: 0.0,
+                Width: 2000.0,
+                Height: 100.0,
+            };
+            let mut bounds = windows::Win32::Graphics::GdiPlus::RectF::default();
+            if GdipMeasureString(
+                graphics,
+                PCWSTR::from_raw(text.as_ptr()),
+                -1,
+                font,
+                &layout,
+                std::ptr::null(),
+                &mut bounds,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+            .0 == 0
+            {
+                width = Some(bounds.Width.ceil() as i32);
+            }
+            GdipDeleteFont(font);
+        }
+        if !font_family.is_null() {
+            GdipDeleteFontFamily(font_family);
+        }
+        GdipDeleteGraphics(graphics);
+        let _ = ReleaseDC(None, screen_dc);
+        width
+    }
+}
+
+/// Renders the whole bar.
+unsafe fn render_bar(hwnd: HWND, state: &StatusBarState) {
+    let _dpi = PerMonitorDpi::enter();
+    // Fades out while sliding toward its edge
+    let visibility = state.visibility.value();
+    let alpha = (visibility * 255.0).round() as u8;
+    let slide = (1.0 - visibility) * SLIDE_DISTANCE;
+    let slide = match state.position {
+        BarPosition::Top => -slide,
+        BarPosition::Bottom => slide,
+    };
+    unsafe {
+        render_layered_window(
+            hwnd,
+            state.width,
+            state.height,
+            state.scale,
+            alpha,
+            |graphics, rect| {
+                if slide != 0.0 {
+                    let _ = GdipTranslateWorldTransform(graphics, 0.0, slide, MatrixOrderPrepend);
+                }
+                draw_background_gdiplus(graphics, rect, state.accent_color, state.theme);
+                draw_workspace_dots_gdiplus(graphics, rect, state);
+                if let Some(buttons) = &state.window_list {
+                    draw_window_list_gdiplus(graphics, rect, buttons, state);
+                }
+                if state.media.is_some() {
+                    draw_media_gdiplus(
+                        graphics,
+                        rect,
+                        &state.media_state,
+                        state.clock_area(),
+                        state.theme,
+                    );
+                }
+                if state.show_clock {
+                    draw_stash_gdiplus(graphics, rect, state);
+                    draw_time_gdiplus(graphics, rect, state);
+                }
+            },
+        );
+    }
+}
+
+/// Renders `draw` to a 32-bit ARGB bitmap and updates the layered window.
+/// `draw` works at 96 DPI in a `width` by `height` rect, which is scaled up
+/// by `scale` on the way to the window, at `alpha` opacity.
+unsafe fn render_layered_window(
+    hwnd: HWND,
+    width: i32,
+    height: i32,
+    scale: f32,
+    alpha: u8,
+    draw: impl FnOnce(*mut GpGraphics, &RECT),
+) {
+    let (logical_width, logical_height) = (width, height);
+    let (width, height) = (scaled(width, scale), scaled(height, scale));
+    unsafe {
         // Get screen DC
         let screen_dc = GetDC(None);
         if screen_dc.0.is_null() {
@@ -372,19 +1310,18 @@ unsafe fn render_layered_window(hwnd: HWND, state: &StatusBarState) {
         // Enable anti-aliasing
         let _ = GdipSetSmoothingMode(graphics, SmoothingModeHighQuality);
         let _ = GdipSetTextRenderingHint(graphics, TextRenderingHintClearTypeGridFit);
+        let _ = GdipScaleWorldTransform(graphics, scale, scale, MatrixOrderPrepend);
 
         // Create rect for drawing
         let rect = RECT {
             left: 0,
             top: 0,
-            right: width,
-            bottom: height,
+            right: logical_width,
+            bottom: logical_height,
         };
 
         // Draw all elements
-        draw_background_gdiplus(graphics, &rect, state.accent_color);
-        draw_workspace_dots_gdiplus(graphics, &rect, state);
-        draw_time_gdiplus(graphics, &rect, state);
+        draw(graphics, &rect);
 
         // Cleanup GDI+
         GdipDeleteGraphics(graphics);
@@ -397,7 +1334,7 @@ unsafe fn render_layered_window(hwnd: HWND, state: &StatusBarState) {
         let blend = BLENDFUNCTION {
             BlendOp: AC_SRC_OVER as u8,
             BlendFlags: 0,
-            SourceConstantAlpha: 255,
+            SourceConstantAlpha: alpha,
             AlphaFormat: AC_SRC_ALPHA as u8,
         };
 
@@ -432,9 +1369,14 @@ unsafe fn render_layered_window(hwnd: HWND, state: &StatusBarState) {
     }
 }
 
-unsafe fn draw_background_gdiplus(graphics: *mut GpGraphics, rect: &RECT, accent_color: u32) {
+unsafe fn draw_background_gdiplus(
+    graphics: *mut GpGraphics,
+    rect: &RECT,
+    accent_color: u32,
+    theme: Theme,
+) {
     unsafe {
-        let bg_color = dimmed_desaturated_background(accent_color);
+        let bg_color = bar_background(accent_color, theme);
         let (r, g, b) = split_color(bg_color);
 
         // Create fill brush for background with full opacity
@@ -536,50 +1478,30 @@ unsafe fn draw_workspace_dots_gdiplus(
     state: &StatusBarState,
 ) {
     unsafe {
-        // Determine which workspaces to display
-        let mut workspaces_to_show = Vec::with_capacity(9);
-
-        // Always show workspaces 1-5
-        for i in 1..=ALWAYS_SHOW_WORKSPACES {
-            workspaces_to_show.push(i);
-        }
+        let workspaces_to_show = shown_workspaces(state);
+        let (spacing, diameter) = dot_layout(workspaces_to_show.len());
 
-        // Conditionally show workspaces 6-9 if they have windows
-        if state.occupied_workspaces_6_9 & 0x01 != 0 {
-            workspaces_to_show.push(6);
-        }
-        if state.occupied_workspaces_6_9 & 0x02 != 0 {
-            workspaces_to_show.push(7);
-        }
-        if state.occupied_workspaces_6_9 & 0x04 != 0 {
-            workspaces_to_show.push(8);
-        }
-        if state.occupied_workspaces_6_9 & 0x08 != 0 {
-            workspaces_to_show.push(9);
-        }
-
-        // Start at left with padding
+        // Start at left with padding, keeping smaller dots vertically centered
         let start_x = rect.left + PADDING_LEFT;
-        let center_y = rect.top + PADDING_VERTICAL;
+        let center_y = rect.top + PADDING_VERTICAL + (DOT_DIAMETER - diameter) / 2;
 
         // Create font for workspace numbers
         let font_family = create_font_family();
-        let font = create_font(font_family, 10.0);
+        let font = create_font(
+            font_family,
+            if diameter < DOT_DIAMETER { 8.0 } else { 10.0 },
+        );
         let string_format = create_centered_string_format();
 
         for (index, workspace_id) in workspaces_to_show.iter().enumerate() {
-            let x = start_x + (index as i32) * DOT_SPACING;
-            let is_active = *workspace_id == state.active_workspace;
+            let x = start_x + (index as i32) * spacing;
 
-            // Get dot color and text color
-            let (dot_color, text_color) = if is_active {
-                (state.accent_color, state.accent_color) // Active: accent color, text same (hidden)
-            } else {
-                (
-                    semi_transparent_dot_color(state.accent_color),
-                    0x00888888_u32,
-                )
-            };
+            // The active dot is covered by the highlight below
+            let gray = text_gray(0x88, state.theme);
+            let (dot_color, text_color) = (
+                semi_transparent_dot_color(state.accent_color, state.theme),
+                compose_color(gray, gray, gray),
+            );
 
             // Draw the ellipse (dot)
             let (dr, dg, db) = split_color(dot_color);
@@ -590,8 +1512,8 @@ unsafe fn draw_workspace_dots_gdiplus(
                     dot_brush as *mut GpBrush,
                     x as f32,
                     center_y as f32,
-                    DOT_DIAMETER as f32,
-                    DOT_DIAMETER as f32,
+                    diameter as f32,
+                    diameter as f32,
                 );
                 GdipDeleteBrush(dot_brush as *mut GpBrush);
             }
@@ -610,8 +1532,8 @@ unsafe fn draw_workspace_dots_gdiplus(
                     let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
                         X: 1.0 + (x as f32),
                         Y: 1.0 + (center_y as f32),
-                        Width: DOT_DIAMETER as f32,
-                        Height: DOT_DIAMETER as f32,
+                        Width: diameter as f32,
+                        Height: diameter as f32,
                     };
 
                     let _ = GdipDrawString(
@@ -628,6 +1550,21 @@ unsafe fn draw_workspace_dots_gdiplus(
             }
         }
 
+        // The active workspace's highlight, between dots while gliding
+        let (r, g, b) = split_color(state.accent_color);
+        let mut highlight_brush: *mut GpSolidFill = std::ptr::null_mut();
+        if GdipCreateSolidFill(make_argb(255, r, g, b), &mut highlight_brush).0 == 0 {
+            let _ = GdipFillEllipse(
+                graphics,
+                highlight_brush as *mut GpBrush,
+                start_x as f32 + state.active_dot.value() * spacing as f32,
+                center_y as f32,
+                diameter as f32,
+                diameter as f32,
+            );
+            GdipDeleteBrush(highlight_brush as *mut GpBrush);
+        }
+
         // Cleanup
         if !string_format.is_null() {
             GdipDeleteStringFormat(string_format);
@@ -641,6 +1578,310 @@ unsafe fn draw_workspace_dots_gdiplus(
     }
 }
 
+/// Draws the parked stash slots (e.g. `S1 S3`) just left of the clock.
+unsafe fn draw_stash_gdiplus(graphics: *mut GpGraphics, rect: &RECT, state: &StatusBarState) {
+    unsafe {
+        if state.parked_stash_slots.is_empty() {
+            return;
+        }
+
+        let font_family = create_font_family();
+        let font = create_font(font_family, 10.0);
+        let string_format = create_right_aligned_string_format();
+        let mut text_brush: *mut GpSolidFill = std::ptr::null_mut();
+
+        if !font.is_null()
+            && !string_format.is_null()
+            && GdipCreateSolidFill(gray_argb(0x88, state.theme), &mut text_brush).0 == 0
+        {
+            let label: Vec<u16> = state
+                .parked_stash_slots
+                .iter()
+                .map(|slot| format!("S{}", slot))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+
+            // The clock is right-aligned in its area and needs about half of it
+            let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
+                X: (rect.right - PADDING_RIGHT - state.clock_width + 50 - 60) as f32,
+                Y: (rect.top + PADDING_VERTICAL + 2) as f32,
+                Width: 60.0,
+                Height: DOT_DIAMETER as f32,
+            };
+
+            let _ = GdipDrawString(
+                graphics,
+                PCWSTR::from_raw(label.as_ptr()),
+                -1,
+                font,
+                &text_rect,
+                string_format,
+                text_brush as *mut GpBrush,
+            );
+            GdipDeleteBrush(text_brush as *mut GpBrush);
+        }
+
+        if !string_format.is_null() {
+            GdipDeleteStringFormat(string_format);
+        }
+        if !font.is_null() {
+            GdipDeleteFont(font);
+        }
+        if !font_family.is_null() {
+            GdipDeleteFontFamily(font_family);
+        }
+    }
+}
+
+/// Draws a button per window: a pill holding its icon and title, brighter
+/// for the focused one.
+unsafe fn draw_window_list_gdiplus(
+    graphics: *mut GpGraphics,
+    rect: &RECT,
+    buttons: &[WindowButton],
+    state: &StatusBarState,
+) {
+    unsafe {
+        if buttons.is_empty() {
+            return;
+        }
+
+        let width = window_button_width(buttons.len());
+        let font_family = create_font_family();
+        let font = create_font(font_family, 9.0);
+        let string_format = create_single_line_string_format();
+        let (ar, ag, ab) = split_color(state.accent_color);
+
+        for (index, button) in buttons.iter().enumerate() {
+            let x = rect.left + WINDOW_LIST_LEFT + index as i32 * width;
+            let y = rect.top + PADDING_VERTICAL;
+
+            let fill = if button.focused {
+                make_argb(160, ar, ag, ab)
+            } else {
+                match state.theme {
+                    Theme::Dark => make_argb(40, 0xFF, 0xFF, 0xFF),
+                    Theme::Light => make_argb(24, 0, 0, 0),
+                }
+            };
+            let mut brush: *mut GpSolidFill = std::ptr::null_mut();
+            if GdipCreateSolidFill(fill, &mut brush).0 == 0 {
+                let path = create_rounded_rect_path(
+                    x as f32,
+                    y as f32,
+                    (width - WINDOW_BUTTON_GAP) as f32,
+                    DOT_DIAMETER as f32,
+                    DOT_DIAMETER as f32 / 2.0,
+                );
+                if !path.is_null() {
+                    let _ = GdipFillPath(graphics, brush as *mut GpBrush, path);
+                    GdipDeletePath(path);
+                }
+                GdipDeleteBrush(brush as *mut GpBrush);
+            }
+
+            // The title makes room for the icon
+            let mut text_left = x + 8;
+            if let Some(icon) = state.icons.cached(button.hwnd) {
+                let size = WINDOW_ICON_SIZE;
+                draw_icon(graphics, icon, x + 6, y + (DOT_DIAMETER - size) / 2, size);
+                text_left += size;
+            }
+
+            if font.is_null() || string_format.is_null() {
+                continue;
+            }
+            let gray = if button.focused { 0xEE } else { 0xAA };
+            let mut text_brush: *mut GpSolidFill = std::ptr::null_mut();
+            if GdipCreateSolidFill(gray_argb(gray, state.theme), &mut text_brush).0 != 0 {
+                continue;
+            }
+            let title: Vec<u16> = button
+                .title
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+            let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
+                X: text_left as f32,
+                Y: y as f32,
+                Width: (x + width - WINDOW_BUTTON_GAP - 8 - text_left) as f32,
+                Height: DOT_DIAMETER as f32,
+            };
+            let _ = GdipDrawString(
+                graphics,
+                PCWSTR::from_raw(title.as_ptr()),
+                -1,
+                font,
+                &text_rect,
+                string_format,
+                text_brush as *mut GpBrush,
+            );
+            GdipDeleteBrush(text_brush as *mut GpBrush);
+        }
+
+        if !string_format.is_null() {
+            GdipDeleteStringFormat(string_format);
+        }
+        if !font.is_null() {
+            GdipDeleteFont(font);
+        }
+        if !font_family.is_null() {
+            GdipDeleteFontFamily(font_family);
+        }
+    }
+}
+
+/// Draws what's playing, brighter while it plays, and the volume after it.
+unsafe fn draw_media_gdiplus(
+    graphics: *mut GpGraphics,
+    rect: &RECT,
+    media: &MediaState,
+    clock_area: i32,
+    theme: Theme,
+) {
+    unsafe {
+        let font_family = create_font_family();
+        let font = create_font(font_family, 9.0);
+        let media_format = create_single_line_string_format();
+        let volume_format = create_right_aligned_string_format();
+
+        let left = rect.left + media_left(rect.right - rect.left, clock_area);
+        let playing = media.now_playing.as_ref().is_some_and(|now| now.playing);
+        let parts = [
+            (
+                media.media_label(),
+                left,
+                MEDIA_WIDTH - MEDIA_VOLUME_WIDTH - 8,
+                if playing { 0xDD } else { 0x88 },
+                media_format,
+            ),
+            (
+                media.volume_label(),
+                left + MEDIA_WIDTH - MEDIA_VOLUME_WIDTH,
+                MEDIA_VOLUME_WIDTH,
+                0xAA,
+                volume_format,
+            ),
+        ];
+
+        for (label, x, width, gray, format) in parts {
+            let Some(label) = label else {
+                continue;
+            };
+            if font.is_null() || format.is_null() {
+                continue;
+            }
+            let mut text_brush: *mut GpSolidFill = std::ptr::null_mut();
+            if GdipCreateSolidFill(gray_argb(gray, theme), &mut text_brush).0 != 0 {
+                continue;
+            }
+            let text: Vec<u16> = label.encode_utf16().chain(std::iter::once(0)).collect();
+            let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
+                X: x as f32,
+                Y: (rect.top + PADDING_VERTICAL) as f32,
+                Width: width as f32,
+                Height: DOT_DIAMETER as f32,
+            };
+            let _ = GdipDrawString(
+                graphics,
+                PCWSTR::from_raw(text.as_ptr()),
+                -1,
+                font,
+                &text_rect,
+                format,
+                text_brush as *mut GpBrush,
+            );
+            GdipDeleteBrush(text_brush as *mut GpBrush);
+        }
+
+        if !media_format.is_null() {
+            GdipDeleteStringFormat(media_format);
+        }
+        if !volume_format.is_null() {
+            GdipDeleteStringFormat(volume_format);
+        }
+        if !font.is_null() {
+            GdipDeleteFont(font);
+        }
+        if !font_family.is_null() {
+            GdipDeleteFontFamily(font_family);
+        }
+    }
+}
+
+/// Draws the tooltip: a rounded backdrop, the heading, then a window title per line.
+unsafe fn draw_tooltip_gdiplus(
+    graphics: *mut GpGraphics,
+    rect: &RECT,
+    lines: &[String],
+    accent_color: u32,
+    theme: Theme,
+) {
+    unsafe {
+        let (r, g, b) = split_color(bar_background(accent_color, theme));
+        let mut background: *mut GpSolidFill = std::ptr::null_mut();
+        if GdipCreateSolidFill(make_argb(245, r, g, b), &mut background).0 == 0 {
+            let path = create_rounded_rect_path(
+                rect.left as f32,
+                rect.top as f32,
+                (rect.right - rect.left) as f32,
+                (rect.bottom - rect.top) as f32,
+                6.0,
+            );
+            if !path.is_null() {
+                let _ = GdipFillPath(graphics, background as *mut GpBrush, path);
+                GdipDeletePath(path);
+            }
+            GdipDeleteBrush(background as *mut GpBrush);
+        }
+
+        let font_family = create_font_family();
+        let font = create_font(font_family, 9.0);
+        let string_format = create_single_line_string_format();
+
+        if !font.is_null() && !string_format.is_null() {
+            for (index, line) in lines.iter().enumerate() {
+                // The heading stands out from the titles below it
+                let gray = if index == 0 { 0xDD } else { 0xAA };
+                let mut text_brush: *mut GpSolidFill = std::ptr::null_mut();
+                if GdipCreateSolidFill(gray_argb(gray, theme), &mut text_brush).0 != 0 {
+                    continue;
+                }
+                let text: Vec<u16> = line.encode_utf16().chain(std::iter::once(0)).collect();
+                let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
+                    X: (rect.left + TOOLTIP_PADDING) as f32,
+                    Y: (rect.top + TOOLTIP_PADDING + index as i32 * TOOLTIP_LINE_HEIGHT) as f32,
+                    Width: (rect.right - rect.left - TOOLTIP_PADDING * 2) as f32,
+                    Height: TOOLTIP_LINE_HEIGHT as f32,
+                };
+                let _ = GdipDrawString(
+                    graphics,
+                    PCWSTR::from_raw(text.as_ptr()),
+                    -1,
+                    font,
+                    &text_rect,
+                    string_format,
+                    text_brush as *mut GpBrush,
+                );
+                GdipDeleteBrush(text_brush as *mut GpBrush);
+            }
+        }
+
+        if !string_format.is_null() {
+            GdipDeleteStringFormat(string_format);
+        }
+        if !font.is_null() {
+            GdipDeleteFont(font);
+        }
+        if !font_family.is_null() {
+            GdipDeleteFontFamily(font_family);
+        }
+    }
+}
+
 unsafe fn draw_time_gdiplus(graphics: *mut GpGraphics, rect: &RECT, state: &StatusBarState) {
     unsafe {
         if state.time_string.is_empty() {
@@ -649,7 +1890,7 @@ unsafe fn draw_time_gdiplus(graphics: *mut GpGraphics, rect: &RECT, state: &Stat
 
         // Create font for time display
         let font_family = create_font_family();
-        let font = create_font(font_family, 12.0);
+        let font = create_font(font_family, CLOCK_FONT_SIZE);
         let string_format = create_right_aligned_string_format();
 
         if font.is_null() || string_format.is_null() {
@@ -667,7 +1908,7 @@ unsafe fn draw_time_gdiplus(graphics: *mut GpGraphics, rect: &RECT, state: &Stat
 
         // Use a muted color for the time text
         let mut text_brush: *mut GpSolidFill = std::ptr::null_mut();
-        if GdipCreateSolidFill(make_argb(255, 0xAA, 0xAA, 0xAA), &mut text_brush).0 != 0 {
+        if GdipCreateSolidFill(gray_argb(0xAA, state.theme), &mut text_brush).0 != 0 {
             GdipDeleteStringFormat(string_format);
             GdipDeleteFont(font);
             GdipDeleteFontFamily(font_family);
@@ -682,9 +1923,9 @@ unsafe fn draw_time_gdiplus(graphics: *mut GpGraphics, rect: &RECT, state: &Stat
 
         // Position time at far right
         let text_rect = windows::Win32::Graphics::GdiPlus::RectF {
-            X: (rect.right - PADDING_RIGHT - 100) as f32,
+            X: (rect.right - PADDING_RIGHT - state.clock_width) as f32,
             Y: (rect.top + PADDING_VERTICAL) as f32,
-            Width: 100.0,
+            Width: state.clock_width as f32,
             Height: DOT_DIAMETER as f32,
         };
 
@@ -731,8 +1972,9 @@ unsafe fn create_font(
             return std::ptr::null_mut();
         }
         let mut font: *mut windows::Win32::Graphics::GdiPlus::GpFont = std::ptr::null_mut();
-        // FontStyleRegular = 0, UnitPoint = 3
-        let _ = GdipCreateFont(font_family, size, 0, Unit(3), &mut font);
+        // Points at 96 DPI, given in pixels so only the world transform scales text
+        // FontStyleRegular = 0, UnitPixel = 2
+        let _ = GdipCreateFont(font_family, size * 96.0 / 72.0, 0, Unit(2), &mut font);
         font
     }
 }
@@ -765,22 +2007,51 @@ unsafe fn create_right_aligned_string_format() -> *mut GpStringFormat {
     }
 }
 
-/// Creates a dimmed and desaturated version of the accent color for the background.
-fn dimmed_desaturated_background(accent_color: u32) -> u32 {
-    let (r, g, b) = split_color(accent_color);
+/// Creates a left-aligned format that keeps text on one line, ending long
+/// text with an ellipsis.
+unsafe fn create_single_line_string_format() -> *mut GpStringFormat {
+    unsafe {
+        let mut format: *mut GpStringFormat = std::ptr::null_mut();
+        if GdipCreateStringFormat(StringFormatFlagsNoWrap.0, 0, &mut format).0 != 0 {
+            return std::ptr::null_mut();
+        }
+        let _ = GdipSetStringFormatAlign(format, StringAlignmentNear);
+        let _ = GdipSetStringFormatLineAlign(format, StringAlignmentCenter);
+        let _ = GdipSetStringFormatTrimming(format, StringTrimmingEllipsisCharacter);
+        format
+    }
+}
 
-    // Convert to grayscale-ish by averaging with gray
-    let gray = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+/// Returns the bar's background: the accent color desaturated, then dimmed
+/// for the dark theme or washed out for the light one.
+fn bar_background(accent_color: u32, theme: Theme) -> u32 {
+    match theme {
+        Theme::Dark => dimmed_desaturated_background(accent_color),
+        Theme::Light => pale_desaturated_background(accent_color),
+    }
+}
 
-    // Blend towards gray (desaturate) and darken
-    let desaturate_factor = 0.6_f32; // More desaturation
-    let darken_factor = 0.35_f32; // Slightly darker
+/// Returns text `gray` as designed for the dark background, mirrored for
+/// the light one so it keeps its contrast.
+fn text_gray(gray: u8, theme: Theme) -> u8 {
+    match theme {
+        Theme::Dark => gray,
+        Theme::Light => 0xFF - gray,
+    }
+}
 
-    let dr = blend_channel(r, gray, desaturate_factor);
-    let dg = blend_channel(g, gray, desaturate_factor);
-    let db = blend_channel(b, gray, desaturate_factor);
+/// Returns an opaque GDI+ brush color for text `gray`.
+fn gray_argb(gray: u8, theme: Theme) -> u32 {
+    let gray = text_gray(gray, theme);
+    make_argb(255, gray, gray, gray)
+}
+
+/// Creates a dimmed and desaturated version of the accent color for the background.
+fn dimmed_desaturated_background(accent_color: u32) -> u32 {
+    let (dr, dg, db) = desaturated(accent_color);
 
     // Then darken
+    let darken_factor = 0.35_f32; // Slightly darker
     let fr = (dr as f32 * darken_factor) as u8;
     let fg = (dg as f32 * darken_factor) as u8;
     let fb = (db as f32 * darken_factor) as u8;
@@ -788,12 +2059,42 @@ fn dimmed_desaturated_background(accent_color: u32) -> u32 {
     compose_color(fr, fg, fb)
 }
 
+/// Creates a washed out, desaturated version of the accent color for the
+/// light theme's background.
+fn pale_desaturated_background(accent_color: u32) -> u32 {
+    let (dr, dg, db) = desaturated(accent_color);
+    compose_color(
+        blend_channel(dr, 0xFF, 0.15),
+        blend_channel(dg, 0xFF, 0.15),
+        blend_channel(db, 0xFF, 0.15),
+    )
+}
+
+/// Blends the accent color's channels towards their average.
+fn desaturated(accent_color: u32) -> (u8, u8, u8) {
+    let (r, g, b) = split_color(accent_color);
+
+    // Convert to grayscale-ish by averaging with gray
+    let gray = ((r as u32 + g as u32 + b as u32) / 3) as u8;
+
+    // Blend towards gray (desaturate)
+    let desaturate_factor = 0.6_f32; // More desaturation
+    (
+        blend_channel(r, gray, desaturate_factor),
+        blend_channel(g, gray, desaturate_factor),
+        blend_channel(b, gray, desaturate_factor),
+    )
+}
+
 /// Creates a semi-transparent looking dot color for inactive workspaces.
-fn semi_transparent_dot_color(accent_color: u32) -> u32 {
+fn semi_transparent_dot_color(accent_color: u32, theme: Theme) -> u32 {
     let (r, g, b) = split_color(accent_color);
 
-    // Blend with a lighter gray to simulate transparency
-    let target = 190u8;
+    // Blend with a gray lighter or darker than the background to simulate transparency
+    let target = match theme {
+        Theme::Dark => 190u8,
+        Theme::Light => 150u8,
+    };
     compose_color(
         blend_channel(r, target, 0.25),
         blend_channel(g, target, 0.25),
@@ -821,7 +2122,6 @@ fn make_argb(a: u8, r: u8, g: u8, b: u8) -> u32 {
     ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
 }
 
-#[allow(dead_code)]
 unsafe fn get_state_ptr(hwnd: HWND) -> *mut StatusBarState {
     unsafe {
         let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
@@ -832,3 +2132,139 @@ unsafe fn get_state_ptr(hwnd: HWND) -> *mut StatusBarState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hovering_a_dot_lists_its_workspace_windows() {
+        let shown = [1, 2, 3, 7];
+        let (spacing, _) = dot_layout(shown.len());
+        let y = PADDING_VERTICAL + DOT_DIAMETER / 2;
+        assert_eq!(workspace_at(&shown, PADDING_LEFT + 2, y), Some(1));
+        assert_eq!(
+            workspace_at(&shown, PADDING_LEFT + spacing * 3 + 2, y),
+            Some(7)
+        );
+        assert_eq!(
+            workspace_at(&shown, PADDING_LEFT + spacing * 4 + 2, y),
+            None
+        );
+        assert_eq!(workspace_at(&shown, PADDING_LEFT + 2, 0), None);
+        assert_eq!(workspace_at(&shown, 0, y), None);
+
+        assert_eq!(tooltip_lines(2, &[]), vec!["Workspace 2", "No windows"]);
+        let titles: Vec<String> = (1..=20).map(|i| format!("Window {}", i)).collect();
+        let lines = tooltip_lines(1, &titles);
+        assert_eq!(lines.len(), TOOLTIP_MAX_LINES);
+        assert_eq!(lines[1], "Window 1");
+        assert_eq!(lines.last().unwrap(), "+10 more");
+    }
+
+    #[test]
+    fn window_buttons_widen_the_bar_and_shrink_to_fit() {
+        let clock = clock_area(CLOCK_WIDTH, true);
+        assert_eq!(bar_width(0, false, clock), STATUSBAR_WIDTH);
+        // Dots only
+        assert_eq!(
+            bar_width(0, false, clock_area(CLOCK_WIDTH, false)),
+            STATUSBAR_WIDTH - STASH_AREA_WIDTH
+        );
+        assert_eq!(
+            bar_width(2, false, clock),
+            STATUSBAR_WIDTH + 2 * WINDOW_BUTTON_WIDTH
+        );
+        assert!(bar_width(10, false, clock) <= STATUSBAR_WIDTH + WINDOW_LIST_MAX_WIDTH);
+
+        let y = PADDING_VERTICAL + 1;
+        let width = window_button_width(3);
+        assert_eq!(window_button_at(3, WINDOW_LIST_LEFT, y), Some(0));
+        assert_eq!(
+            window_button_at(3, WINDOW_LIST_LEFT + width * 2 + 1, y),
+            Some(2)
+        );
+        assert_eq!(
+            window_button_at(3, WINDOW_LIST_LEFT + width * 3 + 1, y),
+            None
+        );
+        // The gap between buttons belongs to neither
+        assert_eq!(window_button_at(3, WINDOW_LIST_LEFT + width - 1, y), None);
+        assert_eq!(window_button_at(3, WINDOW_LIST_LEFT - 1, y), None);
+    }
+
+    #[test]
+    fn clicks_at_high_dpi_land_on_the_same_button() {
+        assert_eq!(scaled(STATUSBAR_HEIGHT, 1.0), STATUSBAR_HEIGHT);
+        assert_eq!(scaled(STATUSBAR_HEIGHT, 1.5), 51);
+        assert_eq!(scaled(STATUSBAR_WIDTH, 2.0), 2 * STATUSBAR_WIDTH);
+
+        for scale in [1.0, 1.25, 1.5, 2.0] {
+            let y = scaled(PADDING_VERTICAL + 1, scale);
+            let first = scaled(WINDOW_LIST_LEFT, scale);
+            let second = scaled(WINDOW_LIST_LEFT + window_button_width(3) + 1, scale);
+            let (x, bar_y) = unscaled(first, y, scale);
+            assert_eq!(window_button_at(3, x, bar_y), Some(0), "at {}", scale);
+            let (x, bar_y) = unscaled(second, y, scale);
+            assert_eq!(window_button_at(3, x, bar_y), Some(1), "at {}", scale);
+        }
+    }
+
+    #[test]
+    fn transitions_ease_towards_their_target() {
+        let mut dot = Transition::at(0.0);
+        assert!(dot.is_done());
+        dot.go_to(3.0, true);
+        assert_eq!(dot.value_after(Duration::ZERO), 0.0);
+        let halfway = dot.value_after(ANIMATION_DURATION / 2);
+        assert!(halfway > 1.5 && halfway < 3.0, "{}", halfway);
+        assert_eq!(dot.value_after(ANIMATION_DURATION * 2), 3.0);
+
+        // Without animations the value jumps
+        dot.go_to(1.0, false);
+        assert_eq!(dot.value(), 1.0);
+        assert!(dot.is_done());
+    }
+
+    #[test]
+    fn the_light_theme_flips_the_palette() {
+        let accent = 0x00D77800;
+        let brightness = |color: u32| {
+            let (r, g, b) = split_color(color);
+            r as u32 + g as u32 + b as u32
+        };
+        let dark = bar_background(accent, Theme::Dark);
+        let light = bar_background(accent, Theme::Light);
+        assert_eq!(dark, dimmed_desaturated_background(accent));
+        assert!(brightness(light) > 3 * 0xE0, "{:#08x}", light);
+        assert!(brightness(dark) < 3 * 0x40, "{:#08x}", dark);
+
+        // Text keeps its contrast against either background
+        assert_eq!(text_gray(0xAA, Theme::Dark), 0xAA);
+        assert_eq!(text_gray(0xAA, Theme::Light), 0x55);
+        assert_eq!(text_gray(0xEE, Theme::Light), 0x11);
+    }
+
+    #[test]
+    fn the_media_module_sits_before_the_clock() {
+        let clock = clock_area(CLOCK_WIDTH + 60, true);
+        let width = bar_width(2, true, clock);
+        assert_eq!(
+            width,
+            bar_width(2, false, clock_area(CLOCK_WIDTH, true)) + MEDIA_WIDTH + 60
+        );
+
+        let y = PADDING_VERTICAL + 1;
+        let left = media_left(width, clock);
+        assert_eq!(media_part_at(width, clock, left, y), Some(MediaPart::Media));
+        assert_eq!(
+            media_part_at(width, clock, left + MEDIA_WIDTH - 1, y),
+            Some(MediaPart::Volume)
+        );
+        assert_eq!(media_part_at(width, clock, left + MEDIA_WIDTH, y), None);
+        assert_eq!(media_part_at(width, clock, left, 0), None);
+        // Clear of the window list and of the wider clock
+        assert!(left >= WINDOW_LIST_LEFT + 2 * window_button_width(2));
+        assert!(left + MEDIA_WIDTH <= width - PADDING_RIGHT - clock);
+    }
+}
@@ -0,0 +1,237 @@
+//! Opt-in performance counters.
+//!
+//! Counters are process-wide atomics so they can be bumped from the WinEvent
+//! hook and worker threads alike. Recording is a no-op until [`enable`] is
+//! called (`--telemetry` on the command line).
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+static WINDOW_MOVES: AtomicU64 = AtomicU64::new(0);
+static LAYOUT: Timer = Timer::new();
+static POSITIONING: Timer = Timer::new();
+
+/// Accumulated call count and duration for one code path.
+struct Timer {
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl Timer {
+    const fn new() -> Self {
+        Timer {
+            count: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+            max_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> TimerSnapshot {
+        TimerSnapshot {
+            count: self.count.load(Ordering::Relaxed),
+            total_micros: self.total_micros.load(Ordering::Relaxed),
+            max_micros: self.max_micros.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Turns on counter recording.
+pub fn enable() {
+    STARTED.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns true if telemetry was enabled for this run.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Counts one event pushed to the main event queue.
+pub fn record_event() {
+    if is_enabled() {
+        EVENTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts windows moved with `SetWindowPos` or `DeferWindowPos`.
+pub fn record_window_moves(count: usize) {
+    if is_enabled() {
+        WINDOW_MOVES.fetch_add(count as u64, Ordering::Relaxed);
+    }
+}
+
+/// Records how long a layout calculation took.
+pub fn record_layout(elapsed: Duration) {
+    if is_enabled() {
+        LAYOUT.record(elapsed);
+    }
+}
+
+/// Records how long applying a layout to the actual windows took.
+pub fn record_positioning(elapsed: Duration) {
+    if is_enabled() {
+        POSITIONING.record(elapsed);
+    }
+}
+
+/// Point-in-time copy of a [`Timer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimerSnapshot {
+    pub count: u64,
+    pub total_micros: u64,
+    /// Longest single call since startup.
+    pub max_micros: u64,
+}
+
+impl TimerSnapshot {
+    fn since(&self, earlier: &TimerSnapshot) -> TimerSnapshot {
+        TimerSnapshot {
+            count: self.count.saturating_sub(earlier.count),
+            total_micros: self.total_micros.saturating_sub(earlier.total_micros),
+            max_micros: self.max_micros,
+        }
+    }
+
+    fn format(&self) -> String {
+        let avg_ms = if self.count == 0 {
+            0.0
+        } else {
+            self.total_micros as f64 / self.count as f64 / 1000.0
+        };
+        format!(
+            "{} (avg {:.2}ms, max {:.2}ms)",
+            self.count,
+            avg_ms,
+            self.max_micros as f64 / 1000.0
+        )
+    }
+}
+
+/// Point-in-time copy of all counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Snapshot {
+    pub events: u64,
+    pub window_moves: u64,
+    pub layout: TimerSnapshot,
+    pub positioning: TimerSnapshot,
+}
+
+impl Snapshot {
+    /// Returns the counter deltas between `earlier` and this snapshot.
+    pub fn since(&self, earlier: &Snapshot) -> Snapshot {
+        Snapshot {
+            events: self.events.saturating_sub(earlier.events),
+            window_moves: self.window_moves.saturating_sub(earlier.window_moves),
+            layout: self.layout.since(&earlier.layout),
+            positioning: self.positioning.since(&earlier.positioning),
+        }
+    }
+
+    /// Formats the counters as a single line, with rates over `elapsed`.
+    pub fn report(&self, elapsed: Duration) -> String {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        format!(
+            "events: {} ({:.1}/s), window moves: {} ({:.1}/s), layouts: {}, positioning: {}",
+            self.events,
+            self.events as f64 / secs,
+            self.window_moves,
+            self.window_moves as f64 / secs,
+            self.layout.format(),
+            self.positioning.format()
+        )
+    }
+}
+
+/// Captures the current counter values.
+pub fn snapshot() -> Snapshot {
+    Snapshot {
+        events: EVENTS.load(Ordering::Relaxed),
+        window_moves: WINDOW_MOVES.load(Ordering::Relaxed),
+        layout: LAYOUT.snapshot(),
+        positioning: POSITIONING.snapshot(),
+    }
+}
+
+/// Returns a report of all counters since startup, as served over IPC.
+pub fn report() -> String {
+    match STARTED.get() {
+        Some(started) if is_enabled() => {
+            let uptime = started.elapsed();
+            format!(
+                "uptime: {}s, {}",
+                uptime.as_secs(),
+                snapshot().report(uptime)
+            )
+        }
+        _ => "telemetry is disabled; start megatile with --telemetry".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_delta_keeps_all_time_max() {
+        let earlier = Snapshot {
+            events: 10,
+            window_moves: 4,
+            layout: TimerSnapshot {
+                count: 2,
+                total_micros: 3000,
+                max_micros: 2000,
+            },
+            positioning: TimerSnapshot::default(),
+        };
+        let later = Snapshot {
+            events: 30,
+            window_moves: 10,
+            layout: TimerSnapshot {
+                count: 4,
+                total_micros: 7000,
+                max_micros: 2500,
+            },
+            positioning: TimerSnapshot::default(),
+        };
+
+        let delta = later.since(&earlier);
+        assert_eq!(delta.events, 20);
+        assert_eq!(delta.window_moves, 6);
+        assert_eq!(delta.layout.count, 2);
+        assert_eq!(delta.layout.total_micros, 4000);
+        assert_eq!(delta.layout.max_micros, 2500);
+    }
+
+    #[test]
+    fn report_includes_rates_and_averages() {
+        let snapshot = Snapshot {
+            events: 50,
+            window_moves: 20,
+            layout: TimerSnapshot {
+                count: 4,
+                total_micros: 2000,
+                max_micros: 900,
+            },
+            positioning: TimerSnapshot::default(),
+        };
+
+        let report = snapshot.report(Duration::from_secs(10));
+        assert!(report.contains("events: 50 (5.0/s)"));
+        assert!(report.contains("window moves: 20 (2.0/s)"));
+        assert!(report.contains("layouts: 4 (avg 0.50ms, max 0.90ms)"));
+        assert!(report.contains("positioning: 0 (avg 0.00ms, max 0.00ms)"));
+    }
+}
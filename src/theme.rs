@@ -0,0 +1,76 @@
+//! The Windows light/dark app theme.
+//!
+//! Read from the `AppsUseLightTheme` value the Settings app flips, and used
+//! to pick the status bar's colors and the shade of the focused border.
+//! Windows announces a flip with `WM_SETTINGCHANGE("ImmersiveColorSet")`.
+
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+use windows::core::{PCWSTR, w};
+
+const PERSONALIZE_KEY: PCWSTR = w!(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize");
+const LIGHT_THEME_VALUE: PCWSTR = w!("AppsUseLightTheme");
+
+/// Whether apps are drawn light or dark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    /// How Megatile looked before it followed the theme.
+    #[default]
+    Dark,
+}
+
+impl Theme {
+    /// Returns the app theme Windows is set to, dark if it can't be read.
+    pub fn current() -> Self {
+        let mut light = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                PERSONALIZE_KEY,
+                LIGHT_THEME_VALUE,
+                RRF_RT_REG_DWORD,
+                None,
+                Some(&mut light as *mut u32 as *mut std::ffi::c_void),
+                Some(&mut size),
+            )
+        };
+        if status == ERROR_SUCCESS && light != 0 {
+            Theme::Light
+        } else {
+            Theme::Dark
+        }
+    }
+
+    /// Returns the focused window border for `accent_color` (0x00BBGGRR):
+    /// a lighter shade on dark themes and a darker one on light themes, as
+    /// Windows' own accent palette does, so it stands out from title bars.
+    pub fn border_color(self, accent_color: u32) -> u32 {
+        let channels = [0, 8, 16].map(|shift| ((accent_color >> shift) & 0xFF) as f32);
+        let shaded = channels.map(|channel| match self {
+            Theme::Dark => channel + (255.0 - channel) * 0.3,
+            Theme::Light => channel * 0.75,
+        });
+        shaded
+            .iter()
+            .zip([0, 8, 16])
+            .fold(0, |color, (channel, shift)| {
+                color | (channel.round() as u32) << shift
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borders_are_shaded_away_from_the_theme() {
+        let accent = 0x00D77800; // Windows' default blue
+        assert_eq!(Theme::Light.border_color(accent), 0x00A15A00);
+        assert_eq!(Theme::Dark.border_color(accent), 0x00E3A14D);
+        assert_eq!(Theme::Dark.border_color(0x00FFFFFF), 0x00FFFFFF);
+        assert_eq!(Theme::Light.border_color(0), 0);
+    }
+}
@@ -3,14 +3,147 @@
 //! This module implements a dwindle-style tiling algorithm where windows
 //! are recursively split into halves, alternating between horizontal
 //! and vertical splits based on the available space aspect ratio.
+//! Monitors can instead use a [`TileLayout::Stack`] of equal rows, which is
+//! the default on portrait monitors.
+//!
+//! The tiler itself only deals in rects and window ids; [`DwindleTiler::tile_windows`]
+//! is the adapter that feeds it a monitor's windows.
 
+use crate::config::BarPosition;
 use crate::statusbar::STATUSBAR_VERTICAL_RESERVE;
 use crate::workspace::{Monitor, Window};
 use log::debug;
+use serde::{Deserialize, Serialize};
 use windows::Win32::Foundation::RECT;
 
+/// Smallest share of a tile either side of a split may take.
+pub const MIN_SPLIT_RATIO: f32 = 0.1;
+/// Largest share of a tile either side of a split may take.
+pub const MAX_SPLIT_RATIO: f32 = 0.9;
+
+/// Default gap in pixels between tiled windows.
+pub const DEFAULT_GAP: i32 = 4;
+/// Default padding in pixels between the tiles and the monitor edges.
+pub const DEFAULT_PADDING: i32 = 2;
+
+/// Calculates the usable work area for tiling on a monitor with the given bounds.
+pub fn work_area(monitor_rect: RECT) -> RECT {
+    padded_work_area(monitor_rect, DEFAULT_PADDING)
+}
+
+/// Calculates the work area with `padding` pixels at the monitor edges.
+pub fn padded_work_area(monitor_rect: RECT, padding: i32) -> RECT {
+    bar_work_area(
+        monitor_rect,
+        padding,
+        STATUSBAR_VERTICAL_RESERVE,
+        BarPosition::Top,
+    )
+}
+
+/// Calculates the work area with `padding` pixels at the monitor edges and
+/// `bar_reserve` pixels at the `position` edge for the status bar (0 without
+/// one).
+pub fn bar_work_area(
+    monitor_rect: RECT,
+    padding: i32,
+    bar_reserve: i32,
+    position: BarPosition,
+) -> RECT {
+    let bar_edge = if bar_reserve > 0 {
+        // The status bar reserve already covers the default padding at its edge
+        bar_reserve + (padding - DEFAULT_PADDING).max(0)
+    } else {
+        padding
+    };
+    let (top, bottom) = match position {
+        BarPosition::Top => (bar_edge, padding),
+        BarPosition::Bottom => (padding, bar_edge),
+    };
+    let mut rect = monitor_rect;
+    rect.left += padding;
+    rect.top += top;
+    rect.right -= padding;
+    rect.bottom -= bottom;
+    if rect.left > rect.right {
+        rect.left = rect.right;
+    }
+    if rect.top > rect.bottom {
+        rect.top = rect.bottom;
+    }
+    rect
+}
+
+/// Centers a rect `width_ratio` of the work area wide, using the full height.
+pub fn zen_rect(area: RECT, width_ratio: f32) -> RECT {
+    let width = area.right - area.left;
+    let zen_width = (width as f32 * width_ratio.clamp(0.0, 1.0)) as i32;
+    let left = area.left + (width - zen_width) / 2;
+    RECT {
+        left,
+        top: area.top,
+        right: left + zen_width,
+        bottom: area.bottom,
+    }
+}
+
+/// Returns `rect` centered over `anchor`, then shifted (and shrunk if
+/// needed) to stay within `bounds`.
+pub fn center_rect(rect: RECT, anchor: RECT, bounds: RECT) -> RECT {
+    let width = (rect.right - rect.left).min(bounds.right - bounds.left);
+    let height = (rect.bottom - rect.top).min(bounds.bottom - bounds.top);
+    let left = (anchor.left + (anchor.right - anchor.left - width) / 2)
+        .clamp(bounds.left, bounds.right - width);
+    let top = (anchor.top + (anchor.bottom - anchor.top - height) / 2)
+        .clamp(bounds.top, bounds.bottom - height);
+    RECT {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// Returns `rect` moved from `from` to `to`, keeping its offset from the
+/// top-left corner, then shifted (and shrunk if needed) to stay within `to`.
+pub fn carry_rect(rect: RECT, from: RECT, to: RECT) -> RECT {
+    let width = (rect.right - rect.left).min(to.right - to.left);
+    let height = (rect.bottom - rect.top).min(to.bottom - to.top);
+    let left = (rect.left - from.left + to.left).clamp(to.left, to.right - width);
+    let top = (rect.top - from.top + to.top).clamp(to.top, to.bottom - height);
+    RECT {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// How a monitor arranges its tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TileLayout {
+    /// Split by aspect ratio, alternating directions.
+    #[default]
+    Dwindle,
+    /// Equal-height rows, one window each. The default for portrait monitors.
+    Stack,
+}
+
+impl TileLayout {
+    /// Layout to use for a monitor with no configured override.
+    pub fn default_for(rect: RECT) -> Self {
+        if rect.bottom - rect.top > rect.right - rect.left {
+            TileLayout::Stack
+        } else {
+            TileLayout::Dwindle
+        }
+    }
+}
+
 /// Direction of a tile split.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SplitDirection {
     /// Split into top and bottom regions.
     Horizontal,
@@ -47,116 +180,212 @@ impl Tile {
     pub fn is_leaf(&self) -> bool {
         self.children.is_none()
     }
+
+    /// Returns the split directly above the leaf holding `hwnd`, and whether
+    /// that leaf is the split's first child.
+    pub fn parent_split_mut(&mut self, hwnd: isize) -> Option<(&mut Tile, bool)> {
+        let holds = |tile: &Tile| tile.is_leaf() && tile.windows.contains(&hwnd);
+        let is_first = match self.children.as_ref() {
+            Some(children) if holds(&children.0) => Some(true),
+            Some(children) if holds(&children.1) => Some(false),
+            _ => None,
+        };
+        if let Some(is_first) = is_first {
+            return Some((self, is_first));
+        }
+        let children = self.children.as_mut()?;
+        match children.0.parent_split_mut(hwnd) {
+            Some(found) => Some(found),
+            None => children.1.parent_split_mut(hwnd),
+        }
+    }
+
+    /// Hands `old`'s leaf to `new`, keeping its place in the tree. Returns
+    /// false if no leaf holds `old`.
+    pub fn replace_window(&mut self, old: isize, new: isize) -> bool {
+        match self.children.as_mut() {
+            Some(children) => {
+                children.0.replace_window(old, new) || children.1.replace_window(old, new)
+            }
+            None => match self.windows.iter_mut().find(|hwnd| **hwnd == old) {
+                Some(hwnd) => {
+                    *hwnd = new;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
 }
 
 /// Implements the dwindle tiling algorithm.
 ///
 /// Windows are placed by recursively splitting the available space,
 /// alternating between horizontal and vertical splits based on aspect ratio.
+#[derive(Debug, Clone, Copy)]
 pub struct DwindleTiler {
     /// Gap in pixels between tiled windows.
     gap: i32,
+    /// Padding in pixels at the monitor edges.
+    padding: i32,
+    /// Pixels kept at the bar's edge of each monitor for the status bar.
+    bar_reserve: i32,
+    /// Which edge `bar_reserve` is kept at.
+    bar_position: BarPosition,
+    /// Dwindle or stacked rows; set per monitor by [`DwindleTiler::tile_windows`].
+    layout: TileLayout,
 }
 
 impl DwindleTiler {
     /// Creates a new tiler with the specified gap between windows.
     pub fn new(gap: i32) -> Self {
-        DwindleTiler { gap }
+        DwindleTiler {
+            gap,
+            padding: DEFAULT_PADDING,
+            bar_reserve: STATUSBAR_VERTICAL_RESERVE,
+            bar_position: BarPosition::Top,
+            layout: TileLayout::Dwindle,
+        }
+    }
+
+    /// Returns this tiler with `padding` pixels at the monitor edges.
+    pub fn with_padding(self, padding: i32) -> Self {
+        DwindleTiler { padding, ..self }
+    }
+
+    /// Returns this tiler keeping `bar_reserve` pixels at the top for the
+    /// status bar instead of the built-in bar's height.
+    pub fn with_bar_reserve(self, bar_reserve: i32) -> Self {
+        DwindleTiler {
+            bar_reserve,
+            ..self
+        }
+    }
+
+    /// Returns this tiler keeping the bar reserve at the `bar_position` edge.
+    pub fn with_bar_position(self, bar_position: BarPosition) -> Self {
+        DwindleTiler {
+            bar_position,
+            ..self
+        }
+    }
+
+    /// Calculates the area tiles are placed in on a monitor with the given bounds.
+    pub fn work_area(&self, monitor_rect: RECT) -> RECT {
+        bar_work_area(
+            monitor_rect,
+            self.padding,
+            self.bar_reserve,
+            self.bar_position,
+        )
+    }
+
+    /// Returns this tiler using `layout` instead.
+    pub fn with_layout(self, layout: TileLayout) -> Self {
+        DwindleTiler { layout, ..self }
     }
 
     /// Calculates and applies tiling layout to windows on a monitor.
     ///
-    /// Reuses the existing layout tree if possible, otherwise creates a new one.
+    /// Thin adapter over [`DwindleTiler::layout`]: collects the tiled window
+    /// handles, computes their rects in the monitor work area and writes them back.
+    /// New windows split the `focused` window's tile. Windows in a tile group
+    /// get the rect of the window holding the group's tile.
     pub fn tile_windows(
         &self,
         monitor: &Monitor,
-        layout_tree: &mut Option<crate::tiling::Tile>,
+        layout_tree: &mut Option<Tile>,
         windows: &mut [Window],
+        focused: Option<isize>,
     ) {
-        use log::debug;
-
-        let active_workspace = monitor.get_active_workspace();
+        let is_tiled = |w: &Window| w.workspace > 0 && w.is_tiled;
+        let tiled_windows: Vec<isize> = windows
+            .iter()
+            .filter(|w| is_tiled(w))
+            .filter(|w| {
+                w.tile_group
+                    .is_none_or(|host| !windows.iter().any(|h| h.hwnd == host && is_tiled(h)))
+            })
+            .map(|w| w.hwnd)
+            .collect();
         debug!(
-            "Tiling active workspace: {:?} on monitor with rect {:?}",
-            active_workspace, monitor.rect
+            "Tiling {} windows on monitor with rect {:?}",
+            tiled_windows.len(),
+            monitor.rect
         );
-        let window_count = active_workspace.window_count();
-        debug!("Window count in workspace: {}", window_count);
 
-        if window_count == 0 {
+        if tiled_windows.is_empty() {
             debug!("No windows to tile, returning");
             return;
         }
 
-        // Get monitor work area (usable space)
-        let work_rect = self.get_work_area(monitor);
+        let work_rect = self.work_area(monitor.rect);
         debug!("Work area rect: {:?}", work_rect);
 
-        // Check if we can reuse existing layout_tree
-        let tiled_windows: Vec<_> = windows
-            .iter()
-            .filter(|w| w.workspace > 0 && w.is_tiled)
-            .map(|w| w.hwnd)
-            .collect();
-
-        if let Some(existing_tree) = layout_tree.as_ref()
-            && self.can_reuse_layout(existing_tree, &tiled_windows)
-        {
-            debug!("Reusing existing layout tree");
-            let mut updated_tree = existing_tree.clone();
-            updated_tree.rect = work_rect;
-            self.update_tree_rects(&mut updated_tree);
-            self.apply_tile_positions(&updated_tree, windows);
-            debug!("Applied positions from existing layout");
-            return;
+        let tiler = self.with_layout(monitor.layout);
+        // A focused group member stands for its group's tile
+        let focused = focused.map(|hwnd| {
+            windows
+                .iter()
+                .find(|w| w.hwnd == hwnd)
+                .and_then(|w| w.tile_group)
+                .filter(|host| tiled_windows.contains(host))
+                .unwrap_or(hwnd)
+        });
+        for (hwnd, rect) in tiler.layout(work_rect, &tiled_windows, layout_tree, focused) {
+            for window in windows
+                .iter_mut()
+                .filter(|w| w.hwnd == hwnd || (w.is_tiled && w.tile_group == Some(hwnd)))
+            {
+                debug!("Setting window hwnd={:?} to rect {:?}", window.hwnd, rect);
+                window.rect = rect;
+            }
         }
+    }
 
-        // Create new layout tree
-        debug!("Creating new layout tree");
-        let mut root_tile = Tile::new(work_rect);
-        debug!("Created initial root tile with rect {:?}", work_rect);
-
-        // Distribute windows across tiles using Dwindle algorithm
-        debug!("Starting window distribution across tiles");
-        self.distribute_windows(&mut root_tile, windows);
-
-        debug!("Window distribution completed");
-
-        // Store the layout tree for future reuse
-        *layout_tree = Some(root_tile.clone());
+    /// Computes tile rects for `ids` inside `area`.
+    ///
+    /// Updates `layout_tree` in place when it shares windows with `ids`, so
+    /// adjusted split ratios and directions survive windows coming and going:
+    /// a closed window's tile is merged into its sibling, and a new window
+    /// splits the `focused` window's tile (or its neighbor's in `ids` order,
+    /// if it isn't last). Otherwise, and always for stacks, a fresh tree is
+    /// built and stored. Returns one `(id, rect)` pair per id.
+    pub fn layout(
+        &self,
+        area: RECT,
+        ids: &[isize],
+        layout_tree: &mut Option<Tile>,
+        focused: Option<isize>,
+    ) -> Vec<(isize, RECT)> {
+        let mut positions = Vec::with_capacity(ids.len());
+        if ids.is_empty() {
+            return positions;
+        }
 
-        // Apply tile positions to windows
-        debug!("Applying calculated tile positions to windows");
-        self.apply_tile_positions(&root_tile, windows);
+        if let Some(existing_tree) = layout_tree.as_mut()
+            && self.can_update_layout(existing_tree, ids)
+        {
+            debug!("Updating existing layout tree");
+            existing_tree.rect = area;
+            self.update_tree_rects(existing_tree);
+            self.reconcile_tree(existing_tree, ids, focused);
+            self.collect_positions(existing_tree, &mut positions);
+            return positions;
+        }
 
-        debug!("Tile positioning completed for {} windows", window_count);
-    }
+        debug!("Creating new layout tree with rect {:?}", area);
+        let mut root_tile = Tile::new(area);
+        self.distribute_windows(&mut root_tile, ids);
+        self.collect_positions(&root_tile, &mut positions);
+        *layout_tree = Some(root_tile);
 
-    /// Calculates the usable work area for tiling on a monitor.
-    fn get_work_area(&self, monitor: &Monitor) -> RECT {
-        // For now, use full monitor rect
-        let mut rect = monitor.rect;
-        // Add minimal gap padding - use smaller gaps at edges for tighter layout
-        let edge_gap = 2; // Minimal edge gap
-        rect.left += edge_gap;
-        rect.top += STATUSBAR_VERTICAL_RESERVE; // No extra gap, status bar reserve is enough
-        rect.right -= edge_gap;
-        rect.bottom -= edge_gap; // Minimal gap at bottom
-        if rect.top > rect.bottom {
-            rect.top = rect.bottom;
-        }
-        rect
+        debug!("Tile positioning completed for {} windows", ids.len());
+        positions
     }
 
     /// Assigns windows to the tile tree and triggers recursive splitting.
-    fn distribute_windows(&self, tile: &mut Tile, windows: &[Window]) {
-        // Count active windows and collect their hwnds
-        let window_hwnds: Vec<isize> = windows
-            .iter()
-            .filter(|w| w.workspace > 0 && w.is_tiled)
-            .map(|w| w.hwnd)
-            .collect();
-
+    fn distribute_windows(&self, tile: &mut Tile, window_hwnds: &[isize]) {
         debug!("Distributing {} windows across tiles", window_hwnds.len());
         debug!("Window hwnds to distribute: {:?}", window_hwnds);
 
@@ -166,7 +395,7 @@ impl DwindleTiler {
         }
 
         // Assign all windows to root tile initially
-        tile.windows = window_hwnds;
+        tile.windows = window_hwnds.to_vec();
         debug!("Assigned all {} windows to root tile", tile.windows.len());
 
         // Recursively split tiles
@@ -195,7 +424,18 @@ impl DwindleTiler {
         let tile_height = tile.rect.bottom - tile.rect.top;
         debug!("Tile dimensions: {}x{}", tile_width, tile_height);
 
-        let split_direction = if tile_width > tile_height {
+        // Stacks peel one window off the top at a time, sized so all rows are equal
+        let split_point = if self.layout == TileLayout::Stack {
+            tile.split_ratio = (1.0 / window_count as f32).max(MIN_SPLIT_RATIO);
+            1
+        } else {
+            window_count / 2
+        };
+
+        let split_direction = if self.layout == TileLayout::Stack {
+            debug!("Splitting horizontally (stack layout)");
+            SplitDirection::Horizontal
+        } else if tile_width > tile_height {
             debug!("Splitting vertically (width > height)");
             SplitDirection::Vertical
         } else {
@@ -206,7 +446,6 @@ impl DwindleTiler {
         tile.split_direction = Some(split_direction);
 
         // Split windows between children
-        let split_point = window_count / 2;
         let left_windows = tile.windows[..split_point].to_vec();
         let right_windows = tile.windows[split_point..].to_vec();
 
@@ -251,6 +490,7 @@ impl DwindleTiler {
     fn split_rect(&self, rect: &RECT, direction: SplitDirection, ratio: f32) -> (RECT, RECT) {
         let gap = self.gap;
         let mid_gap = gap / 2;
+        let ratio = ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
         debug!(
             "Splitting rect {:?} in direction {:?} with gap {}",
             rect, direction, gap
@@ -297,15 +537,135 @@ impl DwindleTiler {
         }
     }
 
-    /// Checks if an existing layout tree can be reused for the current windows.
-    fn can_reuse_layout(&self, tile: &Tile, tiled_windows: &[isize]) -> bool {
+    /// Checks if an existing layout tree can be updated for the current windows.
+    ///
+    /// Stacks keep equal rows, so they are only reused for the same windows.
+    fn can_update_layout(&self, tile: &Tile, tiled_windows: &[isize]) -> bool {
         let tile_windows: Vec<_> = self
             .collect_tile_windows(tile)
             .into_iter()
             .flatten()
             .collect();
-        tile_windows.len() == tiled_windows.len()
-            && tile_windows.iter().all(|hwnd| tiled_windows.contains(hwnd))
+        if self.layout == TileLayout::Stack {
+            return tile_windows.len() == tiled_windows.len()
+                && tile_windows.iter().all(|hwnd| tiled_windows.contains(hwnd));
+        }
+        tile_windows.iter().any(|hwnd| tiled_windows.contains(hwnd))
+    }
+
+    /// Removes the tiles of windows not in `ids` and adds one for each new id,
+    /// leaving the rest of the tree as it is.
+    fn reconcile_tree(&self, tree: &mut Tile, ids: &[isize], focused: Option<isize>) {
+        let existing: Vec<isize> = self
+            .collect_tile_windows(tree)
+            .into_iter()
+            .flatten()
+            .collect();
+        for &hwnd in existing.iter().filter(|hwnd| !ids.contains(hwnd)) {
+            debug!("Merging tile of removed window {:?}", hwnd);
+            Self::remove_leaf(tree, hwnd);
+        }
+        self.update_tree_rects(tree);
+
+        for (index, &hwnd) in ids.iter().enumerate() {
+            if existing.contains(&hwnd) {
+                continue;
+            }
+            // The first window goes before everything, the last one next to the
+            // focused window, and any other right after its predecessor
+            let (target, new_first) = if index == 0 {
+                (Self::first_leaf_window(tree), true)
+            } else if index == ids.len() - 1
+                && let Some(focused) = focused.filter(|focused| Self::has_leaf(tree, *focused))
+            {
+                (Some(focused), false)
+            } else {
+                (Some(ids[index - 1]), false)
+            };
+            debug!("Splitting tile of {:?} for new window {:?}", target, hwnd);
+            if let Some(target) = target {
+                self.split_leaf(tree, target, hwnd, new_first);
+                self.update_tree_rects(tree);
+            }
+        }
+    }
+
+    /// Removes the leaf holding `hwnd`, giving its space to its sibling.
+    fn remove_leaf(tile: &mut Tile, hwnd: isize) -> bool {
+        let Some(children) = tile.children.as_mut() else {
+            return false;
+        };
+        let sibling = if children.0.is_leaf() && children.0.windows.contains(&hwnd) {
+            Some(children.1.clone())
+        } else if children.1.is_leaf() && children.1.windows.contains(&hwnd) {
+            Some(children.0.clone())
+        } else {
+            None
+        };
+        match sibling {
+            Some(sibling) => {
+                let rect = tile.rect;
+                *tile = sibling;
+                tile.rect = rect;
+                true
+            }
+            None => {
+                Self::remove_leaf(&mut children.0, hwnd) || Self::remove_leaf(&mut children.1, hwnd)
+            }
+        }
+    }
+
+    /// Splits the leaf holding `target` in two, putting `hwnd` in the half
+    /// before it if `new_first` is set and after it otherwise.
+    fn split_leaf(&self, tile: &mut Tile, target: isize, hwnd: isize, new_first: bool) -> bool {
+        if let Some(children) = tile.children.as_mut() {
+            return self.split_leaf(&mut children.0, target, hwnd, new_first)
+                || self.split_leaf(&mut children.1, target, hwnd, new_first);
+        }
+        if !tile.windows.contains(&target) {
+            return false;
+        }
+
+        let width = tile.rect.right - tile.rect.left;
+        let height = tile.rect.bottom - tile.rect.top;
+        let direction = if width > height {
+            SplitDirection::Vertical
+        } else {
+            SplitDirection::Horizontal
+        };
+        let (first_rect, second_rect) = self.split_rect(&tile.rect, direction, 0.5);
+        let mut first = Tile::new(first_rect);
+        let mut second = Tile::new(second_rect);
+        let existing = std::mem::take(&mut tile.windows);
+        if new_first {
+            first.windows = vec![hwnd];
+            second.windows = existing;
+        } else {
+            first.windows = existing;
+            second.windows = vec![hwnd];
+        }
+        tile.split_direction = Some(direction);
+        tile.split_ratio = 0.5;
+        tile.children = Some(Box::new((first, second)));
+        true
+    }
+
+    /// Returns the window in the first leaf, in tree order.
+    fn first_leaf_window(tile: &Tile) -> Option<isize> {
+        match tile.children.as_ref() {
+            Some(children) => Self::first_leaf_window(&children.0),
+            None => tile.windows.first().copied(),
+        }
+    }
+
+    /// Returns true if some leaf holds `hwnd`.
+    fn has_leaf(tile: &Tile, hwnd: isize) -> bool {
+        match tile.children.as_ref() {
+            Some(children) => {
+                Self::has_leaf(&children.0, hwnd) || Self::has_leaf(&children.1, hwnd)
+            }
+            None => tile.windows.contains(&hwnd),
+        }
     }
 
     /// Collects all window handles from a tile tree.
@@ -336,41 +696,320 @@ impl DwindleTiler {
         }
     }
 
-    /// Applies tile rectangles to window positions.
-    fn apply_tile_positions(&self, tile: &Tile, windows: &mut [Window]) {
+    /// Collects the rect of every window in the tile tree, in tree order.
+    fn collect_positions(&self, tile: &Tile, positions: &mut Vec<(isize, RECT)>) {
         if tile.is_leaf() {
-            debug!(
-                "Applying positions to leaf tile with {} windows, rect {:?}",
-                tile.windows.len(),
-                tile.rect
-            );
-            // Apply tile rect to all windows in this tile
             for &window_hwnd in &tile.windows {
-                if let Some(window) = windows.iter_mut().find(|w| w.hwnd == window_hwnd) {
-                    debug!(
-                        "Setting window hwnd={:?} to rect {:?}",
-                        window_hwnd, tile.rect
-                    );
-                    window.rect = tile.rect;
-                } else {
-                    debug!(
-                        "Warning: window hwnd {:?} not found in windows list",
-                        window_hwnd
-                    );
-                }
+                positions.push((window_hwnd, tile.rect));
             }
         } else if let Some(ref children) = tile.children {
-            debug!("Recursing into child tiles");
-            self.apply_tile_positions(&children.0, windows);
-            self.apply_tile_positions(&children.1, windows);
-        } else {
-            debug!("Warning: Non-leaf tile with no children");
+            self.collect_positions(&children.0, positions);
+            self.collect_positions(&children.1, positions);
         }
     }
 }
 
 impl Default for DwindleTiler {
     fn default() -> Self {
-        Self::new(4) // Default 4px gap for minimal spacing
+        Self::new(DEFAULT_GAP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn area_strategy() -> impl Strategy<Value = RECT> {
+        (-4000i32..4000, -2000i32..2000, 400i32..4000, 400i32..2400).prop_map(
+            |(left, top, width, height)| RECT {
+                left,
+                top,
+                right: left + width,
+                bottom: top + height,
+            },
+        )
+    }
+
+    fn overlaps(a: &RECT, b: &RECT) -> bool {
+        a.left < b.right && b.left < a.right && a.top < b.bottom && b.top < a.bottom
+    }
+
+    /// Checks that each split's children, plus the gap between them, exactly cover the parent.
+    fn assert_partitioned(tile: &Tile, gap: i32) {
+        let Some(children) = tile.children.as_ref() else {
+            return;
+        };
+        let (first, second) = (&children.0.rect, &children.1.rect);
+        let parent = &tile.rect;
+        match tile.split_direction.unwrap() {
+            SplitDirection::Vertical => {
+                assert_eq!((first.top, first.bottom), (parent.top, parent.bottom));
+                assert_eq!((second.top, second.bottom), (parent.top, parent.bottom));
+                assert_eq!(first.left, parent.left);
+                assert_eq!(first.right + gap, second.left);
+                assert_eq!(second.right, parent.right);
+            }
+            SplitDirection::Horizontal => {
+                assert_eq!((first.left, first.right), (parent.left, parent.right));
+                assert_eq!((second.left, second.right), (parent.left, parent.right));
+                assert_eq!(first.top, parent.top);
+                assert_eq!(first.bottom + gap, second.top);
+                assert_eq!(second.bottom, parent.bottom);
+            }
+        }
+        assert_partitioned(&children.0, gap);
+        assert_partitioned(&children.1, gap);
+    }
+
+    fn set_ratios(tile: &mut Tile, ratios: &mut impl Iterator<Item = f32>) {
+        if let Some(children) = tile.children.as_mut() {
+            tile.split_ratio = ratios.next().unwrap_or(0.5);
+            set_ratios(&mut children.0, ratios);
+            set_ratios(&mut children.1, ratios);
+        }
+    }
+
+    fn assert_ratio_respected(tile: &Tile, gap: i32) {
+        let Some(children) = tile.children.as_ref() else {
+            return;
+        };
+        let ratio = tile.split_ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        let (extent, first_extent) = match tile.split_direction.unwrap() {
+            SplitDirection::Vertical => (
+                tile.rect.right - tile.rect.left,
+                children.0.rect.right - children.0.rect.left,
+            ),
+            SplitDirection::Horizontal => (
+                tile.rect.bottom - tile.rect.top,
+                children.0.rect.bottom - children.0.rect.top,
+            ),
+        };
+        let expected = extent as f32 * ratio;
+        assert!(
+            (first_extent as f32 - expected).abs() <= (gap / 2 + 1) as f32,
+            "first child extent {} too far from {} (ratio {})",
+            first_extent,
+            expected,
+            tile.split_ratio
+        );
+        assert_ratio_respected(&children.0, gap);
+        assert_ratio_respected(&children.1, gap);
+    }
+
+    proptest! {
+        #[test]
+        fn tiles_never_overlap(area in area_strategy(), count in 1usize..16, gap in 0i32..16) {
+            let ids: Vec<isize> = (1..=count as isize).collect();
+            let positions = DwindleTiler::new(gap).layout(area, &ids, &mut None, None);
+
+            prop_assert_eq!(positions.len(), count);
+            for (i, (_, a)) in positions.iter().enumerate() {
+                prop_assert!(a.left < a.right && a.top < a.bottom, "empty tile {:?}", a);
+                for (_, b) in &positions[i + 1..] {
+                    prop_assert!(!overlaps(a, b), "{:?} overlaps {:?}", a, b);
+                }
+            }
+        }
+
+        #[test]
+        fn tiles_cover_area_minus_gaps(
+            area in area_strategy(),
+            count in 1usize..16,
+            gap in 0i32..16,
+        ) {
+            let ids: Vec<isize> = (1..=count as isize).collect();
+            let mut tree = None;
+            let positions = DwindleTiler::new(gap).layout(area, &ids, &mut tree, None);
+            let tree = tree.unwrap();
+
+            prop_assert_eq!(tree.rect, area);
+            assert_partitioned(&tree, gap);
+
+            let mut placed: Vec<isize> = positions.iter().map(|(id, _)| *id).collect();
+            placed.sort();
+            prop_assert_eq!(placed, ids);
+        }
+
+        #[test]
+        fn updated_trees_still_cover_the_area(
+            area in area_strategy(),
+            count in 1usize..10,
+            keep in proptest::collection::vec(proptest::bool::ANY, 10),
+            added in 0usize..4,
+            gap in 0i32..16,
+        ) {
+            let tiler = DwindleTiler::new(gap);
+            let mut tree = None;
+            let ids: Vec<isize> = (1..=count as isize).collect();
+            tiler.layout(area, &ids, &mut tree, None);
+
+            let mut ids: Vec<isize> = ids.into_iter().filter(|id| keep[*id as usize - 1]).collect();
+            ids.extend((0..added as isize).map(|i| 100 + i));
+            let positions = tiler.layout(area, &ids, &mut tree, ids.first().copied());
+
+            let mut placed: Vec<isize> = positions.iter().map(|(id, _)| *id).collect();
+            placed.sort();
+            let mut expected = ids.clone();
+            expected.sort();
+            prop_assert_eq!(placed, expected);
+            if let Some(tree) = tree.as_ref().filter(|_| !ids.is_empty()) {
+                assert_partitioned(tree, gap);
+            }
+        }
+
+        #[test]
+        fn split_ratios_are_bounded(
+            area in area_strategy(),
+            count in 2usize..6,
+            gap in 0i32..16,
+            ratios in proptest::collection::vec(-1.0f32..2.0, 8),
+        ) {
+            let tiler = DwindleTiler::new(gap);
+            let ids: Vec<isize> = (1..=count as isize).collect();
+            let mut tree = None;
+            tiler.layout(area, &ids, &mut tree, None);
+
+            set_ratios(tree.as_mut().unwrap(), &mut ratios.into_iter());
+            let positions = tiler.layout(area, &ids, &mut tree, None);
+            let tree = tree.unwrap();
+
+            prop_assert_eq!(positions.len(), count);
+            assert_ratio_respected(&tree, gap);
+            assert_partitioned(&tree, gap);
+        }
+    }
+
+    #[test]
+    fn layout_reuses_tree_for_same_windows() {
+        let tiler = DwindleTiler::default();
+        let area = RECT {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        let mut tree = None;
+        tiler.layout(area, &[1, 2, 3], &mut tree, None);
+        tree.as_mut().unwrap().split_ratio = 0.7;
+
+        tiler.layout(area, &[3, 1, 2], &mut tree, None);
+        assert_eq!(tree.as_ref().unwrap().split_ratio, 0.7);
+
+        tiler.layout(area, &[4, 5], &mut tree, None);
+        assert_eq!(tree.as_ref().unwrap().split_ratio, 0.5);
+    }
+
+    #[test]
+    fn split_ratios_survive_windows_coming_and_going() {
+        let tiler = DwindleTiler::new(0);
+        let area = RECT {
+            left: 0,
+            top: 0,
+            right: 2000,
+            bottom: 1000,
+        };
+        let mut tree = None;
+        tiler.layout(area, &[1, 2], &mut tree, None);
+        tree.as_mut().unwrap().split_ratio = 0.7;
+
+        // A new window splits the focused tile; the root split is untouched
+        let positions = tiler.layout(area, &[1, 2, 3], &mut tree, Some(1));
+        assert_eq!(tree.as_ref().unwrap().split_ratio, 0.7);
+        let rect_of = |positions: &[(isize, RECT)], id| {
+            positions.iter().find(|(hwnd, _)| *hwnd == id).unwrap().1
+        };
+        assert_eq!(rect_of(&positions, 2).left, 1400);
+        assert_eq!(rect_of(&positions, 3).left, 700);
+
+        // Closing it gives the space back to its sibling
+        let positions = tiler.layout(area, &[1, 2], &mut tree, Some(1));
+        assert_eq!(tree.as_ref().unwrap().split_ratio, 0.7);
+        assert_eq!(rect_of(&positions, 1).right, 1400);
+
+        // A window placed first in order takes the first tile
+        let positions = tiler.layout(area, &[4, 1, 2], &mut tree, Some(2));
+        assert_eq!(rect_of(&positions, 4).left, 0);
+        assert_eq!(rect_of(&positions, 2).left, 1400);
+    }
+
+    #[test]
+    fn stack_layout_gives_equal_full_width_rows() {
+        let tiler = DwindleTiler::new(0).with_layout(TileLayout::Stack);
+        let area = RECT {
+            left: 0,
+            top: 0,
+            right: 1080,
+            bottom: 1920,
+        };
+        let positions = tiler.layout(area, &[1, 2, 3, 4], &mut None, None);
+
+        assert_eq!(positions.len(), 4);
+        for (_, rect) in &positions {
+            assert_eq!((rect.left, rect.right), (0, 1080));
+            assert!((rect.bottom - rect.top - 480).abs() <= 1);
+        }
+        assert_eq!(TileLayout::default_for(area), TileLayout::Stack);
+    }
+
+    #[test]
+    fn work_area_never_inverts() {
+        let tiny = RECT {
+            left: 0,
+            top: 0,
+            right: 100,
+            bottom: 10,
+        };
+        let rect = work_area(tiny);
+        assert!(rect.top <= rect.bottom);
+
+        let rect = padded_work_area(tiny, 80);
+        assert!(rect.left <= rect.right && rect.top <= rect.bottom);
+    }
+
+    #[test]
+    fn padding_insets_every_edge() {
+        let monitor = RECT {
+            left: 0,
+            top: 0,
+            right: 1920,
+            bottom: 1080,
+        };
+        assert_eq!(
+            work_area(monitor),
+            padded_work_area(monitor, DEFAULT_PADDING)
+        );
+
+        let padded = padded_work_area(monitor, 40);
+        assert_eq!(padded.left, 40);
+        assert_eq!(padded.right, 1880);
+        assert_eq!(padded.bottom, 1040);
+        assert_eq!(padded.top, STATUSBAR_VERTICAL_RESERVE + 38);
+
+        let flush = padded_work_area(monitor, 0);
+        assert_eq!((flush.left, flush.right, flush.bottom), (0, 1920, 1080));
+        assert_eq!(flush.top, STATUSBAR_VERTICAL_RESERVE);
+
+        // Without the built-in bar the top gets plain padding
+        assert_eq!(bar_work_area(monitor, 40, 0, BarPosition::Top).top, 40);
+
+        // A bar at the bottom takes its reserve from there instead
+        let bottom = bar_work_area(monitor, 0, STATUSBAR_VERTICAL_RESERVE, BarPosition::Bottom);
+        assert_eq!(bottom.top, 0);
+        assert_eq!(bottom.bottom, 1080 - STATUSBAR_VERTICAL_RESERVE);
+    }
+
+    #[test]
+    fn zen_rect_is_centered_at_full_height() {
+        let area = RECT {
+            left: 100,
+            top: 40,
+            right: 3540,
+            bottom: 1440,
+        };
+        let rect = zen_rect(area, 0.6);
+        assert_eq!(rect.right - rect.left, 2064);
+        assert_eq!(rect.left - area.left, area.right - rect.right);
+        assert_eq!((rect.top, rect.bottom), (area.top, area.bottom));
     }
 }
@@ -1,65 +1,499 @@
 //! System tray icon integration.
 //!
-//! Provides a system tray icon with an exit menu option for graceful shutdown.
+//! Provides a system tray icon with a start-on-login toggle, a settings item,
+//! status bar and window effects toggles, a submenu pausing management per
+//! display, an About item, a restart item, an optional update item and an
+//! exit menu option for graceful shutdown. Exiting can ask for confirmation
+//! first, and shows its progress in the menu and tooltip while hidden
+//! windows are restored.
+//!
+//! The icon changes with [`TrayState`]: active, every display paused, or an
+//! error reported. Each state's icon can be replaced by an `.ico` from
+//! `[tray]`.
 
+use crate::autostart;
+use crate::config::{TrayClickAction, TrayConfig};
+use log::error;
+use std::path::Path;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tray_icon::{
-    Icon, TrayIcon, TrayIconBuilder,
-    menu::{Menu, MenuEvent, MenuItem},
+    Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_YESNO, MessageBoxW,
 };
+use windows::core::HSTRING;
+
+/// Tooltip shown while Megatile is running normally.
+const TOOLTIP: &str = "Megatile - Tiling Window Manager";
 
 /// Global flag indicating the application should exit.
 pub static SHOULD_EXIT: AtomicBool = AtomicBool::new(false);
 
-/// Creates a simple orange 32x32 icon for the system tray.
-pub fn create_default_icon() -> Result<Icon, Box<dyn std::error::Error>> {
-    let width = 32;
-    let height = 32;
-    let mut icon_data = Vec::with_capacity(width * height * 4);
+/// Global flag indicating the application should restart.
+static SHOULD_RESTART: AtomicBool = AtomicBool::new(false);
+
+/// Global flag indicating the settings window should be opened.
+static SHOULD_OPEN_SETTINGS: AtomicBool = AtomicBool::new(false);
+
+/// Global flag indicating the About window should be opened.
+static SHOULD_OPEN_ABOUT: AtomicBool = AtomicBool::new(false);
+
+/// Global flag indicating the status bar item was clicked.
+static STATUSBAR_TOGGLED: AtomicBool = AtomicBool::new(false);
+
+/// Global flag indicating the window effects item was clicked.
+static EFFECTS_TOGGLED: AtomicBool = AtomicBool::new(false);
+
+/// Global flag indicating the offered update should be installed.
+static SHOULD_UPDATE: AtomicBool = AtomicBool::new(false);
+
+/// What the last click or double-click on the icon asked for.
+static CLICK_ACTION: Mutex<Option<TrayClickAction>> = Mutex::new(None);
+
+/// Display whose management was toggled from the menu, by device name.
+static DISPLAY_TOGGLE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Menu item ID prefix of the per-display items.
+const DISPLAY_ITEM_PREFIX: &str = "display:";
+
+/// Asks whether to restore the `windows` managed windows and exit.
+pub fn confirm_exit(windows: usize) -> bool {
+    let question = format!(
+        "Exit Megatile? Its {} windows will be shown and moved back where they were.",
+        windows
+    );
+    let answer = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(question),
+            &HSTRING::from("Megatile"),
+            MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND,
+        )
+    };
+    answer == IDYES
+}
+
+/// Side of the built-in icons, in pixels.
+const ICON_SIZE: usize = 32;
+
+/// What the tray icon shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrayState {
+    /// Managing windows.
+    #[default]
+    Active,
+    /// Every display is paused from "Manage displays".
+    Paused,
+    /// An error was reported, e.g. a broken config file.
+    Error,
+}
+
+impl TrayState {
+    /// Picks the state from the displays listed in the "Manage displays"
+    /// submenu and whether an error was reported.
+    pub fn from_displays(displays: &[(String, bool)], error: bool) -> Self {
+        if error {
+            TrayState::Error
+        } else if !displays.is_empty() && displays.iter().all(|(_, managed)| !managed) {
+            TrayState::Paused
+        } else {
+            TrayState::Active
+        }
+    }
+
+    fn tooltip(self) -> String {
+        match self {
+            TrayState::Active => TOOLTIP.to_string(),
+            TrayState::Paused => "Megatile - All displays paused".to_string(),
+            TrayState::Error => "Megatile - An error occurred, see the log".to_string(),
+        }
+    }
 
-    for _ in 0..(width * height) {
-        icon_data.push(255);
-        icon_data.push(165);
-        icon_data.push(0);
-        icon_data.push(255);
+    /// RGB color of the built-in icon's tiles.
+    fn color(self) -> [u8; 3] {
+        match self {
+            TrayState::Active => [255, 165, 0],
+            TrayState::Paused => [150, 150, 150],
+            TrayState::Error => [232, 60, 60],
+        }
     }
+}
+
+/// Draws the built-in icon for `state` as RGBA: four tiles in the state's
+/// color, with a transparent gap between them.
+fn builtin_icon_rgba(state: TrayState) -> Vec<u8> {
+    const GAP: usize = 4;
+    let [r, g, b] = state.color();
+    let half = ICON_SIZE / 2;
+    let mut icon_data = Vec::with_capacity(ICON_SIZE * ICON_SIZE * 4);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let in_gap = |v: usize| v + GAP / 2 >= half && v < half + GAP / 2;
+            let alpha = if in_gap(x) || in_gap(y) { 0 } else { 255 };
+            icon_data.extend_from_slice(&[r, g, b, alpha]);
+        }
+    }
+    icon_data
+}
 
-    Icon::from_rgba(icon_data, width as u32, height as u32)
+/// Creates the built-in icon for `state`.
+pub fn create_default_icon(state: TrayState) -> Result<Icon, Box<dyn std::error::Error>> {
+    Icon::from_rgba(builtin_icon_rgba(state), ICON_SIZE as u32, ICON_SIZE as u32)
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
 }
 
+/// The icon for each [`TrayState`].
+pub struct TrayIcons {
+    active: Icon,
+    paused: Icon,
+    error: Icon,
+}
+
+impl TrayIcons {
+    /// Loads the `.ico` files set in `[tray]`, using the built-in icon for
+    /// states without one. Files that can't be loaded are reported in the
+    /// returned list and replaced by the built-in icon too.
+    pub fn load(config: &TrayConfig) -> Result<(Self, Vec<String>), Box<dyn std::error::Error>> {
+        let mut problems = Vec::new();
+        let mut load = |path: Option<&Path>, state: TrayState| {
+            if let Some(path) = path {
+                match Icon::from_path(path, None) {
+                    Ok(icon) => return Ok(icon),
+                    Err(e) => problems.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+            create_default_icon(state)
+        };
+        let icons = TrayIcons {
+            active: load(config.icon.as_deref(), TrayState::Active)?,
+            paused: load(config.paused_icon.as_deref(), TrayState::Paused)?,
+            error: load(config.error_icon.as_deref(), TrayState::Error)?,
+        };
+        Ok((icons, problems))
+    }
+
+    fn get(&self, state: TrayState) -> &Icon {
+        match state {
+            TrayState::Active => &self.active,
+            TrayState::Paused => &self.paused,
+            TrayState::Error => &self.error,
+        }
+    }
+}
+
 /// Manages the system tray icon and menu.
 pub struct TrayManager {
     /// The tray icon (kept alive for the duration of the program).
-    _icon: TrayIcon,
+    icon: TrayIcon,
+    icons: TrayIcons,
+    state: TrayState,
+    /// Shows exit progress once exiting starts.
+    exit_item: MenuItem,
+    /// Shows the update check's result, when update checks are on.
+    update_item: Option<MenuItem>,
+    /// One checked item per managed display.
+    displays_menu: Submenu,
+    display_items: Vec<CheckMenuItem>,
+    statusbar_item: CheckMenuItem,
+    effects_item: CheckMenuItem,
+    settings_item: MenuItem,
+    about_item: MenuItem,
+    /// What the two items above were last set to.
+    toggles: Option<(Option<bool>, bool)>,
 }
 
 impl TrayManager {
-    /// Creates a new tray manager with an icon and exit menu.
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    /// Creates a new tray manager with an icon and menu.
+    ///
+    /// `autostart_flags` are the CLI flags written to the login entry when the
+    /// "Start on login" item is checked. With `check_updates`, the menu gets an
+    /// item that stays disabled until [`TrayManager::offer_update`] is called.
+    /// The icon starts out as `icons`' active one. Left-clicking it opens
+    /// the menu only if `click` is [`TrayClickAction::Menu`]; other actions
+    /// are handed out by [`TrayManager::take_click_action`].
+    pub fn new(
+        autostart_flags: Vec<String>,
+        check_updates: bool,
+        icons: TrayIcons,
+        click: TrayClickAction,
+        double_click: TrayClickAction,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let autostart_menu_item = CheckMenuItem::with_id(
+            "autostart",
+            "Start on login",
+            true,
+            autostart::is_enabled(),
+            None,
+        );
+        let settings_menu_item = MenuItem::with_id("settings", "Settings...", true, None);
+        let statusbar_menu_item =
+            CheckMenuItem::with_id("statusbar", "Show status bar", true, true, None);
+        let effects_menu_item =
+            CheckMenuItem::with_id("effects", "Enable window effects", true, true, None);
+        let displays_menu = Submenu::with_id("displays", "Manage displays", true);
+        let about_menu_item = MenuItem::with_id("about", "About Megatile", true, None);
+        let restart_menu_item = MenuItem::with_id("restart", "Restart", true, None);
         let exit_menu_item = MenuItem::with_id("exit", "Exit", true, None);
         let menu = Menu::new();
-        menu.append_items(&[&exit_menu_item])?;
+        menu.append_items(&[
+            &autostart_menu_item,
+            &settings_menu_item,
+            &statusbar_menu_item,
+            &effects_menu_item,
+            &displays_menu,
+            &about_menu_item,
+            &PredefinedMenuItem::separator(),
+            &restart_menu_item,
+        ])?;
+        let update_item = if check_updates {
+            let item = MenuItem::with_id("update", "No update available", false, None);
+            menu.append(&item)?;
+            Some(item)
+        } else {
+            None
+        };
+        menu.append(&exit_menu_item)?;
 
-        let tray_icon = create_default_icon()?;
         let icon = TrayIconBuilder::new()
             .with_menu(Box::new(menu))
-            .with_tooltip("Megatile - Tiling Window Manager")
-            .with_icon(tray_icon)
+            .with_tooltip(TOOLTIP)
+            .with_icon(icons.get(TrayState::Active).clone())
+            .with_menu_on_left_click(click == TrayClickAction::Menu)
             .build()
             .unwrap();
 
-        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
-            if event.id.0.as_str() == "exit" {
-                SHOULD_EXIT.store(true, Ordering::SeqCst);
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            let action = match event {
+                TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } => click,
+                TrayIconEvent::DoubleClick {
+                    button: MouseButton::Left,
+                    ..
+                } => double_click,
+                _ => return,
+            };
+            if !matches!(action, TrayClickAction::None | TrayClickAction::Menu)
+                && let Ok(mut pending) = CLICK_ACTION.lock()
+            {
+                *pending = Some(action);
             }
         }));
 
-        Ok(TrayManager { _icon: icon })
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| match event.id.0.as_str() {
+            "exit" => SHOULD_EXIT.store(true, Ordering::SeqCst),
+            "settings" => SHOULD_OPEN_SETTINGS.store(true, Ordering::SeqCst),
+            "about" => SHOULD_OPEN_ABOUT.store(true, Ordering::SeqCst),
+            "statusbar" => STATUSBAR_TOGGLED.store(true, Ordering::SeqCst),
+            "effects" => EFFECTS_TOGGLED.store(true, Ordering::SeqCst),
+            "restart" => SHOULD_RESTART.store(true, Ordering::SeqCst),
+            "update" => SHOULD_UPDATE.store(true, Ordering::SeqCst),
+            id if id.starts_with(DISPLAY_ITEM_PREFIX) => {
+                if let Ok(mut toggle) = DISPLAY_TOGGLE.lock() {
+                    *toggle = Some(id[DISPLAY_ITEM_PREFIX.len()..].to_string());
+                }
+            }
+            "autostart" => {
+                let result = if autostart::is_enabled() {
+                    autostart::disable()
+                } else {
+                    autostart::enable(&autostart_flags)
+                };
+                if let Err(e) = result {
+                    error!("Failed to toggle autostart: {}", e);
+                }
+            }
+            _ => {}
+        }));
+
+        Ok(TrayManager {
+            icon,
+            icons,
+            state: TrayState::Active,
+            exit_item: exit_menu_item,
+            update_item,
+            displays_menu,
+            display_items: Vec::new(),
+            statusbar_item: statusbar_menu_item,
+            effects_item: effects_menu_item,
+            settings_item: settings_menu_item,
+            about_item: about_menu_item,
+            toggles: None,
+        })
+    }
+
+    /// Switches the icon and tooltip to `state`'s.
+    pub fn set_state(&mut self, state: TrayState) {
+        if state == self.state {
+            return;
+        }
+        self.state = state;
+        if let Err(e) = self.icon.set_icon(Some(self.icons.get(state).clone())) {
+            error!("Failed to update tray icon: {}", e);
+        }
+        if let Err(e) = self.icon.set_tooltip(Some(state.tooltip())) {
+            error!("Failed to update tray tooltip: {}", e);
+        }
     }
 
-    /// Returns true if the exit menu item was clicked.
-    pub fn should_exit(&self) -> bool {
-        SHOULD_EXIT.load(Ordering::SeqCst)
+    /// Checks the "Show status bar" and "Enable window effects" items to
+    /// match, e.g. after the status bar was toggled with its hotkey. The
+    /// status bar item is disabled when there is no built-in bar.
+    pub fn set_toggles(&mut self, statusbar: Option<bool>, effects: bool) {
+        if self.toggles == Some((statusbar, effects)) {
+            return;
+        }
+        self.toggles = Some((statusbar, effects));
+        self.statusbar_item.set_enabled(statusbar.is_some());
+        self.statusbar_item.set_checked(statusbar.unwrap_or(false));
+        self.effects_item.set_checked(effects);
+    }
+
+    /// Returns true once after the "Show status bar" item was clicked.
+    pub fn take_statusbar_toggle(&self) -> bool {
+        STATUSBAR_TOGGLED.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns true once after the "Enable window effects" item was clicked.
+    pub fn take_effects_toggle(&self) -> bool {
+        EFFECTS_TOGGLED.swap(false, Ordering::SeqCst)
+    }
+
+    /// Lists the displays in the "Manage displays" submenu, checked if
+    /// managed. `displays` holds device names like `\\.\DISPLAY1`.
+    pub fn set_displays(&mut self, displays: &[(String, bool)]) {
+        for item in self.display_items.drain(..) {
+            if let Err(e) = self.displays_menu.remove(&item) {
+                error!("Failed to remove display menu item: {}", e);
+            }
+        }
+        for (device, managed) in displays {
+            let item = CheckMenuItem::with_id(
+                format!("{}{}", DISPLAY_ITEM_PREFIX, device),
+                device.trim_start_matches("\\\\.\\"),
+                true,
+                *managed,
+                None,
+            );
+            if let Err(e) = self.displays_menu.append(&item) {
+                error!("Failed to add display menu item: {}", e);
+            }
+            self.display_items.push(item);
+        }
+    }
+
+    /// Returns the device name of a display clicked in the "Manage
+    /// displays" submenu, once.
+    pub fn take_display_toggle(&self) -> Option<String> {
+        DISPLAY_TOGGLE.lock().ok()?.take()
+    }
+
+    /// Returns true once after the exit menu item was clicked.
+    pub fn take_exit_request(&self) -> bool {
+        SHOULD_EXIT.swap(false, Ordering::SeqCst)
+    }
+
+    /// Shows how many of `total` windows have been restored while exiting.
+    pub fn show_exit_progress(&self, restored: usize, total: usize) {
+        let text = format!("Restoring windows ({}/{})...", restored, total);
+        self.exit_item.set_text(&text);
+        self.exit_item.set_enabled(false);
+        if let Err(e) = self.icon.set_tooltip(Some(format!("Megatile - {}", text))) {
+            error!("Failed to update tray tooltip: {}", e);
+        }
+    }
+
+    /// Turns the update item into a "download and restart" button for `version`.
+    pub fn offer_update(&self, version: &str) {
+        if let Some(item) = &self.update_item {
+            item.set_text(format!("Update to {} and restart", version));
+            item.set_enabled(true);
+        }
+    }
+
+    /// Shows that an update is being installed, so it isn't clicked twice.
+    pub fn show_update_installing(&self) {
+        if let Some(item) = &self.update_item {
+            item.set_text("Installing update...");
+            item.set_enabled(false);
+        }
+    }
+
+    /// Returns true once after the update menu item was clicked.
+    pub fn take_update_request(&self) -> bool {
+        SHOULD_UPDATE.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns true once after the settings menu item was clicked.
+    pub fn take_settings_request(&self) -> bool {
+        SHOULD_OPEN_SETTINGS.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns what a click or double-click on the icon asked for, once.
+    pub fn take_click_action(&self) -> Option<TrayClickAction> {
+        CLICK_ACTION.lock().ok()?.take()
+    }
+
+    /// Greys out the Settings item, e.g. when its window couldn't be created.
+    pub fn disable_settings(&self) {
+        self.settings_item.set_enabled(false);
+    }
+
+    /// Greys out the About item, e.g. when its window couldn't be created.
+    pub fn disable_about(&self) {
+        self.about_item.set_enabled(false);
+    }
+
+    /// Returns true once after the About menu item was clicked.
+    pub fn take_about_request(&self) -> bool {
+        SHOULD_OPEN_ABOUT.swap(false, Ordering::SeqCst)
+    }
+
+    /// Returns true once after the restart menu item was clicked.
+    pub fn take_restart_request(&self) -> bool {
+        SHOULD_RESTART.swap(false, Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_state_follows_displays_and_errors() {
+        let display = |managed| (r"\\.\DISPLAY1".to_string(), managed);
+        assert_eq!(TrayState::from_displays(&[], false), TrayState::Active);
+        assert_eq!(
+            TrayState::from_displays(&[display(true), display(false)], false),
+            TrayState::Active
+        );
+        assert_eq!(
+            TrayState::from_displays(&[display(false), display(false)], false),
+            TrayState::Paused
+        );
+        assert_eq!(
+            TrayState::from_displays(&[display(false)], true),
+            TrayState::Error
+        );
+
+        // Built-in icons are tiles in the state's color with see-through gaps
+        let pixel = |data: &[u8], x: usize, y: usize| {
+            let i = (y * ICON_SIZE + x) * 4;
+            [data[i], data[i + 1], data[i + 2], data[i + 3]]
+        };
+        let paused = builtin_icon_rgba(TrayState::Paused);
+        assert_eq!(paused.len(), ICON_SIZE * ICON_SIZE * 4);
+        assert_eq!(pixel(&paused, 0, 0), [150, 150, 150, 255]);
+        assert_eq!(pixel(&paused, ICON_SIZE / 2, 0)[3], 0);
+        assert_eq!(pixel(&paused, 0, ICON_SIZE / 2)[3], 0);
+        assert_eq!(
+            pixel(&builtin_icon_rgba(TrayState::Error), 31, 31),
+            [232, 60, 60, 255]
+        );
     }
 }
@@ -0,0 +1,233 @@
+//! Checking GitHub for a newer release and installing it.
+//!
+//! Only built with the `updates` feature, and only run when `[updates] check`
+//! is on. The check runs once at startup on its own thread. Installing
+//! downloads the release's `megatile.exe` next to the running one, checks it
+//! against the SHA-256 in the release's `megatile.exe.sha256` and swaps the
+//! files; Windows lets a running executable be renamed but not
+//! overwritten, so the old one is parked as `megatile.exe.old` and deleted by
+//! the next start. The caller then restarts into the new binary.
+
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Latest release of the project on GitHub.
+const RELEASES_URL: &str = "https://api.github.com/repos/LostRhapsody/megatile/releases/latest";
+/// Release asset holding the executable.
+const ASSET_NAME: &str = "megatile.exe";
+/// Release asset holding the executable's SHA-256, as `sha256sum` writes it.
+const CHECKSUM_ASSET_NAME: &str = "megatile.exe.sha256";
+
+/// A release newer than the running one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// Version without the `v` prefix, e.g. `"0.3.0"`.
+    pub version: String,
+    download_url: String,
+    checksum_url: String,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Checks for a newer release on a background thread and calls `on_update`
+/// if there is one.
+pub fn spawn_check<F>(on_update: F)
+where
+    F: FnOnce(Update) + Send + 'static,
+{
+    let result = thread::Builder::new()
+        .name("megatile-updates".to_string())
+        .spawn(move || match check() {
+            Ok(Some(update)) => {
+                info!("Megatile {} is available", update.version);
+                on_update(update);
+            }
+            Ok(None) => debug!("Megatile is up to date"),
+            Err(e) => warn!("Update check failed: {}", e),
+        });
+    if let Err(e) = result {
+        error!("Failed to spawn update thread: {}", e);
+    }
+}
+
+/// Asks GitHub for the latest release.
+fn check() -> Result<Option<Update>, String> {
+    let release: Release = ureq::get(RELEASES_URL)
+        .set(
+            "User-Agent",
+            concat!("megatile/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| format!("Unexpected release info: {}", e))?;
+
+    let version = release.tag_name.trim_start_matches('v');
+    if !is_newer(version, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let asset_url = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.clone())
+    };
+    let Some(download_url) = asset_url(ASSET_NAME) else {
+        warn!("Release {} has no {} to install", version, ASSET_NAME);
+        return Ok(None);
+    };
+    let Some(checksum_url) = asset_url(CHECKSUM_ASSET_NAME) else {
+        warn!(
+            "Release {} has no {} to check the download against",
+            version, CHECKSUM_ASSET_NAME
+        );
+        return Ok(None);
+    };
+    Ok(Some(Update {
+        version: version.to_string(),
+        download_url,
+        checksum_url,
+    }))
+}
+
+/// Downloads `update` and puts it in place of the running executable,
+/// unless it doesn't match its published SHA-256. Blocks until the download
+/// is done.
+pub fn install(update: &Update) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the Megatile executable: {}", e))?;
+    let download = with_suffix(&exe, ".new");
+
+    info!("Downloading Megatile {}", update.version);
+    let checksum = ureq::get(&update.checksum_url)
+        .call()
+        .map_err(|e| {
+            format!(
+                "Failed to download the checksum of {}: {}",
+                update.version, e
+            )
+        })?
+        .into_string()
+        .map_err(|e| {
+            format!(
+                "Failed to download the checksum of {}: {}",
+                update.version, e
+            )
+        })?;
+    let expected = parse_checksum(&checksum)
+        .ok_or_else(|| format!("The published checksum of {} is unreadable", update.version))?;
+    let mut bytes = Vec::new();
+    ureq::get(&update.download_url)
+        .call()
+        .map_err(|e| format!("Failed to download {}: {}", update.version, e))?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to download {}: {}", update.version, e))?;
+    if sha256_hex(&bytes) != expected {
+        return Err(format!(
+            "The download of {} doesn't match its published checksum; not installing it",
+            update.version
+        ));
+    }
+    std::fs::write(&download, &bytes)
+        .map_err(|e| format!("Failed to write {}: {}", download.display(), e))?;
+
+    let old = with_suffix(&exe, ".old");
+    let _ = std::fs::remove_file(&old);
+    std::fs::rename(&exe, &old)
+        .map_err(|e| format!("Failed to move {} aside: {}", exe.display(), e))?;
+    if let Err(e) = std::fs::rename(&download, &exe) {
+        // Put the running version back so the next start still works
+        let _ = std::fs::rename(&old, &exe);
+        return Err(format!("Failed to install {}: {}", update.version, e));
+    }
+    info!("Installed Megatile {}", update.version);
+    Ok(())
+}
+
+/// Deletes the executable an update left behind.
+pub fn remove_old_binary() {
+    if let Ok(exe) = std::env::current_exe() {
+        let old = with_suffix(&exe, ".old");
+        if std::fs::remove_file(&old).is_ok() {
+            debug!("Removed {}", old.display());
+        }
+    }
+}
+
+/// Reads the digest from a `sha256sum`-style line (`<hex digest>  <file>`),
+/// lowercased. Returns `None` unless it's 64 hex digits.
+fn parse_checksum(text: &str) -> Option<String> {
+    let digest = text.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| digest.to_ascii_lowercase())
+}
+
+/// Returns the SHA-256 of `bytes` as lowercase hex.
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Returns true if dotted version `latest` is newer than `current`.
+/// Unparsable versions are never newer.
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Option<Vec<u64>> {
+        // Ignore pre-release and build suffixes like "-beta.1"
+        let core = version.split(['-', '+']).next()?;
+        core.split('.').map(|part| part.parse().ok()).collect()
+    };
+    match (parse(latest), parse(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(is_newer("0.2.10", "0.2.9"));
+        assert!(is_newer("1.0.0", "0.9.9"));
+        assert!(!is_newer("0.2.2", "0.2.2"));
+        assert!(!is_newer("0.2.1", "0.2.2"));
+        assert!(!is_newer("nightly", "0.2.2"));
+    }
+
+    #[test]
+    fn downloads_are_checked_against_the_published_digest() {
+        let digest = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+        let expected = parse_checksum(&format!("{}  megatile.exe\n", digest));
+        assert_eq!(expected, Some(digest.to_ascii_lowercase()));
+        assert_eq!(expected, Some(sha256_hex(b"abc")));
+        assert_ne!(expected, Some(sha256_hex(b"abd")));
+
+        assert_eq!(parse_checksum(""), None);
+        assert_eq!(parse_checksum("not-a-digest megatile.exe"), None);
+    }
+}
@@ -0,0 +1,699 @@
+//! The window system the [`WorkspaceManager`](crate::workspace_manager::WorkspaceManager)
+//! drives.
+//!
+//! [`WindowSystem`] covers the calls workspace switching and event handling
+//! make: enumerating monitors, moving, hiding, showing and focusing windows,
+//! and the decorations applied on every focus change. [`Win32WindowSystem`]
+//! makes the real calls; the mock used by the tests keeps the same state in
+//! memory, so whole scenarios run under `cargo test` without touching the
+//! desktop.
+//!
+//! Stripping and restoring title bars still calls
+//! [`windows_lib`](crate::windows_lib) directly.
+
+use crate::config::{CornerPreference, HideMethod};
+use crate::theme::Theme;
+use crate::windows_lib::{self, MonitorInfo};
+use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, HWND, RECT};
+use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONEAREST, MonitorFromWindow};
+use windows::Win32::UI::WindowsAndMessaging::{
+    BeginDeferWindowPos, DeferWindowPos, EndDeferWindowPos, GetForegroundWindow, IsZoomed,
+    SW_RESTORE, SWP_NOACTIVATE, SWP_NOZORDER, SetForegroundWindow, SetWindowPos, ShowWindow,
+};
+
+/// Window operations used by the workspace manager.
+pub trait WindowSystem {
+    /// Returns the connected monitors.
+    fn monitors(&self) -> Vec<MonitorInfo>;
+    /// Returns the handle of the monitor a window is mostly on, as isize.
+    fn monitor_for_window(&self, hwnd: HWND) -> isize;
+    /// Returns the window with keyboard focus.
+    fn foreground_window(&self) -> HWND;
+    /// Gives a window keyboard focus. Returns false if Windows refused.
+    fn focus_window(&self, hwnd: HWND) -> bool;
+    /// Hides a window for an inactive workspace using `method`.
+    fn hide_window(&self, hwnd: HWND, method: HideMethod) -> Result<(), String>;
+    /// Shows a window hidden with `method`.
+    fn show_window(&self, hwnd: HWND, method: HideMethod) -> Result<(), String>;
+    /// Raises a window above its neighbours without focusing it.
+    fn raise_window(&self, hwnd: HWND) -> Result<(), String>;
+    /// Keeps a window above all others, or returns it to the normal band.
+    fn set_topmost(&self, hwnd: HWND, topmost: bool) -> Result<(), String>;
+    /// Moves and resizes windows, un-maximizing them first. Returns the
+    /// windows that refused because they run elevated.
+    fn move_windows(&self, moves: &[(HWND, RECT)]) -> Vec<HWND>;
+    /// Returns the window that owns a dialog or other owned window.
+    fn window_owner(&self, hwnd: HWND) -> Option<HWND>;
+    /// Returns a window's outer rectangle.
+    fn window_rect(&self, hwnd: HWND) -> Result<RECT, String>;
+    /// Restores a window if it's maximized.
+    fn restore_maximized(&self, hwnd: HWND);
+    /// Returns the (left, top, right, bottom) invisible borders around a
+    /// window as it is now.
+    fn invisible_borders(&self, hwnd: HWND) -> Result<(i32, i32, i32, i32), String>;
+    /// Returns true if a managed window still exists and should stay managed.
+    /// Hidden windows are only checked for existence.
+    fn is_window_valid(&self, hwnd: HWND, is_hidden: bool) -> bool;
+    /// Returns the mouse cursor position in screen coordinates.
+    fn cursor_position(&self) -> Result<(i32, i32), String>;
+    /// Moves the mouse cursor to a point in screen coordinates.
+    fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), String>;
+    /// Returns the system accent color (0x00BBGGRR).
+    fn accent_color(&self) -> Result<u32, String>;
+    /// Returns whether apps are drawn light or dark.
+    fn theme(&self) -> Theme;
+    /// Sets the border color of a window (0x00BBGGRR).
+    fn set_border_color(&self, hwnd: HWND, color: u32) -> Result<(), String>;
+    /// Removes the border color and transparency set by Megatile.
+    fn reset_decorations(&self, hwnd: HWND) -> Result<(), String>;
+    /// Sets a window's opacity, 255 being opaque.
+    fn set_transparency(&self, hwnd: HWND, alpha: u8) -> Result<(), String>;
+    /// Sets how a window's corners are rounded.
+    fn set_corners(&self, hwnd: HWND, corners: CornerPreference) -> Result<(), String>;
+    /// Returns true if the app owning a window runs as administrator.
+    fn is_elevated(&self, hwnd: HWND) -> bool;
+    /// Returns a window's title.
+    fn window_title(&self, hwnd: HWND) -> String;
+    /// Returns a window's class name.
+    fn window_class(&self, hwnd: HWND) -> String;
+    /// Returns the executable name of the app owning a window.
+    fn process_name(&self, hwnd: HWND) -> Option<String>;
+    /// Returns true if a window is one Megatile would tile.
+    fn is_normal_window(&self, hwnd: HWND) -> bool;
+    /// Returns true if a window is minimized.
+    fn is_minimized(&self, hwnd: HWND) -> bool;
+    /// Asks a window to close.
+    fn close_window(&self, hwnd: HWND) -> Result<(), String>;
+    /// Minimizes a window to the taskbar.
+    fn minimize_window(&self, hwnd: HWND) -> Result<(), String>;
+    /// Covers `display` with a window, keeping it above all others if
+    /// `topmost`.
+    fn set_fullscreen(&self, hwnd: HWND, display: RECT, topmost: bool) -> Result<(), String>;
+    /// Puts a fullscreen window back at `rect`. `topmost` must match how it
+    /// was made fullscreen.
+    fn exit_fullscreen(&self, hwnd: HWND, rect: RECT, topmost: bool) -> Result<(), String>;
+}
+
+/// The real window system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Win32WindowSystem;
+
+impl Win32WindowSystem {
+    /// Restores a maximized window, as `SetWindowPos` doesn't work on
+    /// maximized windows and their borders hang off the monitor.
+    fn restore_if_maximized(hwnd: HWND) {
+        unsafe {
+            if IsZoomed(hwnd).as_bool() {
+                let _ = ShowWindow(hwnd, SW_RESTORE);
+            }
+        }
+    }
+
+    /// Moves one window. Returns false if access was denied.
+    fn move_window(hwnd: HWND, rect: &RECT) -> bool {
+        Self::restore_if_maximized(hwnd);
+        unsafe {
+            match SetWindowPos(
+                hwnd,
+                None,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            ) {
+                Err(e) => e.code() != ERROR_ACCESS_DENIED.to_hresult(),
+                Ok(()) => true,
+            }
+        }
+    }
+
+    /// Moves windows one at a time, collecting the ones that refused.
+    fn move_individually(moves: &[(HWND, RECT)]) -> Vec<HWND> {
+        moves
+            .iter()
+            .filter(|(hwnd, rect)| !Self::move_window(*hwnd, rect))
+            .map(|(hwnd, _)| *hwnd)
+            .collect()
+    }
+}
+
+impl WindowSystem for Win32WindowSystem {
+    fn monitors(&self) -> Vec<MonitorInfo> {
+        windows_lib::enumerate_monitors()
+    }
+
+    fn monitor_for_window(&self, hwnd: HWND) -> isize {
+        unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST).0 as isize }
+    }
+
+    fn foreground_window(&self) -> HWND {
+        unsafe { GetForegroundWindow() }
+    }
+
+    fn focus_window(&self, hwnd: HWND) -> bool {
+        unsafe { SetForegroundWindow(hwnd).as_bool() }
+    }
+
+    fn hide_window(&self, hwnd: HWND, method: HideMethod) -> Result<(), String> {
+        match method {
+            HideMethod::Hide => windows_lib::hide_window_from_taskbar(hwnd),
+            HideMethod::Offscreen => windows_lib::move_window_offscreen(hwnd),
+        }
+    }
+
+    fn show_window(&self, hwnd: HWND, method: HideMethod) -> Result<(), String> {
+        match method {
+            HideMethod::Hide => windows_lib::show_window_in_taskbar(hwnd),
+            HideMethod::Offscreen => windows_lib::restore_offscreen_window(hwnd),
+        }
+    }
+
+    fn raise_window(&self, hwnd: HWND) -> Result<(), String> {
+        windows_lib::raise_window(hwnd)
+    }
+
+    fn set_topmost(&self, hwnd: HWND, topmost: bool) -> Result<(), String> {
+        windows_lib::set_window_topmost(hwnd, topmost)
+    }
+
+    /// Moves several windows in a single `DeferWindowPos` batch, so the
+    /// layout updates in one frame.
+    ///
+    /// Falls back to positioning windows one at a time if the batch cannot be
+    /// created or a window rejects the deferred move.
+    fn move_windows(&self, moves: &[(HWND, RECT)]) -> Vec<HWND> {
+        if moves.len() < 2 {
+            return Self::move_individually(moves);
+        }
+
+        for (hwnd, _) in moves {
+            Self::restore_if_maximized(*hwnd);
+        }
+        unsafe {
+            let mut hdwp = match BeginDeferWindowPos(moves.len() as i32) {
+                Ok(hdwp) => hdwp,
+                Err(e) => {
+                    log::warn!(
+                        "BeginDeferWindowPos failed, positioning windows individually: {}",
+                        e
+                    );
+                    return Self::move_individually(moves);
+                }
+            };
+
+            for (i, (hwnd, rect)) in moves.iter().enumerate() {
+                match DeferWindowPos(
+                    hdwp,
+                    *hwnd,
+                    None,
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE,
+                ) {
+                    Ok(next) => hdwp = next,
+                    Err(e) => {
+                        // The system frees the batch when DeferWindowPos fails
+                        log::warn!(
+                            "DeferWindowPos failed for {:?}, positioning remaining windows \
+                             individually: {}",
+                            hwnd.0,
+                            e
+                        );
+                        return Self::move_individually(&moves[i..]);
+                    }
+                }
+            }
+
+            if let Err(e) = EndDeferWindowPos(hdwp) {
+                log::warn!("EndDeferWindowPos failed: {}", e);
+            }
+        }
+        Vec::new()
+    }
+
+    fn window_owner(&self, hwnd: HWND) -> Option<HWND> {
+        windows_lib::get_window_owner(hwnd)
+    }
+
+    fn window_rect(&self, hwnd: HWND) -> Result<RECT, String> {
+        windows_lib::get_window_rect(hwnd)
+    }
+
+    fn restore_maximized(&self, hwnd: HWND) {
+        Self::restore_if_maximized(hwnd);
+    }
+
+    fn invisible_borders(&self, hwnd: HWND) -> Result<(i32, i32, i32, i32), String> {
+        windows_lib::measure_invisible_borders(hwnd)
+    }
+
+    fn is_window_valid(&self, hwnd: HWND, is_hidden: bool) -> bool {
+        windows_lib::is_window_still_valid(hwnd, is_hidden)
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        windows_lib::get_cursor_position()
+    }
+
+    fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), String> {
+        windows_lib::set_cursor_position(x, y)
+    }
+
+    fn accent_color(&self) -> Result<u32, String> {
+        windows_lib::get_accent_color()
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::current()
+    }
+
+    fn set_border_color(&self, hwnd: HWND, color: u32) -> Result<(), String> {
+        windows_lib::set_window_border_color(hwnd, color)
+    }
+
+    fn reset_decorations(&self, hwnd: HWND) -> Result<(), String> {
+        windows_lib::reset_window_decorations(hwnd)
+    }
+
+    fn set_transparency(&self, hwnd: HWND, alpha: u8) -> Result<(), String> {
+        windows_lib::set_window_transparency(hwnd, alpha)
+    }
+
+    fn set_corners(&self, hwnd: HWND, corners: CornerPreference) -> Result<(), String> {
+        windows_lib::set_window_corners(hwnd, corners)
+    }
+
+    fn is_elevated(&self, hwnd: HWND) -> bool {
+        windows_lib::is_window_elevated(hwnd)
+    }
+
+    fn window_title(&self, hwnd: HWND) -> String {
+        windows_lib::get_window_title(hwnd)
+    }
+
+    fn window_class(&self, hwnd: HWND) -> String {
+        windows_lib::get_window_class(hwnd)
+    }
+
+    fn process_name(&self, hwnd: HWND) -> Option<String> {
+        windows_lib::get_process_name_for_window(hwnd)
+    }
+
+    fn is_normal_window(&self, hwnd: HWND) -> bool {
+        windows_lib::is_normal_window_hwnd(hwnd)
+    }
+
+    fn is_minimized(&self, hwnd: HWND) -> bool {
+        windows_lib::is_window_minimized(hwnd)
+    }
+
+    fn close_window(&self, hwnd: HWND) -> Result<(), String> {
+        windows_lib::close_window(hwnd)
+    }
+
+    fn minimize_window(&self, hwnd: HWND) -> Result<(), String> {
+        windows_lib::minimize_window(hwnd)
+    }
+
+    fn set_fullscreen(&self, hwnd: HWND, display: RECT, topmost: bool) -> Result<(), String> {
+        windows_lib::set_window_fullscreen(hwnd, display, topmost)
+    }
+
+    fn exit_fullscreen(&self, hwnd: HWND, rect: RECT, topmost: bool) -> Result<(), String> {
+        windows_lib::restore_window_from_fullscreen(hwnd, rect, topmost)
+    }
+}
+
+#[cfg(test)]
+pub use mock::MockWindowSystem;
+
+#[cfg(test)]
+mod mock {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    /// A window as the mock desktop sees it.
+    #[derive(Debug, Clone, Copy)]
+    struct MockWindow {
+        rect: RECT,
+        visible: bool,
+        topmost: bool,
+        owner: isize,
+        hidden_by: Option<HideMethod>,
+        alpha: u8,
+        border: Option<u32>,
+        elevated: bool,
+        minimized: bool,
+    }
+
+    #[derive(Debug, Default)]
+    struct MockState {
+        monitors: Vec<RECT>,
+        windows: HashMap<isize, MockWindow>,
+        foreground: isize,
+        cursor: (i32, i32),
+    }
+
+    /// In-memory desktop for tests. Clones share the same state, so a test
+    /// can keep one to inspect what the workspace manager did with another.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockWindowSystem {
+        state: Rc<RefCell<MockState>>,
+    }
+
+    impl MockWindowSystem {
+        /// Creates a desktop with a monitor per rect; the first is primary.
+        /// Monitor handles are their index plus one.
+        pub fn new(monitors: &[RECT]) -> Self {
+            let system = Self::default();
+            system.state.borrow_mut().monitors = monitors.to_vec();
+            system
+        }
+
+        /// Disconnects the last monitor.
+        pub fn unplug_last_monitor(&self) {
+            self.state.borrow_mut().monitors.pop();
+        }
+
+        /// Opens a visible window.
+        pub fn open_window(&self, hwnd: isize, rect: RECT) {
+            let window = MockWindow {
+                rect,
+                visible: true,
+                topmost: false,
+                owner: 0,
+                hidden_by: None,
+                alpha: 255,
+                border: None,
+                elevated: false,
+                minimized: false,
+            };
+            self.state.borrow_mut().windows.insert(hwnd, window);
+        }
+
+        /// Opens a visible window owned by `owner`, like a dialog.
+        pub fn open_dialog(&self, hwnd: isize, owner: isize, rect: RECT) {
+            self.open_window(hwnd, rect);
+            let _ = self.with_window(HWND(hwnd as *mut _), |window| window.owner = owner);
+        }
+
+        /// Destroys a window.
+        pub fn close_window(&self, hwnd: isize) {
+            let mut state = self.state.borrow_mut();
+            state.windows.remove(&hwnd);
+            if state.foreground == hwnd {
+                state.foreground = 0;
+            }
+        }
+
+        /// Returns true if the window exists and is shown.
+        pub fn is_visible(&self, hwnd: isize) -> bool {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .is_some_and(|window| window.visible)
+        }
+
+        /// Returns where a window is.
+        pub fn rect(&self, hwnd: isize) -> Option<RECT> {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .map(|window| window.rect)
+        }
+
+        /// Returns a window's opacity, 255 being opaque.
+        pub fn alpha(&self, hwnd: isize) -> Option<u8> {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .map(|window| window.alpha)
+        }
+
+        /// Returns a window's border color, if one was set.
+        pub fn border(&self, hwnd: isize) -> Option<u32> {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .and_then(|window| window.border)
+        }
+
+        /// Marks a window as run by an app running as administrator.
+        pub fn elevate_window(&self, hwnd: isize) {
+            let _ = self.with_window(HWND(hwnd as *mut _), |window| window.elevated = true);
+        }
+
+        /// Returns how a window was hidden, if it is.
+        pub fn hidden_by(&self, hwnd: isize) -> Option<HideMethod> {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .and_then(|window| window.hidden_by)
+        }
+
+        /// Returns true if the window exists and is always on top.
+        pub fn is_topmost(&self, hwnd: isize) -> bool {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .is_some_and(|window| window.topmost)
+        }
+
+        /// Returns true if the window exists and is minimized.
+        pub fn is_minimized(&self, hwnd: isize) -> bool {
+            self.state
+                .borrow()
+                .windows
+                .get(&hwnd)
+                .is_some_and(|window| window.minimized)
+        }
+
+        /// Returns the focused window, 0 if none.
+        pub fn foreground(&self) -> isize {
+            self.state.borrow().foreground
+        }
+
+        fn with_window<T>(
+            &self,
+            hwnd: HWND,
+            f: impl FnOnce(&mut MockWindow) -> T,
+        ) -> Result<T, String> {
+            self.state
+                .borrow_mut()
+                .windows
+                .get_mut(&(hwnd.0 as isize))
+                .map(f)
+                .ok_or_else(|| format!("No window {:?}", hwnd.0))
+        }
+    }
+
+    impl WindowSystem for MockWindowSystem {
+        fn monitors(&self) -> Vec<MonitorInfo> {
+            self.state
+                .borrow()
+                .monitors
+                .iter()
+                .enumerate()
+                .map(|(i, rect)| MonitorInfo {
+                    hmonitor: i as isize + 1,
+                    rect: *rect,
+                    is_primary: i == 0,
+                    device_name: format!("\\\\.\\DISPLAY{}", i + 1),
+                })
+                .collect()
+        }
+
+        fn monitor_for_window(&self, hwnd: HWND) -> isize {
+            let state = self.state.borrow();
+            let Some(window) = state.windows.get(&(hwnd.0 as isize)) else {
+                return 1;
+            };
+            let center_x = (window.rect.left + window.rect.right) / 2;
+            let center_y = (window.rect.top + window.rect.bottom) / 2;
+            state
+                .monitors
+                .iter()
+                .position(|rect| {
+                    (rect.left..rect.right).contains(&center_x)
+                        && (rect.top..rect.bottom).contains(&center_y)
+                })
+                .map_or(1, |i| i as isize + 1)
+        }
+
+        fn foreground_window(&self) -> HWND {
+            HWND(self.state.borrow().foreground as *mut _)
+        }
+
+        fn focus_window(&self, hwnd: HWND) -> bool {
+            let mut state = self.state.borrow_mut();
+            let hwnd = hwnd.0 as isize;
+            if !state
+                .windows
+                .get(&hwnd)
+                .is_some_and(|window| window.visible)
+            {
+                return false;
+            }
+            state.foreground = hwnd;
+            true
+        }
+
+        fn hide_window(&self, hwnd: HWND, method: HideMethod) -> Result<(), String> {
+            self.with_window(hwnd, |window| {
+                window.visible = false;
+                window.hidden_by = Some(method);
+            })?;
+            let mut state = self.state.borrow_mut();
+            if state.foreground == hwnd.0 as isize {
+                state.foreground = 0;
+            }
+            Ok(())
+        }
+
+        fn show_window(&self, hwnd: HWND, _method: HideMethod) -> Result<(), String> {
+            self.with_window(hwnd, |window| {
+                window.visible = true;
+                window.hidden_by = None;
+            })
+        }
+
+        fn raise_window(&self, hwnd: HWND) -> Result<(), String> {
+            self.with_window(hwnd, |_| ())
+        }
+
+        fn set_topmost(&self, hwnd: HWND, topmost: bool) -> Result<(), String> {
+            self.with_window(hwnd, |window| window.topmost = topmost)
+        }
+
+        fn move_windows(&self, moves: &[(HWND, RECT)]) -> Vec<HWND> {
+            for (hwnd, rect) in moves {
+                let _ = self.with_window(*hwnd, |window| window.rect = *rect);
+            }
+            Vec::new()
+        }
+
+        fn window_owner(&self, hwnd: HWND) -> Option<HWND> {
+            self.with_window(hwnd, |window| window.owner)
+                .ok()
+                .filter(|owner| *owner != 0)
+                .map(|owner| HWND(owner as *mut _))
+        }
+
+        fn window_rect(&self, hwnd: HWND) -> Result<RECT, String> {
+            self.with_window(hwnd, |window| window.rect)
+        }
+
+        fn restore_maximized(&self, _hwnd: HWND) {}
+
+        fn invisible_borders(&self, _hwnd: HWND) -> Result<(i32, i32, i32, i32), String> {
+            Ok((0, 0, 0, 0))
+        }
+
+        fn is_window_valid(&self, hwnd: HWND, is_hidden: bool) -> bool {
+            self.state
+                .borrow()
+                .windows
+                .get(&(hwnd.0 as isize))
+                .is_some_and(|window| is_hidden || window.visible)
+        }
+
+        fn cursor_position(&self) -> Result<(i32, i32), String> {
+            Ok(self.state.borrow().cursor)
+        }
+
+        fn set_cursor_position(&self, x: i32, y: i32) -> Result<(), String> {
+            self.state.borrow_mut().cursor = (x, y);
+            Ok(())
+        }
+
+        fn accent_color(&self) -> Result<u32, String> {
+            Ok(0x00D77800)
+        }
+
+        fn theme(&self) -> Theme {
+            Theme::Dark
+        }
+
+        fn set_border_color(&self, hwnd: HWND, color: u32) -> Result<(), String> {
+            self.with_window(hwnd, |window| window.border = Some(color))
+        }
+
+        fn reset_decorations(&self, hwnd: HWND) -> Result<(), String> {
+            self.with_window(hwnd, |window| window.border = None)
+        }
+
+        fn set_transparency(&self, hwnd: HWND, alpha: u8) -> Result<(), String> {
+            self.with_window(hwnd, |window| window.alpha = alpha)
+        }
+
+        fn set_corners(&self, hwnd: HWND, _corners: CornerPreference) -> Result<(), String> {
+            self.with_window(hwnd, |_| ())
+        }
+
+        fn is_elevated(&self, hwnd: HWND) -> bool {
+            self.with_window(hwnd, |window| window.elevated)
+                .unwrap_or(false)
+        }
+
+        fn window_title(&self, _hwnd: HWND) -> String {
+            String::new()
+        }
+
+        fn window_class(&self, _hwnd: HWND) -> String {
+            String::new()
+        }
+
+        fn process_name(&self, _hwnd: HWND) -> Option<String> {
+            None
+        }
+
+        fn is_normal_window(&self, hwnd: HWND) -> bool {
+            self.is_visible(hwnd.0 as isize)
+        }
+
+        fn is_minimized(&self, hwnd: HWND) -> bool {
+            MockWindowSystem::is_minimized(self, hwnd.0 as isize)
+        }
+
+        fn close_window(&self, hwnd: HWND) -> Result<(), String> {
+            self.with_window(hwnd, |_| ())?;
+            MockWindowSystem::close_window(self, hwnd.0 as isize);
+            Ok(())
+        }
+
+        fn minimize_window(&self, hwnd: HWND) -> Result<(), String> {
+            self.with_window(hwnd, |window| window.minimized = true)?;
+            let mut state = self.state.borrow_mut();
+            if state.foreground == hwnd.0 as isize {
+                state.foreground = 0;
+            }
+            Ok(())
+        }
+
+        fn set_fullscreen(&self, hwnd: HWND, display: RECT, topmost: bool) -> Result<(), String> {
+            self.with_window(hwnd, |window| {
+                window.rect = display;
+                window.visible = true;
+                window.topmost |= topmost;
+            })
+        }
+
+        fn exit_fullscreen(&self, hwnd: HWND, rect: RECT, topmost: bool) -> Result<(), String> {
+            self.with_window(hwnd, |window| {
+                window.rect = rect;
+                window.topmost &= !topmost;
+            })
+        }
+    }
+}
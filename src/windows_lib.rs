@@ -7,15 +7,19 @@
 //! - Window decorations (borders, transparency)
 //! - Window positioning and fullscreen management
 
+use crate::config::CornerPreference;
 use windows::Win32::Foundation::{
-    COLORREF, GetLastError, HWND, LPARAM, RECT, SetLastError, TRUE, WIN32_ERROR, WPARAM,
+    COLORREF, CloseHandle, ERROR_ACCESS_DENIED, GetLastError, HANDLE, HWND, LPARAM, POINT, RECT,
+    SetLastError, TRUE, WIN32_ERROR, WPARAM,
 };
 use windows::Win32::Graphics::Dwm::*;
 use windows::Win32::Graphics::Gdi::{
-    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
 };
+use windows::Win32::Security::{GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation};
 use windows::Win32::System::Threading::{
-    OpenProcess, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION, QueryFullProcessImageNameW,
+    OpenProcess, OpenProcessToken, PROCESS_NAME_FORMAT, PROCESS_QUERY_LIMITED_INFORMATION,
+    QueryFullProcessImageNameW,
 };
 use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::core::BOOL;
@@ -77,11 +81,33 @@ unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL
     TRUE
 }
 
-/// Gets the title text of a window.
+/// Gets the title text of a window, however long it is.
 pub fn get_window_title(hwnd: HWND) -> String {
-    let mut title_buffer = [0u16; 256];
-    let length = unsafe { GetWindowTextW(hwnd, &mut title_buffer) };
-    String::from_utf16_lossy(&title_buffer[..length as usize])
+    let length = unsafe { GetWindowTextLengthW(hwnd) };
+    if length <= 0 {
+        return String::new();
+    }
+    // Room for the terminator; the title may also have grown since we asked
+    let mut title_buffer = vec![0u16; length as usize + 1];
+    let copied = unsafe { GetWindowTextW(hwnd, &mut title_buffer) };
+    decode_title(&title_buffer[..copied.max(0) as usize])
+}
+
+/// Decodes a window title, dropping a surrogate pair cut in half by truncation.
+fn decode_title(units: &[u16]) -> String {
+    let units = match units.last() {
+        Some(0xD800..=0xDBFF) => &units[..units.len() - 1],
+        _ => units,
+    };
+    String::from_utf16_lossy(units)
+}
+
+/// Class of File Explorer's folder windows.
+pub const EXPLORER_FRAME_CLASS: &str = "CabinetWClass";
+
+/// Returns true if `hwnd` is a File Explorer folder window.
+pub fn is_explorer_frame(hwnd: HWND) -> bool {
+    get_window_class(hwnd) == EXPLORER_FRAME_CLASS
 }
 
 /// Gets the window class name.
@@ -96,6 +122,15 @@ pub fn get_window_class(hwnd: HWND) -> String {
 /// Returns `Some("process.exe")` on success, `None` on failure.
 /// This is used for app-specific filtering and rules.
 pub fn get_process_name_for_window(hwnd: HWND) -> Option<String> {
+    let full_path = get_process_path_for_window(hwnd)?;
+    std::path::Path::new(&full_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|s| s.to_string())
+}
+
+/// Returns the full path of the executable that owns the window.
+pub fn get_process_path_for_window(hwnd: HWND) -> Option<String> {
     unsafe {
         // Get the process ID for this window
         let mut process_id: u32 = 0;
@@ -120,27 +155,84 @@ pub fn get_process_name_for_window(hwnd: HWND) -> Option<String> {
         )
         .is_ok()
         {
-            // Extract just the filename from the full path
-            let full_path = String::from_utf16_lossy(&path_buffer[..size as usize]);
-            std::path::Path::new(&full_path)
-                .file_name()
-                .and_then(|name| name.to_str())
-                .map(|s| s.to_string())
+            Some(String::from_utf16_lossy(&path_buffer[..size as usize]))
         } else {
             None
         }
     }
 }
 
+/// Returns true if the process that owns the window runs as administrator.
+///
+/// Megatile runs unelevated, so an elevated process usually won't let it
+/// read its token at all; that counts as elevated too.
+pub fn is_window_elevated(hwnd: HWND) -> bool {
+    unsafe {
+        let mut process_id: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        if process_id == 0 {
+            return false;
+        }
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) else {
+            return false;
+        };
+        let mut token = HANDLE::default();
+        let elevated = match OpenProcessToken(process, TOKEN_QUERY, &mut token) {
+            Ok(()) => {
+                let mut elevation = TOKEN_ELEVATION::default();
+                let mut size = 0u32;
+                let read = GetTokenInformation(
+                    token,
+                    TokenElevation,
+                    Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+                    std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+                    &mut size,
+                );
+                let _ = CloseHandle(token);
+                read.is_ok() && elevation.TokenIsElevated != 0
+            }
+            Err(e) => e.code() == ERROR_ACCESS_DENIED.to_hresult(),
+        };
+        let _ = CloseHandle(process);
+        elevated
+    }
+}
+
 use log::debug;
 
+/// How Megatile treats a window it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowKind {
+    /// An application window, which is tiled.
+    Normal,
+    /// A dialog or small popup, managed as a centered floating window when
+    /// `float_transient` is on and ignored otherwise.
+    Transient,
+    /// Not managed.
+    Ignored,
+}
+
 /// Checks if a window handle represents a normal, manageable window.
 pub fn is_normal_window_hwnd(hwnd: HWND) -> bool {
+    let process_name = get_process_name_for_window(hwnd);
+    is_normal_window_hwnd_for_process(hwnd, process_name.as_deref())
+}
+
+/// Like [`is_normal_window_hwnd`], but with an already resolved process name.
+///
+/// Passing `None` skips the app-specific filters, which makes this a cheap
+/// pre-check before the process name is known.
+pub fn is_normal_window_hwnd_for_process(hwnd: HWND, process_name: Option<&str>) -> bool {
+    window_kind_hwnd_for_process(hwnd, process_name) == WindowKind::Normal
+}
+
+/// Like [`window_kind_for_process`], looking up the window's class and title.
+pub fn window_kind_hwnd_for_process(hwnd: HWND, process_name: Option<&str>) -> WindowKind {
     let title = get_window_title(hwnd);
     let class_name = get_window_class(hwnd);
-    let is_normal = is_normal_window(hwnd, &class_name, &title);
-    debug!("is normal? {}", is_normal);
-    is_normal
+    let kind = window_kind_for_process(hwnd, &class_name, &title, process_name);
+    debug!("window kind? {:?}", kind);
+    kind
 }
 
 /// Determines if a window is a "normal" window that should be managed.
@@ -148,6 +240,28 @@ pub fn is_normal_window_hwnd(hwnd: HWND) -> bool {
 /// Filters out system windows, tool windows, invisible windows, popups,
 /// dialogs, and other windows that shouldn't be tiled (taskbar, shell windows, etc.).
 pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
+    let process_name = get_process_name_for_window(hwnd);
+    is_normal_window_for_process(hwnd, class_name, title, process_name.as_deref())
+}
+
+/// Like [`is_normal_window`], but with an already resolved process name.
+pub fn is_normal_window_for_process(
+    hwnd: HWND,
+    class_name: &str,
+    title: &str,
+    process_name: Option<&str>,
+) -> bool {
+    window_kind_for_process(hwnd, class_name, title, process_name) == WindowKind::Normal
+}
+
+/// Sorts a window into tiled, transient (dialogs and small popups) or
+/// unmanaged, with an already resolved process name.
+pub fn window_kind_for_process(
+    hwnd: HWND,
+    class_name: &str,
+    title: &str,
+    process_name: Option<&str>,
+) -> WindowKind {
     debug!(
         "Checking if window, title {}, class name {}, hwnd {:?}, is 'normal'.",
         title, class_name, hwnd
@@ -155,18 +269,18 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
     unsafe {
         // Basic visibility check
         if !IsWindowVisible(hwnd).as_bool() {
-            return false;
+            return WindowKind::Ignored;
         }
 
         // Filter minimized windows
         if IsIconic(hwnd).as_bool() {
-            return false;
+            return WindowKind::Ignored;
         }
 
         // Verify the window handle is still valid
         if !IsWindow(Some(hwnd)).as_bool() {
             debug!("Filtered: invalid window handle");
-            return false;
+            return WindowKind::Ignored;
         }
 
         // Filter specific problematic window titles
@@ -186,20 +300,20 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
         for filtered_title in &filtered_titles {
             if title == *filtered_title {
                 debug!("Filtered: problematic title {}", title);
-                return false;
+                return WindowKind::Ignored;
             }
         }
 
         // Filter empty titles (often system windows)
         if title.is_empty() {
             debug!("Filtered: empty title");
-            return false;
+            return WindowKind::Ignored;
         }
 
         // App-specific filtering by process name
         // These are applications known to create problematic splash/login/hidden windows
         // that don't get destroyed properly and cause "zombie" window issues
-        if let Some(process_name) = crate::windows_lib::get_process_name_for_window(hwnd) {
+        if let Some(process_name) = process_name {
             let process_name_lower = process_name.to_lowercase();
 
             // Zoom: Known to hide login/splash windows instead of destroying them
@@ -207,19 +321,19 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
             // Don't filter the main workspace frame though as we want that.
             if process_name_lower == "zoom.exe" && class_name != "ZPPTMainFrmWndClassEx" {
                 debug!("Filtered: Zoom.exe window (known to create zombie windows)");
-                return false;
+                return WindowKind::Ignored;
             }
 
             // Steam: steamwebhelper.exe creates many hidden helper windows
             if process_name_lower == "steamwebhelper.exe" && title != "Steam" {
                 debug!("Filtered: Steam helper window");
-                return false;
+                return WindowKind::Ignored;
             }
 
             // Epic Games Launcher: Creates hidden launcher windows
             if process_name_lower == "epicgameslauncher.exe" && class_name == "UnrealWindow" {
                 debug!("Filtered: Epic Games Launcher splash");
-                return false;
+                return WindowKind::Ignored;
             }
         }
 
@@ -233,28 +347,30 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
         );
         if cloaked != 0 {
             debug!("Filtered: cloaked window");
-            return false;
+            return WindowKind::Ignored;
         }
 
         let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
         let style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        // Dialogs and small popups pass the remaining checks as transient windows
+        let mut kind = WindowKind::Normal;
 
         // Filter tool windows
         if ex_style & WS_EX_TOOLWINDOW.0 != 0 {
             debug!("Filtered: tool window");
-            return false;
+            return WindowKind::Ignored;
         }
 
         // Filter non-activatable windows
         if ex_style & WS_EX_NOACTIVATE.0 != 0 {
             debug!("Filtered: non-activatable");
-            return false;
+            return WindowKind::Ignored;
         }
 
-        // Filter dialog modal frame windows (explicit dialogs)
+        // Dialog modal frame windows (explicit dialogs)
         if ex_style & WS_EX_DLGMODALFRAME.0 != 0 {
-            debug!("Filtered: dialog modal frame");
-            return false;
+            debug!("Transient: dialog modal frame");
+            kind = WindowKind::Transient;
         }
 
         // Filter transparent layered windows with 0 alpha
@@ -272,15 +388,15 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
                 && alpha == 0
             {
                 debug!("Filtered: fully transparent layered window");
-                return false;
+                return WindowKind::Ignored;
             }
         }
 
-        // Filter owned windows (typically dialogs)
+        // Owned windows (typically dialogs)
         let owner = GetWindow(hwnd, GW_OWNER);
         if owner.is_ok() && !owner.unwrap().0.is_null() {
-            debug!("Filtered: owned window (dialog)");
-            return false;
+            debug!("Transient: owned window (dialog)");
+            kind = WindowKind::Transient;
         }
 
         // Check window size - filter very small windows (tooltips, notifications)
@@ -289,38 +405,38 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
             let width = rect.right - rect.left;
             let height = rect.bottom - rect.top;
 
-            // Filter windows smaller than 100x100 (likely tooltips, popups)
-            if width < 100 || height < 100 {
-                debug!("Filtered: too small ({}x{})", width, height);
-                return false;
-            }
-
             // Filter zero-size windows
             if width <= 0 || height <= 0 {
                 debug!("Filtered: zero size");
-                return false;
+                return WindowKind::Ignored;
+            }
+
+            // Windows smaller than 100x100 are likely popups
+            if width < 100 || height < 100 {
+                debug!("Transient: too small ({}x{})", width, height);
+                kind = WindowKind::Transient;
             }
 
             // Filter windows positioned entirely off-screen (likely hidden)
             // This helps filter ghost windows
             if rect.right < -1000 || rect.bottom < -1000 || rect.left > 10000 || rect.top > 10000 {
                 debug!("Filtered: positioned off-screen");
-                return false;
+                return WindowKind::Ignored;
             }
         } else {
             debug!("Filtered: couldn't get window rect");
-            return false;
+            return WindowKind::Ignored;
         }
 
-        // Filter popup windows without resizable frame (likely dialogs)
+        // Popup windows without resizable frame (likely dialogs)
         let is_popup = style & WS_POPUP.0 != 0;
         let has_thick_frame = style & WS_THICKFRAME.0 != 0;
         let has_caption = style & WS_CAPTION.0 != 0;
 
         // A popup without thick frame and without app window style is likely a dialog
         if is_popup && !has_thick_frame && (ex_style & WS_EX_APPWINDOW.0 == 0) {
-            debug!("Filtered: popup without thick frame");
-            return false;
+            debug!("Transient: popup without thick frame");
+            kind = WindowKind::Transient;
         }
 
         // System class filtering
@@ -336,9 +452,11 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
             "tooltips_class32",
             "IME",
             "MSCTFIME UI",
-            "#32770", // Standard Windows dialog class
+            "#32768", // Menus
             "SysShadow",
             "MegatileStatusBar", // Filter our own status bar
+            "MegatileSettings",  // and the settings window
+            "MegatileAbout",     // and the About window
             "TaskListThumbnailWnd",
             "TaskSwitcherWnd",
             "TaskSwitcherOverlayWnd",
@@ -351,36 +469,47 @@ pub fn is_normal_window(hwnd: HWND, class_name: &str, title: &str) -> bool {
         for sys_class in &system_classes {
             if class_name.eq_ignore_ascii_case(sys_class) {
                 debug!("Filtered: system class {}", sys_class);
-                return false;
+                return WindowKind::Ignored;
             }
         }
 
+        // Standard Windows dialog class
+        if class_name == "#32770" {
+            debug!("Transient: dialog class");
+            kind = WindowKind::Transient;
+        }
+
         // Accept windows with WS_EX_APPWINDOW (explicitly meant for taskbar)
         if ex_style & WS_EX_APPWINDOW.0 != 0 {
             debug!("Is normal, case 1: WS_EX_APPWINDOW");
-            return true;
+            return kind;
         }
 
         // Accept windows with a title that have both caption and thick frame (resizable)
         if has_caption && has_thick_frame {
             debug!("Is normal, case 2: titled with caption and thick frame");
-            return true;
+            return kind;
         }
 
         // Accept windows with a title and overlapped style (standard app window)
         if style & WS_OVERLAPPEDWINDOW.0 != 0 {
             debug!("Is normal, case 3: titled with overlapped window style");
-            return true;
+            return kind;
         }
 
         // Accept captioned windows
         if has_caption {
             debug!("Is normal, case 4: has caption");
-            return true;
+            return kind;
+        }
+
+        if kind == WindowKind::Transient {
+            debug!("Is transient: small or dialog-like, but otherwise normal");
+            return kind;
         }
 
         debug!("Filtered: doesn't match any normal window criteria");
-        false
+        WindowKind::Ignored
     }
 }
 
@@ -540,6 +669,44 @@ pub fn show_window_in_taskbar(hwnd: HWND) -> Result<(), String> {
     }
 }
 
+/// How far windows hidden off-screen are moved, well past any real monitor.
+const OFFSCREEN_SHIFT: i32 = 32000;
+
+/// Hides a window by moving it far off-screen, keeping its size. It stays
+/// on the taskbar and keeps rendering.
+pub fn move_window_offscreen(hwnd: HWND) -> Result<(), String> {
+    let rect = get_window_rect(hwnd)?;
+    if rect.left <= -OFFSCREEN_SHIFT / 2 {
+        return Ok(());
+    }
+    shift_window(hwnd, &rect, -OFFSCREEN_SHIFT)
+}
+
+/// Moves a window hidden by `move_window_offscreen` back where it was.
+/// Windows that aren't off-screen are left alone.
+pub fn restore_offscreen_window(hwnd: HWND) -> Result<(), String> {
+    let rect = get_window_rect(hwnd)?;
+    if rect.left > -OFFSCREEN_SHIFT / 2 {
+        return Ok(());
+    }
+    shift_window(hwnd, &rect, OFFSCREEN_SHIFT)
+}
+
+fn shift_window(hwnd: HWND, rect: &RECT, dx: i32) -> Result<(), String> {
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            None,
+            rect.left + dx,
+            rect.top,
+            0,
+            0,
+            SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+        )
+        .map_err(|e| format!("Failed to move window: {}", e))
+    }
+}
+
 /// Gets the bounding rectangle of a window.
 pub fn get_window_rect(hwnd: HWND) -> Result<RECT, String> {
     let mut rect = RECT::default();
@@ -549,7 +716,22 @@ pub fn get_window_rect(hwnd: HWND) -> Result<RECT, String> {
     Ok(rect)
 }
 
+/// Gets the mouse cursor position in screen coordinates.
+pub fn get_cursor_position() -> Result<(i32, i32), String> {
+    let mut point = POINT::default();
+    unsafe {
+        GetCursorPos(&mut point).map_err(|e| e.to_string())?;
+    }
+    Ok((point.x, point.y))
+}
+
+/// Moves the mouse cursor to a point in screen coordinates.
+pub fn set_cursor_position(x: i32, y: i32) -> Result<(), String> {
+    unsafe { SetCursorPos(x, y).map_err(|e| e.to_string()) }
+}
+
 /// Information about a display monitor.
+#[derive(Debug, Clone)]
 pub struct MonitorInfo {
     /// Windows HMONITOR handle as isize.
     pub hmonitor: isize,
@@ -557,6 +739,8 @@ pub struct MonitorInfo {
     pub rect: RECT,
     /// Whether this is the primary monitor.
     pub is_primary: bool,
+    /// GDI device name, e.g. `\\.\DISPLAY1`, used to pick monitors in the config.
+    pub device_name: String,
 }
 
 /// Enumerates all connected display monitors.
@@ -572,16 +756,30 @@ pub fn enumerate_monitors() -> Vec<MonitorInfo> {
         unsafe {
             let monitors = &mut *(lparam.0 as *mut Vec<MonitorInfo>);
 
-            let mut info = MONITORINFO {
-                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            let mut info = MONITORINFOEXW {
+                monitorInfo: MONITORINFO {
+                    cbSize: std::mem::size_of::<MONITORINFOEXW>() as u32,
+                    ..Default::default()
+                },
                 ..Default::default()
             };
 
-            if GetMonitorInfoW(hmonitor, &mut info).as_bool() {
+            if GetMonitorInfoW(
+                hmonitor,
+                &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+            )
+            .as_bool()
+            {
+                let name_len = info
+                    .szDevice
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(info.szDevice.len());
                 monitors.push(MonitorInfo {
                     hmonitor: hmonitor.0 as isize,
-                    rect: info.rcMonitor,
-                    is_primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+                    rect: info.monitorInfo.rcMonitor,
+                    is_primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+                    device_name: String::from_utf16_lossy(&info.szDevice[..name_len]),
                 });
             }
 
@@ -606,6 +804,18 @@ pub fn is_window_minimized(hwnd: HWND) -> bool {
     unsafe { IsIconic(hwnd).as_bool() }
 }
 
+/// Checks if a window is currently maximized.
+pub fn is_window_maximized(hwnd: HWND) -> bool {
+    unsafe { IsZoomed(hwnd).as_bool() }
+}
+
+/// Restores a maximized window to its normal size, without activating it.
+pub fn unmaximize_window(hwnd: HWND) {
+    unsafe {
+        let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+    }
+}
+
 /// Closes a window gracefully by sending WM_CLOSE.
 pub fn close_window(hwnd: HWND) -> Result<(), String> {
     unsafe {
@@ -616,13 +826,36 @@ pub fn close_window(hwnd: HWND) -> Result<(), String> {
     }
 }
 
+/// Returns the window that owns `hwnd` (e.g. a dialog's main window), if any.
+pub fn get_window_owner(hwnd: HWND) -> Option<HWND> {
+    unsafe { GetWindow(hwnd, GW_OWNER) }
+        .ok()
+        .filter(|owner| !owner.0.is_null())
+}
+
+/// Minimizes a window to the taskbar.
+pub fn minimize_window(hwnd: HWND) -> Result<(), String> {
+    unsafe {
+        // Returns whether the window was visible before, not success
+        let _ = ShowWindow(hwnd, SW_MINIMIZE);
+    }
+    if is_window_minimized(hwnd) {
+        Ok(())
+    } else {
+        Err("Window refused to minimize".to_string())
+    }
+}
+
 /// Sets a window to fullscreen mode covering the specified monitor.
-pub fn set_window_fullscreen(hwnd: HWND, monitor_rect: RECT) -> Result<(), String> {
+/// Without `topmost` it is only raised, so popups and other monitors'
+/// dialogs can still appear above it.
+pub fn set_window_fullscreen(hwnd: HWND, monitor_rect: RECT, topmost: bool) -> Result<(), String> {
+    let insert_after = if topmost { HWND_TOPMOST } else { HWND_TOP };
     unsafe {
         // Set window to fullscreen
         SetWindowPos(
             hwnd,
-            Some(HWND_TOPMOST),
+            Some(insert_after),
             monitor_rect.left,
             monitor_rect.top,
             monitor_rect.right - monitor_rect.left,
@@ -635,18 +868,68 @@ pub fn set_window_fullscreen(hwnd: HWND, monitor_rect: RECT) -> Result<(), Strin
     }
 }
 
+/// Brings a window to the top of the (non-topmost) z-order without activating it.
+pub fn raise_window(hwnd: HWND) -> Result<(), String> {
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            Some(HWND_TOP),
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+        )
+        .map_err(|e| format!("Failed to raise window: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Puts a window in or out of the always-on-top band.
+pub fn set_window_topmost(hwnd: HWND, topmost: bool) -> Result<(), String> {
+    let insert_after = if topmost {
+        HWND_TOPMOST
+    } else {
+        HWND_NOTOPMOST
+    };
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            Some(insert_after),
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+        )
+        .map_err(|e| format!("Failed to set always-on-top: {}", e))
+    }
+}
+
 /// Restores a window from fullscreen to its original position.
-pub fn restore_window_from_fullscreen(hwnd: HWND, original_rect: RECT) -> Result<(), String> {
+/// `topmost` must match how it was fullscreened: a topmost window is dropped
+/// back to the normal band, otherwise its z-order is left alone.
+pub fn restore_window_from_fullscreen(
+    hwnd: HWND,
+    original_rect: RECT,
+    topmost: bool,
+) -> Result<(), String> {
+    let (insert_after, z_flags) = if topmost {
+        (Some(HWND_NOTOPMOST), SET_WINDOW_POS_FLAGS(0))
+    } else {
+        (None, SWP_NOZORDER)
+    };
     unsafe {
         // Restore original position and size
         SetWindowPos(
             hwnd,
-            Some(HWND_NOTOPMOST),
+            insert_after,
             original_rect.left,
             original_rect.top,
             original_rect.right - original_rect.left,
             original_rect.bottom - original_rect.top,
-            SWP_SHOWWINDOW | SWP_NOACTIVATE,
+            SWP_SHOWWINDOW | SWP_NOACTIVATE | z_flags,
         )
         .map_err(|e| format!("Failed to restore window from fullscreen: {}", e))?;
 
@@ -686,6 +969,27 @@ pub fn set_window_border_color(hwnd: HWND, color: u32) -> Result<(), String> {
     Ok(())
 }
 
+/// Sets how DWM rounds the window's corners (Windows 11 only; older
+/// versions reject the attribute).
+pub fn set_window_corners(hwnd: HWND, corners: CornerPreference) -> Result<(), String> {
+    let preference = match corners {
+        CornerPreference::Default => DWMWCP_DEFAULT,
+        CornerPreference::Round => DWMWCP_ROUND,
+        CornerPreference::Small => DWMWCP_ROUNDSMALL,
+        CornerPreference::Square => DWMWCP_DONOTROUND,
+    };
+    unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_WINDOW_CORNER_PREFERENCE,
+            &preference as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
+        )
+        .map_err(|e| format!("Failed to set window corners: {}", e))?;
+    }
+    Ok(())
+}
+
 /// Sets the window transparency level.
 ///
 /// # Arguments
@@ -737,6 +1041,57 @@ pub fn set_window_transparency(hwnd: HWND, alpha: u8) -> Result<(), String> {
     Ok(())
 }
 
+/// Removes the title bar and resize frame from a window.
+/// Returns the previous style so it can be put back with [`restore_window_style`].
+pub fn strip_window_frame(hwnd: HWND) -> Result<i32, String> {
+    let style = unsafe {
+        SetLastError(WIN32_ERROR(0));
+        let style = GetWindowLongW(hwnd, GWL_STYLE);
+        if style == 0 && GetLastError() != WIN32_ERROR(0) {
+            return Err(format!(
+                "Failed to read window style: {}",
+                windows::core::Error::from_thread()
+            ));
+        }
+        style
+    };
+    set_window_style(
+        hwnd,
+        (style as u32 & !(WS_CAPTION.0 | WS_THICKFRAME.0)) as i32,
+    )?;
+    Ok(style)
+}
+
+/// Puts back a window style saved by [`strip_window_frame`].
+pub fn restore_window_style(hwnd: HWND, style: i32) -> Result<(), String> {
+    set_window_style(hwnd, style)
+}
+
+fn set_window_style(hwnd: HWND, style: i32) -> Result<(), String> {
+    unsafe {
+        SetLastError(WIN32_ERROR(0));
+        let result = SetWindowLongW(hwnd, GWL_STYLE, style);
+        if result == 0 && GetLastError() != WIN32_ERROR(0) {
+            return Err(format!(
+                "Failed to set window style: {}",
+                windows::core::Error::from_thread()
+            ));
+        }
+        // The frame is cached until told it changed
+        SetWindowPos(
+            hwnd,
+            None,
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_FRAMECHANGED | SWP_NOACTIVATE,
+        )
+        .map_err(|e| format!("Failed to update window frame: {}", e))?;
+    }
+    Ok(())
+}
+
 /// Resets window decorations to default (removes custom border color and transparency).
 pub fn reset_window_decorations(hwnd: HWND) -> Result<(), String> {
     set_window_border_color(hwnd, DWMWA_COLOR_DEFAULT)?;
@@ -761,17 +1116,22 @@ pub fn get_dwm_frame_bounds(hwnd: HWND) -> Result<RECT, String> {
 }
 
 /// Gets the invisible border sizes for a window (difference between window rect and DWM frame).
-/// Returns (left, top, right, bottom) border sizes.
+/// Returns (left, top, right, bottom) border sizes, or zeros if they cannot be measured.
 pub fn get_invisible_borders(hwnd: HWND) -> (i32, i32, i32, i32) {
-    let window_rect = match get_window_rect(hwnd) {
-        Ok(r) => r,
-        Err(_) => return (0, 0, 0, 0),
-    };
+    measure_invisible_borders(hwnd).unwrap_or((0, 0, 0, 0))
+}
 
-    let frame_rect = match get_dwm_frame_bounds(hwnd) {
-        Ok(r) => r,
-        Err(_) => return (0, 0, 0, 0),
-    };
+/// Measures the invisible border sizes for a window.
+///
+/// Fails if the window or DWM frame rect cannot be read, or if the window is
+/// minimized (its rects are parked off-screen and say nothing about its borders).
+pub fn measure_invisible_borders(hwnd: HWND) -> Result<(i32, i32, i32, i32), String> {
+    if is_window_minimized(hwnd) {
+        return Err("Window is minimized".to_string());
+    }
+
+    let window_rect = get_window_rect(hwnd)?;
+    let frame_rect = get_dwm_frame_bounds(hwnd)?;
 
     // Calculate the invisible borders
     let left = frame_rect.left - window_rect.left;
@@ -779,13 +1139,18 @@ pub fn get_invisible_borders(hwnd: HWND) -> (i32, i32, i32, i32) {
     let right = window_rect.right - frame_rect.right;
     let bottom = window_rect.bottom - frame_rect.bottom;
 
-    (left, top, right, bottom)
+    Ok((left, top, right, bottom))
 }
 
 /// Adjusts a target rect to compensate for DWM invisible borders.
 /// Returns a rect that, when set, will result in the visible area matching the target.
 pub fn adjust_rect_for_dwm_borders(hwnd: HWND, target: &RECT) -> RECT {
-    let (left_border, top_border, right_border, bottom_border) = get_invisible_borders(hwnd);
+    expand_rect_by_borders(target, get_invisible_borders(hwnd))
+}
+
+/// Grows `target` by previously measured (left, top, right, bottom) invisible borders.
+pub fn expand_rect_by_borders(target: &RECT, borders: (i32, i32, i32, i32)) -> RECT {
+    let (left_border, top_border, right_border, bottom_border) = borders;
 
     RECT {
         left: target.left - left_border,
@@ -794,3 +1159,17 @@ pub fn adjust_rect_for_dwm_borders(hwnd: HWND, target: &RECT) -> RECT {
         bottom: target.bottom + bottom_border,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_cut_mid_emoji_drop_the_broken_half() {
+        let title: Vec<u16> = "Chat 🎉 会議".encode_utf16().collect();
+        assert_eq!(decode_title(&title), "Chat 🎉 会議");
+        // Cut after the high surrogate of the emoji
+        assert_eq!(decode_title(&title[..6]), "Chat ");
+        assert_eq!(decode_title(&[]), "");
+    }
+}
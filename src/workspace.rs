@@ -5,8 +5,14 @@
 //! - [`Workspace`] - A collection of windows with layout state
 //! - [`Monitor`] - A physical display with multiple workspaces
 
+use std::time::Instant;
 use windows::Win32::Foundation::{HWND, RECT};
 
+/// Workspaces per monitor unless `[workspaces] count` says otherwise.
+pub const DEFAULT_WORKSPACES: u8 = 9;
+/// Most workspaces a monitor can have.
+pub const MAX_WORKSPACES: u8 = 20;
+
 /// Represents a window managed by Megatile.
 ///
 /// Each window tracks its position, workspace assignment, tiling state,
@@ -20,9 +26,16 @@ pub struct Window {
     pub is_focused: bool,
     pub is_tiled: bool,
     pub original_rect: RECT, // For restoring from fullscreen/hidden state
+    pub initial_rect: RECT,  // Where the window was when Megatile started managing it
     pub is_fullscreen: bool,
+    pub fullscreen_topmost: bool, // Whether fullscreen was entered always on top
     pub process_name: Option<String>, // Process name (e.g., "Zoom.exe") for app-specific rules
     pub is_hidden_by_workspace: bool, // True when intentionally hidden due to workspace switching
+    pub title: String,            // Last known window title, kept current as the app changes it
+    pub class: String,            // Window class name, for template slot rules
+    pub registered_at: Instant,   // When Megatile started managing the window
+    pub tile_group: Option<isize>, // Window whose tile this one shares, if grouped with it
+    pub is_topmost: bool,         // Kept always on top by Megatile (floating windows only)
 }
 
 impl Window {
@@ -42,9 +55,16 @@ impl Window {
             is_focused: false,
             is_tiled: true,
             original_rect: rect,
+            initial_rect: rect,
             is_fullscreen: false,
+            fullscreen_topmost: false,
             process_name,
             is_hidden_by_workspace: false, // New windows start visible (added to active workspace)
+            title: String::new(),
+            class: String::new(),
+            registered_at: Instant::now(),
+            tile_group: None,
+            is_topmost: false,
         }
     }
 }
@@ -61,6 +81,12 @@ pub struct Workspace {
     pub focused_window_hwnd: Option<isize>,
     /// The tiling layout tree for this workspace.
     pub layout_tree: Option<crate::tiling::Tile>,
+    /// Window centered alone in zen mode; the other tiled windows are hidden.
+    pub zen_window: Option<isize>,
+    /// Window expanded over the work area; the other tiles stay behind it.
+    pub maximized_window: Option<isize>,
+    /// Still filling its `[[templates]]` layout, having started out empty.
+    pub filling_template: bool,
 }
 
 impl Workspace {
@@ -70,18 +96,24 @@ impl Workspace {
             windows: Vec::new(),
             focused_window_hwnd: None,
             layout_tree: None,
+            zen_window: None,
+            maximized_window: None,
+            filling_template: false,
         }
     }
 
     /// Adds a window to this workspace, setting it as focused if no window is focused.
     pub fn add_window(&mut self, window: Window) {
+        self.insert_window(self.windows.len(), window);
+    }
+
+    /// Adds a window at `index` in tiling order. Earlier windows get the
+    /// tiles further left and up, so index 0 is the top-left tile.
+    pub fn insert_window(&mut self, index: usize, window: Window) {
         if self.focused_window_hwnd.is_none() && window.is_tiled {
             self.focused_window_hwnd = Some(window.hwnd);
         }
-        self.windows.push(window);
-
-        // Clear the layout tree when windows change - force fresh layout calculation
-        self.layout_tree = None;
+        self.windows.insert(index.min(self.windows.len()), window);
     }
 
     /// Removes a window by handle, returning it if found.
@@ -92,8 +124,20 @@ impl Workspace {
         if self.focused_window_hwnd == Some(hwnd_val) {
             self.focused_window_hwnd = None;
         }
+        if self.zen_window == Some(hwnd_val) {
+            self.zen_window = None;
+        }
+        if self.maximized_window == Some(hwnd_val) {
+            self.maximized_window = None;
+        }
 
-        let removed = self.windows.remove(pos);
+        self.leave_tile_group(hwnd_val);
+        let mut removed = self.windows.remove(pos);
+        removed.tile_group = None;
+        // The template only shapes a workspace filling up from empty
+        if removed.is_tiled {
+            self.filling_template = false;
+        }
 
         // If we removed the focused window, try to focus another one
         if self.focused_window_hwnd.is_none()
@@ -102,9 +146,6 @@ impl Workspace {
             self.focused_window_hwnd = Some(first_tiled.hwnd);
         }
 
-        // Clear the layout tree when windows change - force fresh layout calculation
-        self.layout_tree = None;
-
         Some(removed)
     }
 
@@ -118,12 +159,62 @@ impl Workspace {
         self.windows.iter_mut().find(|w| w.hwnd == hwnd.0 as isize)
     }
 
+    /// Returns the windows sharing `hwnd`'s tile, the one holding the tile
+    /// first. A window that isn't grouped is alone in its group.
+    pub fn tile_group(&self, hwnd: isize) -> Vec<isize> {
+        let host = self
+            .windows
+            .iter()
+            .find(|w| w.hwnd == hwnd)
+            .and_then(|w| w.tile_group)
+            .unwrap_or(hwnd);
+        std::iter::once(host)
+            .chain(
+                self.windows
+                    .iter()
+                    .filter(|w| w.tile_group == Some(host))
+                    .map(|w| w.hwnd),
+            )
+            .collect()
+    }
+
+    /// Takes `hwnd` out of its tile group. If it held the group's tile, the
+    /// next window in the group takes the tile over.
+    pub fn leave_tile_group(&mut self, hwnd: isize) {
+        let group = self.tile_group(hwnd);
+        if group[0] != hwnd {
+            if let Some(window) = self.windows.iter_mut().find(|w| w.hwnd == hwnd) {
+                window.tile_group = None;
+            }
+            return;
+        }
+        let Some(&new_host) = group.get(1) else {
+            return;
+        };
+        for window in &mut self.windows {
+            if window.hwnd == new_host {
+                window.tile_group = None;
+            } else if window.tile_group == Some(hwnd) {
+                window.tile_group = Some(new_host);
+            }
+        }
+        if let Some(tree) = self.layout_tree.as_mut() {
+            tree.replace_window(hwnd, new_host);
+        }
+    }
+
     /// Returns the count of tiled windows only.
     pub fn window_count(&self) -> usize {
         self.windows.iter().filter(|w| w.is_tiled).count()
     }
 }
 
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Represents a physical monitor with multiple workspaces.
 ///
 /// Each monitor has 9 workspaces (1-9), with one active at a time.
@@ -132,11 +223,22 @@ impl Workspace {
 pub struct Monitor {
     /// Windows HMONITOR handle as isize.
     pub hmonitor: isize,
-    /// Monitor screen bounds.
+    /// Bounds tiled into; a column of the display when it is split into zones.
     pub rect: RECT,
-    /// Array of 9 workspaces (indices 0-8 map to workspaces 1-9).
-    pub workspaces: [Workspace; 9],
-    /// Currently active workspace number (1-9).
+    /// Bounds of the whole physical display, used for fullscreen.
+    pub display_rect: RECT,
+    /// Index of this zone on its display (0 when the display isn't split).
+    pub zone: usize,
+    /// Device name of the display, e.g. `\\.\DISPLAY2`.
+    pub device_name: String,
+    /// False while the display is left alone: its windows aren't tiled,
+    /// hidden or decorated.
+    pub managed: bool,
+    /// How windows on this monitor are tiled.
+    pub layout: crate::tiling::TileLayout,
+    /// Workspaces by number (index 0 is workspace 1). All monitors have the same count.
+    pub workspaces: Vec<Workspace>,
+    /// Currently active workspace number (1-based).
     pub active_workspace: u8,
 }
 
@@ -146,7 +248,12 @@ impl Monitor {
         Monitor {
             hmonitor,
             rect,
-            workspaces: std::array::from_fn(|_| Workspace::new()),
+            display_rect: rect,
+            zone: 0,
+            device_name: String::new(),
+            managed: true,
+            layout: crate::tiling::TileLayout::default_for(rect),
+            workspaces: vec![Workspace::new(); DEFAULT_WORKSPACES as usize],
             active_workspace: 1,
         }
     }
@@ -156,31 +263,59 @@ impl Monitor {
         &self.workspaces[(self.active_workspace - 1) as usize]
     }
 
-    /// Returns a workspace by number (1-9).
+    /// Returns the number of workspaces on this monitor.
+    pub fn workspace_count(&self) -> u8 {
+        self.workspaces.len() as u8
+    }
+
+    /// Sets the number of workspaces, adding empty ones or dropping the
+    /// highest-numbered ones. Only call this before windows are added.
+    pub fn set_workspace_count(&mut self, count: u8) {
+        let count = count.clamp(1, MAX_WORKSPACES);
+        self.workspaces.resize(count as usize, Workspace::new());
+        self.active_workspace = self.active_workspace.min(count);
+    }
+
+    /// Returns a workspace by number (1-based).
     pub fn get_workspace(&self, workspace_num: u8) -> Option<&Workspace> {
-        if !(1..=9).contains(&workspace_num) {
-            return None;
-        }
-        Some(&self.workspaces[(workspace_num - 1) as usize])
+        self.workspaces
+            .get((workspace_num as usize).checked_sub(1)?)
     }
 
-    /// Returns a mutable workspace by number (1-9).
+    /// Returns a mutable workspace by number (1-based).
     pub fn get_workspace_mut(&mut self, workspace_num: u8) -> Option<&mut Workspace> {
-        if !(1..=9).contains(&workspace_num) {
-            return None;
-        }
-        Some(&mut self.workspaces[(workspace_num - 1) as usize])
+        self.workspaces
+            .get_mut((workspace_num as usize).checked_sub(1)?)
     }
 
     /// Sets the active workspace. Returns false if the workspace number is invalid.
     pub fn set_active_workspace(&mut self, workspace_num: u8) -> bool {
-        if !(1..=9).contains(&workspace_num) {
+        if !(1..=self.workspace_count()).contains(&workspace_num) {
             return false;
         }
         self.active_workspace = workspace_num;
         true
     }
 
+    /// Removes workspace `workspace_num`, shifting the ones after it down by
+    /// one and adding an empty workspace at the end. The workspace should be
+    /// empty; any windows still on it are dropped.
+    pub fn remove_workspace(&mut self, workspace_num: u8) {
+        if !(1..=self.workspace_count()).contains(&workspace_num) {
+            return;
+        }
+        self.workspaces.remove(workspace_num as usize - 1);
+        self.workspaces.push(Workspace::new());
+        for workspace in &mut self.workspaces[workspace_num as usize - 1..] {
+            for window in &mut workspace.windows {
+                window.workspace -= 1;
+            }
+        }
+        if self.active_workspace > workspace_num {
+            self.active_workspace -= 1;
+        }
+    }
+
     /// Adds a window to the appropriate workspace based on its workspace field.
     pub fn add_window(&mut self, window: Window) {
         if let Some(workspace) = self.get_workspace_mut(window.workspace) {
@@ -208,3 +343,154 @@ impl Monitor {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(hwnd: isize, workspace: u8) -> Window {
+        Window::new(hwnd, workspace, 0, RECT::default(), None)
+    }
+
+    #[test]
+    fn first_tiled_window_becomes_focus_target() {
+        let mut workspace = Workspace::new();
+        let mut floating = window(1, 1);
+        floating.is_tiled = false;
+        workspace.add_window(floating);
+        assert_eq!(workspace.focused_window_hwnd, None);
+
+        workspace.add_window(window(2, 1));
+        workspace.add_window(window(3, 1));
+        assert_eq!(workspace.focused_window_hwnd, Some(2));
+        assert_eq!(workspace.window_count(), 2);
+    }
+
+    #[test]
+    fn inserted_windows_take_their_place_in_tiling_order() {
+        let mut workspace = Workspace::new();
+        workspace.add_window(window(1, 1));
+        workspace.insert_window(0, window(2, 1));
+        workspace.insert_window(10, window(3, 1));
+
+        let order: Vec<isize> = workspace.windows.iter().map(|w| w.hwnd).collect();
+        assert_eq!(order, vec![2, 1, 3]);
+        assert_eq!(workspace.focused_window_hwnd, Some(1));
+    }
+
+    #[test]
+    fn removing_focused_window_refocuses_first_tiled() {
+        let mut workspace = Workspace::new();
+        workspace.add_window(window(1, 1));
+        workspace.add_window(window(2, 1));
+
+        let removed = workspace.remove_window(HWND(1 as _));
+        assert_eq!(removed.map(|w| w.hwnd), Some(1));
+        assert_eq!(workspace.focused_window_hwnd, Some(2));
+        assert!(workspace.remove_window(HWND(1 as _)).is_none());
+    }
+
+    #[test]
+    fn monitor_rejects_out_of_range_workspaces() {
+        let mut monitor = Monitor::new(0, RECT::default());
+        assert!(monitor.get_workspace(0).is_none());
+        assert!(monitor.get_workspace(10).is_none());
+        assert!(!monitor.set_active_workspace(10));
+        assert!(monitor.set_active_workspace(9));
+        assert_eq!(monitor.active_workspace, 9);
+        assert!(!monitor.set_active_workspace(10));
+
+        monitor.set_workspace_count(12);
+        assert!(monitor.set_active_workspace(12));
+        assert!(monitor.get_workspace(12).is_some());
+        assert!(monitor.get_workspace(13).is_none());
+        assert!(monitor.get_workspace(0).is_none());
+    }
+
+    #[test]
+    fn removing_a_workspace_shifts_later_ones_down() {
+        let mut monitor = Monitor::new(0, RECT::default());
+        monitor.add_window(window(1, 1));
+        monitor.add_window(window(4, 4));
+        monitor.set_active_workspace(4);
+
+        monitor.remove_workspace(2);
+        assert_eq!(monitor.workspace_count(), 9);
+        assert_eq!(monitor.active_workspace, 3);
+        assert_eq!(
+            monitor.get_window(HWND(1 as _)).map(|w| w.workspace),
+            Some(1)
+        );
+        assert_eq!(
+            monitor.get_window(HWND(4 as _)).map(|w| w.workspace),
+            Some(3)
+        );
+        assert!(
+            monitor
+                .get_workspace(3)
+                .unwrap()
+                .get_window(HWND(4 as _))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn monitor_routes_windows_by_workspace_field() {
+        let mut monitor = Monitor::new(0, RECT::default());
+        monitor.add_window(window(7, 3));
+
+        assert!(
+            monitor
+                .get_workspace(3)
+                .unwrap()
+                .get_window(HWND(7 as _))
+                .is_some()
+        );
+        assert!(monitor.get_window(HWND(7 as _)).is_some());
+        assert_eq!(
+            monitor.remove_window(HWND(7 as _)).map(|w| w.workspace),
+            Some(3)
+        );
+        assert!(monitor.get_window(HWND(7 as _)).is_none());
+    }
+
+    #[test]
+    fn grouped_windows_share_a_tile_that_outlives_its_holder() {
+        let monitor = Monitor::new(
+            0,
+            RECT {
+                left: 0,
+                top: 0,
+                right: 1920,
+                bottom: 1080,
+            },
+        );
+        let tiler = crate::tiling::DwindleTiler::new(0);
+        let mut workspace = Workspace::new();
+        for hwnd in 1..=3 {
+            workspace.add_window(window(hwnd, 1));
+        }
+        workspace.windows[2].tile_group = Some(1);
+        let tile = |workspace: &mut Workspace| {
+            tiler.tile_windows(
+                &monitor,
+                &mut workspace.layout_tree,
+                &mut workspace.windows,
+                None,
+            );
+        };
+        tile(&mut workspace);
+        let rect_of = |workspace: &Workspace, hwnd: isize| {
+            workspace.get_window(HWND(hwnd as _)).unwrap().rect
+        };
+        let shared = rect_of(&workspace, 1);
+        assert_eq!(rect_of(&workspace, 3), shared);
+        assert_ne!(rect_of(&workspace, 2), shared);
+        assert_eq!(workspace.tile_group(3), vec![1, 3]);
+
+        workspace.remove_window(HWND(1 as _));
+        tile(&mut workspace);
+        assert_eq!(rect_of(&workspace, 3), shared);
+        assert_eq!(workspace.tile_group(3), vec![3]);
+    }
+}
@@ -8,21 +8,46 @@
 //! - Focus management and window decorations
 //! - Monitor hot-plugging
 
-use super::workspace::{Monitor, Window};
-use crate::statusbar::{STATUSBAR_MAX_WORKSPACES, StatusBar};
-use crate::tiling::DwindleTiler;
-use crate::windows_lib::{
-    get_accent_color, hide_window_from_taskbar, reset_window_decorations, set_window_border_color,
-    set_window_transparency, show_window_in_taskbar,
+use super::workspace::{DEFAULT_WORKSPACES, Monitor, Window};
+use crate::about::MonitorSummary;
+use crate::config::{
+    AppQuirks, BarPosition, CornerPreference, DecorationsConfig, FocusConfig, HideMethod,
+    LayoutConfig, MonitorConfig, NewWindowFocus, NewWindowMonitor, ReservedEdges,
+    WorkspaceOverride, is_primary_display, monitor_config, quirks_for,
 };
+use crate::float_geometry::FloatRects;
+use crate::focus_history::FocusHistory;
+use crate::layouts::{LayoutNode, LayoutTemplate};
+use crate::notifications::{Severity, notify};
+use crate::opacity::OpacityLevels;
+use crate::recovery::RecoverableWindow;
+use crate::session::{SavedSession, SavedWindow, SavedWorkspace};
+use crate::stash::Stash;
+use crate::state_stream::{BarState, FocusedWindow};
+use crate::statusbar::{STATUSBAR_VERTICAL_RESERVE, StatusBar, WindowButton};
+use crate::theme::Theme;
+use crate::tiling::{DwindleTiler, center_rect, zen_rect};
+use crate::window_system::{Win32WindowSystem, WindowSystem};
+use crate::windows_lib::{restore_window_style, strip_window_frame};
 use log::{debug, error, info, warn};
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use windows::Win32::Foundation::{HWND, RECT};
-use windows::Win32::UI::WindowsAndMessaging::{
-    GetForegroundWindow, IsZoomed, SW_RESTORE, SWP_NOACTIVATE, SWP_NOZORDER, SetWindowPos,
-    ShowWindow,
-};
+
+/// How long after a new window appears its taking focus counts as its own doing.
+const NEW_WINDOW_FOCUS_GRACE: Duration = Duration::from_secs(1);
+/// Times the focused window pulses when asked where focus is.
+const FOCUS_FLASH_PULSES: u32 = 3;
+/// How long each half of a pulse lasts.
+const FOCUS_FLASH_HALF_PULSE: Duration = Duration::from_millis(120);
+/// Opacity of the focused window at the height of a pulse.
+const FOCUS_FLASH_ALPHA: u8 = 140;
+/// How long after the first resize `resize_twice` apps are resized again,
+/// giving them time to undo it first.
+const RESIZE_AGAIN_DELAY: Duration = Duration::from_millis(200);
+/// How long floating rects must stay put before they are saved, so a drag
+/// is written once it ends rather than on every step.
+const FLOAT_RECTS_SAVE_DELAY: Duration = Duration::from_secs(2);
 
 /// Converts an isize window handle to HWND.
 #[inline]
@@ -30,97 +55,806 @@ fn hwnd_from_isize(val: isize) -> HWND {
     HWND(val as *mut std::ffi::c_void)
 }
 
+/// A built-in status bar and the display it sits on.
+struct PlacedBar {
+    /// Device name of the display, e.g. `\\.\DISPLAY1`.
+    device: String,
+    bar: StatusBar,
+    /// Hidden by auto-hide, not by the user
+    auto_hidden: bool,
+    /// Last time the pointer was on the bar or the top edge
+    needed_at: Instant,
+    /// Hidden while a window on its display is fullscreen
+    fullscreen: bool,
+}
+
 /// Central coordinator for window and workspace management.
 ///
 /// Manages all monitors, workspaces, and windows. Provides high-level
 /// operations for workspace switching, window movement, and tiling.
 pub struct WorkspaceManager {
+    system: Box<dyn WindowSystem>,
     monitors: Vec<Monitor>,
     active_workspace_global: u8, // All monitors share the same active workspace
     last_reenumerate: Instant,
-    statusbar: Option<StatusBar>,
+    statusbars: Vec<PlacedBar>,
+    bar_displays: HashSet<String>, // Device names of the displays in `statusbars`
     statusbar_visible: bool,
+    statusbar_auto_hide: Option<Duration>, // Hide the bar after this long away from it
+    fullscreen_displays: Vec<RECT>,        // Displays with a fullscreen window, whose bar is hidden
     last_focused_hwnd: Option<isize>,
+    last_managed_focus: Option<isize>, // Last managed window to have focus
+    urgent_windows: Vec<isize>,        // Windows that flashed for attention, most recent last
+    unadopted_windows: Vec<isize>,     // Windows left floating at startup until adopted
+    focus_guard: Option<(isize, Instant)>, // New window not allowed to take focus
+    focus_history: HashMap<u8, FocusHistory>, // Focus order per workspace number
     last_window_alpha: HashMap<isize, u8>,
+    accent_color: Option<u32>, // Cached system accent color, refreshed on theme changes
+    theme: Theme,              // Light or dark app theme, refreshed on theme changes
+    dwm_borders: HashMap<isize, (i32, i32, i32, i32)>, // Cached invisible border deltas per window
     positioning_windows: HashSet<isize>, // Windows currently being positioned by us
-    last_update_positions: Instant,      // Debounce update_window_positions calls
+    pending_resizes: HashMap<isize, (RECT, Instant)>, // Sizes to re-apply (`resize_twice`) and when
+    elevated_windows: HashSet<isize>, // Windows we were denied access to (already reported)
+    stripped_styles: HashMap<isize, i32>, // Original styles of windows whose title bar we removed
+    window_corners: HashMap<isize, CornerPreference>, // Corner preference last applied per window
+    window_elevation: HashMap<isize, bool>, // Whether each window's app is elevated, for borders
+    focus_flash: Option<(isize, Instant, bool)>, // Window pulsing for focus, since when, if lit
+    last_update_positions: Instant, // Debounce update_window_positions calls
+    dragging_window: Option<isize>, // Window in a user move/size loop; left alone until dropped
+    layout_config: LayoutConfig,
+    monitor_configs: Vec<MonitorConfig>,
+    decorations_config: DecorationsConfig,
+    stash: Stash,
+    locked_workspaces: HashSet<u8>, // New windows are routed away from these
+    workspace_count: u8,            // Workspaces per monitor
+    dynamic_workspaces: bool,       // Collapse empty workspaces between used ones
+    workspace_overrides: Vec<WorkspaceOverride>, // Per-workspace gaps, padding and floating
+    layout_templates: Vec<LayoutTemplate>, // Layouts workspaces grow into as windows arrive
+    opacity_levels: OpacityLevels,  // Opacity chosen per process with the opacity keys
+    float_rects: FloatRects,        // Last floating rect per process
+    float_rects_changed: Option<Instant>, // When `float_rects` last changed, until saved
+    desktop_shown: Option<u8>,      // Workspace whose windows show-desktop hid
+    hide_methods: HashMap<String, HideMethod>, // Apps not hidden the default way
+    app_quirks: Vec<AppQuirks>,     // Per-app positioning workarounds
+    focus_config: FocusConfig,
+    power_saving: bool,   // Skip dimming unfocused windows to save power
+    window_effects: bool, // Focused border and unfocused transparency, switched from the tray
+    display_overrides: HashMap<String, bool>, // Displays paused/resumed at runtime, lowercase names
+    bar_reserve: i32, // Pixels kept at the bar's edge of each monitor for the built-in status bar
+    bar_position: BarPosition, // Which edge of its monitor the built-in status bar sits on
+    external_bar_reserve: ReservedEdges, // Space kept on every monitor for a third-party bar
 }
 
 impl WorkspaceManager {
     /// Creates a new workspace manager with default state.
     pub fn new() -> Self {
+        Self::with_window_system(Box::new(Win32WindowSystem))
+    }
+
+    /// Creates a workspace manager that drives `system` instead of the real
+    /// desktop, e.g. a mock in tests.
+    pub fn with_window_system(system: Box<dyn WindowSystem>) -> Self {
+        let theme = system.theme();
         WorkspaceManager {
+            system,
             monitors: Vec::new(),
             active_workspace_global: 1,
             last_reenumerate: Instant::now() - Duration::from_secs(60),
-            statusbar: None,
+            statusbars: Vec::new(),
+            bar_displays: HashSet::new(),
             statusbar_visible: true,
+            statusbar_auto_hide: None,
+            fullscreen_displays: Vec::new(),
             last_focused_hwnd: None,
+            last_managed_focus: None,
+            urgent_windows: Vec::new(),
+            unadopted_windows: Vec::new(),
+            focus_guard: None,
+            focus_history: HashMap::new(),
             last_window_alpha: HashMap::new(),
+            accent_color: None,
+            theme,
+            dwm_borders: HashMap::new(),
             positioning_windows: HashSet::new(),
+            pending_resizes: HashMap::new(),
+            elevated_windows: HashSet::new(),
+            stripped_styles: HashMap::new(),
+            window_corners: HashMap::new(),
+            window_elevation: HashMap::new(),
+            focus_flash: None,
             last_update_positions: Instant::now() - Duration::from_secs(60),
+            dragging_window: None,
+            layout_config: LayoutConfig::default(),
+            monitor_configs: Vec::new(),
+            decorations_config: DecorationsConfig::default(),
+            stash: Stash::default(),
+            locked_workspaces: HashSet::new(),
+            workspace_count: DEFAULT_WORKSPACES,
+            dynamic_workspaces: false,
+            workspace_overrides: Vec::new(),
+            layout_templates: Vec::new(),
+            opacity_levels: OpacityLevels::new(),
+            float_rects: FloatRects::new(),
+            float_rects_changed: None,
+            desktop_shown: None,
+            hide_methods: HashMap::new(),
+            app_quirks: Vec::new(),
+            focus_config: FocusConfig::default(),
+            power_saving: false,
+            window_effects: true,
+            display_overrides: HashMap::new(),
+            bar_reserve: STATUSBAR_VERTICAL_RESERVE,
+            bar_position: BarPosition::default(),
+            external_bar_reserve: ReservedEdges::default(),
+        }
+    }
+
+    /// Sets the `[layout]` options from the config file.
+    pub fn set_layout_config(&mut self, layout_config: LayoutConfig) {
+        self.layout_config = layout_config;
+    }
+
+    /// Sets the `[focus]` options from the config file.
+    pub fn set_focus_config(&mut self, focus_config: FocusConfig) {
+        self.focus_config = focus_config;
+    }
+
+    /// Sets the `[[monitors]]` options from the config file.
+    pub fn set_monitor_configs(&mut self, monitor_configs: Vec<MonitorConfig>) {
+        self.monitor_configs = monitor_configs;
+    }
+
+    /// Sets the `[decorations]` options from the config file.
+    pub fn set_decorations_config(&mut self, decorations_config: DecorationsConfig) {
+        self.decorations_config = decorations_config;
+    }
+
+    /// Sets the number of `[stash]` slots from the config file.
+    pub fn set_stash_slots(&mut self, count: usize) {
+        self.stash = Stash::new(count);
+    }
+
+    /// Sets the number of workspaces per monitor from the config file.
+    /// Call this before any windows are added.
+    pub fn set_workspace_count(&mut self, count: u8) {
+        self.workspace_count = count;
+        for monitor in &mut self.monitors {
+            monitor.set_workspace_count(count);
+        }
+    }
+
+    /// Returns the number of workspaces per monitor.
+    pub fn workspace_count(&self) -> u8 {
+        self.workspace_count
+    }
+
+    /// Sets the per-workspace overrides from the config file.
+    pub fn set_workspace_overrides(&mut self, overrides: Vec<WorkspaceOverride>) {
+        self.workspace_overrides = overrides;
+    }
+
+    /// Returns whether windows on `workspace` float instead of being tiled.
+    pub fn is_floating_workspace(&self, workspace: u8) -> bool {
+        self.workspace_overrides
+            .iter()
+            .any(|entry| entry.workspace == workspace && entry.floating)
+    }
+
+    /// Sets the `[[templates]]` layouts from the config file.
+    pub fn set_layout_templates(&mut self, templates: Vec<LayoutTemplate>) {
+        self.layout_templates = templates;
+    }
+
+    /// Sets the per-process opacity levels saved by earlier sessions.
+    pub fn set_opacity_levels(&mut self, levels: OpacityLevels) {
+        self.opacity_levels = levels;
+    }
+
+    /// Returns the per-process opacity levels, for saving.
+    pub fn opacity_levels(&self) -> &OpacityLevels {
+        &self.opacity_levels
+    }
+
+    /// Sets the floating window rects saved by earlier sessions.
+    pub fn set_float_rects(&mut self, rects: FloatRects) {
+        self.float_rects = rects;
+    }
+
+    /// Returns the last floating rect of each app, for saving.
+    pub fn float_rects(&self) -> &FloatRects {
+        &self.float_rects
+    }
+
+    /// Returns the floating rects once they have changed and then stayed
+    /// put for a moment, for saving. Call this every loop iteration; each
+    /// change is only returned once.
+    pub fn float_rects_to_save(&mut self) -> Option<&FloatRects> {
+        let changed = self.float_rects_changed?;
+        if changed.elapsed() < FLOAT_RECTS_SAVE_DELAY {
+            return None;
+        }
+        self.float_rects_changed = None;
+        Some(&self.float_rects)
+    }
+
+    /// Sets how windows of particular apps are hidden, by lowercase process name.
+    pub fn set_hide_methods(&mut self, methods: HashMap<String, HideMethod>) {
+        self.hide_methods = methods;
+    }
+
+    /// Sets the `[[quirks]]` workarounds from the config file.
+    pub fn set_app_quirks(&mut self, quirks: Vec<AppQuirks>) {
+        self.app_quirks = quirks;
+    }
+
+    /// Turns power saving on or off. While on, every window is kept opaque,
+    /// including apps given their own opacity, and the status bars don't
+    /// animate.
+    pub fn set_power_saving(&mut self, saving: bool) {
+        for placed in &mut self.statusbars {
+            placed.bar.set_animations(!saving);
+        }
+        if saving == self.power_saving {
+            return;
+        }
+        self.power_saving = saving;
+        if saving {
+            self.make_windows_opaque();
+        }
+        self.last_window_alpha.clear();
+        self.update_decorations();
+    }
+
+    /// Turns the focused window border and unfocused window transparency on
+    /// or off. Turning them off puts every managed window back to opaque
+    /// with its normal border.
+    pub fn set_window_effects(&mut self, enabled: bool) {
+        if enabled == self.window_effects {
+            return;
+        }
+        self.window_effects = enabled;
+        info!(
+            "Window effects turned {}",
+            if enabled { "on" } else { "off" }
+        );
+        for hwnd in self.get_all_managed_hwnds() {
+            if let Err(e) = self.system.reset_decorations(hwnd_from_isize(hwnd)) {
+                error!("Failed to reset window decorations: {}", e);
+            }
+        }
+        if !enabled {
+            self.make_windows_opaque();
+        }
+        self.last_window_alpha.clear();
+        self.update_decorations();
+    }
+
+    /// Puts every managed window back to fully opaque.
+    fn make_windows_opaque(&self) {
+        for hwnd in self.get_all_managed_hwnds() {
+            if let Err(e) = self.system.set_transparency(hwnd_from_isize(hwnd), 255) {
+                error!("Failed to set window transparency: {}", e);
+            }
         }
     }
 
-    /// Sets the status bar instance for workspace indicator updates.
-    pub fn set_statusbar(&mut self, statusbar: StatusBar) {
-        self.statusbar = Some(statusbar);
+    /// Returns true while the focused border and unfocused transparency are on.
+    pub fn window_effects(&self) -> bool {
+        self.window_effects
+    }
+
+    /// Makes room for a third-party bar instead of the built-in one:
+    /// `reserve` is kept clear on every monitor.
+    pub fn set_external_bar(&mut self, reserve: ReservedEdges) {
+        self.bar_reserve = 0;
+        self.external_bar_reserve = reserve;
     }
 
-    /// Updates the status bar to reflect the current workspace.
+    /// Keeps the built-in bar's space at the `position` edge of its monitor.
+    pub fn set_statusbar_position(&mut self, position: BarPosition) {
+        self.bar_position = position;
+    }
+
+    /// Hides the built-in bar `delay` after the pointer leaves it, giving its
+    /// space to the tiled windows. It slides back over them when the pointer
+    /// touches its edge of the monitor, or on a workspace switch.
+    pub fn set_statusbar_auto_hide(&mut self, delay: Duration) {
+        self.bar_reserve = 0;
+        self.statusbar_auto_hide = Some(delay);
+        for placed in &mut self.statusbars {
+            placed.needed_at = Instant::now();
+        }
+    }
+
+    /// Hides or shows a window that is about to be added, the way its app
+    /// is hidden on inactive workspaces.
+    pub fn set_window_hidden(&self, window: &Window, hide: bool) -> Result<(), String> {
+        let hwnd = hwnd_from_isize(window.hwnd);
+        let method = hide_method(&self.hide_methods, window);
+        if hide {
+            self.system.hide_window(hwnd, method)
+        } else {
+            self.system.show_window(hwnd, method)
+        }
+    }
+
+    /// Shows a managed or stashed window at exit, however it was hidden.
+    pub fn reveal_window(&self, hwnd: HWND) -> Result<(), String> {
+        let method = self
+            .monitors
+            .iter()
+            .find_map(|monitor| monitor.get_window(hwnd))
+            .or_else(|| self.stash.parked(hwnd.0 as isize))
+            .map_or(HideMethod::Hide, |window| {
+                hide_method(&self.hide_methods, window)
+            });
+        self.system.show_window(hwnd, method)
+    }
+
+    /// Returns the windows that would stay hidden or without a title bar if
+    /// Megatile crashed now.
+    pub fn recoverable_windows(&self) -> Vec<RecoverableWindow> {
+        self.monitors
+            .iter()
+            .flat_map(|monitor| monitor.workspaces.iter())
+            .flat_map(|workspace| workspace.windows.iter())
+            .chain(self.stash.parked_windows())
+            .filter_map(|window| {
+                let hidden_by = window
+                    .is_hidden_by_workspace
+                    .then(|| hide_method(&self.hide_methods, window));
+                let style = self.stripped_styles.get(&window.hwnd).copied();
+                (hidden_by.is_some() || style.is_some()).then(|| RecoverableWindow {
+                    hwnd: window.hwnd,
+                    process: window.process_name.clone(),
+                    hidden_by,
+                    style,
+                })
+            })
+            .collect()
+    }
+
+    /// Enables dynamic workspaces, from the config file.
+    pub fn set_dynamic_workspaces(&mut self, dynamic: bool) {
+        self.dynamic_workspaces = dynamic;
+    }
+
+    /// Returns the number of workspaces currently on offer. With dynamic
+    /// workspaces this is the ones in use plus one empty one; otherwise all.
+    pub fn used_workspace_count(&self) -> u8 {
+        if !self.dynamic_workspaces {
+            return self.workspace_count;
+        }
+        let last_used = (1..=self.workspace_count)
+            .rev()
+            .find(|&ws| self.get_workspace_window_count(ws) > 0)
+            .unwrap_or(1)
+            .max(self.active_workspace_global);
+        (last_used + 1).min(self.workspace_count)
+    }
+
+    /// With dynamic workspaces, removes empty workspaces that sit before a
+    /// workspace in use, renumbering the ones after them. Only call this once
+    /// a window is gone for good or the active workspace changed, never while
+    /// windows are being moved between workspaces.
+    fn collapse_empty_workspaces(&mut self) {
+        if !self.dynamic_workspaces {
+            return;
+        }
+        while let Some(workspace) =
+            workspace_to_collapse(self.workspace_count, self.active_workspace_global, |ws| {
+                self.get_workspace_window_count(ws) == 0
+            })
+        {
+            debug!("Collapsing empty workspace {}", workspace);
+            for monitor in &mut self.monitors {
+                monitor.remove_workspace(workspace);
+            }
+            if self.active_workspace_global > workspace {
+                self.active_workspace_global -= 1;
+            }
+            self.desktop_shown = match self.desktop_shown {
+                Some(ws) if ws == workspace => None,
+                Some(ws) if ws > workspace => Some(ws - 1),
+                shown => shown,
+            };
+            self.locked_workspaces = self
+                .locked_workspaces
+                .iter()
+                .filter(|&&ws| ws != workspace)
+                .map(|&ws| if ws > workspace { ws - 1 } else { ws })
+                .collect();
+            self.focus_history = std::mem::take(&mut self.focus_history)
+                .into_iter()
+                .filter(|&(ws, _)| ws != workspace)
+                .map(|(ws, history)| (if ws > workspace { ws - 1 } else { ws }, history))
+                .collect();
+        }
+    }
+
+    /// Sets the workspaces that start locked, from the config file.
+    pub fn set_locked_workspaces(&mut self, locked: &[u8]) {
+        self.locked_workspaces = locked.iter().copied().collect();
+    }
+
+    /// Locks or unlocks the active workspace against new windows.
+    /// Returns true if it is now locked.
+    pub fn toggle_workspace_lock(&mut self) -> bool {
+        let workspace = self.active_workspace_global;
+        let locked = !self.locked_workspaces.remove(&workspace);
+        if locked {
+            self.locked_workspaces.insert(workspace);
+        }
+        info!(
+            "Workspace {} {}",
+            workspace,
+            if locked { "locked" } else { "unlocked" }
+        );
+        locked
+    }
+
+    /// Returns the workspace a new window bound for `workspace` should open
+    /// on: the workspace itself, or the next unlocked one if it is locked.
+    pub fn unlocked_workspace_for(&self, workspace: u8) -> u8 {
+        next_unlocked_workspace(workspace, self.workspace_count, &self.locked_workspaces)
+    }
+
+    /// Builds the monitors to tile from the physical displays, splitting
+    /// displays configured with `zones` into side-by-side virtual monitors.
+    pub fn monitors_from_infos(&self, infos: &[crate::windows_lib::MonitorInfo]) -> Vec<Monitor> {
+        let mut monitors = Vec::new();
+        for info in infos {
+            let config = monitor_config(&self.monitor_configs, &info.device_name);
+            let zones = config.map_or(1, |config| config.zones);
+            let managed = self
+                .display_overrides
+                .get(&info.device_name.to_lowercase())
+                .copied()
+                .unwrap_or(!config.is_some_and(|config| config.unmanaged));
+            let area = reserve_edges(info.rect, self.external_bar_reserve);
+            let area = config.map_or(area, |config| reserve_edges(area, config.reserve));
+            for (zone, rect) in split_into_zones(area, zones).into_iter().enumerate() {
+                let mut monitor = Monitor::new(info.hmonitor, rect);
+                monitor.set_workspace_count(self.workspace_count);
+                monitor.display_rect = info.rect;
+                monitor.zone = zone;
+                monitor.device_name = info.device_name.clone();
+                monitor.managed = managed;
+                if let Some(layout) = config.and_then(|config| config.layout) {
+                    monitor.layout = layout;
+                }
+                monitors.push(monitor);
+            }
+        }
+        monitors
+    }
+
+    /// Returns the index of the first monitor on the primary display, as
+    /// `[[monitors]] primary` or else Windows says. Windows that are on no
+    /// monitor go there.
+    pub fn primary_monitor_index(&self) -> usize {
+        let infos = self.system.monitors();
+        let connected: Vec<String> = infos.iter().map(|info| info.device_name.clone()).collect();
+        self.monitors
+            .iter()
+            .position(|monitor| {
+                let windows_primary = infos
+                    .iter()
+                    .any(|info| info.is_primary && info.device_name == monitor.device_name);
+                is_primary_display(
+                    &self.monitor_configs,
+                    &monitor.device_name,
+                    windows_primary,
+                    &connected,
+                )
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the monitor a new window is tiled on, per `[layout]
+    /// new_window_monitor`. Falls back to the one it opened on when the
+    /// policy's pick isn't managed.
+    pub fn monitor_for_new_window(&self, hwnd: HWND) -> usize {
+        let chosen = match self.layout_config.new_window_monitor {
+            NewWindowMonitor::Window => None,
+            NewWindowMonitor::Cursor => self.system.cursor_position().ok().and_then(|(x, y)| {
+                let zones: Vec<usize> = (0..self.monitors.len())
+                    .filter(|&i| {
+                        let rect = self.monitors[i].display_rect;
+                        (rect.left..rect.right).contains(&x) && (rect.top..rect.bottom).contains(&y)
+                    })
+                    .collect();
+                zones
+                    .iter()
+                    .copied()
+                    .find(|&i| x < self.monitors[i].rect.right)
+                    .or(zones.last().copied())
+            }),
+            NewWindowMonitor::Focused => self
+                .last_managed_focus
+                .and_then(|focused| self.get_window(hwnd_from_isize(focused)))
+                .map(|window| window.monitor),
+        };
+        chosen
+            .filter(|&idx| self.is_monitor_managed(idx))
+            .or_else(|| self.get_monitor_for_window(hwnd))
+            .unwrap_or_else(|| self.primary_monitor_index())
+    }
+
+    /// Returns true if windows on the monitor at `monitor_idx` are managed.
+    pub fn is_monitor_managed(&self, monitor_idx: usize) -> bool {
+        self.monitors.get(monitor_idx).is_some_and(|m| m.managed)
+    }
+
+    /// Returns the device name of the display the monitor at `monitor_idx` is on.
+    pub fn monitor_device(&self, monitor_idx: usize) -> Option<&str> {
+        self.monitors
+            .get(monitor_idx)
+            .map(|monitor| monitor.device_name.as_str())
+    }
+
+    /// Returns each display's device name and whether it is managed, in
+    /// enumeration order.
+    pub fn displays(&self) -> Vec<(String, bool)> {
+        let mut displays: Vec<(String, bool)> = Vec::new();
+        for monitor in &self.monitors {
+            if !displays
+                .iter()
+                .any(|(device, _)| *device == monitor.device_name)
+            {
+                displays.push((monitor.device_name.clone(), monitor.managed));
+            }
+        }
+        displays
+    }
+
+    /// Describes each monitor for the About window's diagnostics.
+    pub fn monitor_summaries(&self) -> Vec<MonitorSummary> {
+        let infos = self.system.monitors();
+        let connected: Vec<String> = infos.iter().map(|info| info.device_name.clone()).collect();
+        self.monitors
+            .iter()
+            .map(|monitor| MonitorSummary {
+                device: monitor.device_name.clone(),
+                zone: monitor.zone,
+                width: monitor.rect.right - monitor.rect.left,
+                height: monitor.rect.bottom - monitor.rect.top,
+                primary: is_primary_display(
+                    &self.monitor_configs,
+                    &monitor.device_name,
+                    infos
+                        .iter()
+                        .any(|info| info.is_primary && info.device_name == monitor.device_name),
+                    &connected,
+                ),
+                managed: monitor.managed,
+                windows: monitor
+                    .workspaces
+                    .iter()
+                    .map(|workspace| workspace.windows.len())
+                    .sum(),
+            })
+            .collect()
+    }
+
+    /// Returns the device name of the display under the mouse cursor.
+    pub fn display_under_cursor(&self) -> Option<String> {
+        let (x, y) = self.system.cursor_position().ok()?;
+        self.monitors
+            .iter()
+            .find(|monitor| {
+                let rect = monitor.display_rect;
+                (rect.left..rect.right).contains(&x) && (rect.top..rect.bottom).contains(&y)
+            })
+            .map(|monitor| monitor.device_name.clone())
+    }
+
+    /// Pauses management of a display, or resumes it. Pausing lets go of
+    /// every window on it; resumed displays start out empty, and the caller
+    /// adds the windows already there. Returns whether the display is
+    /// managed now.
+    pub fn toggle_display_managed(&mut self, device: &str) -> Result<bool, String> {
+        let indices: Vec<usize> = (0..self.monitors.len())
+            .filter(|&i| self.monitors[i].device_name.eq_ignore_ascii_case(device))
+            .collect();
+        let Some(&first) = indices.first() else {
+            return Err(format!("No display named {}", device));
+        };
+        let managed = !self.monitors[first].managed;
+        self.display_overrides
+            .insert(device.to_lowercase(), managed);
+
+        let mut released = Vec::new();
+        for &i in &indices {
+            let monitor = &mut self.monitors[i];
+            monitor.managed = managed;
+            released.extend(
+                monitor
+                    .workspaces
+                    .iter()
+                    .flat_map(|workspace| workspace.windows.iter())
+                    .map(|window| window.hwnd),
+            );
+        }
+        for hwnd in released {
+            self.release_window(hwnd_from_isize(hwnd));
+        }
+        self.update_statusbar();
+        Ok(managed)
+    }
+
+    /// Stops managing a window and undoes everything Megatile did to it.
+    fn release_window(&mut self, hwnd: HWND) {
+        let method = self
+            .monitors
+            .iter()
+            .find_map(|monitor| monitor.get_window(hwnd))
+            .map(|window| hide_method(&self.hide_methods, window));
+        let Some(window) = self.remove_window(hwnd) else {
+            return;
+        };
+        if window.is_hidden_by_workspace
+            && let Err(e) = self.system.show_window(hwnd, method.unwrap_or_default())
+        {
+            error!("Failed to show released window {:?}: {}", window.hwnd, e);
+        }
+        if self.window_corners.remove(&window.hwnd).is_some() {
+            let _ = self.system.set_corners(hwnd, CornerPreference::Default);
+        }
+        if let Err(e) = self.system.reset_decorations(hwnd) {
+            error!("Failed to reset decorations of {:?}: {}", window.hwnd, e);
+        }
+    }
+
+    /// Adds a built-in status bar on the display `device` (e.g.
+    /// `\\.\DISPLAY2`), placing it there and showing it unless the bars
+    /// are hidden. The windows there are re-tiled to make room for it.
+    pub fn add_statusbar(&mut self, device: String, mut statusbar: StatusBar) {
+        statusbar.set_animations(!self.power_saving);
+        self.bar_displays.insert(device.clone());
+        self.statusbars.push(PlacedBar {
+            device,
+            bar: statusbar,
+            auto_hidden: false,
+            needed_at: Instant::now(),
+            fullscreen: false,
+        });
+        self.place_statusbars();
+        if self.statusbar_visible
+            && let Some(placed) = self.statusbars.last_mut()
+        {
+            placed.bar.show();
+        }
+        self.update_statusbar();
+        self.update_window_list();
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+    }
+
+    /// Returns true if the display `device` has a built-in status bar.
+    pub fn has_statusbar(&self, device: &str) -> bool {
+        self.statusbars.iter().any(|placed| placed.device == device)
+    }
+
+    /// Updates the status bars to reflect the current workspace.
     pub fn update_statusbar(&mut self) {
         let workspace_num = self.active_workspace_global;
-        let mut occupied_6_9 = 0u8;
-        for ws in 6..=9 {
+        let total = self.used_workspace_count();
+        let mut occupied = 0u32;
+        for ws in 1..=total {
             if self.get_workspace_window_count(ws) > 0 {
-                occupied_6_9 |= 1 << (ws - 6);
+                occupied |= 1 << (ws - 1);
             }
         }
-        if let Some(statusbar) = self.statusbar.as_mut() {
-            statusbar.update_indicator(workspace_num, STATUSBAR_MAX_WORKSPACES, occupied_6_9);
+        let titles: Vec<Vec<String>> = (1..=total)
+            .map(|ws| {
+                self.monitors
+                    .iter()
+                    .filter_map(|monitor| monitor.get_workspace(ws))
+                    .flat_map(|workspace| workspace.windows.iter())
+                    .map(|window| window.title.clone())
+                    .collect()
+            })
+            .collect();
+        let parked_slots = self.stash.parked_slots();
+        for placed in &mut self.statusbars {
+            placed.bar.update_indicator(workspace_num, total, occupied);
+            placed.bar.update_stash(parked_slots.clone());
+            placed.bar.update_window_titles(titles.clone());
         }
     }
 
-    /// Updates only the clock on the status bar without changing workspace indicators.
-    pub fn update_statusbar_clock(&mut self) {
-        if let Some(statusbar) = self.statusbar.as_mut() {
-            statusbar.update_clock();
+    /// Refreshes the status bars' window lists with the active workspace's windows.
+    pub fn update_window_list(&mut self) {
+        if self.statusbars.is_empty() {
+            return;
+        }
+        let foreground = self.system.foreground_window().0 as isize;
+        let buttons: Vec<WindowButton> = self
+            .monitors
+            .iter()
+            .filter_map(|monitor| monitor.get_workspace(self.active_workspace_global))
+            .flat_map(|workspace| workspace.windows.iter())
+            .map(|window| WindowButton {
+                hwnd: window.hwnd,
+                title: window.title.clone(),
+                focused: window.hwnd == foreground,
+            })
+            .collect();
+        for placed in &mut self.statusbars {
+            placed.bar.update_window_list(buttons.clone());
         }
     }
 
-    /// Re-centers the status bar on the primary monitor.
-    ///
-    /// Call this after monitor configuration changes to ensure the status bar
-    /// remains centered on the primary display.
-    pub fn recenter_statusbar(&mut self) {
-        use crate::statusbar::{STATUSBAR_HEIGHT, STATUSBAR_TOP_GAP, STATUSBAR_WIDTH};
+    /// Returns what an external status bar shows.
+    pub fn bar_state(&self) -> BarState {
+        BarState {
+            active_workspace: self.active_workspace_global,
+            window_counts: (1..=self.used_workspace_count())
+                .map(|ws| self.get_workspace_window_count(ws))
+                .collect(),
+            stash: self.stash.parked_slots(),
+            focused: self.get_focused_window().map(|window| FocusedWindow {
+                hwnd: window.hwnd,
+                title: window.title,
+                process: window.process_name,
+            }),
+        }
+    }
 
-        if let Some(statusbar) = self.statusbar.as_mut() {
-            let monitor_infos = crate::windows_lib::enumerate_monitors();
-            if let Some(primary_monitor) = monitor_infos.iter().find(|m| m.is_primary) {
-                let rect = primary_monitor.rect;
-                let statusbar_width = STATUSBAR_WIDTH;
-                let statusbar_height = STATUSBAR_HEIGHT;
-                let x = rect.left + (rect.right - rect.left - statusbar_width) / 2;
-                let y = rect.top + STATUSBAR_TOP_GAP;
+    /// Updates only the clock on the status bars without changing workspace indicators.
+    pub fn update_statusbar_clock(&mut self) {
+        for placed in &mut self.statusbars {
+            placed.bar.update_clock();
+        }
+    }
 
-                statusbar.set_position(x, y, statusbar_width, statusbar_height);
-                debug!("Status bar recentered at ({}, {}) on primary monitor", x, y);
+    /// Puts each status bar back on its display, and drops the bars of
+    /// displays that are gone.
+    ///
+    /// Call this after monitor configuration changes, then add bars for
+    /// displays that came back.
+    pub fn place_statusbars(&mut self) {
+        let monitor_infos = self.system.monitors();
+        self.statusbars.retain_mut(|placed| {
+            match monitor_infos
+                .iter()
+                .find(|info| info.device_name == placed.device)
+            {
+                Some(info) => {
+                    placed.bar.place_on(info.hmonitor);
+                    debug!("Status bar placed on {}", placed.device);
+                    true
+                }
+                None => {
+                    info!("Removing the status bar of {}", placed.device);
+                    self.bar_displays.remove(&placed.device);
+                    false
+                }
             }
-        }
+        });
     }
 
-    /// Shows or hides the status bar.
+    /// Shows or hides the status bars.
     pub fn toggle_statusbar(&mut self, visible: bool) {
         self.statusbar_visible = visible;
-        if let Some(statusbar) = self.statusbar.as_mut() {
-            if visible {
-                statusbar.show();
-                self.update_statusbar();
-            } else {
-                statusbar.hide();
+        for placed in &mut self.statusbars {
+            placed.auto_hidden = false;
+            placed.needed_at = Instant::now();
+            if !visible {
+                placed.bar.hide();
+            } else if !placed.fullscreen {
+                placed.bar.show();
             }
         }
+        if visible {
+            self.update_statusbar();
+        }
+    }
+
+    /// Returns whether the built-in status bars are shown, or `None` without any.
+    pub fn statusbar_visible(&self) -> Option<bool> {
+        (!self.statusbars.is_empty()).then_some(self.statusbar_visible)
     }
 
     /// Toggles the status bar visibility.
@@ -129,14 +863,273 @@ impl WorkspaceManager {
         self.toggle_statusbar(desired);
     }
 
+    /// Brings back the auto-hidden status bars for another auto-hide delay.
+    pub fn reveal_statusbar(&mut self) {
+        for index in 0..self.statusbars.len() {
+            self.reveal_statusbar_at(index);
+        }
+    }
+
+    /// Brings back the auto-hidden status bar at `index` in `statusbars`.
+    fn reveal_statusbar_at(&mut self, index: usize) {
+        if self.statusbar_auto_hide.is_none() || !self.statusbar_visible {
+            return;
+        }
+        let Some(placed) = self.statusbars.get_mut(index) else {
+            return;
+        };
+        if placed.fullscreen {
+            return;
+        }
+        placed.needed_at = Instant::now();
+        if placed.auto_hidden {
+            placed.auto_hidden = false;
+            placed.bar.show();
+            self.update_statusbar();
+        }
+    }
+
+    /// Returns the bounds of the display `device`.
+    fn display_rect(&self, device: &str) -> Option<RECT> {
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.device_name == device)
+            .map(|monitor| monitor.display_rect)
+    }
+
+    /// Hides a status bar while a window on its display is fullscreen,
+    /// either with the fullscreen key or borderless (a game or video player
+    /// sized to the display), and lets that display's tiled windows have the
+    /// bar's space meanwhile. Restores both once fullscreen ends.
+    pub fn update_fullscreen_statusbar(&mut self) {
+        let mut changed = false;
+        for index in 0..self.statusbars.len() {
+            let Some(display) = self.display_rect(&self.statusbars[index].device) else {
+                continue;
+            };
+            let fullscreen = self.monitors.iter().any(|monitor| {
+                monitor.display_rect == display
+                    && monitor
+                        .get_workspace(monitor.active_workspace)
+                        .is_some_and(|workspace| {
+                            workspace.windows.iter().any(|window| {
+                                window.is_fullscreen && !window.is_hidden_by_workspace
+                            })
+                        })
+            }) || self.foreground_is_borderless_fullscreen(display);
+            let statusbar_visible = self.statusbar_visible;
+            let placed = &mut self.statusbars[index];
+            if fullscreen == placed.fullscreen {
+                continue;
+            }
+            changed = true;
+            placed.fullscreen = fullscreen;
+            placed.needed_at = Instant::now();
+            if fullscreen {
+                info!(
+                    "Hiding the status bar on {} while a window is fullscreen",
+                    placed.device
+                );
+                placed.bar.hide();
+                self.fullscreen_displays.push(display);
+            } else {
+                if statusbar_visible && !placed.auto_hidden {
+                    placed.bar.show();
+                }
+                self.fullscreen_displays.retain(|rect| *rect != display);
+            }
+        }
+        if changed {
+            self.tile_active_workspaces();
+            self.apply_window_positions();
+            self.update_statusbar();
+        }
+    }
+
+    /// Returns whether a window exactly covers the display it is on, as a
+    /// borderless fullscreen game or video does.
+    pub fn covers_display(&self, hwnd: HWND) -> bool {
+        let Some(monitor_index) = self.get_monitor_for_window(hwnd) else {
+            return false;
+        };
+        self.system
+            .window_rect(hwnd)
+            .is_ok_and(|rect| rect == self.monitors[monitor_index].display_rect)
+    }
+
+    /// Returns whether the foreground window exactly covers `display`.
+    fn foreground_is_borderless_fullscreen(&self, display: RECT) -> bool {
+        let hwnd = self.system.foreground_window();
+        if hwnd.is_invalid() {
+            return false;
+        }
+        // The desktop covers the display too
+        let class = self.system.window_class(hwnd);
+        if class == "Progman" || class == "WorkerW" {
+            return false;
+        }
+        self.system
+            .window_rect(hwnd)
+            .is_ok_and(|rect| rect == display)
+    }
+
+    /// Hides an auto-hiding status bar once the pointer has been away from
+    /// it for the delay, and reveals it when the pointer touches its edge of
+    /// the monitor. Call this every loop iteration.
+    pub fn update_statusbar_auto_hide(&mut self) {
+        let Some(delay) = self.statusbar_auto_hide else {
+            return;
+        };
+        if !self.statusbar_visible || self.statusbars.is_empty() {
+            return;
+        }
+        let Ok((x, y)) = self.system.cursor_position() else {
+            return;
+        };
+        for index in 0..self.statusbars.len() {
+            let display = self.display_rect(&self.statusbars[index].device);
+            let placed = &mut self.statusbars[index];
+            if placed.fullscreen {
+                continue;
+            }
+            if bar_wanted(placed.bar.rect(), display, self.bar_position, x, y) {
+                self.reveal_statusbar_at(index);
+            } else if !placed.auto_hidden && placed.needed_at.elapsed() >= delay {
+                placed.auto_hidden = true;
+                placed.bar.hide();
+            }
+        }
+    }
+
+    /// Returns the cached accent color, reading it from DWM on first use.
+    fn accent_color(&mut self) -> Result<u32, String> {
+        if let Some(color) = self.accent_color {
+            return Ok(color);
+        }
+        let color = self.system.accent_color()?;
+        self.accent_color = Some(color);
+        Ok(color)
+    }
+
+    /// Re-reads the accent color and light/dark theme after a theme change
+    /// and repaints the status bar and focused window border with them.
+    pub fn refresh_theme(&mut self) {
+        let theme = self.system.theme();
+        if theme != self.theme {
+            info!("App theme changed to {:?}", theme);
+            self.theme = theme;
+        }
+        for placed in &mut self.statusbars {
+            placed.bar.set_theme(theme);
+        }
+        self.accent_color = None;
+        match self.accent_color() {
+            Ok(color) => {
+                info!("Accent color changed to {:#08x}", color);
+                for placed in &mut self.statusbars {
+                    placed.bar.set_accent_color(color);
+                }
+            }
+            Err(e) => error!("Failed to read accent color: {}", e),
+        }
+        self.update_decorations();
+    }
+
+    /// Applies the `[focus] new_windows` policy to a window that was just added.
+    pub fn focus_new_window(&mut self, hwnd: HWND) {
+        let Some(workspace) = self.get_window(hwnd).map(|window| window.workspace) else {
+            return;
+        };
+        match self.focus_config.new_windows {
+            NewWindowFocus::Always => {
+                if workspace != self.active_workspace_global
+                    && let Err(e) = self.switch_workspace_with_windows(workspace)
+                {
+                    error!("Failed to switch to new window's workspace: {}", e);
+                    return;
+                }
+                self.set_window_focus(hwnd);
+            }
+            NewWindowFocus::Active => {
+                if workspace == self.active_workspace_global {
+                    self.set_window_focus(hwnd);
+                }
+            }
+            NewWindowFocus::Never => {
+                // The window may only grab the foreground after we see it
+                if self.system.foreground_window() == hwnd {
+                    self.restore_focus_from(hwnd);
+                } else {
+                    self.focus_guard = Some((hwnd.0 as isize, Instant::now()));
+                }
+            }
+        }
+    }
+
+    /// Hands focus back if a window that may not take it (see
+    /// [`WorkspaceManager::focus_new_window`]) just did.
+    pub fn enforce_focus_guard(&mut self, hwnd: HWND) {
+        let Some((guarded, since)) = self.focus_guard else {
+            return;
+        };
+        // Later focus changes are the user's doing
+        if since.elapsed() > NEW_WINDOW_FOCUS_GRACE {
+            self.focus_guard = None;
+        } else if guarded == hwnd.0 as isize {
+            self.focus_guard = None;
+            self.restore_focus_from(hwnd);
+        }
+    }
+
+    /// Focuses the managed window that had focus before `hwnd`.
+    fn restore_focus_from(&mut self, hwnd: HWND) {
+        if let Some(previous) = self.last_managed_focus
+            && previous != hwnd.0 as isize
+            && self.get_window(hwnd_from_isize(previous)).is_some()
+        {
+            debug!(
+                "Keeping focus on {:?} instead of new window {:?}",
+                previous, hwnd.0
+            );
+            self.set_window_focus(hwnd_from_isize(previous));
+        }
+    }
+
     /// Updates window decorations (border color, transparency) based on focus state.
     pub fn update_decorations(&mut self) {
-        let focused_hwnd = unsafe { GetForegroundWindow() };
+        let focused_hwnd = self.system.foreground_window();
 
         // If focus hasn't changed, we can still update if needed, but usually once is enough
-        self.last_focused_hwnd = Some(focused_hwnd.0 as isize);
+        let previously_focused = self.last_focused_hwnd.replace(focused_hwnd.0 as isize);
+        self.urgent_windows
+            .retain(|&hwnd| hwnd != focused_hwnd.0 as isize);
+        if let Some(workspace) = self.get_window(focused_hwnd).map(|window| window.workspace) {
+            self.last_managed_focus = Some(focused_hwnd.0 as isize);
+            self.focus_history
+                .entry(workspace)
+                .or_default()
+                .record(focused_hwnd.0 as isize);
+        }
+
+        if self.window_effects {
+            self.update_focus_effects(focused_hwnd, previously_focused);
+        }
+
+        let managed_set: HashSet<isize> = self.get_all_managed_hwnds().into_iter().collect();
+        self.last_window_alpha
+            .retain(|hwnd, _| managed_set.contains(hwnd));
+        self.window_corners
+            .retain(|hwnd, _| managed_set.contains(hwnd));
+
+        self.update_corners();
+        if self.update_title_bars() {
+            self.apply_window_positions();
+        }
+    }
 
-        let accent_color = match get_accent_color() {
+    /// Gives the focused window the accent border and dims the others.
+    fn update_focus_effects(&mut self, focused_hwnd: HWND, previously_focused: Option<isize>) {
+        let accent_color = match self.accent_color() {
             Ok(color) => color,
             Err(e) => {
                 error!("Failed to read accent color: {}", e);
@@ -144,40 +1137,238 @@ impl WorkspaceManager {
             }
         };
 
-        let managed_hwnds = self.get_all_managed_hwnds();
-        let managed_set: HashSet<isize> = managed_hwnds.iter().copied().collect();
-        let unfocused_alpha: u8 = 245;
+        // Unfocused opacity is per process, since some apps flicker when layered
+        let managed_windows: Vec<(isize, u8, u8, Option<String>)> = self
+            .monitors
+            .iter()
+            .flat_map(|m| &m.workspaces)
+            .flat_map(|ws| &ws.windows)
+            .map(|w| {
+                let alpha = self.focused_alpha(w.process_name.as_deref());
+                let unfocused_alpha = if self.power_saving {
+                    alpha
+                } else {
+                    self.decorations_config
+                        .unfocused_alpha_for(w.process_name.as_deref())
+                };
+                (
+                    w.hwnd,
+                    alpha,
+                    unfocused_alpha.min(alpha),
+                    w.process_name.clone(),
+                )
+            })
+            .collect();
 
-        for hwnd_val in &managed_hwnds {
+        for (hwnd_val, focused_alpha, unfocused_alpha, process) in &managed_windows {
             let hwnd = HWND(*hwnd_val as _);
             let desired_alpha = if hwnd == focused_hwnd {
-                255
+                *focused_alpha
             } else {
-                unfocused_alpha
+                *unfocused_alpha
             };
             let previous_alpha = self.last_window_alpha.get(hwnd_val).copied();
+            let rule = self.border_rule(hwnd, process.as_deref());
 
             if hwnd == focused_hwnd {
-                if let Err(e) = set_window_border_color(hwnd, accent_color) {
+                let border = rule
+                    .and_then(|(color, _)| color)
+                    .unwrap_or_else(|| self.theme.border_color(accent_color));
+                if let Err(e) = self.system.set_border_color(hwnd, border) {
                     error!("Failed to set window border color: {}", e);
                 }
-            } else if previous_alpha != Some(desired_alpha)
-                && let Err(e) = reset_window_decorations(hwnd)
+            } else if previous_alpha != Some(desired_alpha) || previously_focused == Some(*hwnd_val)
             {
-                error!("Failed to reset window decorations: {}", e);
+                if let Err(e) = self.system.reset_decorations(hwnd) {
+                    error!("Failed to reset window decorations: {}", e);
+                }
+                // Rules can keep their color on unfocused windows
+                if let Some((Some(color), true)) = rule
+                    && let Err(e) = self.system.set_border_color(hwnd, color)
+                {
+                    error!("Failed to set window border color: {}", e);
+                }
             }
 
             if previous_alpha != Some(desired_alpha) {
-                if let Err(e) = set_window_transparency(hwnd, desired_alpha) {
+                if let Err(e) = self.system.set_transparency(hwnd, desired_alpha) {
                     error!("Failed to set window transparency: {}", e);
                 } else {
                     self.last_window_alpha.insert(*hwnd_val, desired_alpha);
                 }
             }
         }
+    }
 
-        self.last_window_alpha
-            .retain(|hwnd, _| managed_set.contains(hwnd));
+    /// Pulses the focused window's border and opacity a few times, to show
+    /// where focus is on a crowded desktop.
+    pub fn flash_focus(&mut self) {
+        let hwnd = self.system.foreground_window();
+        if hwnd.is_invalid() {
+            return;
+        }
+        self.end_focus_flash();
+        self.focus_flash = Some((hwnd.0 as isize, Instant::now(), false));
+        self.update_focus_flash();
+    }
+
+    /// Advances a focus flash. Call this every loop iteration.
+    pub fn update_focus_flash(&mut self) {
+        let Some((hwnd_val, started, lit)) = self.focus_flash else {
+            return;
+        };
+        let hwnd = hwnd_from_isize(hwnd_val);
+        // Focus moving on ends the flash, it has done its job
+        let highlighted = flash_highlighted(started.elapsed())
+            .filter(|_| self.system.foreground_window() == hwnd);
+        match highlighted {
+            None => self.end_focus_flash(),
+            Some(highlighted) if highlighted != lit => {
+                self.focus_flash = Some((hwnd_val, started, highlighted));
+                let (border, alpha) = if highlighted {
+                    let border = match self.theme {
+                        Theme::Dark => 0x00FFFFFF,
+                        Theme::Light => 0x00000000,
+                    };
+                    (border, FOCUS_FLASH_ALPHA)
+                } else {
+                    // Back to the look the focus effects give it
+                    let process = self.get_window(hwnd).and_then(|window| window.process_name);
+                    let accent = self.accent_color().unwrap_or(0x00FFFFFF);
+                    let border = self
+                        .border_rule(hwnd, process.as_deref())
+                        .and_then(|(color, _)| color)
+                        .unwrap_or_else(|| self.theme.border_color(accent));
+                    (border, self.focused_alpha(process.as_deref()))
+                };
+                if let Err(e) = self.system.set_border_color(hwnd, border) {
+                    debug!("Failed to flash window border: {}", e);
+                }
+                if let Err(e) = self.system.set_transparency(hwnd, alpha) {
+                    debug!("Failed to flash window opacity: {}", e);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Stops a focus flash, putting the window's decorations back.
+    fn end_focus_flash(&mut self) {
+        let Some((hwnd_val, _, _)) = self.focus_flash.take() else {
+            return;
+        };
+        let hwnd = hwnd_from_isize(hwnd_val);
+        let _ = self.system.reset_decorations(hwnd);
+        let _ = self.system.set_transparency(hwnd, 255);
+        // Have the next update apply its opacity and border again
+        self.last_window_alpha.remove(&hwnd_val);
+        self.update_decorations();
+    }
+
+    /// Returns the color of the `[[decorations.borders]]` entry for a window
+    /// and whether it stays while the window is unfocused.
+    fn border_rule(&mut self, hwnd: HWND, process: Option<&str>) -> Option<(Option<u32>, bool)> {
+        let elevated = self.decorations_config.borders_need_elevation()
+            && (self.elevated_windows.contains(&(hwnd.0 as isize)) || {
+                let system = &self.system;
+                *self
+                    .window_elevation
+                    .entry(hwnd.0 as isize)
+                    .or_insert_with(|| system.is_elevated(hwnd))
+            });
+        self.decorations_config
+            .border_for(process, elevated)
+            .map(|rule| (rule.colorref(), rule.unfocused))
+    }
+
+    /// Applies the `[decorations]` corner preference for tiled or floating
+    /// windows, skipping windows that already have it.
+    fn update_corners(&mut self) {
+        for monitor in &self.monitors {
+            for workspace in &monitor.workspaces {
+                for window in &workspace.windows {
+                    let corners = if window.is_tiled {
+                        self.decorations_config.corners
+                    } else {
+                        self.decorations_config.floating_corners
+                    };
+                    if self.window_corners.get(&window.hwnd) == Some(&corners) {
+                        continue;
+                    }
+                    // Failures are remembered too: pre-Windows 11 rejects every call
+                    if let Err(e) = self
+                        .system
+                        .set_corners(hwnd_from_isize(window.hwnd), corners)
+                    {
+                        debug!("{}", e);
+                    }
+                    self.window_corners.insert(window.hwnd, corners);
+                }
+            }
+        }
+    }
+
+    /// Strips or restores title bars to match `[decorations]`: only tiled,
+    /// non-excluded windows lose theirs. Returns true if any frame changed,
+    /// since the window then needs repositioning.
+    fn update_title_bars(&mut self) -> bool {
+        let mut changed = false;
+        for monitor in &self.monitors {
+            for workspace in &monitor.workspaces {
+                for window in &workspace.windows {
+                    let hide = window.is_tiled
+                        && self
+                            .decorations_config
+                            .hides_title_bar(window.process_name.as_deref());
+                    let stripped = self.stripped_styles.contains_key(&window.hwnd);
+                    let hwnd = hwnd_from_isize(window.hwnd);
+
+                    if hide && !stripped {
+                        match strip_window_frame(hwnd) {
+                            Ok(style) => {
+                                self.stripped_styles.insert(window.hwnd, style);
+                            }
+                            Err(e) => error!("Failed to hide title bar: {}", e),
+                        }
+                    } else if !hide && let Some(style) = self.stripped_styles.remove(&window.hwnd) {
+                        if let Err(e) = restore_window_style(hwnd, style) {
+                            error!("Failed to restore title bar: {}", e);
+                        }
+                    } else {
+                        continue;
+                    }
+                    // Frame size changes with the style
+                    self.dwm_borders.remove(&window.hwnd);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Restores the title bars, corners and always-on-top state of all
+    /// windows, e.g. before exiting.
+    pub fn restore_window_frames(&mut self) {
+        for monitor in self.monitors.iter_mut() {
+            for workspace in &mut monitor.workspaces {
+                for window in workspace.windows.iter_mut().filter(|w| w.is_topmost) {
+                    window.is_topmost = false;
+                    if let Err(e) = self.system.set_topmost(hwnd_from_isize(window.hwnd), false) {
+                        error!("Failed to unpin {:?}: {}", window.hwnd, e);
+                    }
+                }
+            }
+        }
+        for (hwnd, style) in self.stripped_styles.drain() {
+            if let Err(e) = restore_window_style(hwnd_from_isize(hwnd), style) {
+                error!("Failed to restore title bar for {:?}: {}", hwnd, e);
+            }
+        }
+        for (hwnd, _) in self.window_corners.drain() {
+            let _ = self
+                .system
+                .set_corners(hwnd_from_isize(hwnd), CornerPreference::Default);
+        }
     }
 
     /// Sets the list of monitors for the workspace manager.
@@ -200,7 +1391,7 @@ impl WorkspaceManager {
 
     /// Returns all window handles managed by Megatile across all workspaces.
     pub fn get_all_managed_hwnds(&self) -> Vec<isize> {
-        let mut hwnds = Vec::new();
+        let mut hwnds: Vec<isize> = self.stash.parked_hwnds().collect();
         for monitor in self.monitors.iter() {
             for workspace in &monitor.workspaces {
                 for window in &workspace.windows {
@@ -211,21 +1402,113 @@ impl WorkspaceManager {
         hwnds
     }
 
-    /// Determines which monitor a window belongs to.
-    pub fn get_monitor_for_window(&self, hwnd: HWND) -> Option<usize> {
-        use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONEAREST, MonitorFromWindow};
+    /// Captures which workspace every window is on, each workspace's splits
+    /// and focus, and where floating windows are, for a restart.
+    pub fn saved_session(&self) -> SavedSession {
+        let windows = self
+            .monitors
+            .iter()
+            .flat_map(|monitor| monitor.workspaces.iter())
+            .flat_map(|workspace| workspace.windows.iter())
+            .map(|window| SavedWindow {
+                hwnd: window.hwnd,
+                workspace: window.workspace,
+                is_tiled: window.is_tiled,
+                process: window.process_name.clone(),
+                title: window.title.clone(),
+                monitor: window.monitor,
+                float_rect: (!window.is_tiled).then(|| window.rect.into()),
+            })
+            .collect();
+        let workspaces = self
+            .monitors
+            .iter()
+            .enumerate()
+            .flat_map(|(monitor, m)| {
+                m.workspaces
+                    .iter()
+                    .zip(1..)
+                    .filter(|(workspace, _)| !workspace.windows.is_empty())
+                    .map(move |(workspace, number)| SavedWorkspace {
+                        monitor,
+                        workspace: number,
+                        focused_window_hwnd: workspace.focused_window_hwnd,
+                        layout_tree: workspace.layout_tree.as_ref().map(|tree| {
+                            LayoutNode::from_tile_with_handles(tree, &workspace.windows)
+                        }),
+                    })
+            })
+            .collect();
+        SavedSession {
+            active_workspace: self.active_workspace_global,
+            windows,
+            workspaces,
+        }
+    }
 
-        let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+    /// Moves a floating window that is about to be added to its `rect`.
+    pub fn place_floating(&mut self, window: &Window) {
+        self.set_window_positions(&[(window.hwnd, window.rect)]);
+    }
 
-        for (i, monitor) in self.monitors.iter().enumerate() {
-            if monitor.hmonitor == hmonitor.0 as isize {
-                return Some(i);
+    /// Puts back each workspace's splits and focused window from `session`,
+    /// once its windows are added. `matched` pairs the windows found at
+    /// startup with where they were saved.
+    pub fn restore_session(
+        &mut self,
+        session: &SavedSession,
+        matched: &HashMap<isize, &SavedWindow>,
+    ) {
+        let current: HashMap<isize, isize> = matched
+            .iter()
+            .map(|(&hwnd, saved)| (saved.hwnd, hwnd))
+            .collect();
+        for saved in &session.workspaces {
+            if saved.monitor >= self.monitors.len() {
+                continue;
+            }
+            if let Some(layout) = &saved.layout_tree {
+                self.arrange_workspace(saved.monitor, saved.workspace, layout);
             }
+            let focused = saved
+                .focused_window_hwnd
+                .and_then(|hwnd| current.get(&hwnd).copied());
+            if let Some(workspace) = self.monitors[saved.monitor].get_workspace_mut(saved.workspace)
+                && let Some(hwnd) = focused
+                && workspace.get_window(hwnd_from_isize(hwnd)).is_some()
+            {
+                workspace.focused_window_hwnd = Some(hwnd);
+            }
+        }
+    }
+
+    /// Determines which monitor a window belongs to.
+    pub fn get_monitor_for_window(&self, hwnd: HWND) -> Option<usize> {
+        let hmonitor = self.system.monitor_for_window(hwnd);
+
+        let zones: Vec<usize> = (0..self.monitors.len())
+            .filter(|&i| self.monitors[i].hmonitor == hmonitor)
+            .collect();
+        if zones.len() > 1 {
+            // Display split into zones: pick the one under the window's center
+            let center_x = self
+                .system
+                .window_rect(hwnd)
+                .map(|rect| (rect.left + rect.right) / 2)
+                .unwrap_or_default();
+            return zones
+                .iter()
+                .copied()
+                .find(|&i| center_x < self.monitors[i].rect.right)
+                .or(zones.last().copied());
+        }
+        if let Some(&i) = zones.first() {
+            return Some(i);
         }
 
         // Fallback to containment check if hmonitor doesn't match
         for (i, monitor) in self.monitors.iter().enumerate() {
-            if let Ok(rect) = crate::windows_lib::get_window_rect(hwnd)
+            if let Ok(rect) = self.system.window_rect(hwnd)
                 && rect.left >= monitor.rect.left
                 && rect.top >= monitor.rect.top
                 && rect.right <= monitor.rect.right
@@ -247,71 +1530,39 @@ impl WorkspaceManager {
         monitor_idx: usize,
         direction: FocusDirection,
     ) -> Option<usize> {
-        let current_monitor = self.monitors.get(monitor_idx)?;
-        let current_rect = current_monitor.rect;
-
-        // Calculate center point of current monitor
-        let current_center_x = (current_rect.left + current_rect.right) / 2;
-        let current_center_y = (current_rect.top + current_rect.bottom) / 2;
-
-        let mut candidates: Vec<(usize, i32)> = Vec::new();
-
-        for (i, monitor) in self.monitors.iter().enumerate() {
-            if i == monitor_idx {
-                continue; // Skip the current monitor
-            }
-
-            let monitor_rect = monitor.rect;
-            let monitor_center_x = (monitor_rect.left + monitor_rect.right) / 2;
-            let monitor_center_y = (monitor_rect.top + monitor_rect.bottom) / 2;
-
-            let matches_direction = match direction {
-                FocusDirection::Left => {
-                    // Monitor is to the left if its center X is less than current center X
-                    monitor_center_x < current_center_x
-                }
-                FocusDirection::Right => {
-                    // Monitor is to the right if its center X is greater than current center X
-                    monitor_center_x > current_center_x
-                }
-                FocusDirection::Up => {
-                    // Monitor is above if its center Y is less than current center Y
-                    monitor_center_y < current_center_y
-                }
-                FocusDirection::Down => {
-                    // Monitor is below if its center Y is greater than current center Y
-                    monitor_center_y > current_center_y
-                }
-            };
-
-            if matches_direction {
-                // Calculate distance (Manhattan distance for simplicity)
-                let dx = (monitor_center_x - current_center_x).abs();
-                let dy = (monitor_center_y - current_center_y).abs();
-                let distance = dx + dy;
-                candidates.push((i, distance));
-            }
-        }
-
-        // Return the closest monitor in the specified direction
-        candidates
-            .iter()
-            .min_by_key(|(_, distance)| *distance)
-            .map(|(idx, _)| *idx)
+        // Unmanaged monitors are skipped over
+        let candidates: Vec<usize> = (0..self.monitors.len())
+            .filter(|&i| i == monitor_idx || self.monitors[i].managed)
+            .collect();
+        let rects: Vec<RECT> = candidates.iter().map(|&i| self.monitors[i].rect).collect();
+        let from = candidates.iter().position(|&i| i == monitor_idx)?;
+        find_rect_in_direction(&rects, from, direction).map(|i| candidates[i])
     }
 
     /// Adds a window to the workspace manager.
-    pub fn add_window(&mut self, window: Window) {
+    pub fn add_window(&mut self, mut window: Window) {
         debug!(
             "Adding window {:?} to workspace {} on monitor {}",
             window.hwnd, window.workspace, window.monitor
         );
+        if self.is_floating_workspace(window.workspace) {
+            window.is_tiled = false;
+        }
+        if window.is_tiled {
+            // A new tile would have nowhere to go while one window is centered alone
+            self.exit_zen(window.monitor, window.workspace);
+        }
+        let (monitor_idx, workspace_num, is_tiled) =
+            (window.monitor, window.workspace, window.is_tiled);
         if let Some(monitor) = self.monitors.get_mut(window.monitor) {
             debug!(
                 "Monitor {} found, adding window to workspace {}",
                 window.monitor, window.workspace
             );
             monitor.add_window(window);
+            if is_tiled {
+                self.apply_layout_template(monitor_idx, workspace_num);
+            }
             self.update_statusbar();
             self.update_decorations();
             debug!("Window added successfully");
@@ -324,6 +1575,21 @@ impl WorkspaceManager {
     pub fn remove_window(&mut self, hwnd: HWND) -> Option<Window> {
         debug!("Removing window {:?}", hwnd.0);
         self.last_window_alpha.remove(&(hwnd.0 as isize));
+        self.pending_resizes.remove(&(hwnd.0 as isize));
+        self.dwm_borders.remove(&(hwnd.0 as isize));
+        self.elevated_windows.remove(&(hwnd.0 as isize));
+        self.window_elevation.remove(&(hwnd.0 as isize));
+        self.urgent_windows
+            .retain(|&urgent| urgent != hwnd.0 as isize);
+        self.unadopted_windows
+            .retain(|&unadopted| unadopted != hwnd.0 as isize);
+        if let Some(style) = self.stripped_styles.remove(&(hwnd.0 as isize)) {
+            // Windows that are still around (e.g. hidden by their app) get their frame back
+            let _ = restore_window_style(hwnd, style);
+        }
+        if let Some((monitor_idx, workspace_num)) = self.find_zen_workspace(hwnd.0 as isize) {
+            self.exit_zen(monitor_idx, workspace_num);
+        }
         for (monitor_idx, monitor) in self.monitors.iter_mut().enumerate() {
             debug!("Checking monitor {} for window {:?}", monitor_idx, hwnd.0);
             if let Some(window) = monitor.remove_window(hwnd) {
@@ -331,6 +1597,10 @@ impl WorkspaceManager {
                     "Found and removed window {:?} from monitor {}",
                     window.hwnd, monitor_idx
                 );
+                if window.is_topmost {
+                    // A window that comes back (e.g. restored) starts out unpinned
+                    let _ = self.system.set_topmost(hwnd, false);
+                }
                 return Some(window);
             }
         }
@@ -341,6 +1611,15 @@ impl WorkspaceManager {
     /// Removes a window and re-tiles the affected workspace.
     pub fn remove_window_with_tiling(&mut self, hwnd: HWND) -> Option<Window> {
         debug!("Removing window with tiling update: {:?}", hwnd.0);
+        // A window closed mid-drag never sends the drop
+        if self.dragging_window == Some(hwnd.0 as isize) {
+            self.dragging_window = None;
+        }
+        if let Some(parked) = self.stash.forget(hwnd.0 as isize) {
+            debug!("Window {:?} was parked in the stash", parked.hwnd);
+            self.update_statusbar();
+            return Some(parked);
+        }
         let removed_window = self.remove_window(hwnd);
 
         if let Some(ref window) = removed_window {
@@ -348,6 +1627,7 @@ impl WorkspaceManager {
                 "Window {:?} from workspace {} removed, re-tiling affected workspaces",
                 window.hwnd, window.workspace
             );
+            self.collapse_empty_workspaces();
             // Re-tile the workspace that had the window removed
             self.tile_active_workspaces();
             self.apply_window_positions();
@@ -371,6 +1651,31 @@ impl WorkspaceManager {
         None
     }
 
+    /// Stores a window's new title. Returns false if the window isn't managed
+    /// or the title didn't change.
+    pub fn set_window_title(&mut self, hwnd: HWND, title: &str) -> bool {
+        for monitor in self.monitors.iter_mut() {
+            for workspace in monitor.workspaces.iter_mut() {
+                if let Some(window) = workspace.get_window_mut(hwnd) {
+                    if window.title == title {
+                        return false;
+                    }
+                    window.title = title.to_string();
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Number of physical displays; the zones of a split display count once.
+    pub fn display_count(&self) -> usize {
+        self.monitors
+            .iter()
+            .filter(|monitor| monitor.zone == 0)
+            .count()
+    }
+
     /// Checks if a window is in any active workspace across all monitors.
     ///
     /// Used to determine if a hidden window is expected (inactive workspace) or a zombie (hidden in active workspace).
@@ -396,33 +1701,61 @@ impl WorkspaceManager {
         self.last_reenumerate = Instant::now();
 
         info!("Re-enumerating monitors...");
+        // Monitor DPI may have changed along with the layout
+        self.invalidate_dwm_borders();
 
         // Get current monitor info
-        let monitor_infos = crate::windows_lib::enumerate_monitors();
+        let monitor_infos = self.system.monitors();
         info!("Found {} monitor(s)", monitor_infos.len());
 
-        let mut new_monitors: Vec<Monitor> = Vec::new();
+        let mut new_monitors = self.monitors_from_infos(&monitor_infos);
 
-        for (i, info) in monitor_infos.iter().enumerate() {
-            debug!("Monitor {}: {:?}", i, info.rect);
+        for (i, monitor) in new_monitors.iter_mut().enumerate() {
+            debug!("Monitor {}: {:?} (zone {})", i, monitor.rect, monitor.zone);
 
-            // Try to preserve workspace data from existing monitor by matching hmonitor
-            let existing_workspace_data = if let Some(old_monitor) =
-                self.monitors.iter().find(|m| m.hmonitor == info.hmonitor)
+            // Try to preserve workspace data from existing monitor by matching device and
+            // zone; handles can change when displays are re-plugged
+            if let Some(old_monitor) = self
+                .monitors
+                .iter()
+                .find(|m| m.device_name == monitor.device_name && m.zone == monitor.zone)
             {
-                old_monitor.workspaces.clone()
-            } else {
-                std::array::from_fn(|_| crate::workspace::Workspace::new())
-            };
-
-            let mut monitor = Monitor::new(info.hmonitor, info.rect);
-            monitor.workspaces = existing_workspace_data;
+                monitor.workspaces = old_monitor.workspaces.clone();
+                // A rotated display switches layout; rebuild its trees from scratch
+                if old_monitor.layout != monitor.layout {
+                    for workspace in &mut monitor.workspaces {
+                        workspace.layout_tree = None;
+                    }
+                }
+            }
             monitor.active_workspace = self.active_workspace_global;
-            new_monitors.push(monitor);
         }
 
-        // Update monitors
+        // Windows on displays (or zones) that are gone move to the fallback
+        // monitor, keeping their workspace numbers
+        let lost: Vec<Monitor> = self
+            .monitors
+            .iter()
+            .filter(|old| {
+                !new_monitors
+                    .iter()
+                    .any(|m| m.device_name == old.device_name && m.zone == old.zone)
+            })
+            .cloned()
+            .collect();
         self.monitors = new_monitors;
+        for (i, monitor) in self.monitors.iter_mut().enumerate() {
+            for window in monitor
+                .workspaces
+                .iter_mut()
+                .flat_map(|ws| ws.windows.iter_mut())
+            {
+                window.monitor = i;
+            }
+        }
+        for old_monitor in lost {
+            self.rescue_windows(old_monitor);
+        }
 
         // Re-tile active workspace on all monitors
         self.tile_active_workspaces();
@@ -432,9 +1765,62 @@ impl WorkspaceManager {
         Ok(())
     }
 
+    /// Moves the windows of a monitor that disappeared to the fallback
+    /// monitor: the `[[monitors]]` entry marked `fallback`, else the primary
+    /// one, else any managed monitor. Floating windows keep their place
+    /// relative to the monitor, within the fallback's work area.
+    fn rescue_windows(&mut self, lost: Monitor) {
+        let window_count: usize = lost.workspaces.iter().map(|ws| ws.windows.len()).sum();
+        if window_count == 0 {
+            return;
+        }
+        let fallback = self
+            .monitors
+            .iter()
+            .position(|monitor| {
+                monitor_config(&self.monitor_configs, &monitor.device_name)
+                    .is_some_and(|config| config.fallback && config.device.is_some())
+            })
+            .into_iter()
+            .chain([self.primary_monitor_index()])
+            .chain(0..self.monitors.len())
+            .find(|&idx| self.is_monitor_managed(idx));
+        let Some(fallback) = fallback else {
+            warn!(
+                "{} (zone {}) is gone and no monitor is left to take its {} windows",
+                lost.device_name, lost.zone, window_count
+            );
+            return;
+        };
+        info!(
+            "{} (zone {}) is gone, moving its {} windows to {}",
+            lost.device_name, lost.zone, window_count, self.monitors[fallback].device_name
+        );
+        let area = crate::tiling::work_area(self.monitors[fallback].rect);
+        let mut floating = Vec::new();
+        for (index, workspace) in lost.workspaces.into_iter().enumerate() {
+            let Some(target) = self.monitors[fallback].workspaces.get_mut(index) else {
+                continue;
+            };
+            for mut window in workspace.windows {
+                window.monitor = fallback;
+                window.tile_group = None;
+                window.rect = crate::tiling::carry_rect(window.rect, lost.rect, area);
+                window.original_rect =
+                    crate::tiling::carry_rect(window.original_rect, lost.rect, area);
+                self.dwm_borders.remove(&window.hwnd);
+                if !window.is_tiled && window.workspace == self.active_workspace_global {
+                    floating.push((window.hwnd, window.rect));
+                }
+                target.add_window(window);
+            }
+        }
+        self.set_window_positions(&floating);
+    }
+
     /// Checks if monitor configuration has changed.
     pub fn check_monitor_changes(&mut self) -> bool {
-        let current_infos = crate::windows_lib::enumerate_monitors();
+        let current_infos = self.monitors_from_infos(&self.system.monitors());
         if current_infos.len() != self.monitors.len() {
             return true;
         }
@@ -466,10 +1852,12 @@ impl WorkspaceManager {
 
     /// Switches to a different workspace, hiding/showing windows as needed.
     pub fn switch_workspace_with_windows(&mut self, new_workspace: u8) -> Result<(), String> {
-        if !(1..=9).contains(&new_workspace) {
+        if !(1..=self.workspace_count).contains(&new_workspace) {
             warn!("Invalid workspace number requested: {}", new_workspace);
             return Err("Invalid workspace number".to_string());
         }
+        // Dynamic workspaces only go one past the last one in use
+        let new_workspace = new_workspace.min(self.used_workspace_count());
 
         let old_workspace = self.active_workspace_global;
 
@@ -542,6 +1930,7 @@ impl WorkspaceManager {
         // Update active workspace IMMEDIATELY after hide/show, before tiling
         debug!("Updating active workspace global to {}", new_workspace);
         self.active_workspace_global = new_workspace;
+        self.desktop_shown = None;
 
         // Update all monitors to reflect the new active workspace
         debug!("Updating active workspace on all monitors");
@@ -573,40 +1962,79 @@ impl WorkspaceManager {
 
         // Restore focus for the new workspace
         debug!("Restoring focus for workspace {}", new_workspace);
-        let mut focus_target = None;
+        if let Some(hwnd) = self.workspace_focus_target(new_workspace) {
+            debug!("Auto-focusing window {:?} after workspace switch", hwnd.0);
+            self.set_window_focus(hwnd);
+        } else {
+            debug!("No window to focus in workspace {}", new_workspace);
+        }
+
+        // The workspace left behind may have been an empty one in between
+        self.collapse_empty_workspaces();
+        self.update_statusbar();
+        self.update_decorations();
+
+        debug!("Workspace switch completed successfully");
+        Ok(())
+    }
+
+    /// Returns the window to focus when `workspace_num` comes back into view:
+    /// the one focused there last, or else its first tiled window.
+    fn workspace_focus_target(&self, workspace_num: u8) -> Option<HWND> {
         for monitor in self.monitors.iter() {
-            if let Some(workspace) = monitor.get_workspace(new_workspace) {
+            if let Some(workspace) = monitor.get_workspace(workspace_num) {
                 if let Some(hwnd) = workspace.focused_window_hwnd {
-                    focus_target = Some(hwnd_from_isize(hwnd));
                     debug!(
                         "Found remembered focus target {:?} for workspace {}",
-                        hwnd, new_workspace
+                        hwnd, workspace_num
                     );
-                    break;
+                    return Some(hwnd_from_isize(hwnd));
                 }
                 // If no remembered focus, try the first tiled window
                 if let Some(first_window) = workspace.windows.iter().find(|w| w.is_tiled) {
-                    focus_target = Some(hwnd_from_isize(first_window.hwnd));
                     debug!(
                         "No remembered focus, using first tiled window {:?} for workspace {}",
-                        first_window.hwnd, new_workspace
+                        first_window.hwnd, workspace_num
                     );
-                    break;
+                    return Some(hwnd_from_isize(first_window.hwnd));
                 }
             }
         }
+        None
+    }
 
-        if let Some(hwnd) = focus_target {
-            debug!("Auto-focusing window {:?} after workspace switch", hwnd.0);
-            self.set_window_focus(hwnd);
+    /// Hides every window on the active workspace, or brings them back if
+    /// show-desktop hid them. Like `Win + D`, but windows on other
+    /// workspaces, stash slots and the status bar are left alone.
+    pub fn toggle_show_desktop(&mut self) -> Result<(), String> {
+        let workspace_num = self.active_workspace_global;
+        if self.desktop_shown.take() == Some(workspace_num) {
+            debug!("Bringing back the windows of workspace {}", workspace_num);
+            self.show_workspace_windows(workspace_num)?;
+            self.tile_active_workspaces();
+            self.apply_window_positions();
+            self.restore_fullscreen_workspace(workspace_num);
+            if let Some(hwnd) = self.workspace_focus_target(workspace_num) {
+                self.set_window_focus(hwnd);
+            }
         } else {
-            debug!("No window to focus in workspace {}", new_workspace);
+            debug!("Showing the desktop of workspace {}", workspace_num);
+            // Remember focus so the same window gets it back
+            if let Some(focused) = self.get_focused_window()
+                && focused.workspace == workspace_num
+                && let Some(workspace) = self
+                    .monitors
+                    .get_mut(focused.monitor)
+                    .and_then(|m| m.get_workspace_mut(workspace_num))
+            {
+                workspace.focused_window_hwnd = Some(focused.hwnd);
+            }
+            self.exit_fullscreen_workspace(workspace_num);
+            self.hide_workspace_windows(workspace_num)?;
+            self.desktop_shown = Some(workspace_num);
         }
-
         self.update_statusbar();
         self.update_decorations();
-
-        debug!("Workspace switch completed successfully");
         Ok(())
     }
 
@@ -631,12 +2059,19 @@ impl WorkspaceManager {
                     workspace.windows.len(),
                     workspace_num
                 );
+                let zen_window = workspace.zen_window;
                 for window in &mut workspace.windows {
+                    // Windows set aside by zen mode stay hidden until it ends
+                    if !hide && window.is_tiled && zen_window.is_some_and(|zen| zen != window.hwnd)
+                    {
+                        continue;
+                    }
                     let hwnd = hwnd_from_isize(window.hwnd);
+                    let method = hide_method(&self.hide_methods, window);
                     let result = if hide {
-                        hide_window_from_taskbar(hwnd)
+                        self.system.hide_window(hwnd, method)
                     } else {
-                        show_window_in_taskbar(hwnd)
+                        self.system.show_window(hwnd, method)
                     };
                     match result {
                         Ok(()) => {
@@ -676,15 +2111,21 @@ impl WorkspaceManager {
         self.set_workspace_windows_visibility(workspace_num, false)
     }
 
-    /// Moves the focused window to another workspace.
-    pub fn move_window_to_workspace(&mut self, new_workspace: u8) -> Result<(), String> {
-        if !(1..=9).contains(&new_workspace) {
+    /// Moves the focused window to another workspace, switching to it if
+    /// `follow` is set and otherwise hiding the window there.
+    pub fn move_window_to_workspace(
+        &mut self,
+        new_workspace: u8,
+        follow: bool,
+    ) -> Result<(), String> {
+        if !(1..=self.workspace_count).contains(&new_workspace) {
             warn!(
                 "Invalid workspace number {} requested for window move",
                 new_workspace
             );
             return Err("Invalid workspace number".to_string());
         }
+        let new_workspace = new_workspace.min(self.used_workspace_count());
 
         // Get currently focused window
         let focused = self.get_focused_window();
@@ -741,6 +2182,16 @@ impl WorkspaceManager {
             // Update window's workspace
             window.workspace = new_workspace;
             debug!("Updated window workspace to {}", new_workspace);
+            if window.is_tiled && self.is_floating_workspace(new_workspace) {
+                // It floats where it was tiled
+                window.is_tiled = false;
+            }
+            if !follow && new_workspace != self.active_workspace_global {
+                window.is_hidden_by_workspace = self
+                    .system
+                    .hide_window(hwnd, hide_method(&self.hide_methods, &window))
+                    .is_ok();
+            }
 
             // Keep window on same monitor (find target workspace on same monitor)
             if let Some(monitor) = self.monitors.get_mut(source_monitor_idx) {
@@ -768,7 +2219,24 @@ impl WorkspaceManager {
             if old_workspace == self.active_workspace_global {
                 debug!("Source workspace is active, re-tiling after window removal");
                 // Source workspace is currently active, so tile it
-                let tiler = DwindleTiler::default();
+                let bar_reserve =
+                    self.monitors
+                        .get(source_monitor_idx)
+                        .map_or(self.bar_reserve, |monitor| {
+                            display_bar_reserve(
+                                self.bar_reserve,
+                                &self.bar_displays,
+                                &self.fullscreen_displays,
+                                monitor,
+                            )
+                        });
+                let tiler = tiler_for(
+                    &self.layout_config,
+                    &self.workspace_overrides,
+                    old_workspace,
+                    bar_reserve,
+                    self.bar_position,
+                );
                 if let Some(monitor) = self.monitors.get_mut(source_monitor_idx) {
                     let workspace_idx = (old_workspace - 1) as usize;
                     if !monitor.workspaces[workspace_idx].windows.is_empty() {
@@ -779,9 +2247,10 @@ impl WorkspaceManager {
                         );
                         let monitor_copy = monitor.clone();
                         let workspace = &mut monitor.workspaces[workspace_idx];
+                        let focused = workspace.focused_window_hwnd;
                         let layout_tree = &mut workspace.layout_tree;
                         let windows = &mut workspace.windows;
-                        tiler.tile_windows(&monitor_copy, layout_tree, windows);
+                        tiler.tile_windows(&monitor_copy, layout_tree, windows, focused);
                     } else {
                         debug!(
                             "Source workspace {} is now empty, no tiling needed",
@@ -808,10 +2277,8 @@ impl WorkspaceManager {
                     }
                 }
 
-                // Now position them
-                for (hwnd, rect) in windows_to_position {
-                    self.set_window_position(hwnd_from_isize(hwnd), &rect);
-                }
+                // Now position them in a single batch
+                self.set_window_positions(&windows_to_position);
             } else {
                 debug!(
                     "Source workspace {} is not active, skipping immediate re-tiling",
@@ -819,7 +2286,7 @@ impl WorkspaceManager {
                 );
             }
 
-            should_switch = true;
+            should_switch = follow;
             _result = Ok(());
         } else {
             warn!("Window {:?} not found in any workspace", hwnd.0);
@@ -833,16 +2300,55 @@ impl WorkspaceManager {
             );
             self.switch_workspace_with_windows(new_workspace)?;
             debug!("Window move to workspace completed successfully");
+        } else if _result.is_ok() {
+            // Staying put: hand focus to what is left of the source workspace
+            self.update_statusbar();
+            self.update_decorations();
+            if let Some(next) = self
+                .monitors
+                .get(source_monitor_idx)
+                .and_then(|m| m.get_active_workspace().focused_window_hwnd)
+            {
+                self.set_window_focus(hwnd_from_isize(next));
+            }
         }
 
         _result
     }
 
+    /// Returns the workspace after (or before) the active one, wrapping
+    /// around, optionally skipping workspaces without windows.
+    pub fn adjacent_workspace(&self, forward: bool, skip_empty: bool) -> Option<u8> {
+        cycle_workspace(
+            self.active_workspace_global,
+            self.used_workspace_count(),
+            forward,
+            |ws| !skip_empty || self.get_workspace_window_count(ws) > 0,
+        )
+    }
+
+    /// Moves the focused window to the lowest-numbered empty workspace.
+    pub fn move_window_to_empty_workspace(&mut self, follow: bool) -> Result<u8, String> {
+        let workspace = (1..=self.used_workspace_count())
+            .find(|&ws| self.get_workspace_window_count(ws) == 0)
+            .ok_or("No empty workspace")?;
+        self.move_window_to_workspace(workspace, follow)?;
+        Ok(workspace)
+    }
+
     /// Moves the focused window to an adjacent monitor in the specified direction.
     ///
     /// If no monitor exists in the specified direction, this function returns Ok(())
     /// without moving the window (no-op behavior).
-    pub fn move_window_to_monitor(&mut self, direction: FocusDirection) -> Result<(), String> {
+    ///
+    /// With `follow`, focus stays on the moved window, taking the cursor along
+    /// if `[focus] cursor_follows` is set; otherwise focus goes to the next
+    /// window on the monitor the window left.
+    pub fn move_window_to_monitor(
+        &mut self,
+        direction: FocusDirection,
+        follow: bool,
+    ) -> Result<(), String> {
         debug!("Moving window to monitor in direction {:?}", direction);
 
         // Get currently focused window
@@ -904,16 +2410,30 @@ impl WorkspaceManager {
         }
 
         if let Some(mut window) = window_to_move {
-            // Update window's monitor index
+            // Update window's monitor index; borders may differ at the new monitor's DPI
             window.monitor = target_monitor_idx;
+            self.dwm_borders.remove(&window.hwnd);
             debug!("Updated window monitor to {}", target_monitor_idx);
 
-            // Add window to target monitor's active workspace (same workspace number)
+            // Add window to target monitor's active workspace (same workspace number).
+            // Left and right moves put it on the side it came in from. Up and down
+            // moves put it in the top or bottom region: the last tile is always the
+            // bottom one, but on a wide monitor the first split is left/right, so
+            // the top region is the second tile rather than the full-height first
             if let Some(target_monitor) = self.monitors.get_mut(target_monitor_idx) {
+                let wide = target_monitor.rect.right - target_monitor.rect.left
+                    > target_monitor.rect.bottom - target_monitor.rect.top;
                 if let Some(target_workspace) = target_monitor.get_workspace_mut(current_workspace)
                 {
                     let hwnd_val = window.hwnd;
-                    target_workspace.add_window(window.clone());
+                    let count = target_workspace.windows.len();
+                    let index = match direction {
+                        FocusDirection::Right => 0,
+                        FocusDirection::Left | FocusDirection::Down => count,
+                        FocusDirection::Up if wide => count.min(1),
+                        FocusDirection::Up => 0,
+                    };
+                    target_workspace.insert_window(index, window.clone());
                     target_workspace.focused_window_hwnd = Some(hwnd_val); // Ensure moved window is focused
                     debug!(
                         "Added window to monitor {} workspace {}",
@@ -937,9 +2457,24 @@ impl WorkspaceManager {
             self.tile_active_workspaces();
             self.apply_window_positions();
 
-            // Keep focus on the moved window
-            debug!("Restoring focus to moved window {:?}", hwnd.0);
-            self.set_window_focus(hwnd);
+            if follow {
+                // Keep focus on the moved window
+                debug!("Restoring focus to moved window {:?}", hwnd.0);
+                self.set_window_focus(hwnd);
+                if self.focus_config.cursor_follows
+                    && let Some(rect) = self.get_window(hwnd).map(|window| window.rect)
+                {
+                    let _ = self.system.set_cursor_position(
+                        (rect.left + rect.right) / 2,
+                        (rect.top + rect.bottom) / 2,
+                    );
+                }
+            } else if let Some(next) = self.monitors[source_monitor_idx]
+                .get_workspace(current_workspace)
+                .and_then(|workspace| workspace.focused_window_hwnd)
+            {
+                self.set_window_focus(hwnd_from_isize(next));
+            }
             debug!("Window moved to monitor successfully");
 
             Ok(())
@@ -948,25 +2483,142 @@ impl WorkspaceManager {
         }
     }
 
+    /// Spreads the active workspace's tiled windows evenly over the managed
+    /// monitors, or with `gather` moves them all to the focused window's
+    /// monitor. Returns how many windows changed monitors.
+    pub fn balance_monitors(&mut self, gather: bool) -> Result<usize, String> {
+        let workspace_num = self.active_workspace_global;
+        let managed: Vec<usize> = (0..self.monitors.len())
+            .filter(|&idx| self.is_monitor_managed(idx))
+            .collect();
+        if managed.len() < 2 {
+            return Err("There is only one monitor to tile on".to_string());
+        }
+        let tiled: Vec<Vec<isize>> = managed
+            .iter()
+            .map(|&idx| {
+                self.monitors[idx]
+                    .get_workspace(workspace_num)
+                    .map(|workspace| {
+                        workspace
+                            .windows
+                            .iter()
+                            .filter(|window| window.is_tiled)
+                            .map(|window| window.hwnd)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        let total: usize = tiled.iter().map(Vec::len).sum();
+        let wanted: Vec<usize> = if gather {
+            let target = self
+                .get_focused_window()
+                .map(|window| window.monitor)
+                .ok_or("No focused window")?;
+            managed
+                .iter()
+                .map(|&idx| if idx == target { total } else { 0 })
+                .collect()
+        } else {
+            // The first monitors take one more when they don't divide evenly
+            (0..managed.len())
+                .map(|slot| total / managed.len() + usize::from(slot < total % managed.len()))
+                .collect()
+        };
+
+        // Windows past their monitor's share, the last in tiling order first
+        let mut surplus: Vec<isize> = tiled
+            .iter()
+            .zip(&wanted)
+            .flat_map(|(hwnds, &share)| hwnds.iter().skip(share).copied())
+            .collect();
+        for &idx in &managed {
+            self.exit_zen(idx, workspace_num);
+        }
+        let mut moved = 0;
+        for (slot, &target_idx) in managed.iter().enumerate() {
+            for _ in tiled[slot].len()..wanted[slot] {
+                let Some(hwnd) = surplus.pop() else {
+                    break;
+                };
+                let Some(mut window) = self
+                    .monitors
+                    .iter_mut()
+                    .filter_map(|monitor| monitor.get_workspace_mut(workspace_num))
+                    .find_map(|workspace| {
+                        if workspace.maximized_window == Some(hwnd) {
+                            workspace.maximized_window = None;
+                        }
+                        workspace.remove_window(hwnd_from_isize(hwnd))
+                    })
+                else {
+                    continue;
+                };
+                window.monitor = target_idx;
+                self.dwm_borders.remove(&hwnd);
+                if let Some(workspace) = self.monitors[target_idx].get_workspace_mut(workspace_num)
+                {
+                    workspace.add_window(window);
+                    moved += 1;
+                }
+            }
+        }
+        debug!("Moved {} windows between monitors", moved);
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_statusbar();
+        Ok(moved)
+    }
+
     /// Applies tiling layout to all active workspaces on all monitors.
     pub fn tile_active_workspaces(&mut self) {
-        let tiler = DwindleTiler::default();
+        let started = Instant::now();
         for monitor in self.monitors.iter_mut() {
             let workspace_idx = (monitor.active_workspace - 1) as usize;
+            let tiler = tiler_for(
+                &self.layout_config,
+                &self.workspace_overrides,
+                monitor.active_workspace,
+                display_bar_reserve(
+                    self.bar_reserve,
+                    &self.bar_displays,
+                    &self.fullscreen_displays,
+                    monitor,
+                ),
+                self.bar_position,
+            );
 
-            if !monitor.workspaces[workspace_idx].windows.is_empty() {
+            if let Some(zen_hwnd) = monitor.workspaces[workspace_idx].zen_window {
+                // Zen mode overrides the layout with one centered window
+                let rect = zen_rect(tiler.work_area(monitor.rect), self.layout_config.zen_width);
+                let workspace = &mut monitor.workspaces[workspace_idx];
+                if let Some(window) = workspace.get_window_mut(hwnd_from_isize(zen_hwnd)) {
+                    window.rect = rect;
+                }
+            } else if !monitor.workspaces[workspace_idx].windows.is_empty() {
                 // Create a copy of the monitor for reading
                 let monitor_copy = monitor.clone();
                 let workspace = &mut monitor.workspaces[workspace_idx];
+                let focused = workspace.focused_window_hwnd;
                 let layout_tree = &mut workspace.layout_tree;
                 let windows = &mut workspace.windows;
-                tiler.tile_windows(&monitor_copy, layout_tree, windows);
+                tiler.tile_windows(&monitor_copy, layout_tree, windows, focused);
+
+                // A maximized tile covers the work area; the rest keep their tiles behind it
+                if let Some(maximized_hwnd) = workspace.maximized_window
+                    && let Some(window) = workspace.get_window_mut(hwnd_from_isize(maximized_hwnd))
+                {
+                    window.rect = tiler.work_area(monitor.rect);
+                }
             }
         }
+        crate::telemetry::record_layout(started.elapsed());
     }
 
     /// Applies calculated positions to all tiled windows.
     pub fn apply_window_positions(&mut self) {
+        let started = Instant::now();
         // Collect windows to position first to avoid borrow checker issues
         let mut windows_to_position: Vec<(isize, RECT)> = Vec::new();
 
@@ -974,7 +2626,11 @@ impl WorkspaceManager {
             let active_workspace = monitor.get_active_workspace();
 
             for window in &active_workspace.windows {
-                if window.is_tiled {
+                // Don't fight the user over a window they are dragging
+                if window.is_tiled
+                    && !window.is_hidden_by_workspace
+                    && self.dragging_window != Some(window.hwnd)
+                {
                     windows_to_position.push((window.hwnd, window.rect));
                 }
             }
@@ -993,34 +2649,163 @@ impl WorkspaceManager {
             }
         }
 
-        // Now position them
-        for (hwnd, rect) in windows_to_position {
-            self.set_window_position(hwnd_from_isize(hwnd), &rect);
+        // Now position them in a single batch so the layout updates in one frame
+        self.set_window_positions(&windows_to_position);
+
+        // Batched moves keep the z-order, so lift maximized tiles over their neighbours
+        for monitor in &self.monitors {
+            if let Some(maximized_hwnd) = monitor.get_active_workspace().maximized_window
+                && let Err(e) = self.system.raise_window(hwnd_from_isize(maximized_hwnd))
+            {
+                warn!("{}", e);
+            }
         }
 
         // Clear positioning set after a brief moment to allow events to settle
         // We do this immediately since we've already updated window.rect to match
         self.positioning_windows.clear();
+        crate::telemetry::record_positioning(started.elapsed());
+    }
+
+    /// Centers a floating window on its monitor, keeping its size. Dialogs
+    /// go over the window that opened them instead when `center_dialogs` is on.
+    pub fn center_window(&mut self, hwnd: HWND) -> Result<(), String> {
+        if self.center_dialog(hwnd) {
+            return Ok(());
+        }
+        let window = self.get_window(hwnd).ok_or("Window not found")?;
+        let monitor = self
+            .monitors
+            .get_mut(window.monitor)
+            .ok_or("Monitor not found")?;
+        let area = crate::tiling::work_area(monitor.rect);
+        let rect = center_rect(window.rect, area, area);
+        if let Some(window) = monitor
+            .get_workspace_mut(window.workspace)
+            .and_then(|w| w.get_window_mut(hwnd))
+        {
+            window.rect = rect;
+            window.original_rect = rect;
+        }
+        self.set_window_position(hwnd, &rect);
+        Ok(())
+    }
+
+    /// Centers a dialog over the managed window that owns it, kept on that
+    /// window's monitor, if `center_dialogs` is on. The dialog itself needn't
+    /// be managed. Returns false if it was left alone.
+    pub fn center_dialog(&mut self, hwnd: HWND) -> bool {
+        if !self.layout_config.center_dialogs {
+            return false;
+        }
+        let Some(owner) = self
+            .system
+            .window_owner(hwnd)
+            .and_then(|owner| self.get_window(owner))
+        else {
+            return false;
+        };
+        let (Ok(rect), Some(monitor)) = (
+            self.system.window_rect(hwnd),
+            self.monitors.get(owner.monitor),
+        ) else {
+            return false;
+        };
+        // The same work area as center_window, so dialogs stay clear of the bar
+        let area = crate::tiling::work_area(monitor.rect);
+        let centered = center_rect(rect, owner.rect, area);
+        debug!(
+            "Centering dialog {:?} over its owner {:?}",
+            hwnd.0, owner.hwnd
+        );
+        if let Some(window) = self
+            .monitors
+            .iter_mut()
+            .flat_map(|m| &mut m.workspaces)
+            .find_map(|w| w.get_window_mut(hwnd))
+        {
+            window.rect = centered;
+            window.original_rect = centered;
+        }
+        self.set_window_position(hwnd, &centered);
+        true
+    }
+
+    /// Returns where `window`'s app last had a floating window, if that is
+    /// still on a connected monitor.
+    fn remembered_float_rect(&self, window: &Window) -> Option<RECT> {
+        let process = window.process_name.as_ref()?;
+        let rect = RECT::from(*self.float_rects.get(&process.to_lowercase())?);
+        self.is_on_a_monitor(rect).then_some(rect)
+    }
+
+    /// Returns true if the center of `rect` is on a connected monitor.
+    fn is_on_a_monitor(&self, rect: RECT) -> bool {
+        let (x, y) = ((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2);
+        self.monitors.iter().any(|m| {
+            (m.display_rect.left..m.display_rect.right).contains(&x)
+                && (m.display_rect.top..m.display_rect.bottom).contains(&y)
+        })
+    }
+
+    /// Moves tiled windows back to where they were before Megatile tiled
+    /// them, at exit. Windows whose monitor is gone stay where they are.
+    pub fn restore_initial_rects(&self) {
+        let moves: Vec<(HWND, RECT)> = self
+            .monitors
+            .iter()
+            .flat_map(|monitor| monitor.workspaces.iter())
+            .flat_map(|workspace| workspace.windows.iter())
+            .filter(|window| window.is_tiled && self.is_on_a_monitor(window.initial_rect))
+            .map(|window| (hwnd_from_isize(window.hwnd), window.initial_rect))
+            .collect();
+        debug!("Restoring {} windows to their initial rects", moves.len());
+        for hwnd in self.system.move_windows(&moves) {
+            warn!("Window {:?} runs elevated; leaving it where it is", hwnd.0);
+        }
     }
 
     /// Toggles a window between tiled and floating state.
     pub fn toggle_window_tiling(&mut self, hwnd: HWND) -> Result<(), String> {
         debug!("Toggling tiling for window {:?}", hwnd.0);
+        // Tiled or floated by hand, it no longer waits to be adopted
+        self.unadopted_windows
+            .retain(|&unadopted| unadopted != hwnd.0 as isize);
+        if let Some((monitor_idx, workspace_num)) = self.find_zen_workspace(hwnd.0 as isize) {
+            self.exit_zen(monitor_idx, workspace_num);
+        }
+        let remembered_rect = self
+            .get_window(hwnd)
+            .and_then(|window| self.remembered_float_rect(&window));
         let mut found = false;
         let mut is_now_tiled = false;
         let mut rect_to_restore = None;
 
         for monitor in self.monitors.iter_mut() {
             for workspace in &mut monitor.workspaces {
+                if workspace.get_window(hwnd).is_some_and(|w| w.is_tiled) {
+                    workspace.leave_tile_group(hwnd.0 as isize);
+                }
                 if let Some(window) = workspace.get_window_mut(hwnd) {
                     window.is_tiled = !window.is_tiled;
                     is_now_tiled = window.is_tiled;
                     found = true;
+                    if window.is_tiled && window.is_topmost {
+                        // Tiles never overlap, so there is nothing to stay on top of
+                        window.is_topmost = false;
+                        let _ = self.system.set_topmost(hwnd, false);
+                    }
 
-                    if !window.is_tiled {
-                        // If it's now floating, restore its original rect
-                        window.rect = window.original_rect;
-                        rect_to_restore = Some(window.original_rect);
+                    if window.is_tiled {
+                        if let Some(process) = &window.process_name {
+                            self.float_rects
+                                .insert(process.to_lowercase(), window.rect.into());
+                        }
+                    } else {
+                        // Floating again: where its app last floated, or where it was before tiling
+                        let rect = remembered_rect.unwrap_or(window.original_rect);
+                        window.rect = rect;
+                        rect_to_restore = Some(rect);
                     }
                     break;
                 }
@@ -1051,62 +2836,283 @@ impl WorkspaceManager {
         Ok(())
     }
 
+    /// Flips every window on the active workspace between tiled and floating:
+    /// all float if any is tiled, otherwise all are tiled. Floated windows go
+    /// back to where they floated before, each to its own spot.
+    pub fn toggle_workspace_tiling(&mut self) -> Result<(), String> {
+        let workspace_num = self.active_workspace_global;
+        let windows: Vec<Window> = self
+            .monitors
+            .iter()
+            .filter_map(|monitor| monitor.get_workspace(workspace_num))
+            .flat_map(|workspace| workspace.windows.iter())
+            .filter(|window| !window.is_fullscreen)
+            .cloned()
+            .collect();
+        if windows.is_empty() {
+            return Err("No windows on the workspace".to_string());
+        }
+        let tile = !windows.iter().any(|window| window.is_tiled);
+        debug!(
+            "{} every window on workspace {}",
+            if tile { "Tiling" } else { "Floating" },
+            workspace_num
+        );
+        for monitor_idx in 0..self.monitors.len() {
+            self.exit_zen(monitor_idx, workspace_num);
+        }
+
+        let mut moves = Vec::new();
+        for window in &windows {
+            let hwnd = hwnd_from_isize(window.hwnd);
+            self.unadopted_windows
+                .retain(|&unadopted| unadopted != window.hwnd);
+            if tile {
+                if window.is_topmost {
+                    // Tiles never overlap, so there is nothing to stay on top of
+                    let _ = self.system.set_topmost(hwnd, false);
+                }
+                if let Some(process) = &window.process_name {
+                    self.float_rects
+                        .insert(process.to_lowercase(), window.rect.into());
+                }
+            } else if window.is_tiled {
+                moves.push((window.hwnd, window.original_rect));
+            }
+        }
+        for workspace in self
+            .monitors
+            .iter_mut()
+            .filter_map(|monitor| monitor.get_workspace_mut(workspace_num))
+        {
+            if !tile {
+                workspace.maximized_window = None;
+            }
+            for hwnd in windows.iter().map(|window| window.hwnd) {
+                if !tile {
+                    workspace.leave_tile_group(hwnd);
+                }
+                if let Some(window) = workspace.get_window_mut(hwnd_from_isize(hwnd)) {
+                    if tile {
+                        // Remembered to float back to, as a whole workspace
+                        window.original_rect = window.rect;
+                        window.is_topmost = false;
+                    } else if window.is_tiled {
+                        window.rect = window.original_rect;
+                    }
+                    window.is_tiled = tile;
+                }
+            }
+        }
+
+        self.set_window_positions(&moves);
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_statusbar();
+        Ok(())
+    }
+
     /// Sets a window's position and size, accounting for DWM invisible borders.
     fn set_window_position(&mut self, hwnd: HWND, rect: &RECT) {
-        let hwnd_val = hwnd.0 as isize;
+        self.set_window_positions(&[(hwnd.0 as isize, *rect)]);
+    }
 
-        // Mark this window as being positioned by us
-        self.positioning_windows.insert(hwnd_val);
+    /// Tells the user (once per window) that a window can't be positioned
+    /// because it runs elevated and Megatile does not.
+    fn report_elevated_window(&mut self, hwnd: HWND) {
+        if !self.elevated_windows.insert(hwnd.0 as isize) {
+            return;
+        }
+        let name = self
+            .get_window(hwnd)
+            .and_then(|w| w.process_name)
+            .unwrap_or_else(|| self.system.window_title(hwnd));
+        warn!(
+            "Access denied positioning {:?} ({}), likely elevated",
+            hwnd.0, name
+        );
+        notify(
+            Severity::Warning,
+            "Can't manage window",
+            format!(
+                "{} is running as administrator. Run Megatile as administrator to tile it.",
+                name
+            ),
+        );
+    }
 
-        unsafe {
-            // Restore the window if it's maximized, as SetWindowPos doesn't work on maximized windows
-            if IsZoomed(hwnd).as_bool() {
-                let _ = ShowWindow(hwnd, SW_RESTORE);
+    /// Adjusts a target rect for the window's invisible DWM borders.
+    ///
+    /// Border deltas are measured once per window and cached until
+    /// [`WorkspaceManager::invalidate_dwm_borders`] is called.
+    fn adjust_rect_for_dwm_borders(&mut self, hwnd: HWND, rect: &RECT) -> RECT {
+        let hwnd_val = hwnd.0 as isize;
+        let borders = match self.dwm_borders.get(&hwnd_val) {
+            Some(borders) => *borders,
+            None => {
+                // Measured while maximized, the borders would be cached wrong
+                // for the tile the window is about to be moved into
+                self.system.restore_maximized(hwnd);
+                match self.system.invisible_borders(hwnd) {
+                    Ok(borders) => {
+                        self.dwm_borders.insert(hwnd_val, borders);
+                        borders
+                    }
+                    Err(e) => {
+                        debug!("Could not measure borders for {:?}: {}", hwnd_val, e);
+                        (0, 0, 0, 0)
+                    }
+                }
             }
+        };
+        let (left, top, right, bottom) = borders;
+        RECT {
+            left: rect.left - left,
+            top: rect.top - top,
+            right: rect.right + right,
+            bottom: rect.bottom + bottom,
+        }
+    }
+
+    /// Drops all cached DWM border measurements.
+    ///
+    /// Call this when DPI, theme or display settings change, since any of them
+    /// can change the size of a window's invisible borders.
+    pub fn invalidate_dwm_borders(&mut self) {
+        debug!(
+            "Invalidating {} cached DWM border measurements",
+            self.dwm_borders.len()
+        );
+        self.dwm_borders.clear();
+    }
 
+    /// Moves several windows at once, in a single batch where the window
+    /// system supports it, accounting for DWM invisible borders.
+    fn set_window_positions(&mut self, positions: &[(isize, RECT)]) {
+        let mut moves = Vec::with_capacity(positions.len());
+        for (hwnd_val, rect) in positions {
+            // Mark this window as being positioned by us
+            self.positioning_windows.insert(*hwnd_val);
             // Adjust for DWM invisible borders so the visible area matches our target
-            let adjusted_rect = crate::windows_lib::adjust_rect_for_dwm_borders(hwnd, rect);
+            let hwnd = hwnd_from_isize(*hwnd_val);
+            let mut target = self.adjust_rect_for_dwm_borders(hwnd, rect);
+            let process = self
+                .monitors
+                .iter()
+                .find_map(|monitor| monitor.get_window(hwnd))
+                .and_then(|window| window.process_name.as_deref());
+            if let Some(quirks) = quirks_for(&self.app_quirks, process) {
+                if quirks.no_resize
+                    && let Ok(current) = self.system.window_rect(hwnd)
+                {
+                    target = keep_size_in(current, target);
+                }
+                if quirks.resize_twice {
+                    // Some apps snap back to their old size after the first resize
+                    self.pending_resizes
+                        .insert(*hwnd_val, (target, Instant::now() + RESIZE_AGAIN_DELAY));
+                }
+            }
+            moves.push((hwnd, target));
+        }
 
-            SetWindowPos(
-                hwnd,
-                None,
-                adjusted_rect.left,
-                adjusted_rect.top,
-                adjusted_rect.right - adjusted_rect.left,
-                adjusted_rect.bottom - adjusted_rect.top,
-                SWP_NOZORDER | SWP_NOACTIVATE,
-            )
-            .ok();
+        crate::telemetry::record_window_moves(moves.len());
+        for hwnd in self.system.move_windows(&moves) {
+            self.report_elevated_window(hwnd);
         }
+    }
 
-        // Remove from positioning set after a brief delay to catch follow-up events
-        // We'll clean this up in the next update cycle
+    /// Sets the size of `resize_twice` apps' windows again once they've had
+    /// time to undo the first resize. Call this every maintenance tick.
+    pub fn apply_pending_resizes(&mut self) {
+        if self.pending_resizes.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let due: Vec<(isize, RECT)> = self
+            .pending_resizes
+            .iter()
+            .filter(|(_, (_, at))| *at <= now)
+            .map(|(&hwnd, &(rect, _))| (hwnd, rect))
+            .collect();
+        self.pending_resizes.retain(|_, (_, at)| *at > now);
+        let moves: Vec<(HWND, RECT)> = due
+            .into_iter()
+            .map(|(hwnd, rect)| (hwnd_from_isize(hwnd), rect))
+            .filter(|(hwnd, _)| self.get_window(*hwnd).is_some())
+            .collect();
+        if !moves.is_empty() {
+            debug!("Resizing {} window(s) again", moves.len());
+            self.system.move_windows(&moves);
+        }
     }
 
     /// Returns the currently focused window if it's managed by Megatile.
     pub fn get_focused_window(&self) -> Option<Window> {
-        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+        self.get_window(self.system.foreground_window())
+    }
 
-        unsafe {
-            let hwnd = GetForegroundWindow();
-            self.get_window(hwnd)
-        }
+    /// Focuses the window focused before (`back`) or after the current one on
+    /// the active workspace, regardless of where it is on screen.
+    pub fn step_focus_history(&mut self, back: bool) -> Result<(), String> {
+        let workspace = self.active_workspace_global;
+        let mut history = self.focus_history.remove(&workspace).unwrap_or_default();
+        // Drop windows that were closed or moved away since they were focused
+        history.retain(|hwnd| {
+            self.get_window(hwnd_from_isize(hwnd))
+                .is_some_and(|window| {
+                    window.workspace == workspace && !window.is_hidden_by_workspace
+                })
+        });
+        let target = if back {
+            history.back()
+        } else {
+            history.forward()
+        };
+        self.focus_history.insert(workspace, history);
+
+        let Some(hwnd) = target else {
+            debug!(
+                "No {} window in the focus history",
+                if back { "earlier" } else { "later" }
+            );
+            return Ok(());
+        };
+        self.exit_maximize_for_focused();
+        self.set_window_focus(hwnd_from_isize(hwnd));
+        self.update_decorations();
+        Ok(())
     }
 
     /// Moves focus to the nearest window in the specified direction.
     pub fn move_focus(&mut self, direction: FocusDirection) -> Result<(), String> {
         debug!("Moving focus in direction {:?}", direction);
+        self.exit_maximize_for_focused();
 
         let focused = self.get_focused_window();
         debug!(
             "Currently focused window: {:?}",
             focused.as_ref().map(|w| w.hwnd)
         );
+        // Without cross-monitor focus, only the focused window's monitor is searched
+        let focused_monitor = focused.as_ref().and_then(|focused| {
+            self.monitors.iter().position(|monitor| {
+                monitor
+                    .get_active_workspace()
+                    .get_window(hwnd_from_isize(focused.hwnd))
+                    .is_some()
+            })
+        });
 
         // Find all windows in active workspace on all monitors first
         let mut active_windows: Vec<(Window, RECT)> = Vec::new();
         debug!("Gathering active windows from all monitors");
         for (monitor_idx, monitor) in self.monitors.iter().enumerate() {
+            if !self.focus_config.cross_monitor
+                && focused_monitor.is_some_and(|focused_monitor| focused_monitor != monitor_idx)
+            {
+                continue;
+            }
             let active_workspace = monitor.get_active_workspace();
             debug!(
                 "Monitor {} active workspace has {} windows",
@@ -1114,8 +3120,8 @@ impl WorkspaceManager {
                 active_workspace.windows.len()
             );
             for window in &active_workspace.windows {
-                // allow focusing on tiled or fullscreen windows
-                if window.is_tiled || (window.is_fullscreen && !window.is_tiled) {
+                // allow focusing on tiled or fullscreen windows, but not ones zen mode hid
+                if (window.is_tiled || window.is_fullscreen) && !window.is_hidden_by_workspace {
                     active_windows.push((window.clone(), window.rect));
                     debug!(
                         "Active window: hwnd={:?}, rect={:?}",
@@ -1136,6 +3142,22 @@ impl WorkspaceManager {
             // Find window to move focus to based on direction
             debug!("Finding next focus from current focused window");
             self.find_next_focus(&focused, direction, &active_windows)
+                .or_else(|| {
+                    if !self.focus_config.wrap {
+                        return None;
+                    }
+                    debug!(
+                        "No window {:?} of the focused one, wrapping around",
+                        direction
+                    );
+                    let candidates: Vec<&(Window, RECT)> = active_windows
+                        .iter()
+                        .filter(|(w, _)| w.hwnd != focused.hwnd)
+                        .collect();
+                    let rects: Vec<RECT> = candidates.iter().map(|(_, rect)| *rect).collect();
+                    find_wrap_target(&focused.rect, &rects, direction)
+                        .map(|idx| candidates[idx].0.clone())
+                })
         } else {
             // No window focused, focus the first window
             debug!("No window currently focused, focusing first window");
@@ -1160,76 +3182,86 @@ impl WorkspaceManager {
         direction: FocusDirection,
         windows: &[(Window, RECT)],
     ) -> Option<Window> {
-        let focused_rect = focused.rect;
-        let focused_center_x = (focused_rect.left + focused_rect.right) / 2;
-        let focused_center_y = (focused_rect.top + focused_rect.bottom) / 2;
-
         debug!(
             "Finding next focus from window {:?} with rect {:?}",
-            focused.hwnd, focused_rect
+            focused.hwnd, focused.rect
         );
 
         let candidates: Vec<&(Window, RECT)> = windows
             .iter()
             .filter(|(w, _)| w.hwnd != focused.hwnd)
             .collect();
+        let candidate_rects: Vec<RECT> = candidates.iter().map(|(_, rect)| *rect).collect();
 
-        if candidates.is_empty() {
-            return None;
-        }
-
-        let filtered_candidates: Vec<_> = candidates
-            .iter()
-            .filter(|(_, rect)| match direction {
-                FocusDirection::Left => rect.right <= focused_rect.left,
-                FocusDirection::Right => rect.left >= focused_rect.right,
-                FocusDirection::Up => rect.bottom <= focused_rect.top,
-                FocusDirection::Down => rect.top >= focused_rect.bottom,
-            })
-            .collect();
+        find_nearest_in_direction(&focused.rect, &candidate_rects, direction)
+            .map(|idx| candidates[idx].0.clone())
+    }
 
-        debug!(
-            "{} windows found in direction {:?}",
-            filtered_candidates.len(),
-            direction
-        );
+    /// Remembers that a window flashed its taskbar button for attention,
+    /// unless it has focus already.
+    pub fn mark_urgent(&mut self, hwnd: HWND) {
+        if self.get_window(hwnd).is_none() || self.system.foreground_window() == hwnd {
+            return;
+        }
+        debug!("Window {:?} wants attention", hwnd.0);
+        self.urgent_windows
+            .retain(|&urgent| urgent != hwnd.0 as isize);
+        self.urgent_windows.push(hwnd.0 as isize);
+    }
 
-        filtered_candidates
-            .iter()
-            .min_by_key(|(_, rect)| {
-                let rect_center_x = (rect.left + rect.right) / 2;
-                let rect_center_y = (rect.top + rect.bottom) / 2;
-
-                let (dist_primary, dist_secondary) = match direction {
-                    FocusDirection::Left => (
-                        focused_rect.left - rect.right,
-                        (focused_center_y - rect_center_y).abs(),
-                    ),
-                    FocusDirection::Right => (
-                        rect.left - focused_rect.right,
-                        (focused_center_y - rect_center_y).abs(),
-                    ),
-                    FocusDirection::Up => (
-                        focused_rect.top - rect.bottom,
-                        (focused_center_x - rect_center_x).abs(),
-                    ),
-                    FocusDirection::Down => (
-                        rect.top - focused_rect.bottom,
-                        (focused_center_x - rect_center_x).abs(),
-                    ),
-                };
+    /// Focuses the window that most recently wanted attention, switching to
+    /// its workspace first if needed.
+    pub fn focus_urgent_window(&mut self) -> Result<(), String> {
+        while let Some(hwnd_val) = self.urgent_windows.pop() {
+            let hwnd = hwnd_from_isize(hwnd_val);
+            let Some(window) = self.get_window(hwnd) else {
+                continue;
+            };
+            if window.workspace != self.active_workspace_global {
+                self.switch_workspace_with_windows(window.workspace)?;
+            }
+            self.set_window_focus(hwnd);
+            return Ok(());
+        }
+        Err("No window wants attention".to_string())
+    }
 
-                // Prioritize primary distance, then secondary
-                // Use a large multiplier for primary distance to ensure it's the main factor
-                dist_primary * 1000 + dist_secondary
-            })
-            .map(|(w, _)| w.clone())
+    /// Leaves a window that was open at startup floating where it is until
+    /// [`Self::adopt_windows`] tiles it.
+    pub fn leave_unadopted(&mut self, hwnd: HWND) {
+        self.unadopted_windows.push(hwnd.0 as isize);
+    }
+
+    /// Tiles the windows left floating at startup. Returns how many there were.
+    pub fn adopt_windows(&mut self) -> Result<usize, String> {
+        let unadopted = std::mem::take(&mut self.unadopted_windows);
+        let mut adopted = 0;
+        for hwnd_val in unadopted {
+            let window = self
+                .monitors
+                .iter_mut()
+                .flat_map(|monitor| monitor.workspaces.iter_mut())
+                .find_map(|workspace| workspace.get_window_mut(hwnd_from_isize(hwnd_val)));
+            if let Some(window) = window
+                && !window.is_tiled
+                && !window.is_fullscreen
+            {
+                window.is_tiled = true;
+                adopted += 1;
+            }
+        }
+        if adopted == 0 {
+            return Err("No windows are waiting to be adopted".to_string());
+        }
+        info!("Adopted {} windows", adopted);
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_statusbar();
+        Ok(adopted)
     }
 
     /// Sets focus to a specific window.
     pub fn set_window_focus(&mut self, hwnd: HWND) {
-        use windows::Win32::UI::WindowsAndMessaging::*;
-
         debug!("Setting focus to window {:?}", hwnd.0);
 
         // Update focus memory in the workspace
@@ -1247,19 +3279,18 @@ impl WorkspaceManager {
             }
         }
 
-        unsafe {
-            let result = SetForegroundWindow(hwnd);
-            if result.as_bool() {
-                debug!("Successfully set focus to window {:?}", hwnd.0);
-            } else {
-                warn!("Failed to set focus to window {:?}", hwnd.0);
-            }
+        if self.system.focus_window(hwnd) {
+            debug!("Successfully set focus to window {:?}", hwnd.0);
+        } else {
+            warn!("Failed to set focus to window {:?}", hwnd.0);
         }
     }
 
-    /// Swaps the focused window with the window in the specified direction.
+    /// Swaps the focused window with the window in the specified direction,
+    /// or moves it to the adjacent monitor if there is no window that way.
     pub fn move_window(&mut self, direction: FocusDirection) -> Result<(), String> {
         debug!("Moving window in direction {:?}", direction);
+        self.exit_maximize_for_focused();
 
         // Find all windows in active workspace on all monitors first
         let mut active_windows: Vec<(Window, RECT)> = Vec::new();
@@ -1272,7 +3303,7 @@ impl WorkspaceManager {
                 active_workspace.windows.len()
             );
             for window in &active_workspace.windows {
-                if window.is_tiled {
+                if window.is_tiled && !window.is_hidden_by_workspace {
                     active_windows.push((window.clone(), window.rect));
                     debug!(
                         "Window for moving: hwnd={:?}, rect={:?}",
@@ -1293,10 +3324,7 @@ impl WorkspaceManager {
         );
 
         // Find the focused window in our active windows list
-        let focused_hwnd = unsafe {
-            use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
-            GetForegroundWindow()
-        };
+        let focused_hwnd = self.system.foreground_window();
 
         debug!("Current foreground window: {:?}", focused_hwnd.0);
 
@@ -1338,10 +3366,18 @@ impl WorkspaceManager {
                 "Swapping positions of windows {:?} and {:?}",
                 focused.hwnd, target_window.hwnd
             );
-            let swap_result =
-                self.swap_window_positions(HWND(focused.hwnd as _), HWND(target_window.hwnd as _));
+            // Grouped windows move with their whole group
+            let focused_group = self.tile_group_of(focused.hwnd);
+            let target_group = self.tile_group_of(target_window.hwnd);
+            let swap_result = self.swap_window_positions(
+                hwnd_from_isize(focused_group[0]),
+                hwnd_from_isize(target_group[0]),
+            );
             match swap_result {
                 Ok(()) => {
+                    if focused_group.len() > 1 || target_group.len() > 1 {
+                        self.tile_active_workspaces();
+                    }
                     // Re-apply window positions after swap
                     debug!("Re-applying window positions after successful swap");
                     self.apply_window_positions();
@@ -1356,7 +3392,9 @@ impl WorkspaceManager {
                 }
             }
         } else {
-            debug!("No suitable target window found to swap with");
+            // At the edge of the layout the same keys carry on to the next monitor
+            debug!("No suitable target window found to swap with, trying the adjacent monitor");
+            return self.move_window_to_monitor(direction, self.focus_config.follow_to_monitor);
         }
 
         Ok(())
@@ -1478,7 +3516,7 @@ impl WorkspaceManager {
         debug!("Handling restored window {:?}", hwnd.0);
 
         // Check if it's a normal window
-        if !crate::windows_lib::is_normal_window_hwnd(hwnd) {
+        if !self.system.is_normal_window(hwnd) {
             debug!("Window {:?} is not a normal window, ignoring", hwnd.0);
             return;
         }
@@ -1490,7 +3528,7 @@ impl WorkspaceManager {
         }
 
         // Check if window is still minimized (shouldn't be, but verify)
-        if crate::windows_lib::is_window_minimized(hwnd) {
+        if self.system.is_minimized(hwnd) {
             debug!("Window {:?} is still minimized, ignoring", hwnd.0);
             return;
         }
@@ -1498,14 +3536,16 @@ impl WorkspaceManager {
         debug!("Re-registering restored window {:?}", hwnd.0);
 
         // Get current window rect
-        let rect = crate::windows_lib::get_window_rect(hwnd).unwrap_or_default();
+        let rect = self.system.window_rect(hwnd).unwrap_or_default();
 
         // Get active workspace and monitor
         let active_workspace = self.active_workspace_global;
-        let monitor_index = self.get_monitor_for_window(hwnd).unwrap_or(0);
+        let monitor_index = self
+            .get_monitor_for_window(hwnd)
+            .unwrap_or_else(|| self.primary_monitor_index());
 
         // Get process name for app-specific filtering
-        let process_name = crate::windows_lib::get_process_name_for_window(hwnd);
+        let process_name = self.system.process_name(hwnd);
 
         // Create new window object
         let window = super::workspace::Window::new(
@@ -1517,7 +3557,9 @@ impl WorkspaceManager {
         );
 
         // Show in taskbar
-        let _ = show_window_in_taskbar(hwnd);
+        let _ = self
+            .system
+            .show_window(hwnd, hide_method(&self.hide_methods, &window));
 
         // Add window and re-tile
         self.add_window(window);
@@ -1551,10 +3593,10 @@ impl WorkspaceManager {
 
                     // Check if window is still valid using comprehensive validation
                     // Pass is_hidden_by_workspace to skip visibility check for intentionally hidden windows
-                    if !crate::windows_lib::is_window_still_valid(
-                        hwnd,
-                        window.is_hidden_by_workspace,
-                    ) {
+                    if !self
+                        .system
+                        .is_window_valid(hwnd, window.is_hidden_by_workspace)
+                    {
                         debug!(
                             "Cleanup: found invalid window {:?} (process: {:?}, hidden_by_ws: {})",
                             hwnd.0, window.process_name, window.is_hidden_by_workspace
@@ -1565,6 +3607,14 @@ impl WorkspaceManager {
             }
         }
 
+        // Parked windows are hidden on purpose, like inactive workspaces
+        for hwnd_val in self.stash.parked_hwnds() {
+            let hwnd = hwnd_from_isize(hwnd_val);
+            if !self.system.is_window_valid(hwnd, true) {
+                invalid_windows.push(hwnd);
+            }
+        }
+
         // Remove all invalid windows and re-tile affected workspaces
         for hwnd in invalid_windows {
             debug!("Cleaning up zombie/invalid window {:?}", hwnd.0);
@@ -1574,20 +3624,51 @@ impl WorkspaceManager {
 
     /// Updates internal tracking when windows are moved externally.
     pub fn update_window_positions(&mut self) {
-        // Debounce: Don't update more frequently than every 50ms
-        if self.last_update_positions.elapsed() < Duration::from_millis(50) {
+        // Wait for the drop while the user is dragging
+        if self.dragging_window.is_some() {
+            return;
+        }
+        // Debounce: Don't update more often than configured
+        if self.last_update_positions.elapsed()
+            < Duration::from_millis(self.layout_config.move_debounce_ms)
+        {
             return;
         }
+        self.sync_window_positions();
+    }
+
+    /// Handles the user starting to drag or resize a window. Retiling is
+    /// suspended until it is dropped.
+    pub fn handle_window_drag_started(&mut self, hwnd: HWND) {
+        debug!("Window {:?} drag started", hwnd.0);
+        self.dragging_window = Some(hwnd.0 as isize);
+    }
+
+    /// Handles the user dropping a window they dragged or resized, applying
+    /// a single layout update for the whole drag.
+    pub fn handle_window_dropped(&mut self, hwnd: HWND) {
+        debug!("Window {:?} dropped", hwnd.0);
+        self.dragging_window = None;
+        if !self.is_positioning_window(hwnd) {
+            self.sync_window_positions();
+        }
+    }
+
+    /// Picks up windows moved by the user: floating windows keep their new
+    /// place, tiled ones are swapped or snapped back, and windows dragged
+    /// onto another monitor move there.
+    fn sync_window_positions(&mut self) {
         self.last_update_positions = Instant::now();
 
         // Get monitor rects first
         let monitor_rects: Vec<RECT> = self.monitors.iter().map(|m| m.rect).collect();
         let mut moves: Vec<(isize, usize, usize)> = Vec::new(); // (hwnd, old_monitor_idx, new_monitor_idx)
-        let mut any_tiled_moved = false;
+        // Tiled windows moved by the user, and the monitor they're on
+        let mut dragged: Vec<(isize, usize)> = Vec::new();
 
         // Movement threshold: only consider it moved if changed by more than this
-        // Set higher to account for DWM border adjustments
-        const MOVE_THRESHOLD: i32 = 50;
+        // The default is high enough to absorb DWM border adjustments
+        let move_threshold = self.layout_config.move_threshold;
 
         for monitor_idx in 0..self.monitors.len() {
             // To avoid borrowing issues, we'll iterate through indices
@@ -1604,7 +3685,14 @@ impl WorkspaceManager {
                         continue;
                     }
 
-                    if let Ok(current_rect) = crate::windows_lib::get_window_rect(hwnd) {
+                    // Windows moved off-screen for another workspace haven't really moved
+                    if self.monitors[monitor_idx].workspaces[ws_idx].windows[win_idx]
+                        .is_hidden_by_workspace
+                    {
+                        continue;
+                    }
+
+                    if let Ok(current_rect) = self.system.window_rect(hwnd) {
                         let window =
                             &mut self.monitors[monitor_idx].workspaces[ws_idx].windows[win_idx];
 
@@ -1614,10 +3702,10 @@ impl WorkspaceManager {
                         let right_diff = (window.rect.right - current_rect.right).abs();
                         let bottom_diff = (window.rect.bottom - current_rect.bottom).abs();
 
-                        let moved_significantly = left_diff > MOVE_THRESHOLD
-                            || top_diff > MOVE_THRESHOLD
-                            || right_diff > MOVE_THRESHOLD
-                            || bottom_diff > MOVE_THRESHOLD;
+                        let moved_significantly = left_diff > move_threshold
+                            || top_diff > move_threshold
+                            || right_diff > move_threshold
+                            || bottom_diff > move_threshold;
 
                         if moved_significantly {
                             debug!(
@@ -1634,9 +3722,13 @@ impl WorkspaceManager {
                             if !window.is_tiled {
                                 // If it's floating, also update its current tracking rect
                                 window.rect = current_rect;
+                                if let Some(process) = &window.process_name {
+                                    self.float_rects
+                                        .insert(process.to_lowercase(), current_rect.into());
+                                }
                             } else {
                                 // Tiled window moved, will need to re-tile
-                                any_tiled_moved = true;
+                                dragged.push((hwnd_val, monitor_idx));
                                 debug!("Tiled window {:?} moved by user, will re-tile", hwnd_val);
                             }
                         }
@@ -1667,8 +3759,12 @@ impl WorkspaceManager {
             }
         }
 
-        // Apply moves
+        // Apply moves; windows dragged onto an unmanaged monitor are let go
         for (hwnd, _old_monitor_idx, new_monitor_idx) in moves {
+            if !self.is_monitor_managed(new_monitor_idx) {
+                self.release_window(hwnd_from_isize(hwnd));
+                continue;
+            }
             if let Some(window) = self.remove_window(hwnd_from_isize(hwnd))
                 && let Some(new_monitor) = self.monitors.get_mut(new_monitor_idx)
             {
@@ -1677,14 +3773,33 @@ impl WorkspaceManager {
             }
         }
 
-        // If any tiled window moved, sort windows by position and re-tile
-        if any_tiled_moved {
+        // A tiled window dropped on another tile of its monitor swaps places with
+        // it; everything else stays put, and the dragged window snaps back otherwise
+        if !dragged.is_empty() {
             debug!("Re-tiling due to user-moved tiled windows");
-            for monitor in self.monitors.iter_mut() {
-                let ws_idx = (monitor.active_workspace - 1) as usize;
-                monitor.workspaces[ws_idx]
-                    .windows
-                    .sort_by_key(|w| (w.rect.left, w.rect.top));
+            if let Ok((x, y)) = self.system.cursor_position() {
+                for (hwnd, monitor_idx) in dragged {
+                    // Windows that changed monitors were already moved above
+                    let Some(monitor) = self.monitors.get(monitor_idx) else {
+                        continue;
+                    };
+                    let tiles: Vec<(isize, RECT)> = monitor
+                        .get_active_workspace()
+                        .windows
+                        .iter()
+                        .filter(|w| w.is_tiled && !w.is_hidden_by_workspace)
+                        .map(|w| (w.hwnd, w.rect))
+                        .collect();
+                    if !tiles.iter().any(|(tile, _)| *tile == hwnd) {
+                        continue;
+                    }
+                    if let Some(target) = tile_under_point(&tiles, x, y, hwnd)
+                        && let Err(e) = self
+                            .swap_window_positions(hwnd_from_isize(hwnd), hwnd_from_isize(target))
+                    {
+                        warn!("Failed to swap dragged window {:?}: {}", hwnd, e);
+                    }
+                }
             }
             self.tile_active_workspaces();
             self.apply_window_positions();
@@ -1695,7 +3810,7 @@ impl WorkspaceManager {
     pub fn print_workspace_status(&self) {
         for (m_idx, monitor) in self.monitors.iter().enumerate() {
             debug!("Monitor {}:", m_idx);
-            for ws in 1..=9 {
+            for ws in 1..=monitor.workspace_count() {
                 if let Some(workspace) = monitor.get_workspace(ws) {
                     let count = workspace.windows.len();
                     let active = if monitor.active_workspace == ws {
@@ -1711,16 +3826,14 @@ impl WorkspaceManager {
 
     /// Closes the currently focused window.
     pub fn close_focused_window(&mut self) -> Result<(), String> {
-        // Get currently focused window
-        let focused = self.get_focused_window();
-
-        if focused.is_none() {
-            return Err("No focused window".to_string());
-        }
-
-        let focused_window = focused.unwrap();
-        let hwnd = HWND(focused_window.hwnd as *mut std::ffi::c_void);
+        let focused_window = self
+            .get_focused_window()
+            .ok_or_else(|| "No focused window".to_string())?;
+        self.close_window(hwnd_from_isize(focused_window.hwnd))
+    }
 
+    /// Closes a managed window and re-tiles what's left.
+    pub fn close_window(&mut self, hwnd: HWND) -> Result<(), String> {
         info!("Closing window {:?}", hwnd.0);
 
         // Remove window from workspace tracking
@@ -1731,7 +3844,7 @@ impl WorkspaceManager {
         }
 
         // Close the actual window
-        crate::windows_lib::close_window(hwnd)?;
+        self.system.close_window(hwnd)?;
 
         // Re-tile active workspace
         self.tile_active_workspaces();
@@ -1761,6 +3874,464 @@ impl WorkspaceManager {
         Ok(())
     }
 
+    /// Returns the opacity chosen for windows of `process`, opaque if none was.
+    fn process_opacity(&self, process: Option<&str>) -> u8 {
+        process
+            .and_then(|process| self.opacity_levels.get(&process.to_lowercase()))
+            .copied()
+            .unwrap_or(255)
+    }
+
+    /// Returns the opacity of a focused window of `process`. Layered windows
+    /// cost power, so saving keeps them all opaque.
+    fn focused_alpha(&self, process: Option<&str>) -> u8 {
+        if self.power_saving {
+            255
+        } else {
+            self.process_opacity(process)
+        }
+    }
+
+    /// Makes the focused window's process one step more or less opaque.
+    /// Returns the new opacity.
+    pub fn adjust_opacity(&mut self, increase: bool) -> Result<u8, String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        let process = focused
+            .process_name
+            .ok_or("The focused window's process is unknown")?;
+        let alpha = crate::opacity::step(self.process_opacity(Some(&process)), increase);
+        if alpha == 255 {
+            self.opacity_levels.remove(&process.to_lowercase());
+        } else {
+            self.opacity_levels.insert(process.to_lowercase(), alpha);
+        }
+        info!("Opacity of {} windows is now {}", process, alpha);
+        self.update_decorations();
+        Ok(alpha)
+    }
+
+    /// Keeps the focused floating window above all others, e.g. a
+    /// picture-in-picture video, or lets it go back behind them. Returns
+    /// whether it is now on top.
+    pub fn toggle_topmost(&mut self) -> Result<bool, String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        if focused.is_tiled {
+            return Err("Only floating windows can stay on top".to_string());
+        }
+        let hwnd = hwnd_from_isize(focused.hwnd);
+        let topmost = !focused.is_topmost;
+        self.system.set_topmost(hwnd, topmost)?;
+        if let Some(window) = self
+            .monitors
+            .get_mut(focused.monitor)
+            .and_then(|m| m.get_workspace_mut(focused.workspace))
+            .and_then(|w| w.get_window_mut(hwnd))
+        {
+            window.is_topmost = topmost;
+        }
+        info!(
+            "Window {:?} {} on top",
+            hwnd.0,
+            if topmost { "stays" } else { "no longer stays" }
+        );
+        Ok(topmost)
+    }
+
+    /// Minimizes the focused window. Like a minimize from its title bar, it
+    /// leaves the layout until it is restored.
+    pub fn minimize_focused_window(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        let hwnd = hwnd_from_isize(focused.hwnd);
+
+        info!("Minimizing window {:?}", hwnd.0);
+        self.system.minimize_window(hwnd)?;
+        // Don't wait for the minimize event, so focus lands inside the workspace
+        self.handle_window_minimized(hwnd);
+
+        if let Some(hwnd) = self.workspace_focus_target(self.active_workspace_global) {
+            debug!("Auto-focusing window {:?} after minimize", hwnd.0);
+            self.set_window_focus(hwnd);
+        }
+        Ok(())
+    }
+
+    /// Toggles zen mode on the focused window's workspace: the window is
+    /// centered at `zen_width` of the work area and the other tiles are hidden.
+    pub fn toggle_zen(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        let workspace_num = focused.workspace;
+        let monitor_idx = self
+            .monitors
+            .iter()
+            .position(|m| m.get_window(hwnd_from_isize(focused.hwnd)).is_some())
+            .ok_or("Window not found")?;
+
+        let zen_active = self.monitors[monitor_idx]
+            .get_workspace(workspace_num)
+            .is_some_and(|ws| ws.zen_window.is_some());
+        if zen_active {
+            self.exit_zen(monitor_idx, workspace_num);
+        } else {
+            if !focused.is_tiled {
+                return Err("Zen mode needs a tiled window".to_string());
+            }
+            info!("Entering zen mode with window {:?}", focused.hwnd);
+            if let Some(workspace) = self.monitors[monitor_idx].get_workspace_mut(workspace_num) {
+                workspace.zen_window = Some(focused.hwnd);
+                workspace.maximized_window = None;
+                for window in &mut workspace.windows {
+                    if window.is_tiled && window.hwnd != focused.hwnd {
+                        window.is_hidden_by_workspace = self
+                            .system
+                            .hide_window(
+                                hwnd_from_isize(window.hwnd),
+                                hide_method(&self.hide_methods, window),
+                            )
+                            .is_ok();
+                    }
+                }
+            }
+        }
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_decorations();
+        Ok(())
+    }
+
+    /// Ends zen mode on a workspace, showing the windows it hid.
+    fn exit_zen(&mut self, monitor_idx: usize, workspace_num: u8) {
+        let visible = self.active_workspace_global == workspace_num;
+        let Some(workspace) = self
+            .monitors
+            .get_mut(monitor_idx)
+            .and_then(|m| m.get_workspace_mut(workspace_num))
+        else {
+            return;
+        };
+        let Some(zen_hwnd) = workspace.zen_window.take() else {
+            return;
+        };
+
+        info!("Leaving zen mode on workspace {}", workspace_num);
+        // Windows of an inactive workspace are shown by the next switch to it
+        if visible {
+            for window in &mut workspace.windows {
+                if window.is_tiled && window.hwnd != zen_hwnd && window.is_hidden_by_workspace {
+                    let _ = self.system.show_window(
+                        hwnd_from_isize(window.hwnd),
+                        hide_method(&self.hide_methods, window),
+                    );
+                    window.is_hidden_by_workspace = false;
+                }
+            }
+        }
+    }
+
+    /// Returns the monitor and workspace where `hwnd` is the zen window.
+    fn find_zen_workspace(&self, hwnd: isize) -> Option<(usize, u8)> {
+        self.monitors
+            .iter()
+            .enumerate()
+            .find_map(|(monitor_idx, monitor)| {
+                monitor
+                    .workspaces
+                    .iter()
+                    .position(|ws| ws.zen_window == Some(hwnd))
+                    .map(|idx| (monitor_idx, idx as u8 + 1))
+            })
+    }
+
+    /// Toggles maximize-within-tile for the focused window: it covers the
+    /// monitor's work area, below the status bar and without being topmost,
+    /// while the other tiles keep their positions behind it.
+    pub fn toggle_maximize(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        if !focused.is_tiled {
+            return Err("Only tiled windows can be maximized".to_string());
+        }
+        let monitor_idx = self
+            .monitors
+            .iter()
+            .position(|m| m.get_window(hwnd_from_isize(focused.hwnd)).is_some())
+            .ok_or("Window not found")?;
+
+        if let Some(workspace) = self.monitors[monitor_idx].get_workspace_mut(focused.workspace) {
+            if workspace.maximized_window == Some(focused.hwnd) {
+                info!("Restoring maximized window {:?} to its tile", focused.hwnd);
+                workspace.maximized_window = None;
+            } else {
+                info!("Maximizing window {:?} within its workspace", focused.hwnd);
+                workspace.maximized_window = Some(focused.hwnd);
+            }
+        }
+        // Zen mode hides the other tiles, which maximizing is meant to keep
+        self.exit_zen(monitor_idx, focused.workspace);
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_decorations();
+        Ok(())
+    }
+
+    /// Restores a maximized tile on the active workspace of `monitor_idx`, if any.
+    fn exit_maximize(&mut self, monitor_idx: usize) {
+        let Some(monitor) = self.monitors.get_mut(monitor_idx) else {
+            return;
+        };
+        let workspace_idx = (monitor.active_workspace - 1) as usize;
+        if monitor.workspaces[workspace_idx]
+            .maximized_window
+            .take()
+            .is_some()
+        {
+            debug!("Restoring maximized window on monitor {}", monitor_idx);
+            self.tile_active_workspaces();
+            self.apply_window_positions();
+        }
+    }
+
+    /// Restores the focused window to its tile if it is maximized, so
+    /// directional focus and moves work from its place in the layout.
+    fn exit_maximize_for_focused(&mut self) {
+        let Some(focused) = self.get_focused_window() else {
+            return;
+        };
+        if let Some(monitor_idx) = self
+            .monitors
+            .iter()
+            .position(|m| m.get_active_workspace().maximized_window == Some(focused.hwnd))
+        {
+            self.exit_maximize(monitor_idx);
+        }
+    }
+
+    /// Returns the windows sharing `hwnd`'s tile, the one holding the tile first.
+    fn tile_group_of(&self, hwnd: isize) -> Vec<isize> {
+        self.monitors
+            .iter()
+            .flat_map(|m| &m.workspaces)
+            .find(|ws| ws.get_window(hwnd_from_isize(hwnd)).is_some())
+            .map_or_else(|| vec![hwnd], |ws| ws.tile_group(hwnd))
+    }
+
+    /// Groups the focused window into the tile of the window focused before
+    /// it, or takes it out of its tile group if it is in one. Grouped windows
+    /// are stacked in one tile; [`WorkspaceManager::cycle_tile_group`] raises
+    /// the next one.
+    pub fn toggle_tile_group(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        if !focused.is_tiled {
+            return Err("Only tiled windows can be grouped".to_string());
+        }
+        if self.tile_group_of(focused.hwnd).len() > 1 {
+            return self.regroup(&focused, None);
+        }
+        let previous = self
+            .focus_history
+            .get(&focused.workspace)
+            .and_then(|history| history.previous())
+            .filter(|&hwnd| hwnd != focused.hwnd)
+            .ok_or("No previously focused window to group with")?;
+        self.regroup(&focused, Some(previous))
+    }
+
+    /// Moves the focused window into the tile group of the nearest tile in
+    /// `direction` on its monitor.
+    pub fn merge_into_neighbor(&mut self, direction: FocusDirection) -> Result<(), String> {
+        self.exit_maximize_for_focused();
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        if !focused.is_tiled {
+            return Err("Only tiled windows can be grouped".to_string());
+        }
+        let group = self.tile_group_of(focused.hwnd);
+        let candidates: Vec<(Window, RECT)> = self
+            .monitors
+            .iter()
+            .filter_map(|m| m.get_workspace(focused.workspace))
+            .find(|ws| ws.get_window(hwnd_from_isize(focused.hwnd)).is_some())
+            .map(|ws| {
+                ws.windows
+                    .iter()
+                    .filter(|w| w.is_tiled && !w.is_hidden_by_workspace)
+                    .filter(|w| !group.contains(&w.hwnd))
+                    .map(|w| (w.clone(), w.rect))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let target = self
+            .find_next_focus(&focused, direction, &candidates)
+            .ok_or("No tile in that direction")?;
+        self.regroup(&focused, Some(target.hwnd))
+    }
+
+    /// Takes `window` out of its tile group and, with a `target`, puts it in
+    /// the target's group. Re-tiles and keeps `window` focused on top.
+    fn regroup(&mut self, window: &Window, target: Option<isize>) -> Result<(), String> {
+        let workspace = self
+            .monitors
+            .iter_mut()
+            .filter_map(|m| m.get_workspace_mut(window.workspace))
+            .find(|ws| ws.get_window(hwnd_from_isize(window.hwnd)).is_some())
+            .ok_or("Window not found")?;
+        if let Some(target) = target
+            && !workspace
+                .get_window(hwnd_from_isize(target))
+                .is_some_and(|w| w.is_tiled)
+        {
+            return Err("Windows can only be grouped with a tile on their workspace".to_string());
+        }
+
+        workspace.leave_tile_group(window.hwnd);
+        match target {
+            Some(target) => {
+                // Read after leaving, in case the window held the target's tile
+                let host = workspace
+                    .get_window(hwnd_from_isize(target))
+                    .and_then(|w| w.tile_group)
+                    .unwrap_or(target);
+                info!(
+                    "Grouping window {:?} into the tile of {:?}",
+                    window.hwnd, host
+                );
+                if let Some(window) = workspace.get_window_mut(hwnd_from_isize(window.hwnd)) {
+                    window.tile_group = Some(host);
+                }
+            }
+            None => info!("Took window {:?} out of its tile group", window.hwnd),
+        }
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        // Batched moves keep the z-order, so bring the grouped window back on top
+        self.set_window_focus(hwnd_from_isize(window.hwnd));
+        self.update_decorations();
+        Ok(())
+    }
+
+    /// Raises and focuses the next window in the focused window's tile group.
+    pub fn cycle_tile_group(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        let group = self.tile_group_of(focused.hwnd);
+        if group.len() < 2 {
+            return Ok(());
+        }
+        let position = group.iter().position(|&hwnd| hwnd == focused.hwnd);
+        let next = group[position.map_or(0, |i| (i + 1) % group.len())];
+        debug!("Raising window {:?} in its tile group", next);
+        self.set_window_focus(hwnd_from_isize(next));
+        self.update_decorations();
+        Ok(())
+    }
+
+    /// Returns true if `hwnd` is parked in a stash slot.
+    pub fn is_stashed(&self, hwnd: HWND) -> bool {
+        self.stash.is_parked(hwnd.0 as isize)
+    }
+
+    /// Parks the focused window in its stash slot, or the first free one.
+    pub fn stash_focused_window(&mut self) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        let slot = self
+            .stash
+            .slot_of(focused.hwnd)
+            .or_else(|| self.stash.free_slot())
+            .ok_or(if self.stash.is_empty() {
+                "No stash slots configured"
+            } else {
+                "All stash slots are in use"
+            })?;
+        self.park_window(hwnd_from_isize(focused.hwnd), slot);
+        Ok(())
+    }
+
+    /// Summons the window of a stash slot (1-based) onto the current
+    /// workspace, or parks it again if it is already here.
+    pub fn toggle_stash_slot(&mut self, slot_num: u8) -> Result<(), String> {
+        let slot = (slot_num as usize)
+            .checked_sub(1)
+            .ok_or("Stash slots start at 1")?;
+        let hwnd_val = self
+            .stash
+            .bound(slot)
+            .ok_or_else(|| format!("Stash slot {} is empty", slot_num))?;
+        let hwnd = hwnd_from_isize(hwnd_val);
+
+        if let Some(window) = self.stash.take_parked(slot) {
+            self.summon_window(window);
+        } else if self.is_window_in_active_workspace(hwnd) {
+            self.park_window(hwnd, slot);
+        } else if let Some(window) = self.remove_window(hwnd) {
+            // Summoned earlier and left on another workspace: bring it here.
+            // Unlike `remove_window_with_tiling`, this keeps the slot bound
+            self.summon_window(window);
+            self.collapse_empty_workspaces();
+            self.update_statusbar();
+        } else {
+            self.stash.forget(hwnd_val);
+            self.update_statusbar();
+            return Err(format!(
+                "Window in stash slot {} no longer exists",
+                slot_num
+            ));
+        }
+        Ok(())
+    }
+
+    /// Hides a managed window and moves it from its workspace into `slot`.
+    fn park_window(&mut self, hwnd: HWND, slot: usize) {
+        let Some(mut window) = self.remove_window(hwnd) else {
+            return;
+        };
+        info!("Parking window {:?} in stash slot {}", hwnd.0, slot + 1);
+        window.is_hidden_by_workspace = self
+            .system
+            .hide_window(hwnd, hide_method(&self.hide_methods, &window))
+            .is_ok();
+        let monitor_idx = window.monitor;
+        self.stash.park(slot, window);
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.update_statusbar();
+        self.update_decorations();
+
+        if let Some(next) = self
+            .monitors
+            .get(monitor_idx)
+            .and_then(|m| m.get_active_workspace().focused_window_hwnd)
+        {
+            self.set_window_focus(hwnd_from_isize(next));
+        }
+    }
+
+    /// Shows a stashed window on the active workspace of the focused monitor.
+    fn summon_window(&mut self, mut window: Window) {
+        let monitor_idx = self
+            .get_focused_window()
+            .and_then(|focused| {
+                self.monitors
+                    .iter()
+                    .position(|m| m.get_window(hwnd_from_isize(focused.hwnd)).is_some())
+            })
+            .unwrap_or(0);
+        let hwnd = hwnd_from_isize(window.hwnd);
+        info!("Summoning window {:?} onto monitor {}", hwnd.0, monitor_idx);
+
+        window.workspace = self.active_workspace_global;
+        window.monitor = monitor_idx;
+        window.is_hidden_by_workspace = false;
+        let _ = self
+            .system
+            .show_window(hwnd, hide_method(&self.hide_methods, &window));
+        self.add_window(window);
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        self.set_window_focus(hwnd);
+        self.update_decorations();
+    }
+
     /// Toggles fullscreen mode for the focused window.
     pub fn toggle_fullscreen(&mut self) -> Result<(), String> {
         // Get currently focused window
@@ -1771,20 +4342,22 @@ impl WorkspaceManager {
         }
 
         let focused_hwnd = hwnd_from_isize(focused.unwrap().hwnd);
+        let fullscreen_topmost = self.layout_config.fullscreen_topmost;
         let mut handled = false;
 
         // Find and update the window in workspace
         for monitor in self.monitors.iter_mut() {
-            let monitor_rect = monitor.rect;
+            let monitor_rect = monitor.display_rect;
             if let Some(workspace) = monitor.get_workspace_mut(monitor.active_workspace)
                 && let Some(window) = workspace.get_window_mut(focused_hwnd)
             {
                 if window.is_fullscreen {
                     // Restore from fullscreen
                     info!("Restoring window {:?} from fullscreen", focused_hwnd);
-                    crate::windows_lib::restore_window_from_fullscreen(
+                    self.system.exit_fullscreen(
                         focused_hwnd,
                         window.original_rect,
+                        window.fullscreen_topmost,
                     )?;
                     window.is_fullscreen = false;
                     window.is_tiled = true;
@@ -1792,8 +4365,10 @@ impl WorkspaceManager {
                     // Set to fullscreen
                     info!("Setting window {:?} to fullscreen", focused_hwnd);
                     window.original_rect = window.rect; // Store current position
-                    crate::windows_lib::set_window_fullscreen(focused_hwnd, monitor_rect)?;
+                    self.system
+                        .set_fullscreen(focused_hwnd, monitor_rect, fullscreen_topmost)?;
                     window.is_fullscreen = true;
+                    window.fullscreen_topmost = fullscreen_topmost;
                     window.is_tiled = false;
                 }
                 handled = true;
@@ -1824,9 +4399,10 @@ impl WorkspaceManager {
                             "Exiting fullscreen for window {:?} in workspace {} (preserving flag)",
                             window.hwnd, workspace_num
                         );
-                        if let Err(e) = crate::windows_lib::restore_window_from_fullscreen(
+                        if let Err(e) = self.system.exit_fullscreen(
                             hwnd_from_isize(window.hwnd),
                             window.original_rect,
+                            window.fullscreen_topmost,
                         ) {
                             error!("Failed to restore window from fullscreen: {}", e);
                         }
@@ -1841,7 +4417,7 @@ impl WorkspaceManager {
     /// Called when switching TO a workspace to restore windows marked as fullscreen.
     fn restore_fullscreen_workspace(&mut self, workspace_num: u8) {
         for monitor in self.monitors.iter_mut() {
-            let monitor_rect = monitor.rect;
+            let monitor_rect = monitor.display_rect;
             if let Some(workspace) = monitor.get_workspace_mut(workspace_num) {
                 for window in &mut workspace.windows {
                     if window.is_fullscreen {
@@ -1849,9 +4425,10 @@ impl WorkspaceManager {
                             "Restoring fullscreen for window {:?} in workspace {}",
                             window.hwnd, workspace_num
                         );
-                        if let Err(e) = crate::windows_lib::set_window_fullscreen(
+                        if let Err(e) = self.system.set_fullscreen(
                             hwnd_from_isize(window.hwnd),
                             monitor_rect,
+                            window.fullscreen_topmost,
                         ) {
                             error!("Failed to set window fullscreen: {}", e);
                         }
@@ -1885,23 +4462,192 @@ impl WorkspaceManager {
                     ResizeDirection::Vertical => crate::tiling::SplitDirection::Horizontal,
                 };
 
-                if let Some(target_tile) = Self::find_ancestor_with_direction(
-                    layout_tree,
-                    focused_window.hwnd,
-                    target_direction,
-                ) {
-                    // Adjust the split ratio
-                    target_tile.split_ratio = (target_tile.split_ratio + amount).clamp(0.1, 0.9);
+                if let Some(target_tile) = Self::find_ancestor_with_direction(
+                    layout_tree,
+                    focused_window.hwnd,
+                    target_direction,
+                ) {
+                    // Adjust the split ratio
+                    target_tile.split_ratio = (target_tile.split_ratio + amount).clamp(
+                        crate::tiling::MIN_SPLIT_RATIO,
+                        crate::tiling::MAX_SPLIT_RATIO,
+                    );
+
+                    // Re-apply tiling with updated ratios
+                    self.tile_active_workspaces();
+                    self.apply_window_positions();
+                    return Ok(());
+                }
+            }
+        }
+
+        Err("No suitable ancestor found for resizing in this direction".to_string())
+    }
+
+    /// Returns the monitor of the focused window, or the first monitor.
+    fn focused_monitor_index(&self) -> usize {
+        self.get_focused_window()
+            .and_then(|focused| {
+                self.monitors
+                    .iter()
+                    .position(|m| m.get_window(hwnd_from_isize(focused.hwnd)).is_some())
+            })
+            .unwrap_or(0)
+    }
+
+    /// Describes the layout of the active workspace on the focused monitor,
+    /// for saving under a name.
+    pub fn current_layout(&self) -> Result<LayoutNode, String> {
+        let workspace = self
+            .monitors
+            .get(self.focused_monitor_index())
+            .map(|m| m.get_active_workspace())
+            .ok_or("No monitors")?;
+        let tree = workspace
+            .layout_tree
+            .as_ref()
+            .ok_or("The workspace has no tiled windows")?;
+        Ok(LayoutNode::from_tile(tree, &workspace.windows))
+    }
+
+    /// Rearranges the active workspace on the focused monitor into `layout`,
+    /// matching its tiles to the windows there by process and title.
+    pub fn apply_layout(&mut self, layout: &LayoutNode) -> Result<(), String> {
+        let monitor_idx = self.focused_monitor_index();
+        let workspace_num = self
+            .monitors
+            .get(monitor_idx)
+            .map(|m| m.active_workspace)
+            .ok_or("No monitors")?;
+        self.exit_zen(monitor_idx, workspace_num);
+        if !self.arrange_workspace(monitor_idx, workspace_num, layout) {
+            return Err("Workspace not found".to_string());
+        }
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        Ok(())
+    }
+
+    /// Gives a workspace the splits of `layout`, matching its tiles to the
+    /// windows there. Returns false if there's no such workspace.
+    fn arrange_workspace(
+        &mut self,
+        monitor_idx: usize,
+        workspace_num: u8,
+        layout: &LayoutNode,
+    ) -> bool {
+        let Some(workspace) = self
+            .monitors
+            .get_mut(monitor_idx)
+            .and_then(|m| m.get_workspace_mut(workspace_num))
+        else {
+            return false;
+        };
+        // Grouped windows follow the window holding their tile
+        let candidates: Vec<Window> = workspace
+            .windows
+            .iter()
+            .filter(|w| w.is_tiled && w.tile_group.is_none())
+            .cloned()
+            .collect();
+        let (tree, order) = layout.to_tile(&candidates);
+        info!(
+            "Applying layout to workspace {}: {} of {} windows matched",
+            workspace_num,
+            order.len(),
+            candidates.len()
+        );
+
+        // Matched windows go first, in tile order, so unmatched ones are split in after them
+        workspace.windows.sort_by_key(|w| {
+            order
+                .iter()
+                .position(|hwnd| *hwnd == w.hwnd)
+                .unwrap_or(order.len())
+        });
+        workspace.layout_tree = Some(tree);
+        workspace.maximized_window = None;
+        true
+    }
 
-                    // Re-apply tiling with updated ratios
-                    self.tile_active_workspaces();
-                    self.apply_window_positions();
-                    return Ok(());
-                }
-            }
+    /// Rearranges a workspace into its `[[templates]]` layout while it fills
+    /// up from empty, as long as every tiled window there still fits a slot.
+    /// After that its tree is left alone.
+    fn apply_layout_template(&mut self, monitor_idx: usize, workspace_num: u8) {
+        let Some(template) = self
+            .layout_templates
+            .iter()
+            .find(|t| t.workspace == workspace_num)
+        else {
+            return;
+        };
+        let Some(workspace) = self
+            .monitors
+            .get_mut(monitor_idx)
+            .and_then(|m| m.get_workspace_mut(workspace_num))
+        else {
+            return;
+        };
+        // Grouped windows follow the window holding their tile
+        let candidates: Vec<Window> = workspace
+            .windows
+            .iter()
+            .filter(|w| w.is_tiled && w.tile_group.is_none())
+            .cloned()
+            .collect();
+        if candidates.len() == 1 {
+            workspace.filling_template = true;
         }
+        if !workspace.filling_template {
+            return;
+        }
+        let Some((tree, order)) = template.fill(&candidates) else {
+            debug!(
+                "Workspace {} outgrew its template, tiling as usual",
+                workspace_num
+            );
+            workspace.filling_template = false;
+            return;
+        };
+        workspace.filling_template = order.len() < template.slots.len();
+        workspace.windows.sort_by_key(|w| {
+            order
+                .iter()
+                .position(|hwnd| *hwnd == w.hwnd)
+                .unwrap_or(order.len())
+        });
+        workspace.layout_tree = Some(tree);
+        workspace.maximized_window = None;
+    }
 
-        Err("No suitable ancestor found for resizing in this direction".to_string())
+    /// Sets the split next to the focused window so the window gets `share`
+    /// (0.0-1.0) of it, e.g. 0.5 to even it out.
+    pub fn set_split_ratio(&mut self, share: f32) -> Result<(), String> {
+        let focused = self.get_focused_window().ok_or("No focused window")?;
+        // Grouped windows are in the tree under the window holding their tile
+        let hwnd = self.tile_group_of(focused.hwnd)[0];
+        let split = self
+            .monitors
+            .iter_mut()
+            .filter_map(|m| m.get_workspace_mut(focused.workspace))
+            .filter_map(|ws| ws.layout_tree.as_mut())
+            .find_map(|tree| tree.parent_split_mut(hwnd))
+            .ok_or("The focused window has no split to adjust")?;
+        let (tile, is_first) = split;
+        let ratio = if is_first { share } else { 1.0 - share };
+        tile.split_ratio = ratio.clamp(
+            crate::tiling::MIN_SPLIT_RATIO,
+            crate::tiling::MAX_SPLIT_RATIO,
+        );
+        debug!(
+            "Set split ratio to {} for window {:?}",
+            tile.split_ratio, hwnd
+        );
+
+        self.tile_active_workspaces();
+        self.apply_window_positions();
+        Ok(())
     }
 
     fn find_ancestor_with_direction(
@@ -2077,8 +4823,304 @@ impl WorkspaceManager {
     }
 }
 
+/// Steps from `current` to the next of `count` workspaces (wrapping) accepted
+/// by `eligible`. Returns `None` if no other workspace is eligible.
+pub fn cycle_workspace(
+    current: u8,
+    count: u8,
+    forward: bool,
+    eligible: impl Fn(u8) -> bool,
+) -> Option<u8> {
+    (1..count)
+        .map(|step| {
+            let offset = if forward { step } else { count - step };
+            (current - 1 + offset) % count + 1
+        })
+        .find(|&ws| eligible(ws))
+}
+
+/// Returns `current` moved to the middle of `target` without changing its
+/// size. Windows bigger than `target` start at its top-left corner.
+fn keep_size_in(current: RECT, target: RECT) -> RECT {
+    let width = current.right - current.left;
+    let height = current.bottom - current.top;
+    let left = target.left + ((target.right - target.left - width) / 2).max(0);
+    let top = target.top + ((target.bottom - target.top - height) / 2).max(0);
+    RECT {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// Returns how `window` is hidden on inactive workspaces.
+fn hide_method(methods: &HashMap<String, HideMethod>, window: &Window) -> HideMethod {
+    window
+        .process_name
+        .as_ref()
+        .and_then(|process| methods.get(&process.to_lowercase()))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Returns the tiler for `workspace`, with its gap and padding overrides applied.
+fn tiler_for(
+    layout: &LayoutConfig,
+    overrides: &[WorkspaceOverride],
+    workspace: u8,
+    bar_reserve: i32,
+    bar_position: BarPosition,
+) -> DwindleTiler {
+    let entry = overrides.iter().find(|entry| entry.workspace == workspace);
+    let gap = entry.and_then(|entry| entry.gap).unwrap_or(layout.gap);
+    let padding = entry
+        .and_then(|entry| entry.padding)
+        .unwrap_or(layout.padding);
+    DwindleTiler::new(gap)
+        .with_padding(padding)
+        .with_bar_reserve(bar_reserve)
+        .with_bar_position(bar_position)
+}
+
+/// Returns the bar reserve for `monitor`: none if its display has no bar
+/// (it isn't in `bar_displays`), or while its bar is hidden for a fullscreen
+/// window there.
+fn display_bar_reserve(
+    bar_reserve: i32,
+    bar_displays: &HashSet<String>,
+    fullscreen_displays: &[RECT],
+    monitor: &Monitor,
+) -> i32 {
+    if !bar_displays.contains(&monitor.device_name)
+        || fullscreen_displays.contains(&monitor.display_rect)
+    {
+        0
+    } else {
+        bar_reserve
+    }
+}
+
+/// Returns the tile containing the point `(x, y)`, other than `exclude`.
+pub fn tile_under_point(tiles: &[(isize, RECT)], x: i32, y: i32, exclude: isize) -> Option<isize> {
+    tiles
+        .iter()
+        .find(|(hwnd, rect)| {
+            *hwnd != exclude && x >= rect.left && x < rect.right && y >= rect.top && y < rect.bottom
+        })
+        .map(|(hwnd, _)| *hwnd)
+}
+
+/// Returns whether the pointer at `(x, y)` calls for the auto-hiding bar:
+/// it's over the `bar`, or touching the bar's `position` edge of the `display`
+/// under it.
+fn bar_wanted(bar: RECT, display: Option<RECT>, position: BarPosition, x: i32, y: i32) -> bool {
+    let over_bar = (bar.left..bar.right).contains(&x) && (bar.top..bar.bottom).contains(&y);
+    let at_edge = display.is_some_and(|display| {
+        (display.left..display.right).contains(&x)
+            && match position {
+                BarPosition::Top => y <= display.top,
+                BarPosition::Bottom => y >= display.bottom - 1,
+            }
+    });
+    over_bar || at_edge
+}
+
+/// Returns whether a focus flash `elapsed` into it is in the lit half of a
+/// pulse, or `None` once it's over.
+fn flash_highlighted(elapsed: Duration) -> Option<bool> {
+    let half = elapsed.as_millis() / FOCUS_FLASH_HALF_PULSE.as_millis();
+    (half < 2 * FOCUS_FLASH_PULSES as u128).then_some(half.is_multiple_of(2))
+}
+
+/// Returns the first empty workspace (other than 1 and `active`) that has a
+/// workspace in use or the active one after it, for dynamic workspaces.
+pub fn workspace_to_collapse(count: u8, active: u8, is_empty: impl Fn(u8) -> bool) -> Option<u8> {
+    let last_used = (1..=count)
+        .rev()
+        .find(|&ws| !is_empty(ws))
+        .unwrap_or(1)
+        .max(active);
+    (2..last_used).find(|&ws| ws != active && is_empty(ws))
+}
+
+/// Returns `workspace` if it isn't locked, else the next unlocked one of
+/// `count` workspaces after it (wrapping). If all are locked, `workspace` is kept.
+pub fn next_unlocked_workspace(workspace: u8, count: u8, locked: &HashSet<u8>) -> u8 {
+    (0..count)
+        .map(|offset| (workspace - 1 + offset) % count + 1)
+        .find(|candidate| !locked.contains(candidate))
+        .unwrap_or(workspace)
+}
+
+/// Splits a display into `zones` equal side-by-side columns.
+pub fn split_into_zones(rect: RECT, zones: u8) -> Vec<RECT> {
+    let zones = zones.max(1) as i32;
+    let width = rect.right - rect.left;
+    (0..zones)
+        .map(|i| RECT {
+            left: rect.left + width * i / zones,
+            right: rect.left + width * (i + 1) / zones,
+            ..rect
+        })
+        .collect()
+}
+
+/// Returns `rect` without the space `reserve` keeps clear at its edges. The
+/// result never inverts, however much is reserved.
+pub fn reserve_edges(rect: RECT, reserve: ReservedEdges) -> RECT {
+    let left = (rect.left + reserve.left).min(rect.right);
+    let top = (rect.top + reserve.top).min(rect.bottom);
+    RECT {
+        left,
+        top,
+        right: (rect.right - reserve.right).max(left),
+        bottom: (rect.bottom - reserve.bottom).max(top),
+    }
+}
+
+/// Finds the nearest rect in `direction` from `rects[from]`, comparing rect centers.
+///
+/// Used to locate adjacent monitors. Returns `None` if `from` is out of range or
+/// no rect lies in that direction.
+pub fn find_rect_in_direction(
+    rects: &[RECT],
+    from: usize,
+    direction: FocusDirection,
+) -> Option<usize> {
+    let current_rect = rects.get(from)?;
+
+    // Calculate center point of current rect
+    let current_center_x = (current_rect.left + current_rect.right) / 2;
+    let current_center_y = (current_rect.top + current_rect.bottom) / 2;
+
+    let mut candidates: Vec<(usize, i32)> = Vec::new();
+
+    for (i, rect) in rects.iter().enumerate() {
+        if i == from {
+            continue; // Skip the current rect
+        }
+
+        let center_x = (rect.left + rect.right) / 2;
+        let center_y = (rect.top + rect.bottom) / 2;
+
+        let matches_direction = match direction {
+            FocusDirection::Left => center_x < current_center_x,
+            FocusDirection::Right => center_x > current_center_x,
+            FocusDirection::Up => center_y < current_center_y,
+            FocusDirection::Down => center_y > current_center_y,
+        };
+
+        if matches_direction {
+            // Calculate distance (Manhattan distance for simplicity)
+            let dx = (center_x - current_center_x).abs();
+            let dy = (center_y - current_center_y).abs();
+            candidates.push((i, dx + dy));
+        }
+    }
+
+    // Return the closest rect in the specified direction
+    candidates
+        .iter()
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(idx, _)| *idx)
+}
+
+/// Finds the candidate rect closest to `focused` in `direction`.
+///
+/// Only candidates lying entirely beyond the focused rect's edge are considered.
+/// Distance along the direction dominates; the perpendicular center offset breaks ties.
+/// Returns the index into `candidates`.
+pub fn find_nearest_in_direction(
+    focused: &RECT,
+    candidates: &[RECT],
+    direction: FocusDirection,
+) -> Option<usize> {
+    let focused_center_x = (focused.left + focused.right) / 2;
+    let focused_center_y = (focused.top + focused.bottom) / 2;
+
+    let filtered_candidates: Vec<(usize, &RECT)> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, rect)| match direction {
+            FocusDirection::Left => rect.right <= focused.left,
+            FocusDirection::Right => rect.left >= focused.right,
+            FocusDirection::Up => rect.bottom <= focused.top,
+            FocusDirection::Down => rect.top >= focused.bottom,
+        })
+        .collect();
+
+    debug!(
+        "{} windows found in direction {:?}",
+        filtered_candidates.len(),
+        direction
+    );
+
+    filtered_candidates
+        .iter()
+        .min_by_key(|(_, rect)| {
+            let rect_center_x = (rect.left + rect.right) / 2;
+            let rect_center_y = (rect.top + rect.bottom) / 2;
+
+            let (dist_primary, dist_secondary) = match direction {
+                FocusDirection::Left => (
+                    focused.left - rect.right,
+                    (focused_center_y - rect_center_y).abs(),
+                ),
+                FocusDirection::Right => (
+                    rect.left - focused.right,
+                    (focused_center_y - rect_center_y).abs(),
+                ),
+                FocusDirection::Up => (
+                    focused.top - rect.bottom,
+                    (focused_center_x - rect_center_x).abs(),
+                ),
+                FocusDirection::Down => (
+                    rect.top - focused.bottom,
+                    (focused_center_x - rect_center_x).abs(),
+                ),
+            };
+
+            // Prioritize primary distance, then secondary
+            // Use a large multiplier for primary distance to ensure it's the main factor
+            dist_primary * 1000 + dist_secondary
+        })
+        .map(|(idx, _)| *idx)
+}
+
+/// Finds the window to wrap around to when nothing lies in `direction` of
+/// `focused`: the one furthest the other way, nearest in line with `focused`.
+///
+/// Returns the index of the selected candidate, or `None` if there are none.
+pub fn find_wrap_target(
+    focused: &RECT,
+    candidates: &[RECT],
+    direction: FocusDirection,
+) -> Option<usize> {
+    let focused_center_x = (focused.left + focused.right) / 2;
+    let focused_center_y = (focused.top + focused.bottom) / 2;
+
+    candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, rect)| {
+            let rect_center_x = (rect.left + rect.right) / 2;
+            let rect_center_y = (rect.top + rect.bottom) / 2;
+
+            // Moving right wraps to the leftmost window, and so on
+            let (edge, offset) = match direction {
+                FocusDirection::Left => (-rect.right, (focused_center_y - rect_center_y).abs()),
+                FocusDirection::Right => (rect.left, (focused_center_y - rect_center_y).abs()),
+                FocusDirection::Up => (-rect.bottom, (focused_center_x - rect_center_x).abs()),
+                FocusDirection::Down => (rect.top, (focused_center_x - rect_center_x).abs()),
+            };
+            (edge, offset)
+        })
+        .map(|(idx, _)| idx)
+}
+
 /// Direction for focus and window movement operations.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FocusDirection {
     Left,
     Right,
@@ -2100,3 +5142,1188 @@ impl Default for WorkspaceManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_system::MockWindowSystem;
+
+    /// A manager driving a mock desktop with one 1920x1080 monitor.
+    fn mock_manager() -> (WorkspaceManager, MockWindowSystem) {
+        mock_manager_with(&[rect(0, 0, 1920, 1080)])
+    }
+
+    /// A manager on a mock desktop with a monitor per rect.
+    fn mock_manager_with(monitors: &[RECT]) -> (WorkspaceManager, MockWindowSystem) {
+        let system = MockWindowSystem::new(monitors);
+        let mut wm = WorkspaceManager::with_window_system(Box::new(system.clone()));
+        let monitors = wm.monitors_from_infos(&system.monitors());
+        wm.set_monitors(monitors);
+        (wm, system)
+    }
+
+    /// Opens a window on the mock desktop and manages it on `workspace`.
+    fn open_window(
+        wm: &mut WorkspaceManager,
+        system: &MockWindowSystem,
+        hwnd: isize,
+        workspace: u8,
+    ) {
+        system.open_window(hwnd, rect(100, 100, 500, 500));
+        wm.add_window(Window::new(
+            hwnd,
+            workspace,
+            0,
+            rect(100, 100, 500, 500),
+            None,
+        ));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+    }
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> RECT {
+        RECT {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn switching_workspaces_hides_windows_and_restores_focus() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        let (left, right) = (system.rect(1).unwrap(), system.rect(2).unwrap());
+        assert!(
+            left.right <= right.left,
+            "tiles overlap: {:?} {:?}",
+            left,
+            right
+        );
+        wm.set_window_focus(hwnd_from_isize(2));
+
+        wm.switch_workspace_with_windows(2).unwrap();
+        assert!(!system.is_visible(1) && !system.is_visible(2));
+        assert_eq!(wm.get_active_workspace(), 2);
+
+        wm.switch_workspace_with_windows(1).unwrap();
+        assert!(system.is_visible(1) && system.is_visible(2));
+        assert_eq!(system.foreground(), 2);
+        assert_eq!(system.rect(1), Some(left));
+    }
+
+    #[test]
+    fn closed_windows_are_dropped_and_the_rest_retiled() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        let half = system.rect(1).unwrap();
+
+        system.close_window(2);
+        wm.cleanup_invalid_windows();
+        assert!(wm.get_window(hwnd_from_isize(2)).is_none());
+        let full = system.rect(1).unwrap();
+        assert!(full.right > half.right, "{:?} did not grow", full);
+    }
+
+    #[test]
+    fn fullscreen_covers_the_display_until_toggled_back() {
+        let (mut wm, system) = mock_manager();
+        wm.layout_config.fullscreen_topmost = true;
+        open_window(&mut wm, &system, 1, 1);
+        let tile = system.rect(1).unwrap();
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        wm.toggle_fullscreen().unwrap();
+        assert_eq!(system.rect(1), Some(rect(0, 0, 1920, 1080)));
+        assert!(system.is_topmost(1));
+
+        // Restored the way it went fullscreen, whatever the config says now
+        wm.layout_config.fullscreen_topmost = false;
+        wm.switch_workspace_with_windows(2).unwrap();
+        assert!(!system.is_topmost(1));
+        wm.switch_workspace_with_windows(1).unwrap();
+        assert!(system.is_topmost(1));
+
+        wm.toggle_fullscreen().unwrap();
+        assert_eq!(system.rect(1), Some(tile));
+        assert!(!system.is_topmost(1));
+    }
+
+    #[test]
+    fn closing_and_minimizing_go_through_the_window_system() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        open_window(&mut wm, &system, 3, 1);
+
+        wm.close_window(hwnd_from_isize(3)).unwrap();
+        assert!(system.rect(3).is_none());
+        let half = system.rect(1).unwrap();
+
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.minimize_focused_window().unwrap();
+        assert!(system.is_minimized(2));
+        assert!(wm.get_window(hwnd_from_isize(2)).is_none());
+        assert!(system.rect(1).unwrap().right > half.right);
+    }
+
+    #[test]
+    fn stash_slots_follow_their_window_to_other_workspaces() {
+        let (mut wm, system) = mock_manager();
+        wm.set_stash_slots(1);
+        open_window(&mut wm, &system, 1, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.stash_focused_window().unwrap();
+        assert!(wm.is_stashed(hwnd_from_isize(1)));
+        assert!(!system.is_visible(1));
+
+        // Summoned, then left behind on workspace 1
+        wm.toggle_stash_slot(1).unwrap();
+        assert!(system.is_visible(1));
+        wm.switch_workspace_with_windows(2).unwrap();
+        assert!(!system.is_visible(1));
+
+        wm.toggle_stash_slot(1).unwrap();
+        assert!(system.is_visible(1));
+        assert_eq!(wm.get_window(hwnd_from_isize(1)).unwrap().workspace, 2);
+
+        wm.toggle_stash_slot(1).unwrap();
+        assert!(wm.is_stashed(hwnd_from_isize(1)));
+    }
+
+    #[test]
+    fn merged_windows_share_a_tile_until_toggled_out() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        let own_tile = system.rect(1).unwrap();
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        wm.merge_into_neighbor(FocusDirection::Right).unwrap();
+        assert_eq!(system.rect(1), system.rect(2));
+        assert_eq!(system.foreground(), 1);
+        wm.cycle_tile_group().unwrap();
+        assert_eq!(system.foreground(), 2);
+
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.toggle_tile_group().unwrap();
+        assert_ne!(system.rect(1), system.rect(2));
+        assert_eq!(system.rect(1), Some(own_tile));
+    }
+
+    #[test]
+    fn show_desktop_hides_the_workspace_until_pressed_again() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        wm.toggle_show_desktop().unwrap();
+        assert!(!system.is_visible(1));
+        assert!(!system.is_visible(2));
+
+        wm.toggle_show_desktop().unwrap();
+        assert!(system.is_visible(1));
+        assert!(system.is_visible(2));
+        assert_eq!(system.foreground(), 1);
+    }
+
+    #[test]
+    fn no_resize_quirk_moves_windows_without_resizing() {
+        let (mut wm, system) = mock_manager();
+        wm.set_app_quirks(vec![AppQuirks {
+            process: "launcher.exe".to_string(),
+            no_resize: true,
+            ..Default::default()
+        }]);
+        open_window(&mut wm, &system, 1, 1);
+        system.open_window(2, rect(0, 0, 300, 200));
+        wm.add_window(Window::new(
+            2,
+            1,
+            0,
+            rect(0, 0, 300, 200),
+            Some("Launcher.exe".to_string()),
+        ));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+
+        let tile = wm.get_window(hwnd_from_isize(2)).unwrap().rect;
+        let placed = system.rect(2).unwrap();
+        assert_eq!(
+            (placed.right - placed.left, placed.bottom - placed.top),
+            (300, 200)
+        );
+        assert!(placed.left >= tile.left && placed.right <= tile.right);
+        assert_ne!(system.rect(1), Some(rect(100, 100, 500, 500)));
+    }
+
+    #[test]
+    fn resize_twice_quirk_sets_the_size_again_later() {
+        let (mut wm, system) = mock_manager();
+        wm.set_app_quirks(vec![AppQuirks {
+            process: "stubborn.exe".to_string(),
+            resize_twice: true,
+            ..Default::default()
+        }]);
+        system.open_window(1, rect(0, 0, 300, 200));
+        wm.add_window(Window::new(
+            1,
+            1,
+            0,
+            rect(0, 0, 300, 200),
+            Some("Stubborn.exe".to_string()),
+        ));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        let tiled = system.rect(1).unwrap();
+
+        // The app undoes the resize; nothing is due yet
+        system.move_windows(&[(hwnd_from_isize(1), rect(0, 0, 300, 200))]);
+        wm.apply_pending_resizes();
+        assert_eq!(system.rect(1), Some(rect(0, 0, 300, 200)));
+
+        for (_, at) in wm.pending_resizes.values_mut() {
+            *at = Instant::now();
+        }
+        wm.apply_pending_resizes();
+        assert_eq!(system.rect(1), Some(tiled));
+        assert!(wm.pending_resizes.is_empty());
+    }
+
+    #[test]
+    fn tiled_windows_go_back_where_they_were_at_exit() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.toggle_window_tiling(hwnd_from_isize(2)).unwrap();
+        system.open_window(3, rect(5000, 100, 5400, 500));
+        wm.add_window(Window::new(3, 1, 0, rect(5000, 100, 5400, 500), None));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        let floating = system.rect(2);
+        let tiled = system.rect(3);
+        assert_ne!(system.rect(1), Some(rect(100, 100, 500, 500)));
+
+        wm.restore_initial_rects();
+        assert_eq!(system.rect(1), Some(rect(100, 100, 500, 500)));
+        assert_eq!(system.rect(2), floating);
+        assert_eq!(system.rect(3), tiled);
+    }
+
+    #[test]
+    fn apps_can_be_hidden_without_hiding_their_windows() {
+        let (mut wm, system) = mock_manager();
+        wm.set_hide_methods(HashMap::from([(
+            "spotify.exe".to_string(),
+            HideMethod::Offscreen,
+        )]));
+        open_window(&mut wm, &system, 1, 1);
+        system.open_window(2, rect(100, 100, 500, 500));
+        wm.add_window(Window::new(
+            2,
+            1,
+            0,
+            rect(100, 100, 500, 500),
+            Some("Spotify.exe".to_string()),
+        ));
+
+        wm.switch_workspace_with_windows(2).unwrap();
+        assert_eq!(system.hidden_by(1), Some(HideMethod::Hide));
+        assert_eq!(system.hidden_by(2), Some(HideMethod::Offscreen));
+
+        wm.switch_workspace_with_windows(1).unwrap();
+        assert!(system.is_visible(2));
+        assert_eq!(system.hidden_by(2), None);
+    }
+
+    #[test]
+    fn only_floating_windows_stay_on_top() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+        assert!(wm.toggle_topmost().is_err());
+
+        wm.toggle_window_tiling(hwnd_from_isize(1)).unwrap();
+        assert_eq!(wm.toggle_topmost(), Ok(true));
+        assert!(system.is_topmost(1));
+
+        // Tiling it again lets it go
+        wm.toggle_window_tiling(hwnd_from_isize(1)).unwrap();
+        assert!(!system.is_topmost(1));
+    }
+
+    #[test]
+    fn floated_windows_open_where_their_app_last_floated() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        system.open_window(2, rect(100, 100, 500, 500));
+        let mut window = Window::new(2, 1, 0, rect(100, 100, 500, 500), None);
+        window.process_name = Some("Calc.exe".to_string());
+        wm.add_window(window);
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+
+        wm.set_float_rects(FloatRects::from([(
+            "calc.exe".to_string(),
+            rect(1500, 700, 1800, 1000).into(),
+        )]));
+        wm.toggle_window_tiling(hwnd_from_isize(2)).unwrap();
+        assert_eq!(system.rect(2), Some(rect(1500, 700, 1800, 1000)));
+
+        // Rects off every monitor are ignored
+        wm.toggle_window_tiling(hwnd_from_isize(2)).unwrap();
+        wm.set_float_rects(FloatRects::from([(
+            "calc.exe".to_string(),
+            rect(5000, 0, 5300, 300).into(),
+        )]));
+        wm.toggle_window_tiling(hwnd_from_isize(2)).unwrap();
+        assert_eq!(system.rect(2), Some(rect(100, 100, 500, 500)));
+
+        // Changes are saved once they settle, and only once
+        assert!(wm.float_rects_to_save().is_none());
+        wm.float_rects_changed = Some(Instant::now() - FLOAT_RECTS_SAVE_DELAY);
+        assert!(wm.float_rects_to_save().is_some());
+        assert!(wm.float_rects_to_save().is_none());
+    }
+
+    #[test]
+    fn transient_windows_float_in_the_middle() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        let tile = system.rect(1);
+        system.open_window(2, rect(10, 10, 90, 60));
+        let mut dialog = Window::new(2, 1, 0, rect(10, 10, 90, 60), None);
+        dialog.is_tiled = false;
+        wm.add_window(dialog);
+        wm.center_window(hwnd_from_isize(2)).unwrap();
+
+        let centered = system.rect(2).unwrap();
+        assert_eq!(centered.right - centered.left, 80);
+        assert_eq!(centered.bottom - centered.top, 50);
+        assert!(((centered.left + centered.right) / 2 - 960).abs() <= 1);
+        assert_eq!(system.rect(1), tile);
+    }
+
+    #[test]
+    fn dialogs_open_over_their_owner() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        system.open_dialog(3, 2, rect(0, 0, 300, 200));
+        assert!(!wm.center_dialog(hwnd_from_isize(3)));
+
+        wm.set_layout_config(LayoutConfig {
+            center_dialogs: true,
+            ..Default::default()
+        });
+        assert!(wm.center_dialog(hwnd_from_isize(3)));
+        let owner = system.rect(2).unwrap();
+        let dialog = system.rect(3).unwrap();
+        assert_eq!(dialog.right - dialog.left, 300);
+        assert!(((dialog.left + dialog.right) - (owner.left + owner.right)).abs() <= 2);
+        assert!(((dialog.top + dialog.bottom) - (owner.top + owner.bottom)).abs() <= 2);
+
+        // Windows nobody owns are left alone
+        assert!(!wm.center_dialog(hwnd_from_isize(1)));
+    }
+
+    #[test]
+    fn moving_past_the_last_tile_crosses_to_the_next_monitor() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        open_window(&mut wm, &system, 1, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        wm.move_window(FocusDirection::Right).unwrap();
+        assert_eq!(wm.get_window(hwnd_from_isize(1)).unwrap().monitor, 1);
+        assert!(system.rect(1).unwrap().left >= 1920);
+    }
+
+    #[test]
+    fn moving_down_a_monitor_lands_in_the_bottom_region() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(0, 1080, 1920, 2160)]);
+        for hwnd in 1..=3 {
+            open_window(&mut wm, &system, hwnd, 1);
+        }
+        for hwnd in [2, 3, 1] {
+            wm.set_window_focus(hwnd_from_isize(hwnd));
+            wm.move_window_to_monitor(FocusDirection::Down, false)
+                .unwrap();
+        }
+
+        // Left column for 2, then 3 above 1 on the right
+        let (moved, above) = (system.rect(1).unwrap(), system.rect(3).unwrap());
+        assert!(moved.top > above.top, "{:?} not below {:?}", moved, above);
+        assert!(system.rect(2).unwrap().right <= moved.left);
+    }
+
+    #[test]
+    fn balancing_spreads_tiles_over_monitors_and_gathering_pulls_them_back() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        for hwnd in 1..=5 {
+            open_window(&mut wm, &system, hwnd, 1);
+        }
+        let on_monitor = |wm: &WorkspaceManager, idx| {
+            (1..=5)
+                .filter(|&hwnd| wm.get_window(hwnd_from_isize(hwnd)).unwrap().monitor == idx)
+                .count()
+        };
+
+        assert_eq!(wm.balance_monitors(false), Ok(2));
+        assert_eq!((on_monitor(&wm, 0), on_monitor(&wm, 1)), (3, 2));
+        assert!(system.rect(5).unwrap().left >= 1920);
+        assert_eq!(wm.balance_monitors(false), Ok(0));
+
+        wm.set_window_focus(hwnd_from_isize(5));
+        assert_eq!(wm.balance_monitors(true), Ok(3));
+        assert_eq!(on_monitor(&wm, 1), 5);
+        assert!(system.rect(1).unwrap().left >= 1920);
+    }
+
+    #[test]
+    fn new_windows_can_follow_the_cursor_or_focus() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        system.open_window(1, rect(100, 100, 500, 500));
+        system.set_cursor_position(2500, 500).unwrap();
+        assert_eq!(wm.monitor_for_new_window(hwnd_from_isize(1)), 0);
+
+        let mut layout = LayoutConfig {
+            new_window_monitor: NewWindowMonitor::Cursor,
+            ..Default::default()
+        };
+        wm.set_layout_config(layout.clone());
+        assert_eq!(wm.monitor_for_new_window(hwnd_from_isize(1)), 1);
+
+        layout.new_window_monitor = NewWindowMonitor::Focused;
+        wm.set_layout_config(layout);
+        system.open_window(2, rect(2000, 100, 2500, 500));
+        wm.add_window(Window::new(2, 1, 1, rect(2000, 100, 2500, 500), None));
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.update_decorations();
+        assert_eq!(wm.monitor_for_new_window(hwnd_from_isize(1)), 1);
+    }
+
+    #[test]
+    fn windows_of_an_unplugged_monitor_move_to_the_fallback() {
+        let (mut wm, system) = mock_manager_with(&[
+            rect(0, 0, 1920, 1080),
+            rect(1920, 0, 3840, 1080),
+            rect(3840, 0, 5760, 1080),
+        ]);
+        wm.set_monitor_configs(vec![MonitorConfig {
+            device: Some("\\\\.\\DISPLAY2".to_string()),
+            fallback: true,
+            ..Default::default()
+        }]);
+        // Monitors pick up their `[[monitors]]` entry when they are set
+        let monitors = wm.monitors_from_infos(&system.monitors());
+        wm.set_monitors(monitors);
+        for (hwnd, workspace) in [(1, 1), (2, 3)] {
+            system.open_window(hwnd, rect(4000, 100, 4500, 500));
+            wm.add_window(Window::new(
+                hwnd,
+                workspace,
+                2,
+                rect(4000, 100, 4500, 500),
+                None,
+            ));
+        }
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+
+        system.unplug_last_monitor();
+        wm.reenumerate_monitors().unwrap();
+        let rescued = [1, 2].map(|hwnd| wm.get_window(hwnd_from_isize(hwnd)).unwrap());
+        assert_eq!(
+            rescued.map(|window| (window.monitor, window.workspace)),
+            [(1, 1), (1, 3)]
+        );
+        assert_eq!(wm.monitors[1].get_workspace(3).unwrap().windows.len(), 1);
+        let tiled = system.rect(1).unwrap();
+        assert!(tiled.left >= 1920 && tiled.right <= 3840);
+    }
+
+    #[test]
+    fn rescued_floating_windows_land_inside_the_fallback() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        let floating = rect(2000, 600, 2800, 1200);
+        system.open_window(1, floating);
+        let mut window = Window::new(1, 1, 1, floating, None);
+        window.is_tiled = false;
+        window.original_rect = floating;
+        wm.add_window(window);
+
+        system.unplug_last_monitor();
+        wm.reenumerate_monitors().unwrap();
+        let rescued = wm.get_window(hwnd_from_isize(1)).unwrap();
+        let area = crate::tiling::work_area(rect(0, 0, 1920, 1080));
+        for placed in [rescued.rect, rescued.original_rect, system.rect(1).unwrap()] {
+            assert_eq!(placed.left, area.left + 80, "{:?}", placed);
+            assert!(placed.top >= area.top && placed.bottom <= area.bottom);
+            assert_eq!(placed.right - placed.left, 800);
+        }
+    }
+
+    #[test]
+    fn primary_monitor_follows_the_config() {
+        let (mut wm, _system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        assert_eq!(wm.primary_monitor_index(), 0);
+
+        wm.set_monitor_configs(vec![MonitorConfig {
+            device: Some("\\\\.\\DISPLAY2".to_string()),
+            primary: true,
+            ..Default::default()
+        }]);
+        assert_eq!(wm.primary_monitor_index(), 1);
+        let summaries = wm.monitor_summaries();
+        assert!(!summaries[0].primary && summaries[1].primary);
+    }
+
+    #[test]
+    fn windows_sent_to_another_monitor_can_leave_focus_behind() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        wm.set_focus_config(FocusConfig {
+            cursor_follows: true,
+            ..Default::default()
+        });
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        open_window(&mut wm, &system, 3, 1);
+
+        wm.set_window_focus(hwnd_from_isize(3));
+        wm.move_window_to_monitor(FocusDirection::Right, false)
+            .unwrap();
+        assert_eq!(wm.get_window(hwnd_from_isize(3)).unwrap().monitor, 1);
+        assert_ne!(system.foreground(), 3);
+        assert_eq!(system.cursor_position(), Ok((0, 0)));
+
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.move_window_to_monitor(FocusDirection::Right, true)
+            .unwrap();
+        assert_eq!(system.foreground(), 2);
+        let rect = system.rect(2).unwrap();
+        assert_eq!(
+            system.cursor_position(),
+            Ok(((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2))
+        );
+    }
+
+    #[test]
+    fn paused_displays_let_go_of_their_windows_and_are_skipped() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        wm.set_monitor_configs(vec![MonitorConfig {
+            device: Some("\\\\.\\DISPLAY2".to_string()),
+            unmanaged: true,
+            ..Default::default()
+        }]);
+        // Monitors pick up their `[[monitors]]` entry when they are set
+        let monitors = wm.monitors_from_infos(&system.monitors());
+        wm.set_monitors(monitors);
+        assert!(wm.is_monitor_managed(0));
+        assert!(!wm.is_monitor_managed(1));
+        open_window(&mut wm, &system, 1, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        // Moving right stops at the paused display
+        wm.move_window(FocusDirection::Right).unwrap();
+        assert_eq!(wm.get_window(hwnd_from_isize(1)).unwrap().monitor, 0);
+
+        // Pausing the first display shows and releases its windows
+        open_window(&mut wm, &system, 2, 2);
+        wm.switch_workspace_with_windows(2).unwrap();
+        assert!(!system.is_visible(1));
+        assert_eq!(
+            wm.display_under_cursor().as_deref(),
+            Some("\\\\.\\DISPLAY1")
+        );
+        assert_eq!(wm.toggle_display_managed("\\\\.\\DISPLAY1"), Ok(false));
+        assert!(wm.get_all_managed_hwnds().is_empty());
+        assert!(system.is_visible(1));
+        assert_eq!(wm.toggle_display_managed("\\\\.\\display2"), Ok(true));
+        assert_eq!(
+            wm.displays(),
+            vec![
+                ("\\\\.\\DISPLAY1".to_string(), false),
+                ("\\\\.\\DISPLAY2".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn split_ratio_presets_size_the_focused_side() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        let width = |hwnd| system.rect(hwnd).map(|r| r.right - r.left).unwrap();
+        let total = width(1) + width(2);
+
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.set_split_ratio(0.75).unwrap();
+        assert!(
+            (width(2) - total * 3 / 4).abs() <= 8,
+            "{} of {}",
+            width(2),
+            total
+        );
+    }
+
+    #[test]
+    fn cycling_wraps_and_skips_ineligible_workspaces() {
+        assert_eq!(cycle_workspace(9, 9, true, |_| true), Some(1));
+        assert_eq!(cycle_workspace(1, 9, false, |_| true), Some(9));
+        assert_eq!(cycle_workspace(9, 12, true, |_| true), Some(10));
+        assert_eq!(cycle_workspace(1, 12, false, |_| true), Some(12));
+
+        let occupied = |ws: u8| [2, 5].contains(&ws);
+        assert_eq!(cycle_workspace(2, 9, true, occupied), Some(5));
+        assert_eq!(cycle_workspace(5, 9, true, occupied), Some(2));
+        assert_eq!(cycle_workspace(2, 9, false, occupied), Some(5));
+        assert_eq!(cycle_workspace(3, 9, true, |ws| ws == 3), None);
+    }
+
+    #[test]
+    fn restart_restores_splits_and_focus() {
+        let (mut wm, system) = mock_manager();
+        for hwnd in 1..=3 {
+            open_window(&mut wm, &system, hwnd, 1);
+        }
+        let workspace = &mut wm.monitors[0].workspaces[0];
+        workspace.layout_tree.as_mut().unwrap().split_ratio = 0.7;
+        workspace.focused_window_hwnd = Some(2);
+        let session = wm.saved_session();
+
+        // The new instance finds the windows in a different order
+        let (mut wm, system) = mock_manager();
+        for hwnd in [3, 1, 2] {
+            open_window(&mut wm, &system, hwnd, 1);
+        }
+        let matched: HashMap<isize, &SavedWindow> = session
+            .windows
+            .iter()
+            .map(|saved| (saved.hwnd, saved))
+            .collect();
+        wm.restore_session(&session, &matched);
+        wm.tile_active_workspaces();
+
+        let workspace = &wm.monitors[0].workspaces[0];
+        let tree = workspace.layout_tree.as_ref().unwrap();
+        assert_eq!(tree.split_ratio, 0.7);
+        assert_eq!(
+            Some(LayoutNode::from_tile_with_handles(tree, &workspace.windows)),
+            session.workspaces[0].layout_tree
+        );
+        assert_eq!(workspace.focused_window_hwnd, Some(2));
+    }
+
+    #[test]
+    fn auto_hide_gives_the_bar_space_to_windows() {
+        let (mut wm, system) = mock_manager();
+        wm.bar_displays.insert(r"\\.\DISPLAY1".to_string());
+        open_window(&mut wm, &system, 1, 1);
+        let reserved_top = system.rect(1).unwrap().top;
+        wm.set_statusbar_auto_hide(Duration::from_secs(1));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        assert!(system.rect(1).unwrap().top < reserved_top);
+
+        let bar = rect(760, 2, 1160, 36);
+        let display = Some(rect(0, 0, 1920, 1080));
+        let top = BarPosition::Top;
+        assert!(bar_wanted(bar, display, top, 800, 20));
+        assert!(bar_wanted(bar, display, top, 100, 0));
+        assert!(!bar_wanted(bar, display, top, 100, 1));
+        assert!(!bar_wanted(bar, None, top, 100, 0));
+        assert!(bar_wanted(bar, display, BarPosition::Bottom, 100, 1079));
+        assert!(!bar_wanted(bar, display, BarPosition::Bottom, 100, 0));
+    }
+
+    #[test]
+    fn only_displays_with_a_bar_keep_room_for_it() {
+        let (mut wm, system) =
+            mock_manager_with(&[rect(0, 0, 1920, 1080), rect(1920, 0, 3840, 1080)]);
+        wm.bar_displays.insert(r"\\.\DISPLAY1".to_string());
+        for (hwnd, monitor) in [(1, 0), (2, 1)] {
+            system.open_window(hwnd, rect(100, 100, 500, 500));
+            wm.add_window(Window::new(
+                hwnd,
+                1,
+                monitor,
+                rect(100, 100, 500, 500),
+                None,
+            ));
+        }
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+
+        assert!(system.rect(1).unwrap().top >= STATUSBAR_VERTICAL_RESERVE);
+        // The second monitor has no bar, so its windows only leave the padding
+        assert_eq!(system.rect(2).unwrap().top, LayoutConfig::default().padding);
+    }
+
+    #[test]
+    fn fullscreen_drops_the_bar_reserve_on_its_display() {
+        let (mut wm, system) = mock_manager();
+        wm.bar_displays.insert(r"\\.\DISPLAY1".to_string());
+        open_window(&mut wm, &system, 1, 1);
+        let reserved_top = system.rect(1).unwrap().top;
+
+        wm.fullscreen_displays = vec![rect(1920, 0, 3840, 1080)];
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        assert_eq!(system.rect(1).unwrap().top, reserved_top);
+
+        wm.fullscreen_displays = vec![rect(0, 0, 1920, 1080)];
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        assert!(system.rect(1).unwrap().top < reserved_top);
+    }
+
+    #[test]
+    fn window_effects_can_be_switched_off() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.update_decorations();
+        assert!(system.border(1).is_some());
+        assert_eq!(system.alpha(2), Some(245));
+
+        wm.set_window_effects(false);
+        assert_eq!(system.border(1), None);
+        assert_eq!(system.alpha(2), Some(255));
+        wm.update_decorations();
+        assert_eq!(system.alpha(2), Some(255));
+
+        wm.set_window_effects(true);
+        assert!(system.border(1).is_some());
+        assert_eq!(system.alpha(2), Some(245));
+    }
+
+    #[test]
+    fn power_saving_keeps_windows_opaque() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.opacity_levels.insert("app.exe".to_string(), 200);
+        wm.monitors[0].workspaces[0].windows[0].process_name = Some("app.exe".to_string());
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.update_decorations();
+        assert_eq!(system.alpha(1), Some(200));
+        assert_eq!(system.alpha(2), Some(245));
+
+        wm.set_power_saving(true);
+        assert_eq!(system.alpha(1), Some(255));
+        assert_eq!(system.alpha(2), Some(255));
+        assert!(system.border(1).is_some());
+
+        wm.set_power_saving(false);
+        assert_eq!(system.alpha(1), Some(200));
+        assert_eq!(system.alpha(2), Some(245));
+    }
+
+    #[test]
+    fn border_rules_color_elevated_windows() {
+        use crate::config::BorderRule;
+
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        system.elevate_window(1);
+        wm.set_decorations_config(DecorationsConfig {
+            borders: vec![BorderRule {
+                elevated: true,
+                color: "#FF0000".to_string(),
+                unfocused: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.update_decorations();
+        assert_eq!(system.border(1), Some(0x000000FF));
+        // Stays red while unfocused; the other window gets the accent border
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.update_decorations();
+        assert_eq!(system.border(1), Some(0x000000FF));
+        assert_eq!(system.border(2), Some(Theme::Dark.border_color(0x00D77800)));
+    }
+
+    #[test]
+    fn flashing_focus_pulses_until_focus_moves() {
+        assert_eq!(flash_highlighted(Duration::ZERO), Some(true));
+        assert_eq!(flash_highlighted(FOCUS_FLASH_HALF_PULSE), Some(false));
+        assert_eq!(
+            flash_highlighted(FOCUS_FLASH_HALF_PULSE * 2 * FOCUS_FLASH_PULSES),
+            None
+        );
+
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.opacity_levels.insert("app.exe".to_string(), 200);
+        wm.monitors[0].workspaces[0].windows[0].process_name = Some("app.exe".to_string());
+        wm.set_window_focus(hwnd_from_isize(1));
+        wm.update_decorations();
+        let focused_border = system.border(1);
+        wm.flash_focus();
+        assert_eq!(system.alpha(1), Some(FOCUS_FLASH_ALPHA));
+        assert_eq!(system.border(1), Some(0x00FFFFFF));
+
+        // Between pulses the window looks as it does when focused
+        wm.focus_flash = Some((1, Instant::now() - FOCUS_FLASH_HALF_PULSE, true));
+        wm.update_focus_flash();
+        assert_eq!(system.alpha(1), Some(200));
+        assert_eq!(system.border(1), focused_border);
+
+        wm.set_window_focus(hwnd_from_isize(2));
+        wm.update_focus_flash();
+        assert_eq!(system.alpha(1), Some(200));
+        assert_eq!(system.border(1), None);
+        assert_eq!(system.alpha(2), Some(255));
+    }
+
+    #[test]
+    fn urgent_windows_are_one_jump_away() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 3);
+        open_window(&mut wm, &system, 3, 2);
+        wm.switch_workspace_with_windows(1).unwrap();
+        wm.set_window_focus(hwnd_from_isize(1));
+
+        wm.mark_urgent(hwnd_from_isize(2));
+        wm.mark_urgent(hwnd_from_isize(3));
+        wm.mark_urgent(hwnd_from_isize(1)); // Focused already
+        wm.focus_urgent_window().unwrap();
+        assert_eq!(wm.get_active_workspace(), 2);
+        assert_eq!(system.foreground(), 3);
+
+        // Focusing a window some other way settles it too
+        wm.update_decorations();
+        wm.focus_urgent_window().unwrap();
+        assert_eq!(wm.get_active_workspace(), 3);
+        assert_eq!(system.foreground(), 2);
+        assert!(wm.focus_urgent_window().is_err());
+    }
+
+    #[test]
+    fn floating_workspaces_leave_windows_where_they_are() {
+        let (mut wm, system) = mock_manager();
+        wm.set_workspace_overrides(vec![WorkspaceOverride {
+            workspace: 2,
+            floating: true,
+            ..Default::default()
+        }]);
+        open_window(&mut wm, &system, 1, 2);
+        open_window(&mut wm, &system, 2, 1);
+        assert!(!wm.get_window(hwnd_from_isize(1)).unwrap().is_tiled);
+
+        wm.set_window_focus(hwnd_from_isize(2));
+        let tiled_rect = system.rect(2);
+        wm.move_window_to_workspace(2, true).unwrap();
+        assert!(!wm.get_window(hwnd_from_isize(2)).unwrap().is_tiled);
+        assert_eq!(system.rect(2), tiled_rect);
+        assert_eq!(system.rect(1), Some(rect(100, 100, 500, 500)));
+    }
+
+    #[test]
+    fn workspace_tiling_flips_all_windows_at_once() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        open_window(&mut wm, &system, 2, 1);
+        wm.toggle_window_tiling(hwnd_from_isize(2)).unwrap();
+        system.open_window(2, rect(600, 600, 900, 900));
+        wm.handle_window_dropped(hwnd_from_isize(2));
+
+        // One tiled window is enough to float them all
+        wm.toggle_workspace_tiling().unwrap();
+        let windows = [1, 2].map(|hwnd| wm.get_window(hwnd_from_isize(hwnd)).unwrap());
+        assert!(windows.iter().all(|window| !window.is_tiled));
+        assert_eq!(system.rect(1), Some(rect(100, 100, 500, 500)));
+
+        wm.toggle_workspace_tiling().unwrap();
+        assert!(wm.get_window(hwnd_from_isize(2)).unwrap().is_tiled);
+        assert_ne!(system.rect(2), Some(rect(600, 600, 900, 900)));
+
+        // Each floats back to where it was before
+        wm.toggle_workspace_tiling().unwrap();
+        assert_eq!(system.rect(1), Some(rect(100, 100, 500, 500)));
+        assert_eq!(system.rect(2), Some(rect(600, 600, 900, 900)));
+    }
+
+    #[test]
+    fn startup_windows_float_until_adopted() {
+        let (mut wm, system) = mock_manager();
+        open_window(&mut wm, &system, 1, 1);
+        system.open_window(2, rect(300, 300, 700, 700));
+        let mut window = Window::new(2, 1, 0, rect(300, 300, 700, 700), None);
+        window.is_tiled = false;
+        wm.add_window(window);
+        wm.leave_unadopted(hwnd_from_isize(2));
+        wm.tile_active_workspaces();
+        wm.apply_window_positions();
+        assert_eq!(system.rect(2), Some(rect(300, 300, 700, 700)));
+
+        assert_eq!(wm.adopt_windows(), Ok(1));
+        assert!(wm.get_window(hwnd_from_isize(2)).unwrap().is_tiled);
+        assert_ne!(system.rect(2), Some(rect(300, 300, 700, 700)));
+        assert!(wm.adopt_windows().is_err());
+    }
+
+    #[test]
+    fn windows_covering_their_display_count_as_fullscreen() {
+        let (wm, system) = mock_manager();
+        system.open_window(1, rect(0, 0, 1920, 1080));
+        system.open_window(2, rect(0, 0, 1920, 1040));
+        assert!(wm.covers_display(hwnd_from_isize(1)));
+        assert!(!wm.covers_display(hwnd_from_isize(2)));
+    }
+
+    #[test]
+    fn dynamic_workspaces_collapse_gaps_before_used_ones() {
+        let used = |occupied: &'static [u8]| move |ws: u8| !occupied.contains(&ws);
+        assert_eq!(workspace_to_collapse(9, 1, used(&[1, 4])), Some(2));
+        assert_eq!(workspace_to_collapse(9, 2, used(&[1, 4])), Some(3));
+        assert_eq!(workspace_to_collapse(9, 3, used(&[1, 2])), None);
+        assert_eq!(workspace_to_collapse(9, 5, used(&[])), Some(2));
+        assert_eq!(workspace_to_collapse(9, 1, used(&[1, 2, 3])), None);
+    }
+
+    #[test]
+    fn templates_only_shape_a_workspace_filling_from_empty() {
+        use crate::layouts::{LayoutTemplate, TemplateShape};
+        use crate::rules::WindowRule;
+
+        let (mut wm, system) = mock_manager();
+        let slot = WindowRule {
+            process: Some("app.exe".to_string()),
+            ..Default::default()
+        };
+        wm.set_layout_templates(vec![LayoutTemplate {
+            workspace: 1,
+            shape: TemplateShape::MainStack,
+            main_ratio: 0.8,
+            slots: vec![slot.clone(), slot.clone(), slot],
+        }]);
+        let open = |wm: &mut WorkspaceManager, hwnd: isize| {
+            system.open_window(hwnd, rect(100, 100, 500, 500));
+            wm.add_window(Window::new(
+                hwnd,
+                1,
+                0,
+                rect(100, 100, 500, 500),
+                Some("app.exe".to_string()),
+            ));
+            wm.tile_active_workspaces();
+        };
+        let root_ratio = |wm: &WorkspaceManager| {
+            wm.monitors[0].workspaces[0]
+                .layout_tree
+                .as_ref()
+                .unwrap()
+                .split_ratio
+        };
+
+        open(&mut wm, 1);
+        open(&mut wm, 2);
+        assert_eq!(root_ratio(&wm), 0.8);
+        assert!(wm.monitors[0].workspaces[0].filling_template);
+
+        // Once a window has left, new ones are tiled as usual
+        wm.remove_window_with_tiling(hwnd_from_isize(2));
+        open(&mut wm, 3);
+        assert!(!wm.monitors[0].workspaces[0].filling_template);
+        assert_ne!(root_ratio(&wm), 0.8);
+    }
+
+    #[test]
+    fn empty_workspaces_collapse_once_left_or_emptied() {
+        let (mut wm, system) = mock_manager();
+        wm.set_dynamic_workspaces(true);
+        open_window(&mut wm, &system, 1, 1);
+        wm.switch_workspace_with_windows(2).unwrap();
+        open_window(&mut wm, &system, 2, 2);
+        wm.switch_workspace_with_windows(3).unwrap();
+        open_window(&mut wm, &system, 3, 3);
+
+        // Updating the bar alone leaves the numbering alone
+        wm.remove_window(hwnd_from_isize(2));
+        wm.update_statusbar();
+        assert_eq!(wm.get_window(hwnd_from_isize(3)).unwrap().workspace, 3);
+
+        wm.switch_workspace_with_windows(1).unwrap();
+        assert_eq!(wm.get_window(hwnd_from_isize(3)).unwrap().workspace, 2);
+
+        wm.remove_window_with_tiling(hwnd_from_isize(1));
+        assert_eq!(wm.get_window(hwnd_from_isize(3)).unwrap().workspace, 2);
+        assert_eq!(wm.used_workspace_count(), 3);
+
+        // The workspace show-desktop cleared is renumbered with the rest
+        open_window(&mut wm, &system, 4, 4);
+        wm.desktop_shown = Some(4);
+        wm.collapse_empty_workspaces();
+        assert_eq!(wm.get_window(hwnd_from_isize(4)).unwrap().workspace, 3);
+        assert_eq!(wm.desktop_shown, Some(3));
+    }
+
+    #[test]
+    fn locked_workspaces_route_to_the_next_unlocked_one() {
+        let locked: HashSet<u8> = [3, 4, 9].into_iter().collect();
+        assert_eq!(next_unlocked_workspace(2, 9, &locked), 2);
+        assert_eq!(next_unlocked_workspace(3, 9, &locked), 5);
+        assert_eq!(next_unlocked_workspace(9, 9, &locked), 1);
+        assert_eq!(next_unlocked_workspace(9, 10, &locked), 10);
+
+        let all: HashSet<u8> = (1..=9).collect();
+        assert_eq!(next_unlocked_workspace(6, 9, &all), 6);
+    }
+
+    #[test]
+    fn zones_tile_the_display_without_gaps() {
+        let zones = split_into_zones(rect(0, 0, 5120, 1440), 3);
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones[0].left, 0);
+        assert_eq!(zones[0].right, zones[1].left);
+        assert_eq!(zones[1].right, zones[2].left);
+        assert_eq!(zones[2].right, 5120);
+        assert!(zones.iter().all(|z| z.top == 0 && z.bottom == 1440));
+        assert_eq!(split_into_zones(rect(0, 0, 100, 100), 0).len(), 1);
+    }
+
+    #[test]
+    fn reserved_edges_shrink_the_tiled_area() {
+        let reserve = ReservedEdges {
+            right: 40,
+            top: 30,
+            ..Default::default()
+        };
+        assert_eq!(
+            reserve_edges(rect(0, 0, 1920, 1080), reserve),
+            rect(0, 30, 1880, 1080)
+        );
+        let huge = ReservedEdges {
+            left: 5000,
+            ..Default::default()
+        };
+        let squeezed = reserve_edges(rect(0, 0, 1920, 1080), huge);
+        assert!(squeezed.left <= squeezed.right);
+    }
+
+    #[test]
+    fn finds_adjacent_monitor_by_center() {
+        // Three monitors side by side, plus one stacked above the middle
+        let monitors = [
+            rect(-1920, 0, 0, 1080),
+            rect(0, 0, 1920, 1080),
+            rect(1920, 0, 3840, 1080),
+            rect(0, -1080, 1920, 0),
+        ];
+
+        assert_eq!(
+            find_rect_in_direction(&monitors, 1, FocusDirection::Left),
+            Some(0)
+        );
+        assert_eq!(
+            find_rect_in_direction(&monitors, 1, FocusDirection::Right),
+            Some(2)
+        );
+        assert_eq!(
+            find_rect_in_direction(&monitors, 1, FocusDirection::Up),
+            Some(3)
+        );
+        assert_eq!(
+            find_rect_in_direction(&monitors, 1, FocusDirection::Down),
+            None
+        );
+        assert_eq!(
+            find_rect_in_direction(&monitors, 0, FocusDirection::Left),
+            None
+        );
+        assert_eq!(
+            find_rect_in_direction(&monitors, 9, FocusDirection::Left),
+            None
+        );
+    }
+
+    #[test]
+    fn nearest_in_direction_prefers_primary_distance() {
+        let focused = rect(0, 0, 100, 100);
+        let candidates = [
+            rect(300, 0, 400, 100),   // right, far
+            rect(110, 200, 210, 300), // right and below, close on primary axis
+            rect(110, 0, 210, 100),   // right, close and aligned
+            rect(-200, 0, -100, 100), // left
+        ];
+
+        assert_eq!(
+            find_nearest_in_direction(&focused, &candidates, FocusDirection::Right),
+            Some(2)
+        );
+        assert_eq!(
+            find_nearest_in_direction(&focused, &candidates, FocusDirection::Left),
+            Some(3)
+        );
+        assert_eq!(
+            find_nearest_in_direction(&focused, &candidates, FocusDirection::Up),
+            None
+        );
+    }
+
+    #[test]
+    fn nearest_in_direction_ignores_overlapping_candidates() {
+        let focused = rect(0, 0, 100, 100);
+        let candidates = [rect(50, 0, 150, 100)];
+
+        assert_eq!(
+            find_nearest_in_direction(&focused, &candidates, FocusDirection::Right),
+            None
+        );
+    }
+
+    #[test]
+    fn dropped_windows_swap_with_the_tile_under_the_cursor() {
+        let tiles = [
+            (1, rect(0, 0, 100, 100)),
+            (2, rect(100, 0, 200, 100)),
+            (3, rect(0, 100, 200, 200)),
+        ];
+        assert_eq!(tile_under_point(&tiles, 150, 50, 1), Some(2));
+        assert_eq!(tile_under_point(&tiles, 100, 150, 1), Some(3));
+        // Dropped back on its own tile, or outside every tile
+        assert_eq!(tile_under_point(&tiles, 50, 50, 1), None);
+        assert_eq!(tile_under_point(&tiles, 250, 50, 1), None);
+    }
+
+    #[test]
+    fn wrapping_picks_the_far_side_in_line_with_focus() {
+        let focused = rect(400, 0, 500, 100);
+        let candidates = [
+            rect(0, 200, 100, 300), // far left, below
+            rect(0, 0, 100, 100),   // far left, aligned
+            rect(200, 0, 300, 100), // left, closer
+        ];
+
+        assert_eq!(
+            find_wrap_target(&focused, &candidates, FocusDirection::Right),
+            Some(1)
+        );
+        assert_eq!(
+            find_wrap_target(&focused, &candidates, FocusDirection::Up),
+            Some(0)
+        );
+        assert_eq!(find_wrap_target(&focused, &[], FocusDirection::Left), None);
+    }
+}